@@ -3,7 +3,9 @@
 //! Defines all command-line arguments and subcommands.
 
 use clap::{Parser, Subcommand, ValueEnum};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use crate::core::CoreError;
 
 #[derive(Parser)]
 #[command(name = "re-x")]
@@ -12,28 +14,145 @@ pub struct Cli {
     #[command(subcommand)]
     pub command: Option<Commands>,
 
-    /// Output format
-    #[arg(long, short = 'f', global = true, default_value = "json")]
+    /// Output format [default: json, or the RE_X_FORMAT environment variable]
+    #[arg(long = "format", short = 'f', global = true)]
+    format_arg: Option<OutputFormat>,
+
+    /// Effective output format: --format, else RE_X_FORMAT, else JSON (resolved in `parse`)
+    #[arg(skip)]
     pub format: OutputFormat,
 
+    /// Field separator for --format csv: a single character, or `tab` for TSV
+    #[arg(long, global = true, default_value = ",", value_parser = parse_delimiter)]
+    pub delimiter: u8,
+
     /// Enable MCP server mode
     #[arg(long)]
     pub mcp: bool,
+
+    /// Wall-clock budget for each MCP tool call; slower calls return a TIMEOUT error (regex_apply always runs to completion)
+    #[arg(long, value_name = "MS", default_value_t = 30_000, requires = "mcp")]
+    pub mcp_timeout_ms: u64,
+
+    /// Reject MCP tool calls whose pattern has more capture groups than this
+    #[arg(long, value_name = "N", default_value_t = crate::core::engine::DEFAULT_MAX_GROUPS, requires = "mcp")]
+    pub max_groups: usize,
+
+    /// Maximum pattern nesting depth accepted by explain/validate (and the MCP server);
+    /// stricter than regex-syntax's own 250 so recursive AST walks stay within the stack
+    #[arg(long, global = true, default_value_t = crate::core::engine::DEFAULT_MAX_DEPTH)]
+    pub max_depth: u32,
+
+    /// Free-spacing mode: prepend (?x) so whitespace and # comments in the pattern are ignored
+    #[arg(long, short = 'X', global = true)]
+    pub extended: bool,
+
+    /// Treat the PATTERN argument as a path and read the pattern from that file
+    #[arg(long, global = true)]
+    pub pattern_file: bool,
+
+    /// Print nothing on success; only the exit code reports the outcome (like grep -q)
+    #[arg(long, short = 'q', global = true)]
+    pub quiet: bool,
+}
+
+/// Process exit codes, so scripts can branch on the outcome without parsing JSON
+pub mod exit_code {
+    /// Success (for `test`: at least one match was found)
+    pub const SUCCESS: u8 = 0;
+    /// `test` found no matches
+    pub const NO_MATCH: u8 = 1;
+    /// The pattern does not compile (or `validate` reported it invalid)
+    pub const INVALID_PATTERN: u8 = 2;
+    /// `benchmark` detected catastrophic backtracking
+    pub const REDOS_DETECTED: u8 = 3;
+    /// `validate --target-lang` found the pattern incompatible with the target
+    pub const INCOMPATIBLE: u8 = 4;
+    /// Any other failure (I/O errors, bad arguments)
+    pub const ERROR: u8 = 5;
+}
+
+/// Rendered output of a command together with the exit code it should produce
+pub struct CommandOutput {
+    pub output: String,
+    pub exit_code: u8,
+    /// Whether a newline is printed after `output` (false for raw text)
+    pub trailing_newline: bool,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+impl CommandOutput {
+    fn new(output: String, exit_code: u8) -> Self {
+        Self {
+            output,
+            exit_code,
+            trailing_newline: true,
+        }
+    }
+
+    /// Output printed byte-for-byte, e.g. transformed text for a pipeline
+    fn raw(output: String) -> Self {
+        Self {
+            trailing_newline: false,
+            ..Self::new(output, exit_code::SUCCESS)
+        }
+    }
+}
+
+impl From<String> for CommandOutput {
+    fn from(output: String) -> Self {
+        Self::new(output, exit_code::SUCCESS)
+    }
+}
+
+#[derive(Copy, Clone, Default, PartialEq, Eq, ValueEnum)]
 pub enum OutputFormat {
     /// JSON output (default, for AI consumption)
+    #[default]
     Json,
     /// Human-readable text
     Text,
+    /// Comma-separated values, one row per match (test only; other commands print JSON)
+    Csv,
+}
+
+/// When to use ANSI colors in text output
+#[derive(Copy, Clone, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Color when stdout is a terminal and NO_COLOR is not set
+    #[default]
+    Auto,
+    /// Always color
+    Always,
+    /// Never color
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether to color output, resolving `auto` against stdout and NO_COLOR
+    fn enabled(self) -> bool {
+        use std::io::IsTerminal;
+
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+                    && std::io::stdout().is_terminal()
+            }
+        }
+    }
 }
 
 #[derive(Subcommand)]
 pub enum Commands {
     /// Test a regex pattern against input
     Test {
-        /// The regex pattern to test
+        /// The regex pattern to test (omitted with --patterns-from)
+        #[arg(
+            default_value = "",
+            hide_default_value = true,
+            required_unless_present = "patterns_from"
+        )]
         pattern: String,
 
         /// Input text to test against (use --file for file input)
@@ -43,7 +162,217 @@ pub enum Commands {
         #[arg(long, short = 'F')]
         file: Option<PathBuf>,
 
-        /// Maximum number of matches to return
+        /// Maximum number of matches to return [default: 100, or no limit with
+        /// --stream-json]
+        #[arg(long)]
+        max_matches: Option<usize>,
+
+        /// Force specific engine (regex or fancy-regex)
+        #[arg(long)]
+        engine: Option<String>,
+
+        /// Enable multiline mode (dot matches newline, ^/$ match line boundaries)
+        #[arg(long, short = 'm')]
+        multiline: bool,
+
+        /// Treat NUL as the record separator instead of newline (pairs with find -print0)
+        #[arg(long, short = 'z', conflicts_with = "multiline")]
+        null_data: bool,
+
+        /// Report only this capture group (number or name) for each match
+        #[arg(long, short = 'g')]
+        group: Option<String>,
+
+        /// ASCII-only \w, \d, \s and \b (disables Unicode mode with (?-u))
+        #[arg(long)]
+        no_unicode: bool,
+
+        /// Answer whether the entire input matches (as if wrapped in ^(?:...)$)
+        #[arg(long, conflicts_with = "null_data")]
+        full_match: bool,
+
+        /// Count how often each distinct value of this capture group (number or name) occurs
+        #[arg(long, value_name = "N|NAME")]
+        by_capture: Option<String>,
+
+        /// With --by-capture, report only the N most frequent values
+        #[arg(long, requires = "by_capture")]
+        top: Option<usize>,
+
+        /// Run an empty pattern anyway (it matches at every position)
+        #[arg(long)]
+        allow_empty: bool,
+
+        /// Keep the empty matches of a pattern like `a*` (left out by default)
+        #[arg(long)]
+        allow_empty_matches: bool,
+
+        /// Order of reported matches (text and length keep each match's original index)
+        #[arg(long, default_value = "position", value_parser = ["position", "text", "length"])]
+        sort: String,
+
+        /// Collapse matches with identical text into one entry with occurrences and positions
+        #[arg(long)]
+        dedup: bool,
+
+        /// Report only how many matches each matching line has (like grep -c, per line)
+        #[arg(
+            long,
+            requires = "file",
+            conflicts_with_all = ["multiline", "null_data", "full_match", "group", "by_capture", "dedup"]
+        )]
+        per_line_count: bool,
+
+        /// Show N characters of surrounding text around each match, with the match marked «»
+        #[arg(long, value_name = "N")]
+        context_chars: Option<usize>,
+
+        /// Color each capture group within a match in its own ANSI color (text output; see --color)
+        #[arg(long, conflicts_with_all = ["group", "dedup"])]
+        highlight_captures: bool,
+
+        /// Color --highlight-captures output: auto (terminal without NO_COLOR), always or never
+        #[arg(
+            long,
+            value_name = "WHEN",
+            default_value = "auto",
+            requires = "highlight_captures"
+        )]
+        color: ColorChoice,
+
+        /// Output only a table of capture values: one row per match, one column per group
+        #[arg(
+            long,
+            conflicts_with_all = ["group", "by_capture", "dedup", "per_line_count", "highlight_captures"]
+        )]
+        only_groups: bool,
+
+        /// Stop at the first match; with --file, small files are streamed too when the
+        /// pattern cannot span lines, so the rest of the file is never read
+        #[arg(
+            long,
+            conflicts_with_all = ["max_matches", "by_capture", "per_line_count", "dedup"]
+        )]
+        first: bool,
+
+        /// Give each line of --file this long to match; the scan stops at the first
+        /// slower line, listed in timed_out_lines, instead of stalling
+        #[arg(
+            long,
+            value_name = "MS",
+            requires = "file",
+            conflicts_with_all = ["multiline", "full_match", "per_line_count"]
+        )]
+        timeout_per_line_ms: Option<u64>,
+
+        /// Match raw bytes instead of UTF-8 text, for input that is not valid UTF-8;
+        /// offsets are byte offsets and match text is decoded lossily (regex engine only)
+        #[arg(
+            long,
+            conflicts_with_all = ["null_data", "per_line_count", "context_chars", "timeout_per_line_ms", "highlight_captures"]
+        )]
+        byte_mode: bool,
+
+        /// Print a warning on stderr when the pattern silently falls back from the
+        /// linear-time regex engine to fancy-regex
+        #[arg(long)]
+        engine_fallback_warning: bool,
+
+        /// Give each match its 1-based line and column (and end_line/end_column)
+        /// in the file, for editor integrations; columns count characters
+        #[arg(long, requires = "file", conflicts_with = "per_line_count")]
+        context_json: bool,
+
+        /// Match any pattern in FILE (one per line), like grep -f; each becomes a
+        /// named group alt<N> (N = line number) showing which one matched.
+        /// Numbered backreferences in the patterns shift, so prefer named ones (other
+        /// than alt<N>, which is reserved)
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["pattern_file", "extended"])]
+        patterns_from: Option<PathBuf>,
+
+        /// Match the --patterns-from lines as literal strings, not regexes
+        #[arg(long, alias = "fixed-string", requires = "patterns_from")]
+        fixed_strings: bool,
+
+        /// Write the matches as a JSON array, each one as soon as it is found,
+        /// instead of a summary built in memory (for huge files; ignores --format)
+        #[arg(
+            long,
+            requires = "file",
+            conflicts_with_all = [
+                "multiline", "full_match", "group", "by_capture", "top", "dedup", "sort",
+                "per_line_count", "highlight_captures", "only_groups", "timeout_per_line_ms",
+                "byte_mode", "context_json", "quiet",
+            ]
+        )]
+        stream_json: bool,
+
+        /// Experimental: instead of matching, find the longest prefix of the
+        /// pattern that still matches the input, to see where it diverges
+        #[arg(
+            long,
+            conflicts_with_all = [
+                "full_match", "group", "by_capture", "top", "dedup", "per_line_count",
+                "only_groups", "timeout_per_line_ms", "byte_mode", "context_json", "stream_json",
+            ]
+        )]
+        coverage: bool,
+
+        /// JavaScript `/y` (sticky) semantics: each match must start exactly where
+        /// the previous one ended, the first at offset 0; matching stops at the first gap
+        #[arg(
+            long,
+            conflicts_with_all = ["null_data", "byte_mode", "timeout_per_line_ms", "stream_json", "coverage"]
+        )]
+        sticky: bool,
+
+        /// Print each match as `path:line:col:text` for editor quickfix lists, like
+        /// `rg --vimgrep`; input without a path is named `<stdin>` (ignores --format)
+        #[arg(
+            long,
+            conflicts_with_all = [
+                "per_line_count", "only_groups", "byte_mode", "stream_json", "coverage",
+            ]
+        )]
+        vimgrep: bool,
+
+        /// Report `effective_pattern`: the pattern exactly as handed to the engine,
+        /// with the flags from --multiline, --no-unicode and --full-match spelled out
+        #[arg(long, conflicts_with_all = ["stream_json", "coverage", "vimgrep"])]
+        show_flags_normalized: bool,
+    },
+
+    /// Test every pattern in a file (one per line) against the same input
+    TestMany {
+        /// File with one pattern per line (blank lines are skipped)
+        patterns_file: PathBuf,
+
+        /// Input text to test against (use --file for file input)
+        input: Option<String>,
+
+        /// File to test against
+        #[arg(long, short = 'F', conflicts_with = "input")]
+        file: Option<PathBuf>,
+
+        /// Enable multiline mode (dot matches newline, ^/$ match line boundaries)
+        #[arg(long, short = 'm')]
+        multiline: bool,
+
+        /// Run empty patterns anyway (they match at every position)
+        #[arg(long)]
+        allow_empty: bool,
+    },
+
+    /// Re-run a pattern against a file every time the file changes
+    Watch {
+        /// The regex pattern to test
+        pattern: String,
+
+        /// File to watch
+        #[arg(long, short = 'F')]
+        file: PathBuf,
+
+        /// Maximum number of matches to return per run
         #[arg(long, default_value = "100")]
         max_matches: usize,
 
@@ -54,6 +383,18 @@ pub enum Commands {
         /// Enable multiline mode (dot matches newline, ^/$ match line boundaries)
         #[arg(long, short = 'm')]
         multiline: bool,
+
+        /// Wait this long after a change for further writes before re-running
+        #[arg(long, default_value = "200", value_name = "MS")]
+        debounce_ms: u64,
+
+        /// Exit after N runs, counting the initial one (default: run until interrupted)
+        #[arg(
+            long,
+            value_name = "N",
+            value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+        )]
+        max_runs: Option<usize>,
     },
 
     /// Test regex replacement
@@ -61,7 +402,7 @@ pub enum Commands {
         /// The regex pattern
         pattern: String,
 
-        /// The replacement string (supports $1, $2, etc.)
+        /// The replacement string (supports $1, ${name}, \1 and \g<name>)
         replacement: String,
 
         /// Input text to transform
@@ -78,6 +419,27 @@ pub enum Commands {
         /// Enable multiline mode (dot matches newline, ^/$ match line boundaries)
         #[arg(long, short = 'm')]
         multiline: bool,
+
+        /// Truncate the echoed original/result strings (and their spans) to this many bytes
+        #[arg(long)]
+        max_result_bytes: Option<usize>,
+
+        /// Insert the replacement verbatim ($1, ${name}, \1 and $$ are not expanded)
+        #[arg(long)]
+        literal_replacement: bool,
+
+        /// Run an empty pattern anyway (it matches at every position)
+        #[arg(long)]
+        allow_empty: bool,
+
+        /// Print only the transformed text (whole file with --file), like sed
+        #[arg(long, conflicts_with = "max_result_bytes")]
+        raw: bool,
+
+        /// Replace only the first N matches and leave the rest untouched
+        /// (remaining_matches counts them)
+        #[arg(long, value_name = "N", alias = "replace-count-limit")]
+        count: Option<usize>,
     },
 
     /// Validate regex syntax and check portability
@@ -88,12 +450,108 @@ pub enum Commands {
         /// Target language to check compatibility
         #[arg(long, short = 't')]
         target_lang: Option<String>,
+
+        /// Report compatibility and suggested rewrites for every target language
+        #[arg(long, conflicts_with = "target_lang")]
+        all_targets: bool,
+
+        /// Report the effective flags set by inline directives like (?i)
+        #[arg(long)]
+        flags: bool,
+
+        /// Report portability per engine with the reasons for each incompatibility
+        #[arg(long)]
+        verbose_portability: bool,
+        /// Compiled program size limit in bytes (default: 10485760)
+        #[arg(long, value_name = "BYTES")]
+        size_limit: Option<usize>,
+
+        /// Print a warning on stderr when the pattern silently falls back from the
+        /// linear-time regex engine to fancy-regex
+        #[arg(long)]
+        engine_fallback_warning: bool,
+
+        /// Report `effective_pattern`: the pattern exactly as compiled, after
+        /// --extended and --pattern-file
+        #[arg(long)]
+        show_flags_normalized: bool,
+    },
+
+    /// Show which target languages accept a pattern, and why the others reject it
+    CompareLanguages {
+        /// The regex pattern to compare
+        pattern: String,
     },
 
     /// Explain a regex pattern
     Explain {
         /// The regex pattern to explain
         pattern: String,
+
+        /// Annotate parts that won't port to this language (rust|python|javascript|go|java|pcre|dotnet|ruby|ere|bre)
+        #[arg(long, short = 't')]
+        target: Option<String>,
+
+        /// Draw the breakdown as a tree with ├─/└─ connectors (text output)
+        #[arg(long)]
+        tree: bool,
+
+        /// Only list the named capture groups and their indices
+        #[arg(long, conflicts_with_all = ["target", "tree"])]
+        names_only: bool,
+    },
+
+    /// Simplify a regex pattern into an equivalent shorter form
+    Simplify {
+        /// The regex pattern to simplify
+        pattern: String,
+    },
+
+    /// Rewrite a pattern into a canonical form, e.g. for deduplicating patterns
+    Normalize {
+        /// The regex pattern to normalize
+        pattern: String,
+    },
+
+    /// Group the patterns of a file that normalize to the same canonical form
+    DedupePatterns {
+        /// File with one pattern per line (blank lines are skipped)
+        file: PathBuf,
+    },
+
+    /// Lint a pattern for style issues such as unnecessary escapes
+    Lint {
+        /// The regex pattern to lint
+        pattern: String,
+    },
+
+    /// Build a pattern from a JSON syntax tree (see `core::build` for the schema)
+    Build {
+        /// JSON file describing the pattern's syntax tree
+        ast_file: PathBuf,
+    },
+
+    /// Compare the parse structure of two patterns (added/removed/changed nodes)
+    AstDiff {
+        /// The first (old) pattern
+        a: String,
+
+        /// The second (new) pattern
+        b: String,
+    },
+
+    /// Generate strings a pattern matches (and near misses it rejects) for tests
+    GenerateCorpus {
+        /// The regex pattern to generate strings for
+        pattern: String,
+
+        /// Number of strings to generate (of each kind)
+        #[arg(long, default_value = "10")]
+        count: usize,
+
+        /// Also generate non-matching strings one character away from a match
+        #[arg(long)]
+        include_near_misses: bool,
     },
 
     /// Infer regex pattern from examples
@@ -105,6 +563,14 @@ pub enum Commands {
         /// Strings that should NOT match
         #[arg(long, short = 'n', num_args = 1..)]
         negative: Option<Vec<String>>,
+
+        /// List the examples each candidate fails to match (always in JSON output)
+        #[arg(long)]
+        show_unmatched: bool,
+
+        /// Only suggest known formats (dates, UUIDs, emails, ...), skipping heuristic guesses
+        #[arg(long)]
+        format_only: bool,
     },
 
     /// Apply regex replacement to a file (with backup)
@@ -112,12 +578,25 @@ pub enum Commands {
         /// The regex pattern
         pattern: String,
 
-        /// The replacement string (supports $1, $2, etc.)
+        /// The replacement string (supports $1, ${name}, \1 and \g<name>)
         replacement: String,
 
         /// File to apply replacements to
-        #[arg(long, short = 'F', required = true)]
-        file: PathBuf,
+        #[arg(
+            long,
+            short = 'F',
+            required_unless_present = "stdin_list",
+            conflicts_with = "stdin_list"
+        )]
+        file: Option<PathBuf>,
+
+        /// Read the files to apply to from stdin, one path per line (e.g. from fd or find)
+        #[arg(long, conflicts_with = "interactive")]
+        stdin_list: bool,
+
+        /// With --stdin-list, paths are NUL-separated (pairs with find -print0)
+        #[arg(long, short = 'z', requires = "stdin_list")]
+        null_data: bool,
 
         /// Dry-run mode (show what would change, don't write)
         #[arg(long)]
@@ -134,6 +613,44 @@ pub enum Commands {
         /// Enable multiline mode (dot matches newline, ^/$ match line boundaries)
         #[arg(long, short = 'm')]
         multiline: bool,
+
+        /// Insert the replacement verbatim ($1, ${name}, \1 and $$ are not expanded)
+        #[arg(long)]
+        literal_replacement: bool,
+
+        /// Run an empty pattern anyway (it matches at every position)
+        #[arg(long)]
+        allow_empty: bool,
+
+        /// Review each replacement and confirm it on stdin (y/n/a/q)
+        #[arg(long)]
+        interactive: bool,
+
+        /// Replace only the first N matches of each file and leave the rest
+        /// untouched (remaining_matches counts them)
+        #[arg(
+            long,
+            value_name = "N",
+            alias = "replace-count-limit",
+            conflicts_with = "interactive"
+        )]
+        count: Option<usize>,
+
+        /// Replace only on lines that this guard pattern also matches
+        #[arg(
+            long,
+            value_name = "GUARD",
+            conflicts_with_all = ["multiline", "interactive"]
+        )]
+        only_lines_matching: Option<String>,
+
+        /// Show the changes as a unified diff instead of the preview (implies --dry-run)
+        #[arg(long, conflicts_with = "interactive")]
+        diff: bool,
+
+        /// Color the --diff output: auto (terminal without NO_COLOR), always or never
+        #[arg(long, value_name = "WHEN", default_value = "auto", requires = "diff")]
+        color: ColorChoice,
     },
 
     /// Benchmark regex performance and detect ReDoS
@@ -149,6 +666,10 @@ pub enum Commands {
         #[arg(long, short = 'F')]
         file: Option<PathBuf>,
 
+        /// Read the input sample from a file; it is used like --input, not as a corpus
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["input", "file"])]
+        input_file: Option<PathBuf>,
+
         /// Timeout in milliseconds
         #[arg(long, default_value = "5000")]
         timeout_ms: u64,
@@ -156,50 +677,308 @@ pub enum Commands {
         /// Number of iterations
         #[arg(long, default_value = "100")]
         iterations: usize,
+
+        /// Run iterations until a time budget is spent instead of a fixed count (default 1000ms, capped at --timeout-ms)
+        #[arg(
+            long,
+            value_name = "MS",
+            num_args = 0..=1,
+            default_missing_value = "1000",
+            conflicts_with_all = ["iterations", "detect_only"]
+        )]
+        auto: Option<u64>,
+
+        /// Warmup iterations run before measuring (excluded from statistics)
+        #[arg(long, default_value = "3")]
+        warmup: usize,
+
+        /// Concatenate the input N times to build a larger corpus for throughput
+        #[arg(
+            long,
+            value_name = "N",
+            default_value = "1",
+            value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+        )]
+        repeat_input: usize,
+
+        /// Only run static ReDoS detection; skip timing and never execute the pattern
+        #[arg(long)]
+        detect_only: bool,
+
+        /// Also time each line of --file separately and report the slowest lines
+        #[arg(long, requires = "file")]
+        per_line: bool,
+
+        /// Number of slowest lines to report with --per-line
+        #[arg(long, default_value = "5", requires = "per_line")]
+        top_lines: usize,
+
+        /// Time the input stretched to each size in bytes (e.g. 10,100,1000) to show the growth curve
+        #[arg(
+            long,
+            value_name = "SIZES",
+            value_delimiter = ',',
+            conflicts_with = "detect_only"
+        )]
+        sweep: Vec<usize>,
+
+        /// Flag catastrophic backtracking when one iteration takes longer than this
+        #[arg(
+            long,
+            default_value = "100",
+            value_parser = clap::value_parser!(u64).range(1..),
+            conflicts_with = "detect_only"
+        )]
+        catastrophic_threshold_ms: u64,
+
+        /// Flag catastrophic backtracking when the timing stddev exceeds this multiple of the mean
+        #[arg(long, default_value = "2.0", conflicts_with = "detect_only")]
+        stddev_ratio: f64,
+
+        /// Benchmark N randomly chosen lines of --file instead of all of it
+        #[arg(
+            long,
+            value_name = "N",
+            requires = "file",
+            conflicts_with_all = ["per_line", "detect_only"]
+        )]
+        sample: Option<usize>,
+
+        /// Seed for choosing the --sample lines (same seed, same lines)
+        #[arg(long, default_value = "0", requires = "sample")]
+        seed: u64,
+
+        /// JSON file of extra {"fragment", "input"} pairs: a pattern containing a
+        /// fragment is benchmarked against its input (checked before the builtin list)
+        #[arg(
+            long,
+            value_name = "FILE",
+            conflicts_with_all = ["input", "file", "input_file", "detect_only"]
+        )]
+        redos_templates: Option<PathBuf>,
     },
 }
 
+impl Commands {
+    fn pattern_mut(&mut self) -> Option<&mut String> {
+        match self {
+            Commands::Test { pattern, .. }
+            | Commands::Watch { pattern, .. }
+            | Commands::Replace { pattern, .. }
+            | Commands::Validate { pattern, .. }
+            | Commands::CompareLanguages { pattern }
+            | Commands::Explain { pattern, .. }
+            | Commands::Simplify { pattern }
+            | Commands::Normalize { pattern }
+            | Commands::Lint { pattern }
+            | Commands::GenerateCorpus { pattern, .. }
+            | Commands::Apply { pattern, .. }
+            | Commands::Benchmark { pattern, .. } => Some(pattern),
+            Commands::TestMany { .. }
+            | Commands::DedupePatterns { .. }
+            | Commands::FromExamples { .. }
+            | Commands::Build { .. }
+            | Commands::AstDiff { .. } => None,
+        }
+    }
+
+    /// Apply `--pattern-file` and `--extended` to the pattern argument, or
+    /// build it from `test --patterns-from`
+    fn resolve_pattern(&mut self, pattern_file: bool, extended: bool) -> Result<(), String> {
+        if let Commands::Test {
+            pattern,
+            input,
+            file,
+            patterns_from: Some(patterns_from),
+            fixed_strings,
+            ..
+        } = self
+        {
+            // The first positional is the input when the patterns come from a file
+            if !pattern.is_empty() {
+                if input.is_some() || file.is_some() {
+                    return Err(format!(
+                        "Unexpected argument '{}': --patterns-from replaces PATTERN",
+                        pattern
+                    ));
+                }
+                *input = Some(std::mem::take(pattern));
+            }
+            let patterns = std::fs::read_to_string(&*patterns_from)
+                .map_err(|e| format!("Failed to read patterns file: {}", e))?;
+            *pattern = crate::core::alternation_from_lines(&patterns, *fixed_strings)?;
+            return Ok(());
+        }
+
+        let Some(pattern) = self.pattern_mut() else {
+            return Ok(());
+        };
+
+        if pattern_file {
+            let content = std::fs::read_to_string(&*pattern)
+                .map_err(|e| format!("Failed to read pattern file '{}': {}", pattern, e))?;
+            *pattern = content.trim_end_matches(['\n', '\r']).to_string();
+        }
+        if extended {
+            pattern.insert_str(0, "(?x)");
+        }
+        Ok(())
+    }
+}
+
 /// Parse CLI arguments
+///
+/// Usage errors exit with [`exit_code::ERROR`] rather than clap's default of 2,
+/// which is reserved for invalid patterns.
 pub fn parse() -> Cli {
-    Cli::parse()
+    let mut cli = Cli::try_parse().unwrap_or_else(|e| {
+        let _ = e.print();
+        let code = if e.use_stderr() {
+            exit_code::ERROR
+        } else {
+            exit_code::SUCCESS
+        };
+        std::process::exit(code.into())
+    });
+
+    cli.format = cli.format_arg.unwrap_or_else(format_from_env);
+
+    if let Some(command) = cli.command.as_mut() {
+        if let Err(e) = command.resolve_pattern(cli.pattern_file, cli.extended) {
+            let error = crate::output::ErrorResponse::new("COMMAND_ERROR", &e);
+            let error_json = serde_json::to_string(&error)
+                .unwrap_or_else(|_| format!(r#"{{"error":true,"message":"{}"}}"#, e));
+            eprintln!("{}", error_json);
+            std::process::exit(exit_code::ERROR.into());
+        }
+    }
+
+    cli
+}
+
+/// Default output format from `RE_X_FORMAT`, falling back to JSON (with a
+/// warning) when the value is not a known format.
+fn format_from_env() -> OutputFormat {
+    match std::env::var("RE_X_FORMAT") {
+        Ok(value) if !value.trim().is_empty() => OutputFormat::from_str(value.trim(), true)
+            .unwrap_or_else(|_| {
+                eprintln!(
+                    "re-x: ignoring unknown RE_X_FORMAT value '{}' (expected json, text or csv)",
+                    value
+                );
+                OutputFormat::Json
+            }),
+        _ => OutputFormat::Json,
+    }
+}
+
+/// Parse a `--delimiter` value: one ASCII character, with `tab` and `\t` for a tab
+fn parse_delimiter(value: &str) -> Result<u8, String> {
+    match value {
+        "tab" | "\\t" => Ok(b'\t'),
+        _ => match value.as_bytes() {
+            [byte] if byte.is_ascii() => Ok(*byte),
+            _ => Err(format!(
+                "expected a single ASCII character or 'tab', got '{}'",
+                value
+            )),
+        },
+    }
 }
 
 /// Handle the test command
+#[allow(clippy::too_many_arguments)]
 pub fn handle_test(
     pattern: &str,
     input: Option<&str>,
     file: Option<&PathBuf>,
-    max_matches: usize,
+    max_matches: Option<usize>,
     engine: Option<&str>,
     multiline: bool,
+    null_data: bool,
+    group: Option<&str>,
+    no_unicode: bool,
+    full_match: bool,
+    by_capture: Option<&str>,
+    top: Option<usize>,
+    allow_empty: bool,
+    allow_empty_matches: bool,
+    sort: &str,
+    dedup: bool,
+    per_line_count: bool,
+    context_chars: Option<usize>,
+    highlight_captures: bool,
+    color: ColorChoice,
+    only_groups: bool,
+    first: bool,
+    timeout_per_line_ms: Option<u64>,
+    byte_mode: bool,
+    engine_fallback_warning: bool,
+    context_json: bool,
+    stream_json: bool,
+    coverage: bool,
+    sticky: bool,
+    vimgrep: bool,
+    show_flags_normalized: bool,
+    delimiter: u8,
     format: OutputFormat,
-) -> Result<String, String> {
-    use crate::core::{test_file, test_stdin, test_string, EngineType, TestOptions};
+) -> Result<CommandOutput, CoreError> {
+    use crate::core::{
+        dedup_matches, group_rows, sort_matches, stream_file_json, test_file, test_stdin,
+        test_string, validate_runnable_pattern, TestOptions,
+    };
+    use crate::output::csv::{format_group_rows_csv, format_test_csv};
     use crate::output::json::format_json;
-    use crate::output::text::format_test_result;
+    use crate::output::text::{format_group_rows, format_test_result, format_vimgrep};
     use std::io::IsTerminal;
 
-    let engine_type = match engine {
-        Some(e) => Some(match e {
-            "regex" => EngineType::Regex,
-            "fancy-regex" | "fancy" => EngineType::FancyRegex,
-            _ => {
-                return Err(format!(
-                    "Unknown engine '{}'. Valid options: regex, fancy-regex",
-                    e
-                ))
-            }
-        }),
-        None => None,
-    };
+    validate_runnable_pattern(pattern, allow_empty)?;
 
+    // A stream holds one match at a time, so it is only capped on request
+    let max_matches = if stream_json {
+        max_matches
+    } else {
+        Some(max_matches.unwrap_or(100))
+    };
     let options = TestOptions {
-        max_matches: Some(max_matches),
-        engine: engine_type,
+        max_matches,
+        engine: parse_engine(engine)?,
         multiline,
+        null_data,
+        group: group.map(String::from),
+        no_unicode,
+        full_match,
+        by_capture: by_capture.map(String::from),
+        top,
+        per_line_count,
+        context_chars,
+        allow_empty_matches,
+        first,
+        timeout_per_line_ms,
+        byte_mode,
+        context_json: context_json || vimgrep,
+        sticky,
+        effective_pattern: show_flags_normalized,
     };
 
-    let result = if let Some(file_path) = file {
+    if let (true, Some(file_path)) = (stream_json, file) {
+        let written = stream_file_json(pattern, file_path, &options, std::io::stdout().lock())?;
+        let code = if written > 0 {
+            exit_code::SUCCESS
+        } else {
+            exit_code::NO_MATCH
+        };
+        return Ok(CommandOutput {
+            exit_code: code,
+            ..CommandOutput::raw(String::new())
+        });
+    }
+
+    if coverage {
+        return coverage_output(pattern, input, file, &options, format);
+    }
+
+    let mut result = if let Some(file_path) = file {
         test_file(pattern, file_path, &options)?
     } else if let Some(text) = input {
         test_string(pattern, text, &options)?
@@ -214,14 +993,259 @@ pub fn handle_test(
         }
         test_stdin(pattern, &options)?
     };
+    if engine_fallback_warning && result.fallback_occurred {
+        warn_fallback(result.engine_reason.as_deref());
+    }
+    if dedup {
+        result.matches = dedup_matches(std::mem::take(&mut result.matches));
+    }
+    sort_matches(&mut result.matches, sort)?;
 
-    match format {
-        OutputFormat::Json => Ok(format_json(&result)),
-        OutputFormat::Text => Ok(format_test_result(&result)),
+    let code = if result.matched {
+        exit_code::SUCCESS
+    } else {
+        exit_code::NO_MATCH
+    };
+
+    if vimgrep {
+        let path = file.map_or("<stdin>".into(), |path| path.to_string_lossy());
+        return Ok(CommandOutput {
+            exit_code: code,
+            ..CommandOutput::raw(format_vimgrep(&result, &path))
+        });
+    }
+
+    let table = group_rows(pattern, &result.matches);
+    if only_groups && table.columns.is_empty() {
+        return Err("--only-groups needs a pattern with capture groups".into());
+    }
+
+    // CSV already ends each record with a newline
+    Ok(match format {
+        OutputFormat::Json if only_groups => CommandOutput::new(format_json(&table), code),
+        OutputFormat::Json => CommandOutput::new(format_json(&result), code),
+        OutputFormat::Text if only_groups => CommandOutput::new(format_group_rows(&table), code),
+        OutputFormat::Text => CommandOutput::new(
+            format_test_result(&result, highlight_captures && color.enabled()),
+            code,
+        ),
+        OutputFormat::Csv if only_groups => CommandOutput {
+            exit_code: code,
+            ..CommandOutput::raw(format_group_rows_csv(&table, delimiter))
+        },
+        OutputFormat::Csv => CommandOutput {
+            exit_code: code,
+            ..CommandOutput::raw(format_test_csv(&result, &table, delimiter))
+        },
+    })
+}
+
+/// `--engine-fallback-warning`: report on stderr that the pattern only
+/// compiled by falling back to fancy-regex
+fn warn_fallback(reason: Option<&str>) {
+    eprintln!(
+        "re-x: warning: {}",
+        reason.unwrap_or("Fell back to fancy-regex; matching is no longer guaranteed linear time")
+    );
+}
+
+/// Run `test --coverage` over the input, file or stdin. Exits 0 only if the
+/// whole pattern matches, like `test`.
+fn coverage_output(
+    pattern: &str,
+    input: Option<&str>,
+    file: Option<&PathBuf>,
+    options: &crate::core::TestOptions,
+    format: OutputFormat,
+) -> Result<CommandOutput, CoreError> {
+    use crate::core::pattern_coverage;
+    use crate::output::json::format_json;
+    use crate::output::text::format_coverage_result;
+
+    let text = match (file, input) {
+        (Some(file_path), _) => {
+            std::fs::read_to_string(file_path).map_err(|e| format!("Failed to read file: {}", e))?
+        }
+        (None, Some(text)) => text.to_string(),
+        (None, None) => std::io::read_to_string(std::io::stdin())
+            .map_err(|e| format!("Failed to read stdin: {}", e))?,
+    };
+
+    let result = pattern_coverage(pattern, &text, options)?;
+    let code = if result.matched {
+        exit_code::SUCCESS
+    } else {
+        exit_code::NO_MATCH
+    };
+    let output = match format {
+        OutputFormat::Json | OutputFormat::Csv => format_json(&result),
+        OutputFormat::Text => format_coverage_result(&result),
+    };
+    Ok(CommandOutput::new(output, code))
+}
+
+/// Handle the test-many command: every pattern in `patterns_file` against one input.
+///
+/// Exits like `test`: 0 if any pattern matched, 1 if none did. Patterns
+/// that fail are reported in their own entries and do not change the code.
+pub fn handle_test_many(
+    patterns_file: &Path,
+    input: Option<&str>,
+    file: Option<&PathBuf>,
+    multiline: bool,
+    allow_empty: bool,
+    format: OutputFormat,
+) -> Result<CommandOutput, CoreError> {
+    use crate::core::{test_many, TestOptions};
+    use crate::output::json::format_json;
+    use crate::output::text::format_test_many_result;
+    use std::io::{IsTerminal, Read};
+
+    let patterns = std::fs::read_to_string(patterns_file)
+        .map_err(|e| format!("Failed to read patterns file: {}", e))?;
+
+    let input = if let Some(file_path) = file {
+        std::fs::read_to_string(file_path).map_err(|e| format!("Failed to read file: {}", e))?
+    } else if let Some(text) = input {
+        text.to_string()
+    } else {
+        if std::io::stdin().is_terminal() {
+            eprintln!("re-x: reading from stdin (pipe data or press Ctrl-D when done)");
+        }
+        let mut text = String::new();
+        std::io::stdin()
+            .read_to_string(&mut text)
+            .map_err(|e| format!("Failed to read stdin: {}", e))?;
+        text
+    };
+
+    let options = TestOptions {
+        multiline,
+        ..Default::default()
+    };
+    let result = test_many(&patterns, &input, allow_empty, &options);
+
+    let code = if result.matched_patterns > 0 {
+        exit_code::SUCCESS
+    } else {
+        exit_code::NO_MATCH
+    };
+
+    let output = match format {
+        OutputFormat::Json | OutputFormat::Csv => format_json(&result),
+        OutputFormat::Text => format_test_many_result(&result),
+    };
+    Ok(CommandOutput::new(output, code))
+}
+
+/// Resolve an `--engine` value
+fn parse_engine(engine: Option<&str>) -> Result<Option<crate::core::EngineType>, String> {
+    use crate::core::EngineType;
+
+    engine
+        .map(|e| match e {
+            "regex" => Ok(EngineType::Regex),
+            "fancy-regex" | "fancy" => Ok(EngineType::FancyRegex),
+            _ => Err(format!(
+                "Unknown engine '{}'. Valid options: regex, fancy-regex",
+                e
+            )),
+        })
+        .transpose()
+}
+
+/// Handle the watch command: print a test result now and again after every change.
+///
+/// Runs until interrupted, or for `max_runs` runs. JSON output is one
+/// compact result per line so a consumer can read it as a stream.
+#[allow(clippy::too_many_arguments)]
+pub fn handle_watch(
+    pattern: &str,
+    file: &Path,
+    max_matches: usize,
+    engine: Option<&str>,
+    multiline: bool,
+    debounce_ms: u64,
+    max_runs: Option<usize>,
+    format: OutputFormat,
+) -> Result<CommandOutput, CoreError> {
+    use crate::core::{test_file, validate_runnable_pattern, TestOptions};
+    use crate::output::json::format_json_compact;
+    use crate::output::text::format_test_result;
+    use notify_debouncer_mini::{new_debouncer, notify::RecursiveMode};
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    validate_runnable_pattern(pattern, false)?;
+
+    let options = TestOptions {
+        max_matches: Some(max_matches),
+        engine: parse_engine(engine)?,
+        multiline,
+        ..Default::default()
+    };
+
+    let path = file
+        .canonicalize()
+        .map_err(|e| format!("Failed to open file: {}", e))?;
+    // Watch the directory rather than the file: editors often save by
+    // replacing the file, which would end a watch on the old one
+    let dir = path
+        .parent()
+        .ok_or_else(|| format!("Cannot watch '{}'", file.display()))?;
+
+    let (tx, rx) = mpsc::channel();
+    let mut debouncer = new_debouncer(Duration::from_millis(debounce_ms), tx)
+        .map_err(|e| format!("Failed to start file watcher: {}", e))?;
+    debouncer
+        .watcher()
+        .watch(dir, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch '{}': {}", file.display(), e))?;
+
+    // Reading the file raises access events too, so only a new modification
+    // time or length counts as a change
+    let stamp = || {
+        std::fs::metadata(&path)
+            .ok()
+            .map(|m| (m.modified().ok(), m.len()))
+    };
+    let mut last = stamp();
+
+    // A failed run (file deleted mid-save, say) is reported and watching goes on
+    let run = || match test_file(pattern, &path, &options) {
+        Ok(result) => match format {
+            OutputFormat::Json | OutputFormat::Csv => println!("{}", format_json_compact(&result)),
+            OutputFormat::Text => println!("{}", format_test_result(&result, false)),
+        },
+        Err(e) => eprintln!("re-x: {}", e),
+    };
+
+    run();
+    let mut runs = 1;
+    while max_runs.is_none_or(|max| runs < max) {
+        let Ok(events) = rx.recv() else {
+            break;
+        };
+        let events = events.map_err(|e| format!("File watcher failed: {}", e))?;
+        if !events.iter().any(|event| event.path == path) {
+            continue;
+        }
+        let current = stamp();
+        if current != last {
+            last = current;
+            if format == OutputFormat::Text {
+                println!("--- {} changed ---", file.display());
+            }
+            run();
+            runs += 1;
+        }
     }
+
+    Ok(CommandOutput::raw(String::new()))
 }
 
 /// Handle the replace command
+#[allow(clippy::too_many_arguments)]
 pub fn handle_replace(
     pattern: &str,
     replacement: &str,
@@ -229,9 +1253,97 @@ pub fn handle_replace(
     file: Option<&PathBuf>,
     max_preview: usize,
     multiline: bool,
+    max_result_bytes: Option<usize>,
+    literal_replacement: bool,
+    allow_empty: bool,
+    raw: bool,
+    count: Option<usize>,
     format: OutputFormat,
-) -> Result<String, String> {
-    use crate::core::{replace_file_preview, replace_with_captures};
+) -> Result<CommandOutput, CoreError> {
+    use crate::core::{replace_file_content, replace_with_captures, validate_runnable_pattern};
+
+    validate_runnable_pattern(pattern, allow_empty)?;
+
+    if !raw {
+        return format_replace(
+            pattern,
+            replacement,
+            input,
+            file,
+            max_preview,
+            multiline,
+            max_result_bytes,
+            literal_replacement,
+            count,
+            format,
+        )
+        .map(Into::into);
+    }
+
+    let output = if let Some(file_path) = file {
+        replace_file_content(
+            pattern,
+            replacement,
+            file_path,
+            multiline,
+            literal_replacement,
+            count,
+        )?
+    } else {
+        let stdin;
+        let text = match input {
+            Some(text) => text,
+            None => {
+                stdin = read_replace_stdin(pattern, replacement)?;
+                &stdin
+            }
+        };
+        replace_with_captures(
+            pattern,
+            replacement,
+            text,
+            multiline,
+            literal_replacement,
+            count,
+        )?
+        .result
+    };
+    Ok(CommandOutput::raw(output))
+}
+
+/// Read the replace command's input from stdin, with a hint on a terminal
+fn read_replace_stdin(pattern: &str, replacement: &str) -> Result<String, String> {
+    use std::io::{self, IsTerminal, Read};
+
+    if io::stdin().is_terminal() {
+        eprintln!("re-x: reading from stdin (pipe data or press Ctrl-D when done)");
+        eprintln!(
+            "  hint: re-x replace '{}' '{}' \"text\" — or — cat file | re-x replace '{}' '{}'",
+            pattern, replacement, pattern, replacement
+        );
+    }
+    let mut input = String::new();
+    io::stdin()
+        .read_to_string(&mut input)
+        .map_err(|e| format!("Failed to read stdin: {}", e))?;
+    Ok(input)
+}
+
+/// Run the replace command and render the result envelope
+#[allow(clippy::too_many_arguments)]
+fn format_replace(
+    pattern: &str,
+    replacement: &str,
+    input: Option<&str>,
+    file: Option<&PathBuf>,
+    max_preview: usize,
+    multiline: bool,
+    max_result_bytes: Option<usize>,
+    literal_replacement: bool,
+    count: Option<usize>,
+    format: OutputFormat,
+) -> Result<String, CoreError> {
+    use crate::core::{replace_file_preview, replace_with_captures, truncate_result};
     use crate::output::json::format_json;
     use crate::output::text::format_replace_result;
 
@@ -242,9 +1354,11 @@ pub fn handle_replace(
             file_path,
             Some(max_preview),
             multiline,
+            literal_replacement,
+            count,
         )?;
         match format {
-            OutputFormat::Json => Ok(format_json(&result)),
+            OutputFormat::Json | OutputFormat::Csv => Ok(format_json(&result)),
             OutputFormat::Text => {
                 // Simple text format for file preview
                 let mut output = format!(
@@ -252,9 +1366,13 @@ pub fn handle_replace(
                     result.pattern, result.replacement
                 );
                 output.push_str(&format!(
-                    "Total replacements: {}\n\nPreview:\n",
+                    "Total replacements: {}\n",
                     result.replacements_made
                 ));
+                if let Some(remaining) = result.remaining_matches {
+                    output.push_str(&format!("Left untouched: {}\n", remaining));
+                }
+                output.push_str("\nPreview:\n");
                 for preview in &result.preview {
                     output.push_str(&format!(
                         "Line {}: {} → {}\n",
@@ -265,84 +1383,285 @@ pub fn handle_replace(
             }
         }
     } else if let Some(text) = input {
-        let result = replace_with_captures(pattern, replacement, text, multiline)?;
+        let mut result = replace_with_captures(
+            pattern,
+            replacement,
+            text,
+            multiline,
+            literal_replacement,
+            count,
+        )?;
+        if let Some(max_bytes) = max_result_bytes {
+            truncate_result(&mut result, max_bytes);
+        }
         match format {
-            OutputFormat::Json => Ok(format_json(&result)),
+            OutputFormat::Json | OutputFormat::Csv => Ok(format_json(&result)),
             OutputFormat::Text => Ok(format_replace_result(&result)),
         }
     } else {
-        use std::io::{self, IsTerminal, Read};
         // Read from stdin like test does
-        if io::stdin().is_terminal() {
-            eprintln!("re-x: reading from stdin (pipe data or press Ctrl-D when done)");
-            eprintln!(
-                "  hint: re-x replace '{}' '{}' \"text\" — or — cat file | re-x replace '{}' '{}'",
-                pattern, replacement, pattern, replacement
-            );
+        let input = read_replace_stdin(pattern, replacement)?;
+        let mut result = replace_with_captures(
+            pattern,
+            replacement,
+            &input,
+            multiline,
+            literal_replacement,
+            count,
+        )?;
+        if let Some(max_bytes) = max_result_bytes {
+            truncate_result(&mut result, max_bytes);
         }
-        let mut input = String::new();
-        io::stdin()
-            .read_to_string(&mut input)
-            .map_err(|e| format!("Failed to read stdin: {}", e))?;
-        let result = replace_with_captures(pattern, replacement, &input, multiline)?;
         match format {
-            OutputFormat::Json => Ok(format_json(&result)),
+            OutputFormat::Json | OutputFormat::Csv => Ok(format_json(&result)),
             OutputFormat::Text => Ok(format_replace_result(&result)),
         }
     }
 }
 
 /// Handle the validate command
+#[allow(clippy::too_many_arguments)]
 pub fn handle_validate(
     pattern: &str,
     target_lang: Option<&str>,
+    all_targets: bool,
+    flags: bool,
+    verbose_portability: bool,
+    size_limit: Option<usize>,
+    engine_fallback_warning: bool,
+    show_flags_normalized: bool,
+    max_depth: u32,
     format: OutputFormat,
-) -> Result<String, String> {
-    use crate::core::{validate_for_language, validate_pattern};
+) -> Result<CommandOutput, CoreError> {
+    use crate::core::{
+        detail_portability, validate_all_targets, validate_for_language, validate_pattern,
+        ValidateOptions,
+    };
     use crate::output::json::format_json;
     use crate::output::text::format_validate_result;
 
-    let result = if let Some(lang) = target_lang {
-        validate_for_language(pattern, lang)
-    } else {
-        validate_pattern(pattern)
+    let options = ValidateOptions {
+        max_depth,
+        flags,
+        size_limit,
+        effective_pattern: show_flags_normalized,
     };
 
-    match format {
-        OutputFormat::Json => Ok(format_json(&result)),
-        OutputFormat::Text => Ok(format_validate_result(&result)),
+    let mut result = if all_targets {
+        validate_all_targets(pattern, &options)
+    } else if let Some(lang) = target_lang {
+        validate_for_language(pattern, lang, &options)
+    } else {
+        validate_pattern(pattern, &options)
+    };
+    if verbose_portability {
+        detail_portability(&mut result, pattern);
     }
+    if engine_fallback_warning && result.fallback_occurred {
+        warn_fallback(result.reason.as_deref());
+    }
+
+    let code = if result.valid && result.error.is_some() {
+        // Valid pattern, but not supported by the requested target language
+        exit_code::INCOMPATIBLE
+    } else if result.valid {
+        exit_code::SUCCESS
+    } else {
+        exit_code::INVALID_PATTERN
+    };
+
+    let output = match format {
+        OutputFormat::Json | OutputFormat::Csv => format_json(&result),
+        OutputFormat::Text => format_validate_result(&result),
+    };
+    Ok(CommandOutput::new(output, code))
 }
 
 /// Handle the explain command
-pub fn handle_explain(pattern: &str, format: OutputFormat) -> Result<String, String> {
-    use crate::core::explain_pattern;
+pub fn handle_explain(
+    pattern: &str,
+    target: Option<&str>,
+    tree: bool,
+    names_only: bool,
+    max_depth: u32,
+    format: OutputFormat,
+) -> Result<String, CoreError> {
+    use crate::core::{explain_group_names, explain_pattern, ExplainOptions};
     use crate::output::json::format_json;
-    use crate::output::text::format_explain_result;
+    use crate::output::text::{format_explain_result, format_explain_tree, format_group_names};
+
+    let options = ExplainOptions {
+        max_depth,
+        target: target.map(String::from),
+    };
+
+    if names_only {
+        let result = explain_group_names(pattern, &options)?;
+        return match format {
+            OutputFormat::Json | OutputFormat::Csv => Ok(format_json(&result)),
+            OutputFormat::Text => Ok(format_group_names(&result)),
+        };
+    }
 
-    let result = explain_pattern(pattern)?;
+    let result = explain_pattern(pattern, &options)?;
 
     match format {
-        OutputFormat::Json => Ok(format_json(&result)),
+        OutputFormat::Json | OutputFormat::Csv => Ok(format_json(&result)),
+        OutputFormat::Text if tree => Ok(format_explain_tree(&result)),
         OutputFormat::Text => Ok(format_explain_result(&result)),
     }
 }
 
+/// Handle the simplify command
+pub fn handle_simplify(pattern: &str, format: OutputFormat) -> Result<String, CoreError> {
+    use crate::core::simplify;
+    use crate::output::json::format_json;
+    use crate::output::text::format_simplify_result;
+
+    let result = simplify(pattern)?;
+
+    match format {
+        OutputFormat::Json | OutputFormat::Csv => Ok(format_json(&result)),
+        OutputFormat::Text => Ok(format_simplify_result(&result)),
+    }
+}
+
+/// Handle the normalize command
+pub fn handle_normalize(pattern: &str, format: OutputFormat) -> Result<String, CoreError> {
+    use crate::core::canonicalize;
+    use crate::output::json::format_json;
+    use crate::output::text::format_normalize_result;
+
+    let result = canonicalize(pattern)?;
+
+    match format {
+        OutputFormat::Json | OutputFormat::Csv => Ok(format_json(&result)),
+        OutputFormat::Text => Ok(format_normalize_result(&result)),
+    }
+}
+
+/// Handle the dedupe-patterns command
+pub fn handle_dedupe_patterns(file: &Path, format: OutputFormat) -> Result<String, CoreError> {
+    use crate::core::dedupe_patterns;
+    use crate::output::json::format_json;
+    use crate::output::text::format_dedupe_patterns_result;
+
+    let patterns = std::fs::read_to_string(file)
+        .map_err(|e| format!("Failed to read patterns file: {}", e))?;
+    let result = dedupe_patterns(&patterns);
+
+    match format {
+        OutputFormat::Json | OutputFormat::Csv => Ok(format_json(&result)),
+        OutputFormat::Text => Ok(format_dedupe_patterns_result(&result)),
+    }
+}
+
+/// Handle the lint command
+pub fn handle_lint(pattern: &str, format: OutputFormat) -> Result<String, CoreError> {
+    use crate::core::lint_pattern;
+    use crate::output::json::format_json;
+    use crate::output::text::format_lint_result;
+
+    let result = lint_pattern(pattern)?;
+
+    match format {
+        OutputFormat::Json | OutputFormat::Csv => Ok(format_json(&result)),
+        OutputFormat::Text => Ok(format_lint_result(&result)),
+    }
+}
+
+/// Handle the compare-languages command
+pub fn handle_compare_languages(
+    pattern: &str,
+    max_depth: u32,
+    format: OutputFormat,
+) -> Result<String, CoreError> {
+    use crate::core::{compare_languages, ValidateOptions};
+    use crate::output::json::format_json;
+    use crate::output::text::format_compare_languages_result;
+
+    let options = ValidateOptions {
+        max_depth,
+        ..Default::default()
+    };
+    let result = compare_languages(pattern, &options)?;
+
+    match format {
+        OutputFormat::Json | OutputFormat::Csv => Ok(format_json(&result)),
+        OutputFormat::Text => Ok(format_compare_languages_result(&result)),
+    }
+}
+
+/// Handle the build command
+pub fn handle_build(ast_file: &Path, format: OutputFormat) -> Result<String, CoreError> {
+    use crate::core::build_pattern;
+    use crate::output::json::format_json;
+    use crate::output::text::format_build_result;
+
+    let json =
+        std::fs::read_to_string(ast_file).map_err(|e| format!("Failed to read AST file: {}", e))?;
+    let result = build_pattern(&json)?;
+
+    match format {
+        OutputFormat::Json | OutputFormat::Csv => Ok(format_json(&result)),
+        OutputFormat::Text => Ok(format_build_result(&result)),
+    }
+}
+
+/// Handle the ast-diff command
+pub fn handle_ast_diff(a: &str, b: &str, format: OutputFormat) -> Result<String, CoreError> {
+    use crate::core::ast_diff;
+    use crate::output::json::format_json;
+    use crate::output::text::format_ast_diff_result;
+
+    let result = ast_diff(a, b)?;
+
+    match format {
+        OutputFormat::Json | OutputFormat::Csv => Ok(format_json(&result)),
+        OutputFormat::Text => Ok(format_ast_diff_result(&result)),
+    }
+}
+
+/// Handle the generate-corpus command
+pub fn handle_generate_corpus(
+    pattern: &str,
+    count: usize,
+    include_near_misses: bool,
+    format: OutputFormat,
+) -> Result<String, CoreError> {
+    use crate::core::{generate_corpus, CorpusOptions};
+    use crate::output::json::format_json;
+    use crate::output::text::format_corpus_result;
+
+    let options = CorpusOptions {
+        count,
+        include_near_misses,
+    };
+    let result = generate_corpus(pattern, &options)?;
+
+    match format {
+        OutputFormat::Json | OutputFormat::Csv => Ok(format_json(&result)),
+        OutputFormat::Text => Ok(format_corpus_result(&result)),
+    }
+}
+
 /// Handle the from-examples command
 pub fn handle_from_examples(
     examples: &[String],
     negative: Option<&[String]>,
+    show_unmatched: bool,
+    format_only: bool,
     format: OutputFormat,
-) -> Result<String, String> {
+) -> Result<String, CoreError> {
     use crate::core::infer_patterns;
     use crate::output::json::format_json;
     use crate::output::text::format_from_examples_result;
 
-    let result = infer_patterns(examples, negative)?;
+    let result = infer_patterns(examples, negative, format_only)?;
 
     match format {
-        OutputFormat::Json => Ok(format_json(&result)),
-        OutputFormat::Text => Ok(format_from_examples_result(&result)),
+        OutputFormat::Json | OutputFormat::Csv => Ok(format_json(&result)),
+        OutputFormat::Text => Ok(format_from_examples_result(&result, show_unmatched)),
     }
 }
 
@@ -351,65 +1670,276 @@ pub fn handle_from_examples(
 pub fn handle_apply(
     pattern: &str,
     replacement: &str,
-    file: &std::path::Path,
+    file: Option<&std::path::Path>,
+    stdin_list: bool,
+    null_data: bool,
     dry_run: bool,
     no_backup: bool,
     max_preview: usize,
     multiline: bool,
+    literal_replacement: bool,
+    allow_empty: bool,
+    interactive: bool,
+    count: Option<usize>,
+    only_lines_matching: Option<&str>,
+    diff: bool,
+    color: ColorChoice,
     format: OutputFormat,
-) -> Result<String, String> {
-    use crate::core::apply_file;
+) -> Result<CommandOutput, CoreError> {
+    use crate::core::{
+        apply_file, apply_file_selective, apply_files, apply_hunks, validate_runnable_pattern,
+    };
     use crate::output::json::format_json;
-    use crate::output::text::format_apply_result;
-
-    let result = apply_file(
-        pattern,
-        replacement,
-        file,
-        dry_run,
-        !no_backup,
-        Some(max_preview),
-        multiline,
-    )?;
+    use crate::output::text::{format_apply_result, format_apply_results};
 
-    match format {
-        OutputFormat::Json => Ok(format_json(&result)),
-        OutputFormat::Text => Ok(format_apply_result(&result)),
+    validate_runnable_pattern(pattern, allow_empty)?;
+    let dry_run = dry_run || diff;
+    let color = color.enabled();
+
+    if stdin_list {
+        let stdin = std::io::stdin();
+        let delimiter = if null_data { b'\0' } else { b'\n' };
+        let paths = read_path_list(stdin.lock(), delimiter)
+            .map_err(|e| format!("Failed to read file list from stdin: {}", e))?;
+        let results = apply_files(
+            pattern,
+            replacement,
+            &paths,
+            dry_run,
+            !no_backup,
+            Some(max_preview),
+            multiline,
+            literal_replacement,
+            count,
+            only_lines_matching,
+            diff,
+        )?;
+
+        let code = if results.iter().any(|r| r.error.is_some()) {
+            exit_code::ERROR
+        } else {
+            exit_code::SUCCESS
+        };
+        let output = match format {
+            OutputFormat::Json | OutputFormat::Csv => format_json(&results),
+            OutputFormat::Text => format_apply_results(&results, color),
+        };
+        return Ok(CommandOutput::new(output, code));
+    }
+    let file = file.ok_or("--file or --stdin-list is required")?;
+
+    let result = if interactive {
+        let file_hunks = apply_hunks(pattern, replacement, file, multiline, literal_replacement)?;
+        let stdin = std::io::stdin();
+        let approved = confirm_hunks(&file_hunks.hunks, file, stdin.lock(), std::io::stderr())
+            .map_err(|e| format!("Failed to read confirmation: {}", e))?;
+        apply_file_selective(
+            pattern,
+            replacement,
+            file,
+            file_hunks,
+            dry_run,
+            !no_backup,
+            Some(max_preview),
+            &approved,
+        )?
+    } else {
+        apply_file(
+            pattern,
+            replacement,
+            file,
+            dry_run,
+            !no_backup,
+            Some(max_preview),
+            multiline,
+            literal_replacement,
+            count,
+            only_lines_matching,
+            diff,
+        )?
+    };
+
+    let output = match format {
+        OutputFormat::Json | OutputFormat::Csv => format_json(&result),
+        OutputFormat::Text => format_apply_result(&result, color),
+    };
+    Ok(output.into())
+}
+
+/// Read a list of paths separated by `delimiter`, skipping empty entries
+fn read_path_list(
+    mut reader: impl std::io::BufRead,
+    delimiter: u8,
+) -> std::io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    let mut entry = Vec::new();
+
+    while reader.read_until(delimiter, &mut entry)? > 0 {
+        let path = entry.strip_suffix(&[delimiter]).unwrap_or(&entry);
+        let path = if delimiter == b'\n' {
+            path.strip_suffix(b"\r").unwrap_or(path)
+        } else {
+            path
+        };
+        if !path.is_empty() {
+            paths.push(path_from_bytes(path));
+        }
+        entry.clear();
+    }
+
+    Ok(paths)
+}
+
+/// Build a path from raw bytes, keeping names that are not valid UTF-8
+#[cfg(unix)]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+}
+
+/// Build a path from raw bytes; paths are not byte strings off unix, so
+/// invalid UTF-8 is replaced
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+}
+
+/// Show each hunk on `prompt` and read a y/n/a/q answer from `answers`,
+/// returning the indices of the approved hunks. End of input counts as `q`.
+fn confirm_hunks(
+    hunks: &[crate::core::ApplyHunk],
+    file: &std::path::Path,
+    mut answers: impl std::io::BufRead,
+    mut prompt: impl std::io::Write,
+) -> std::io::Result<Vec<usize>> {
+    let mut approved = Vec::new();
+
+    for (index, hunk) in hunks.iter().enumerate() {
+        writeln!(
+            prompt,
+            "[{}/{}] {}:{}",
+            index + 1,
+            hunks.len(),
+            file.display(),
+            hunk.line
+        )?;
+        writeln!(prompt, "- {}", hunk.before)?;
+        writeln!(prompt, "+ {}", hunk.after)?;
+
+        loop {
+            write!(prompt, "Apply this replacement [y,n,a,q]? ")?;
+            prompt.flush()?;
+
+            let mut answer = String::new();
+            if answers.read_line(&mut answer)? == 0 {
+                return Ok(approved);
+            }
+            match answer.trim() {
+                "y" => approved.push(index),
+                "n" => {}
+                "a" => {
+                    approved.extend(index..hunks.len());
+                    return Ok(approved);
+                }
+                "q" => return Ok(approved),
+                _ => {
+                    writeln!(
+                        prompt,
+                        "y - apply, n - skip, a - apply this and all remaining, q - skip this and all remaining"
+                    )?;
+                    continue;
+                }
+            }
+            break;
+        }
     }
+
+    Ok(approved)
 }
 
 /// Handle the benchmark command
+#[allow(clippy::too_many_arguments)]
 pub fn handle_benchmark(
     pattern: &str,
     input: Option<&str>,
     file: Option<&PathBuf>,
+    input_file: Option<&Path>,
     timeout_ms: u64,
     iterations: usize,
+    auto: Option<u64>,
+    warmup: usize,
+    repeat_input: usize,
+    detect_only: bool,
+    per_line: bool,
+    top_lines: usize,
+    sweep: Vec<usize>,
+    catastrophic_threshold_ms: u64,
+    stddev_ratio: f64,
+    sample: Option<usize>,
+    seed: u64,
+    redos_templates: Option<&Path>,
     format: OutputFormat,
-) -> Result<String, String> {
+) -> Result<CommandOutput, CoreError> {
     use crate::core::{
-        benchmark::generate_redos_input, benchmark_file, benchmark_pattern, BenchmarkOptions,
+        benchmark::{self, generate_redos_input, parse_redos_templates},
+        benchmark_file, benchmark_pattern, BenchmarkOptions,
     };
     use crate::output::json::format_json;
     use crate::output::text::format_benchmark_result;
 
     let options = BenchmarkOptions {
         iterations,
+        auto_ms: auto,
         timeout_ms,
+        warmup,
+        repeat_input,
+        per_line,
+        top_lines,
+        sweep,
+        catastrophic_threshold_ms,
+        stddev_ratio,
+        sample,
+        seed,
+        deadline: None,
     };
 
-    let result = if let Some(file_path) = file {
+    let sample = match input_file {
+        Some(path) => Some(
+            std::fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read input file: {}", e))?,
+        ),
+        None => None,
+    };
+    let input = sample.as_deref().or(input);
+
+    let result = if detect_only {
+        benchmark::detect_only(pattern)?
+    } else if let Some(file_path) = file {
         benchmark_file(pattern, file_path, &options)?
     } else if let Some(text) = input {
         benchmark_pattern(pattern, text, &options)?
     } else {
         // Generate adversarial input for ReDoS testing
-        let evil_input = generate_redos_input(pattern);
+        let templates = match redos_templates {
+            Some(path) => parse_redos_templates(
+                &std::fs::read_to_string(path)
+                    .map_err(|e| format!("Failed to read ReDoS templates file: {}", e))?,
+            )?,
+            None => Vec::new(),
+        };
+        let evil_input = generate_redos_input(pattern, &templates);
         benchmark_pattern(pattern, &evil_input, &options)?
     };
 
-    match format {
-        OutputFormat::Json => Ok(format_json(&result)),
-        OutputFormat::Text => Ok(format_benchmark_result(&result)),
-    }
+    let code = if result.catastrophic_backtracking {
+        exit_code::REDOS_DETECTED
+    } else {
+        exit_code::SUCCESS
+    };
+
+    let output = match format {
+        OutputFormat::Json | OutputFormat::Csv => format_json(&result),
+        OutputFormat::Text => format_benchmark_result(&result),
+    };
+    Ok(CommandOutput::new(output, code))
 }
@@ -0,0 +1,286 @@
+//! Implementation of `re-x ast-diff` command
+//!
+//! Compares how two patterns parse rather than what they match: both are
+//! parsed with `regex_syntax`, concatenations are flattened into sequences,
+//! sequences are aligned by their longest common subsequence, and nodes left
+//! unaligned are paired up by kind and compared recursively.
+
+use regex_syntax::ast::{self, Ast, GroupKind};
+
+use super::engine::{CompiledRegex, CoreError};
+use crate::output::{AstChange, AstDiffResult, Span};
+
+/// Structurally diff two patterns
+pub fn ast_diff(a: &str, b: &str) -> Result<AstDiffResult, CoreError> {
+    let tree_a = parse(a, "A")?;
+    let tree_b = parse(b, "B")?;
+
+    let mut changes = Vec::new();
+    diff_sequences(a, &tree_a, b, &tree_b, &mut changes);
+
+    Ok(AstDiffResult {
+        a: a.to_string(),
+        b: b.to_string(),
+        identical: changes.is_empty(),
+        changes,
+    })
+}
+
+/// A node of the simplified tree: concatenations are flattened away, so every
+/// list of children is a sequence
+struct Node {
+    kind: &'static str,
+    label: String,
+    span: Span,
+    children: Vec<Node>,
+}
+
+fn parse(pattern: &str, which: &str) -> Result<Vec<Node>, CoreError> {
+    CompiledRegex::new(pattern)
+        .map_err(|e| CoreError::invalid_pattern(format!("pattern {}: {}", which, e)))?;
+    // The pattern compiles, so a parse failure here means it needs fancy-regex
+    let parsed = ast::parse::Parser::new().parse(pattern).map_err(|_| {
+        format!(
+            "pattern {} could not be parsed (lookaround and backreferences are not supported)",
+            which
+        )
+    })?;
+    Ok(sequence(pattern, &parsed))
+}
+
+/// The nodes of `ast` as a sequence (a concatenation yields its items)
+fn sequence(pattern: &str, ast: &Ast) -> Vec<Node> {
+    match ast {
+        Ast::Concat(concat) => concat.asts.iter().map(|a| node(pattern, a)).collect(),
+        Ast::Empty(_) => Vec::new(),
+        other => vec![node(pattern, other)],
+    }
+}
+
+fn node(pattern: &str, ast: &Ast) -> Node {
+    let span = to_span(ast.span());
+    let text = &pattern[span.start..span.end];
+
+    let (kind, label, children) = match ast {
+        Ast::Literal(lit) => ("literal", format!("{:?}", lit.c), Vec::new()),
+        Ast::Dot(_) => ("dot", "any character".to_string(), Vec::new()),
+        Ast::Assertion(_) => ("assertion", text.to_string(), Vec::new()),
+        Ast::ClassUnicode(_) | Ast::ClassPerl(_) | Ast::ClassBracketed(_) => {
+            ("class", text.to_string(), Vec::new())
+        }
+        Ast::Flags(_) => ("flags", text.to_string(), Vec::new()),
+        Ast::Repetition(rep) => {
+            let op = to_span(&rep.op.span);
+            (
+                "quantifier",
+                pattern[op.start..op.end].to_string(),
+                sequence(pattern, &rep.ast),
+            )
+        }
+        Ast::Group(group) => {
+            let label = match &group.kind {
+                GroupKind::CaptureIndex(index) => format!("capture group #{}", index),
+                GroupKind::CaptureName { name, .. } => {
+                    format!("named group '{}' (#{})", name.name, name.index)
+                }
+                GroupKind::NonCapturing(flags) if flags.items.is_empty() => {
+                    "non-capturing group".to_string()
+                }
+                GroupKind::NonCapturing(_) => {
+                    let open_end = group.ast.span().start.offset;
+                    format!("group with flags {}", &pattern[span.start..open_end])
+                }
+            };
+            ("group", label, sequence(pattern, &group.ast))
+        }
+        Ast::Alternation(alt) => (
+            "alternation",
+            "alternation".to_string(),
+            alt.asts
+                .iter()
+                .map(|branch| Node {
+                    kind: "branch",
+                    label: "branch".to_string(),
+                    span: to_span(branch.span()),
+                    children: sequence(pattern, branch),
+                })
+                .collect(),
+        ),
+        Ast::Empty(_) => ("empty", String::new(), Vec::new()),
+        Ast::Concat(_) => ("sequence", String::new(), sequence(pattern, ast)),
+    };
+
+    Node {
+        kind,
+        label,
+        span,
+        children,
+    }
+}
+
+fn to_span(span: &ast::Span) -> Span {
+    Span {
+        start: span.start.offset,
+        end: span.end.offset,
+    }
+}
+
+fn text<'p>(pattern: &'p str, node: &Node) -> &'p str {
+    &pattern[node.span.start..node.span.end]
+}
+
+/// Align two sequences by identical source text and diff what is left over
+fn diff_sequences(a: &str, xs: &[Node], b: &str, ys: &[Node], changes: &mut Vec<AstChange>) {
+    // Longest common subsequence over the nodes' source text
+    let mut lcs = vec![vec![0usize; ys.len() + 1]; xs.len() + 1];
+    for i in (0..xs.len()).rev() {
+        for j in (0..ys.len()).rev() {
+            lcs[i][j] = if text(a, &xs[i]) == text(b, &ys[j]) {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+    while i < xs.len() || j < ys.len() {
+        if i < xs.len() && j < ys.len() && text(a, &xs[i]) == text(b, &ys[j]) {
+            diff_unaligned(a, &removed, b, &added, changes);
+            removed.clear();
+            added.clear();
+            i += 1;
+            j += 1;
+        } else if j < ys.len() && (i == xs.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            added.push(&ys[j]);
+            j += 1;
+        } else {
+            removed.push(&xs[i]);
+            i += 1;
+        }
+    }
+    diff_unaligned(a, &removed, b, &added, changes);
+}
+
+/// Pair up removed and added nodes of the same kind (in order) and compare
+/// them; anything unpaired is reported as removed or added
+fn diff_unaligned(
+    a: &str,
+    removed: &[&Node],
+    b: &str,
+    added: &[&Node],
+    changes: &mut Vec<AstChange>,
+) {
+    let mut paired = vec![false; added.len()];
+    for x in removed {
+        let partner = added
+            .iter()
+            .enumerate()
+            .find(|(j, y)| !paired[*j] && y.kind == x.kind)
+            .map(|(j, _)| j);
+        match partner {
+            Some(j) => {
+                paired[j] = true;
+                diff_nodes(a, x, b, added[j], changes);
+            }
+            None => changes.push(AstChange {
+                change: "removed".to_string(),
+                node: x.kind.to_string(),
+                before: Some(text(a, x).to_string()),
+                after: None,
+                a_span: Some(x.span),
+                b_span: None,
+                detail: None,
+            }),
+        }
+    }
+    for (y, _) in added.iter().zip(&paired).filter(|(_, &p)| !p) {
+        changes.push(AstChange {
+            change: "added".to_string(),
+            node: y.kind.to_string(),
+            before: None,
+            after: Some(text(b, y).to_string()),
+            a_span: None,
+            b_span: Some(y.span),
+            detail: None,
+        });
+    }
+}
+
+/// Compare two nodes of the same kind: report a differing label, then recurse
+fn diff_nodes(a: &str, x: &Node, b: &str, y: &Node, changes: &mut Vec<AstChange>) {
+    if x.label != y.label || x.children.is_empty() {
+        changes.push(AstChange {
+            change: "changed".to_string(),
+            node: x.kind.to_string(),
+            before: Some(text(a, x).to_string()),
+            after: Some(text(b, y).to_string()),
+            a_span: Some(x.span),
+            b_span: Some(y.span),
+            detail: (x.label != y.label).then(|| format!("{} → {}", x.label, y.label)),
+        });
+    }
+    diff_sequences(a, &x.children, b, &y.children, changes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identical() {
+        let result = ast_diff(r"(\d+)-x", r"(\d+)-x").unwrap();
+        assert!(result.identical);
+        assert!(result.changes.is_empty());
+    }
+
+    #[test]
+    fn test_quantifier_bound_changed() {
+        let result = ast_diff(r"\d{2,5}-x", r"\d{2,6}-x").unwrap();
+        assert_eq!(result.changes.len(), 1);
+        let change = &result.changes[0];
+        assert_eq!(change.change, "changed");
+        assert_eq!(change.node, "quantifier");
+        assert_eq!(change.detail.as_deref(), Some("{2,5} → {2,6}"));
+    }
+
+    #[test]
+    fn test_group_added() {
+        let result = ast_diff(r"ab", r"a(c)b").unwrap();
+        assert_eq!(result.changes.len(), 1);
+        assert_eq!(result.changes[0].change, "added");
+        assert_eq!(result.changes[0].node, "group");
+        assert_eq!(result.changes[0].after.as_deref(), Some("(c)"));
+        assert_eq!(result.changes[0].b_span, Some(Span { start: 1, end: 4 }));
+    }
+
+    #[test]
+    fn test_group_kind_changed_and_child_removed() {
+        let result = ast_diff(r"(ab)+", r"(?:a)+").unwrap();
+        let details: Vec<_> = result.changes.iter().map(|c| c.change.as_str()).collect();
+        assert_eq!(details, ["changed", "removed"]);
+        assert_eq!(
+            result.changes[0].detail.as_deref(),
+            Some("capture group #1 → non-capturing group")
+        );
+        assert_eq!(result.changes[1].before.as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn test_rejects_fancy_pattern() {
+        assert!(matches!(
+            ast_diff(r"a(?=b)", "ab"),
+            Err(CoreError::Failed(_))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_pattern_error() {
+        assert!(matches!(
+            ast_diff("ab", "a("),
+            Err(CoreError::InvalidPattern(message)) if message.starts_with("pattern B")
+        ));
+    }
+}
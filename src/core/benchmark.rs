@@ -3,7 +3,7 @@
 //! Measures regex performance and detects catastrophic backtracking (ReDoS).
 
 use std::fs::File;
-use std::io::Read;
+use std::io::{BufRead, BufReader, Read};
 use std::path::Path;
 use std::sync::LazyLock;
 use std::time::{Duration, Instant};
@@ -13,131 +13,279 @@ static NESTED_QUANTIFIER_RE: LazyLock<regex::Regex> = LazyLock::new(|| {
         .expect("BUG: nested quantifier detection pattern is invalid")
 });
 
-use super::engine::CompiledRegex;
-use crate::output::BenchmarkResult;
+use serde::Deserialize;
+
+use super::corpus::Rng;
+use super::engine::{normalize_pattern, CompiledRegex, CoreError};
+use crate::output::{
+    BenchmarkResult, BenchmarkSample, GrowthPoint, PatternComplexity, SlowLine, Span,
+};
 
 /// Options for benchmarking
 pub struct BenchmarkOptions {
     /// Number of iterations to run
     pub iterations: usize,
+    /// Instead of a fixed count, run iterations until this many milliseconds
+    /// of measurement have elapsed
+    pub auto_ms: Option<u64>,
     /// Timeout in milliseconds
     pub timeout_ms: u64,
+    /// Warmup iterations run before measuring (excluded from statistics)
+    pub warmup: usize,
+    /// Concatenate the input this many times to build a larger corpus
+    pub repeat_input: usize,
+    /// Also time each line of a file separately and report the slowest ones
+    pub per_line: bool,
+    /// Number of slowest lines to report in per-line mode
+    pub top_lines: usize,
+    /// Input sizes (bytes) to time the pattern at, to expose its growth curve
+    pub sweep: Vec<usize>,
+    /// A measured iteration slower than this flags catastrophic backtracking
+    /// (default 100). Fancy-regex passes are aborted at ten times this.
+    pub catastrophic_threshold_ms: u64,
+    /// Flag catastrophic backtracking when the timing standard deviation
+    /// exceeds this multiple of the mean (default 2.0). Only applies once the
+    /// mean is above 1ms, so fast patterns aren't flagged for jitter.
+    pub stddev_ratio: f64,
+    /// Benchmark a file against this many randomly chosen lines instead of
+    /// its whole content
+    pub sample: Option<usize>,
+    /// Seed for choosing the sampled lines
+    pub seed: u64,
+    /// Hard stop shared by every phase (measurement, sweep, per-line), on
+    /// top of each phase's own `timeout_ms`. A pass still running when it
+    /// arrives is cut short.
+    pub deadline: Option<Instant>,
 }
 
 impl Default for BenchmarkOptions {
     fn default() -> Self {
         Self {
             iterations: 100,
+            auto_ms: None,
             timeout_ms: 5000,
+            warmup: 3,
+            repeat_input: 1,
+            per_line: false,
+            top_lines: 5,
+            sweep: Vec::new(),
+            catastrophic_threshold_ms: 100,
+            stddev_ratio: 2.0,
+            sample: None,
+            seed: 0,
+            deadline: None,
         }
     }
 }
 
+impl BenchmarkOptions {
+    /// How long a single fancy-regex pass may run before it is aborted
+    fn abort_after(&self) -> Duration {
+        Duration::from_millis(self.catastrophic_threshold_ms.saturating_mul(10))
+    }
+
+    /// When a phase started at `start` has to stop: after `timeout_ms`, or
+    /// at the shared `deadline` if that comes first
+    fn phase_deadline(&self, start: Instant) -> Option<Instant> {
+        earliest(
+            start.checked_add(Duration::from_millis(self.timeout_ms)),
+            self.deadline,
+        )
+    }
+}
+
+/// The sooner of two optional instants (`None` never arrives)
+fn earliest(a: Option<Instant>, b: Option<Instant>) -> Option<Instant> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Whether `deadline` has passed
+fn expired(deadline: Option<Instant>) -> bool {
+    deadline.is_some_and(|deadline| Instant::now() >= deadline)
+}
+
+/// Run `benchmark` on the normalized pattern (see [`normalize_pattern`]),
+/// reporting the pattern as given alongside the rewrite
+fn on_normalized(
+    pattern: &str,
+    benchmark: impl FnOnce(&str) -> Result<BenchmarkResult, CoreError>,
+) -> Result<BenchmarkResult, CoreError> {
+    let normalized = normalize_pattern(pattern);
+    let mut result = benchmark(normalized.as_deref().unwrap_or(pattern))?;
+    result.pattern = pattern.to_string();
+    result.normalized_pattern = normalized;
+    Ok(result)
+}
+
 /// Benchmark a pattern against input
 pub fn benchmark_pattern(
     pattern: &str,
     input: &str,
     options: &BenchmarkOptions,
-) -> Result<BenchmarkResult, String> {
-    let (compiled, engine_type) = CompiledRegex::new(pattern).map_err(|e| e.to_string())?;
+) -> Result<BenchmarkResult, CoreError> {
+    on_normalized(pattern, |pattern| benchmark_input(pattern, input, options))
+}
 
-    let timeout = Duration::from_millis(options.timeout_ms);
-    let mut timings_ns = Vec::with_capacity(options.iterations);
+/// [`benchmark_pattern`] for a pattern that is already normalized
+fn benchmark_input(
+    pattern: &str,
+    input: &str,
+    options: &BenchmarkOptions,
+) -> Result<BenchmarkResult, CoreError> {
+    if options.sweep.contains(&0) {
+        return Err("sweep sizes must be greater than zero".into());
+    }
+    if !(options.stddev_ratio.is_finite() && options.stddev_ratio > 0.0) {
+        return Err("stddev ratio must be a positive number".into());
+    }
 
-    let start_total = Instant::now();
-    let mut catastrophic = false;
-    let mut timed_out = false;
+    let mut result = time_input(pattern, input, options)?;
 
-    for _ in 0..options.iterations {
-        if start_total.elapsed() > timeout {
-            timed_out = true;
-            break;
+    if !options.sweep.is_empty() {
+        let (compiled, _) = CompiledRegex::new(pattern).map_err(CoreError::invalid_pattern)?;
+        let (growth, catastrophic) = sweep_sizes(&compiled, input, options);
+        if catastrophic && !result.catastrophic_backtracking {
+            flag_catastrophic(&mut result, pattern);
         }
+        result.growth_class = classify_growth(&growth, catastrophic);
+        result.growth = growth;
+    }
 
-        let start = Instant::now();
+    if result.catastrophic_backtracking {
+        result.vulnerable_span = vulnerable_span(pattern);
+    }
 
-        match &compiled {
-            CompiledRegex::Regex(re) => {
-                let _ = re.find_iter(input).count();
-            }
-            CompiledRegex::FancyRegex(re) => {
-                let mut pos = 0;
-                while pos < input.len() {
-                    match re.find_from_pos(input, pos) {
-                        Ok(Some(m)) => {
-                            let next =
-                                pos + input[pos..].chars().next().map_or(1, |c| c.len_utf8());
-                            pos = m.end().max(next);
-                        }
-                        Ok(None) => break,
-                        Err(_) => break,
-                    }
+    Ok(result)
+}
 
-                    // Check for timeout within iteration
-                    if start.elapsed() > Duration::from_millis(1000) {
-                        catastrophic = true;
-                        break;
-                    }
-                }
-            }
+/// Mark a result as catastrophic after a later pass (sweep, per-line) blew up
+fn flag_catastrophic(result: &mut BenchmarkResult, pattern: &str) {
+    result.catastrophic_backtracking = true;
+    result.warning = Some("Pattern exhibits exponential time complexity".to_string());
+    result.suggestion = suggest_fix(pattern);
+    result.vulnerable_span = vulnerable_span(pattern);
+}
+
+/// Time repeated passes of the pattern over the (optionally repeated) input
+fn time_input(
+    pattern: &str,
+    input: &str,
+    options: &BenchmarkOptions,
+) -> Result<BenchmarkResult, CoreError> {
+    let (compiled, engine_type) =
+        CompiledRegex::new(pattern).map_err(CoreError::invalid_pattern)?;
+
+    // Expand small samples into a larger corpus so MB/s is meaningful
+    let expanded;
+    let input = if options.repeat_input > 1 {
+        expanded = input.repeat(options.repeat_input);
+        expanded.as_str()
+    } else {
+        input
+    };
+
+    let threshold = Duration::from_millis(options.catastrophic_threshold_ms);
+    let abort_after = options.abort_after();
+    let mut timings = TimingStats::new();
+
+    let deadline = options.phase_deadline(Instant::now());
+    let mut catastrophic = false;
+    let mut timed_out = false;
+
+    // Warmup: run but discard timings so cold-start effects don't skew stats
+    let mut warmup_done = 0;
+    while warmup_done < options.warmup && !expired(deadline) {
+        let Some((_, iteration_catastrophic)) =
+            run_iteration(&compiled, input, abort_after, deadline)
+        else {
+            break;
+        };
+        catastrophic |= iteration_catastrophic;
+        warmup_done += 1;
+    }
+
+    // An --auto budget longer than the timeout is cut to it: running out
+    // of budget is how auto mode finishes, not a timeout
+    let start_measure = Instant::now();
+    let budget_end = options.auto_ms.map(|budget| {
+        earliest(
+            start_measure.checked_add(Duration::from_millis(budget)),
+            deadline,
+        )
+    });
+    loop {
+        let done = match budget_end {
+            // Always take at least one sample, however small the budget
+            Some(end) => timings.count > 0 && expired(end),
+            None => timings.count >= options.iterations,
+        };
+        if done {
+            break;
+        }
+        if expired(deadline) {
+            timed_out = true;
+            break;
         }
 
-        let elapsed = start.elapsed();
-        timings_ns.push(elapsed.as_nanos() as u64);
+        let Some((elapsed, iteration_catastrophic)) =
+            run_iteration(&compiled, input, abort_after, deadline)
+        else {
+            timed_out = budget_end.is_none() || timings.count == 0;
+            break;
+        };
+        catastrophic |= iteration_catastrophic;
+        timings.add(elapsed.as_nanos() as u64);
 
         // Detect catastrophic backtracking
-        if elapsed > Duration::from_millis(100) && timings_ns.len() > 1 {
-            // If a single iteration takes > 100ms, likely catastrophic
+        if elapsed > threshold && timings.count > 1 {
+            // A single iteration over the threshold is likely catastrophic
             catastrophic = true;
         }
     }
 
     // Calculate statistics
-    if timings_ns.is_empty() {
+    if timings.count == 0 {
         return Ok(BenchmarkResult {
             pattern: pattern.to_string(),
+            normalized_pattern: None,
             engine: engine_type.to_string(),
             input_size_bytes: input.len(),
             iterations: 0,
+            warmup_iterations: warmup_done,
             avg_us: 0.0,
             median_us: 0.0,
             throughput_mb_s: 0.0,
             catastrophic_backtracking: true,
+            complexity: pattern_complexity(pattern),
             timeout: Some(true),
             warning: Some("Pattern timed out immediately".to_string()),
             suggestion: suggest_fix(pattern),
+            note: None,
+            slowest_lines: Vec::new(),
+            growth: Vec::new(),
+            growth_class: None,
+            sample: None,
+            vulnerable_span: None,
         });
     }
 
-    timings_ns.sort();
-    let avg_ns = timings_ns.iter().sum::<u64>() / timings_ns.len() as u64;
-    let median_ns = timings_ns[timings_ns.len() / 2];
-    let avg_us = avg_ns as f64 / 1_000.0;
-    let median_us = median_ns as f64 / 1_000.0;
+    let avg_ns = timings.mean_ns;
+    let avg_us = avg_ns / 1_000.0;
+    let median_us = timings.median_ns() as f64 / 1_000.0;
 
     // Calculate throughput (use nanosecond precision to avoid div-by-zero)
-    let throughput_mb_s = if avg_ns > 0 {
-        (input.len() as f64 / 1_000_000.0) / (avg_ns as f64 / 1_000_000_000.0)
-    } else {
-        0.0
-    };
-
-    // Check for backtracking indicators using nanosecond precision
-    let stddev_ns = if timings_ns.len() > 1 {
-        let mean = avg_ns as f64;
-        let variance: f64 = timings_ns
-            .iter()
-            .map(|&t| (t as f64 - mean).powi(2))
-            .sum::<f64>()
-            / (timings_ns.len() - 1) as f64;
-        variance.sqrt()
+    let throughput_mb_s = if avg_ns > 0.0 {
+        (input.len() as f64 / 1_000_000.0) / (avg_ns / 1_000_000_000.0)
     } else {
         0.0
     };
 
     // Only flag as catastrophic if avg is slow enough to matter (>1ms)
     // AND variance is high relative to mean
-    if avg_ns > 1_000_000 && stddev_ns > avg_ns as f64 * 2.0 {
+    if avg_ns > 1_000_000.0 && timings.stddev_ns() > avg_ns * options.stddev_ratio {
         catastrophic = true;
     }
 
@@ -151,13 +299,16 @@ pub fn benchmark_pattern(
 
     Ok(BenchmarkResult {
         pattern: pattern.to_string(),
+        normalized_pattern: None,
         engine: engine_type.to_string(),
         input_size_bytes: input.len(),
-        iterations: timings_ns.len(),
+        iterations: timings.count,
+        warmup_iterations: warmup_done,
         avg_us,
         median_us,
         throughput_mb_s,
         catastrophic_backtracking: catastrophic,
+        complexity: pattern_complexity(pattern),
         timeout: if timed_out { Some(true) } else { None },
         warning,
         suggestion: if catastrophic {
@@ -165,26 +316,498 @@ pub fn benchmark_pattern(
         } else {
             None
         },
+        note: None,
+        slowest_lines: Vec::new(),
+        growth: Vec::new(),
+        growth_class: None,
+        sample: None,
+        vulnerable_span: None,
+    })
+}
+
+/// Running statistics over iteration timings, in constant memory however
+/// long `--auto` runs: mean and variance are exact (Welford's method), the
+/// median is taken from a uniform sample of at most `SAMPLE_CAP` timings.
+struct TimingStats {
+    count: usize,
+    mean_ns: f64,
+    /// Sum of squared differences from the running mean
+    m2: f64,
+    sample: Vec<u64>,
+    rng: Rng,
+}
+
+impl TimingStats {
+    const SAMPLE_CAP: usize = 10_000;
+
+    fn new() -> Self {
+        Self {
+            count: 0,
+            mean_ns: 0.0,
+            m2: 0.0,
+            sample: Vec::new(),
+            rng: Rng::new(),
+        }
+    }
+
+    fn add(&mut self, ns: u64) {
+        self.count += 1;
+        let delta = ns as f64 - self.mean_ns;
+        self.mean_ns += delta / self.count as f64;
+        self.m2 += delta * (ns as f64 - self.mean_ns);
+
+        if self.sample.len() < Self::SAMPLE_CAP {
+            self.sample.push(ns);
+        } else {
+            let slot = self.rng.below(self.count);
+            if slot < Self::SAMPLE_CAP {
+                self.sample[slot] = ns;
+            }
+        }
+    }
+
+    /// Sample standard deviation (0 for a single timing)
+    fn stddev_ns(&self) -> f64 {
+        if self.count > 1 {
+            (self.m2 / (self.count - 1) as f64).sqrt()
+        } else {
+            0.0
+        }
+    }
+
+    fn median_ns(&mut self) -> u64 {
+        let middle = self.sample.len() / 2;
+        *self.sample.select_nth_unstable(middle).1
+    }
+}
+
+/// Report the static ReDoS verdict for a pattern without running it.
+///
+/// Timing fields are zeroed; nothing is executed against adversarial input,
+/// so this is safe to run on every pattern in a lint pass.
+pub fn detect_only(pattern: &str) -> Result<BenchmarkResult, CoreError> {
+    on_normalized(pattern, detect_normalized)
+}
+
+/// [`detect_only`] for a pattern that is already normalized
+fn detect_normalized(pattern: &str) -> Result<BenchmarkResult, CoreError> {
+    let (_, engine_type) = CompiledRegex::new(pattern).map_err(CoreError::invalid_pattern)?;
+    let vulnerability = detect_redos_vulnerability(pattern);
+
+    Ok(BenchmarkResult {
+        pattern: pattern.to_string(),
+        normalized_pattern: None,
+        engine: engine_type.to_string(),
+        input_size_bytes: 0,
+        iterations: 0,
+        warmup_iterations: 0,
+        avg_us: 0.0,
+        median_us: 0.0,
+        throughput_mb_s: 0.0,
+        catastrophic_backtracking: vulnerability.is_some(),
+        complexity: pattern_complexity(pattern),
+        timeout: None,
+        suggestion: vulnerability.as_ref().and_then(|_| suggest_fix(pattern)),
+        warning: vulnerability,
+        note: Some(
+            "Static analysis only (--detect-only): the pattern was not executed and no timing was measured"
+                .to_string(),
+        ),
+        slowest_lines: Vec::new(),
+        growth: Vec::new(),
+        growth_class: None,
+        sample: None,
+        vulnerable_span: vulnerable_span(pattern),
     })
 }
 
+/// Run a single timed pass of the pattern over the input.
+///
+/// Returns the elapsed time and whether a fancy-regex pass had to be
+/// aborted for running longer than `abort_after` (catastrophic backtracking),
+/// or `None` if `deadline` arrived before the pass finished.
+fn run_iteration(
+    compiled: &CompiledRegex,
+    input: &str,
+    abort_after: Duration,
+    deadline: Option<Instant>,
+) -> Option<(Duration, bool)> {
+    // Reading the clock after every match would skew timings of fast
+    // patterns, so the linear engine only checks every this many matches
+    const DEADLINE_CHECK_INTERVAL: usize = 256;
+
+    let start = Instant::now();
+    let mut catastrophic = false;
+
+    match compiled {
+        CompiledRegex::Regex(re) => {
+            for (count, _) in re.find_iter(input).enumerate() {
+                if count % DEADLINE_CHECK_INTERVAL == 0 && expired(deadline) {
+                    return None;
+                }
+            }
+        }
+        CompiledRegex::FancyRegex(re) => {
+            let mut pos = 0;
+            while pos < input.len() {
+                match re.find_from_pos(input, pos) {
+                    Ok(Some(m)) => {
+                        let next = pos + input[pos..].chars().next().map_or(1, |c| c.len_utf8());
+                        pos = m.end().max(next);
+                    }
+                    Ok(None) => break,
+                    Err(_) => break,
+                }
+
+                if expired(deadline) {
+                    return None;
+                }
+                // Check for timeout within iteration
+                if start.elapsed() > abort_after {
+                    catastrophic = true;
+                    break;
+                }
+            }
+        }
+    }
+
+    Some((start.elapsed(), catastrophic))
+}
+
 /// Benchmark a pattern against a file
 pub fn benchmark_file(
     pattern: &str,
     file_path: &Path,
     options: &BenchmarkOptions,
-) -> Result<BenchmarkResult, String> {
+) -> Result<BenchmarkResult, CoreError> {
+    on_normalized(pattern, |pattern| {
+        benchmark_file_normalized(pattern, file_path, options)
+    })
+}
+
+/// [`benchmark_file`] for a pattern that is already normalized
+fn benchmark_file_normalized(
+    pattern: &str,
+    file_path: &Path,
+    options: &BenchmarkOptions,
+) -> Result<BenchmarkResult, CoreError> {
     let mut file = File::open(file_path).map_err(|e| format!("Failed to open file: {}", e))?;
 
+    if let Some(count) = options.sample {
+        if count == 0 {
+            return Err("sample size must be greater than zero".into());
+        }
+        if options.per_line {
+            return Err("per-line timing cannot be combined with sampling".into());
+        }
+
+        let (lines, total_lines) = sample_lines(BufReader::new(file), count, options.seed)?;
+        let mut result = benchmark_input(pattern, &lines.join("\n"), options)?;
+        result.sample = Some(BenchmarkSample {
+            lines: lines.len(),
+            total_lines,
+            seed: options.seed,
+        });
+        return Ok(result);
+    }
+
     let mut content = String::new();
     file.read_to_string(&mut content)
         .map_err(|e| format!("Failed to read file: {}", e))?;
 
-    benchmark_pattern(pattern, &content, options)
+    let mut result = benchmark_input(pattern, &content, options)?;
+
+    if options.per_line {
+        let (compiled, _) = CompiledRegex::new(pattern).map_err(CoreError::invalid_pattern)?;
+        let (slowest, catastrophic) = time_lines(&compiled, &content, options);
+        if catastrophic && !result.catastrophic_backtracking {
+            flag_catastrophic(&mut result, pattern);
+        }
+        result.slowest_lines = slowest;
+    }
+
+    Ok(result)
+}
+
+/// Choose `count` lines uniformly at random in one pass (reservoir
+/// sampling), so only the sample is ever held in memory. Returns the chosen
+/// lines in file order and the total number of lines read.
+fn sample_lines(
+    reader: impl BufRead,
+    count: usize,
+    seed: u64,
+) -> Result<(Vec<String>, usize), String> {
+    let mut rng = Rng::with_seed(seed);
+    let mut reservoir: Vec<(usize, String)> = Vec::with_capacity(count.min(1 << 16));
+    let mut total = 0;
+
+    for line in reader.lines() {
+        let line = line.map_err(|e| format!("Failed to read file: {}", e))?;
+        if reservoir.len() < count {
+            reservoir.push((total, line));
+        } else {
+            let slot = rng.below(total + 1);
+            if slot < count {
+                reservoir[slot] = (total, line);
+            }
+        }
+        total += 1;
+    }
+
+    reservoir.sort_unstable_by_key(|&(index, _)| index);
+    Ok((reservoir.into_iter().map(|(_, line)| line).collect(), total))
 }
 
-/// Generate ReDoS test inputs for common patterns
-pub fn generate_redos_input(pattern: &str) -> String {
+/// Time each line and return the `top_lines` slowest, slowest first, plus
+/// whether any line had to be aborted as catastrophic.
+///
+/// A line's time is the fastest of a few passes, so a scheduler hiccup
+/// during one pass cannot make a fast line look slow. Stops early once the
+/// overall timeout is exhausted.
+fn time_lines(
+    compiled: &CompiledRegex,
+    content: &str,
+    options: &BenchmarkOptions,
+) -> (Vec<SlowLine>, bool) {
+    const MAX_CONTENT_CHARS: usize = 80;
+    const PASSES_PER_LINE: usize = 3;
+
+    let deadline = options.phase_deadline(Instant::now());
+    let mut catastrophic = false;
+    let mut timings = Vec::new();
+
+    'lines: for (index, line) in content.lines().enumerate() {
+        let mut fastest = Duration::MAX;
+        for _ in 0..PASSES_PER_LINE {
+            let Some((elapsed, line_catastrophic)) =
+                run_iteration(compiled, line, options.abort_after(), deadline)
+            else {
+                break 'lines;
+            };
+            fastest = fastest.min(elapsed);
+            // An aborted pass is slow enough already; don't repeat it
+            if line_catastrophic {
+                catastrophic = true;
+                break;
+            }
+        }
+        timings.push((fastest, index, line));
+    }
+
+    timings.sort_by_key(|&(elapsed, _, _)| std::cmp::Reverse(elapsed));
+    let slowest = timings
+        .into_iter()
+        .take(options.top_lines)
+        .map(|(elapsed, index, line)| {
+            let mut content: String = line.chars().take(MAX_CONTENT_CHARS).collect();
+            if content.len() < line.len() {
+                content.push('…');
+            }
+            SlowLine {
+                line: index + 1,
+                time_us: elapsed.as_nanos() as f64 / 1_000.0,
+                content,
+            }
+        })
+        .collect();
+
+    (slowest, catastrophic)
+}
+
+/// Time the pattern on `base` stretched to each size in `options.sweep`,
+/// smallest first, plus whether a pass had to be aborted as catastrophic.
+///
+/// All sizes share one timeout budget; the sweep stops at the first
+/// catastrophic size since larger inputs can only be slower.
+fn sweep_sizes(
+    compiled: &CompiledRegex,
+    base: &str,
+    options: &BenchmarkOptions,
+) -> (Vec<GrowthPoint>, bool) {
+    let mut sizes = options.sweep.clone();
+    sizes.sort_unstable();
+    sizes.dedup();
+
+    let deadline = options.phase_deadline(Instant::now());
+    let mut growth = Vec::new();
+
+    for size in sizes {
+        if expired(deadline) {
+            break;
+        }
+        let input = scale_input(base, size);
+
+        let mut total = Duration::ZERO;
+        let mut runs = 0;
+        let mut catastrophic = false;
+        while runs < options.iterations.max(1) {
+            let Some((elapsed, run_catastrophic)) =
+                run_iteration(compiled, &input, options.abort_after(), deadline)
+            else {
+                break;
+            };
+            total += elapsed;
+            runs += 1;
+            if run_catastrophic {
+                catastrophic = true;
+                break;
+            }
+        }
+        if runs == 0 {
+            break;
+        }
+
+        growth.push(GrowthPoint {
+            size: input.len(),
+            avg_us: total.as_nanos() as f64 / runs as f64 / 1_000.0,
+        });
+        if catastrophic {
+            return (growth, true);
+        }
+    }
+
+    (growth, false)
+}
+
+/// Stretch `base` to `size` bytes by cycling everything but its last
+/// character, then appending that character. Keeping the suffix preserves
+/// adversarial inputs like `aaaa…b`, whose final mismatch forces backtracking.
+fn scale_input(base: &str, size: usize) -> String {
+    let mut chars = base.chars();
+    let Some(last) = chars.next_back() else {
+        return String::new();
+    };
+    let body = chars.as_str();
+    if body.is_empty() {
+        return last.to_string().repeat(size / last.len_utf8());
+    }
+
+    let mut scaled = String::with_capacity(size);
+    for c in body.chars().cycle() {
+        if scaled.len() + c.len_utf8() + last.len_utf8() > size {
+            break;
+        }
+        scaled.push(c);
+    }
+    scaled.push(last);
+    scaled
+}
+
+/// Classify a growth curve from the two largest sizes: if growing the input
+/// tenfold grows the time much more than tenfold, it is superlinear.
+fn classify_growth(growth: &[GrowthPoint], catastrophic: bool) -> Option<String> {
+    if catastrophic {
+        return Some("superlinear".to_string());
+    }
+    let [.., smaller, larger] = growth else {
+        return None;
+    };
+    if smaller.avg_us <= 0.0 || larger.size <= smaller.size {
+        return None;
+    }
+
+    let exponent =
+        (larger.avg_us / smaller.avg_us).ln() / (larger.size as f64 / smaller.size as f64).ln();
+    Some(
+        if exponent > 1.5 {
+            "superlinear"
+        } else {
+            "linear"
+        }
+        .to_string(),
+    )
+}
+
+/// Compute structural complexity metrics by walking the `regex_syntax` AST.
+///
+/// Returns `None` for patterns the AST parser cannot handle (fancy-regex only).
+pub fn pattern_complexity(pattern: &str) -> Option<PatternComplexity> {
+    use regex_syntax::ast::parse::Parser as AstParser;
+    use regex_syntax::ast::{Ast, RepetitionKind, RepetitionRange};
+
+    fn walk(ast: &Ast, depth: usize, c: &mut PatternComplexity) {
+        c.node_count += 1;
+        match ast {
+            Ast::Group(group) => {
+                c.max_group_depth = c.max_group_depth.max(depth + 1);
+                walk(&group.ast, depth + 1, c);
+            }
+            Ast::Repetition(rep) => {
+                c.repetition_count += 1;
+                if let RepetitionKind::Range(range) = &rep.op.kind {
+                    let bound = match *range {
+                        RepetitionRange::Exactly(n) | RepetitionRange::AtLeast(n) => n,
+                        RepetitionRange::Bounded(_, n) => n,
+                    };
+                    c.max_quantifier_bound = Some(c.max_quantifier_bound.unwrap_or(0).max(bound));
+                }
+                walk(&rep.ast, depth, c);
+            }
+            Ast::Alternation(alt) => {
+                c.alternation_count += 1;
+                for child in &alt.asts {
+                    walk(child, depth, c);
+                }
+            }
+            Ast::Concat(concat) => {
+                for child in &concat.asts {
+                    walk(child, depth, c);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let ast = AstParser::new().parse(pattern).ok()?;
+    let mut complexity = PatternComplexity::default();
+    walk(&ast, 0, &mut complexity);
+    Some(complexity)
+}
+
+/// A user-supplied ReDoS input template (`benchmark --redos-templates`):
+/// patterns containing `fragment` are benchmarked against `input`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RedosTemplate {
+    /// Text to look for in the pattern, e.g. `(\w+\s?)+`
+    pub fragment: String,
+    /// Adversarial input for patterns containing the fragment
+    pub input: String,
+}
+
+/// Parse a ReDoS templates file: a JSON array of
+/// `{"fragment": "...", "input": "..."}` objects
+pub fn parse_redos_templates(json: &str) -> Result<Vec<RedosTemplate>, String> {
+    let templates: Vec<RedosTemplate> =
+        serde_json::from_str(json).map_err(|e| format!("Invalid ReDoS templates JSON: {}", e))?;
+
+    for (i, template) in templates.iter().enumerate() {
+        if template.fragment.is_empty() {
+            return Err(format!(
+                "Invalid ReDoS template {}: fragment must not be empty",
+                i + 1
+            ));
+        }
+        if template.input.is_empty() {
+            return Err(format!(
+                "Invalid ReDoS template {} (`{}`): input must not be empty",
+                i + 1,
+                template.fragment
+            ));
+        }
+    }
+    Ok(templates)
+}
+
+/// Generate ReDoS test inputs for common patterns. `templates` are checked
+/// in order before the builtin list.
+pub fn generate_redos_input(pattern: &str, templates: &[RedosTemplate]) -> String {
+    if let Some(template) = templates.iter().find(|t| pattern.contains(&t.fragment)) {
+        return template.input.clone();
+    }
+    let normalized = normalize_pattern(pattern);
+    let pattern = normalized.as_deref().unwrap_or(pattern);
+
     // Common ReDoS patterns and their corresponding evil inputs
     let evil_inputs = [
         // (a+)+$ pattern
@@ -206,6 +829,9 @@ pub fn generate_redos_input(pattern: &str) -> String {
     }
 
     // Generate input based on pattern analysis
+    if let Some(ambiguous) = ambiguous_alternation(pattern) {
+        return ambiguous.text.repeat(30 / ambiguous.text.len().max(1)) + "!";
+    }
     if pattern.contains("a+)+") || pattern.contains("a*)*") {
         return "aaaaaaaaaaaaaaaaaaaab".to_string();
     }
@@ -214,8 +840,199 @@ pub fn generate_redos_input(pattern: &str) -> String {
     "a".repeat(30) + "X"
 }
 
+/// A group under an unbounded quantifier whose literal branches can split
+/// the same text in more than one way, like `(a|aa)+` or `(x|x)*`: each
+/// ambiguous stretch of input doubles the splits a backtracking engine tries
+/// when the overall match fails. Branches that merely share a prefix, like
+/// `(a|ab)+`, split any text at most one way and only cost polynomial time.
+struct AmbiguousAlternation {
+    /// Byte span of the alternation in the pattern
+    span: Span,
+    /// Shortest text found that splits between the branches in two ways
+    text: String,
+    /// A branch (as written) that repeating the others already covers, and
+    /// whether it is an exact duplicate of an earlier branch
+    redundant: Option<(String, bool)>,
+}
+
+impl AmbiguousAlternation {
+    fn warning(&self) -> String {
+        format!(
+            "Ambiguous alternation under a quantifier at {}..{}: `{}` splits between the branches in more than one way",
+            self.span.start, self.span.end, self.text
+        )
+    }
+
+    fn suggestion(&self) -> String {
+        match &self.redundant {
+            Some((branch, true)) => format!("Remove the duplicate branch `{}`", branch),
+            Some((branch, false)) => format!(
+                "Remove the branch `{}`: repeating the other branches already matches it",
+                branch
+            ),
+            None => "Rewrite the branches so no text splits between them in more than one way, or make the group atomic: `(?>...)`".to_string(),
+        }
+    }
+}
+
+/// A text with two different splits into `words`, if there is one
+/// (Sardinas–Patterson: follow the dangling suffix of whichever split is
+/// ahead until the other catches up exactly)
+fn split_ambiguity(words: &[&str]) -> Option<String> {
+    use std::collections::{HashSet, VecDeque};
+
+    let mut queue = VecDeque::new();
+    let mut seen = HashSet::new();
+    for (i, short) in words.iter().enumerate() {
+        for (j, long) in words.iter().enumerate() {
+            if i == j {
+                continue;
+            }
+            if short == long {
+                return Some(short.to_string());
+            }
+            if let Some(dangling) = long.strip_prefix(short) {
+                if seen.insert(dangling.to_string()) {
+                    queue.push_back((dangling.to_string(), long.to_string()));
+                }
+            }
+        }
+    }
+    while let Some((dangling, text)) = queue.pop_front() {
+        for word in words {
+            if *word == dangling {
+                return Some(text);
+            }
+            let next = if let Some(rest) = dangling.strip_prefix(word) {
+                (rest.to_string(), text.clone())
+            } else if let Some(rest) = word.strip_prefix(dangling.as_str()) {
+                (rest.to_string(), format!("{}{}", text, rest))
+            } else {
+                continue;
+            };
+            if seen.insert(next.0.clone()) {
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+/// Whether `text` is a concatenation of one or more `words`
+fn splits_into(text: &str, words: &[&str]) -> bool {
+    let mut reachable = vec![false; text.len() + 1];
+    reachable[0] = true;
+    for end in 1..=text.len() {
+        reachable[end] = words.iter().any(|word| {
+            end >= word.len() && reachable[end - word.len()] && text[..end].ends_with(word)
+        });
+    }
+    reachable[text.len()]
+}
+
+/// Find the first quantified alternation whose branches are ambiguous
+fn ambiguous_alternation(pattern: &str) -> Option<AmbiguousAlternation> {
+    use regex_syntax::ast::parse::Parser as AstParser;
+    use regex_syntax::ast::{Ast, RepetitionKind, RepetitionRange};
+
+    /// The text a branch matches, when it is nothing but literals
+    fn literal_text(branch: &Ast) -> Option<String> {
+        let items = match branch {
+            Ast::Concat(concat) => concat.asts.as_slice(),
+            other => std::slice::from_ref(other),
+        };
+        items
+            .iter()
+            .map(|item| match item {
+                Ast::Literal(lit) => Some(lit.c),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn check(alt: &regex_syntax::ast::Alternation, pattern: &str) -> Option<AmbiguousAlternation> {
+        // Branches with anything but literals are left out: an ambiguity
+        // among the rest is still one, so this never over-reports
+        let branches: Vec<(&str, String)> = alt
+            .asts
+            .iter()
+            .filter_map(|branch| {
+                let span = branch.span();
+                let text = literal_text(branch).filter(|text| !text.is_empty())?;
+                Some((&pattern[span.start.offset..span.end.offset], text))
+            })
+            .collect();
+        let words: Vec<&str> = branches.iter().map(|(_, text)| text.as_str()).collect();
+        let text = split_ambiguity(&words)?;
+
+        let duplicate = branches
+            .iter()
+            .enumerate()
+            .find(|(i, (_, text))| words[..*i].contains(&text.as_str()))
+            .map(|(_, (written, _))| (written.to_string(), true));
+        let redundant = duplicate.or_else(|| {
+            branches
+                .iter()
+                .enumerate()
+                .find_map(|(i, (written, text))| {
+                    let others: Vec<&str> = words
+                        .iter()
+                        .enumerate()
+                        .filter(|&(j, _)| j != i)
+                        .map(|(_, word)| *word)
+                        .collect();
+                    splits_into(text, &others).then(|| (written.to_string(), false))
+                })
+        });
+        Some(AmbiguousAlternation {
+            span: Span {
+                start: alt.span.start.offset,
+                end: alt.span.end.offset,
+            },
+            text,
+            redundant,
+        })
+    }
+
+    /// `repeated`: the node sits directly (through groups) under an
+    /// unbounded quantifier
+    fn walk(ast: &Ast, pattern: &str, repeated: bool) -> Option<AmbiguousAlternation> {
+        match ast {
+            Ast::Repetition(rep) => {
+                let unbounded = matches!(
+                    rep.op.kind,
+                    RepetitionKind::ZeroOrMore
+                        | RepetitionKind::OneOrMore
+                        | RepetitionKind::Range(RepetitionRange::AtLeast(_))
+                );
+                walk(&rep.ast, pattern, unbounded)
+            }
+            Ast::Group(group) => walk(&group.ast, pattern, repeated),
+            Ast::Alternation(alt) => repeated
+                .then(|| check(alt, pattern))
+                .flatten()
+                .or_else(|| alt.asts.iter().find_map(|a| walk(a, pattern, false))),
+            Ast::Concat(concat) => concat.asts.iter().find_map(|a| walk(a, pattern, false)),
+            _ => None,
+        }
+    }
+
+    let ast = AstParser::new().parse(pattern).ok()?;
+    walk(&ast, pattern, false)
+}
+
+/// Span of the construct that makes a pattern vulnerable, when it can be
+/// pinned down (currently: ambiguous alternations)
+fn vulnerable_span(pattern: &str) -> Option<Span> {
+    ambiguous_alternation(pattern).map(|ambiguous| ambiguous.span)
+}
+
 /// Detect potential ReDoS vulnerability in a pattern
 pub fn detect_redos_vulnerability(pattern: &str) -> Option<String> {
+    if let Some(ambiguous) = ambiguous_alternation(pattern) {
+        return Some(ambiguous.warning());
+    }
+
     // Patterns that are known to be vulnerable to ReDoS
     let vulnerable_patterns = [
         (r"(\w+)+", "Nested quantifiers on word characters"),
@@ -254,6 +1071,10 @@ fn suggest_fix(pattern: &str) -> Option<String> {
         return Some("Use atomic group: (?>.+)+ or limit repetition".to_string());
     }
 
+    if let Some(ambiguous) = ambiguous_alternation(pattern) {
+        return Some(ambiguous.suggestion());
+    }
+
     if detect_redos_vulnerability(pattern).is_some() {
         return Some("Consider using atomic groups (?>...) or possessive quantifiers to prevent backtracking".to_string());
     }
@@ -262,7 +1083,6 @@ fn suggest_fix(pattern: &str) -> Option<String> {
 }
 
 /// Quick check if a pattern might be vulnerable (without benchmarking)
-#[allow(dead_code)]
 pub fn quick_vulnerability_check(pattern: &str) -> bool {
     detect_redos_vulnerability(pattern).is_some()
 }
@@ -280,6 +1100,199 @@ mod tests {
         assert!(result.avg_us < 10000.0); // Should be very fast
     }
 
+    #[test]
+    fn test_warmup_excluded_from_iterations() {
+        let options = BenchmarkOptions {
+            iterations: 10,
+            warmup: 5,
+            ..Default::default()
+        };
+        let result = benchmark_pattern(r"\d+", "hello 123", &options).unwrap();
+        assert_eq!(result.iterations, 10);
+        assert_eq!(result.warmup_iterations, 5);
+    }
+
+    #[test]
+    fn test_repeat_input_expands_corpus() {
+        let options = BenchmarkOptions {
+            iterations: 5,
+            repeat_input: 4,
+            ..Default::default()
+        };
+        let result = benchmark_pattern(r"\d+", "abc 123\n", &options).unwrap();
+        assert_eq!(result.input_size_bytes, 32);
+    }
+
+    #[test]
+    fn test_deadline_cuts_every_phase_short() {
+        let options = BenchmarkOptions {
+            sweep: vec![10, 100],
+            deadline: Some(Instant::now()),
+            ..Default::default()
+        };
+        let result = benchmark_pattern(r"\d+", "abc 123", &options).unwrap();
+        assert_eq!(result.iterations, 0);
+        assert_eq!(result.timeout, Some(true));
+        assert!(result.growth.is_empty());
+    }
+
+    #[test]
+    fn test_sample_lines_reservoir() {
+        let content: String = (0..1000).map(|i| format!("line {}\n", i)).collect();
+
+        let (lines, total) = sample_lines(content.as_bytes(), 10, 7).unwrap();
+        assert_eq!(total, 1000);
+        assert_eq!(lines.len(), 10);
+        // Kept in file order, and the same seed picks the same lines
+        let numbers: Vec<usize> = lines.iter().map(|l| l[5..].parse().unwrap()).collect();
+        assert!(numbers.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(sample_lines(content.as_bytes(), 10, 7).unwrap().0, lines);
+        assert_ne!(sample_lines(content.as_bytes(), 10, 8).unwrap().0, lines);
+
+        // Asking for more lines than there are returns them all
+        let (lines, total) = sample_lines("a\nb\n".as_bytes(), 5, 0).unwrap();
+        assert_eq!((lines, total), (vec!["a".to_string(), "b".to_string()], 2));
+    }
+
+    #[test]
+    fn test_auto_iterations_fill_budget() {
+        let options = BenchmarkOptions {
+            auto_ms: Some(50),
+            ..Default::default()
+        };
+        let start = Instant::now();
+        let result = benchmark_pattern(r"\d+", "abc 123", &options).unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(50));
+        assert!(result.iterations > 100, "{}", result.iterations);
+        assert!(result.timeout.is_none());
+    }
+
+    #[test]
+    fn test_auto_budget_capped_at_timeout() {
+        let options = BenchmarkOptions {
+            auto_ms: Some(10_000),
+            timeout_ms: 50,
+            ..Default::default()
+        };
+        let start = Instant::now();
+        let result = benchmark_pattern(r"\d+", "abc 123", &options).unwrap();
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(result.iterations > 0);
+        assert!(result.timeout.is_none());
+    }
+
+    #[test]
+    fn test_timing_stats() {
+        let mut stats = TimingStats::new();
+        for ns in [4, 1, 3, 2, 5] {
+            stats.add(ns);
+        }
+        assert_eq!(stats.count, 5);
+        assert!((stats.mean_ns - 3.0).abs() < 1e-9);
+        assert!((stats.stddev_ns() - 2.5f64.sqrt()).abs() < 1e-9);
+        assert_eq!(stats.median_ns(), 3);
+
+        // Memory stays bounded however many timings come in
+        let mut stats = TimingStats::new();
+        for ns in 0..100_000 {
+            stats.add(ns);
+        }
+        assert_eq!(stats.sample.len(), TimingStats::SAMPLE_CAP);
+        assert!((stats.mean_ns - 49_999.5).abs() < 1e-6);
+        assert!(stats.median_ns().abs_diff(50_000) < 5_000);
+    }
+
+    #[test]
+    fn test_catastrophic_threshold_configurable() {
+        let input = "a".repeat(200_000);
+        let options = BenchmarkOptions {
+            iterations: 3,
+            warmup: 0,
+            catastrophic_threshold_ms: 0,
+            ..Default::default()
+        };
+        let result = benchmark_pattern(r"\w+\d", &input, &options).unwrap();
+        assert!(result.catastrophic_backtracking);
+
+        let options = BenchmarkOptions {
+            stddev_ratio: 0.0,
+            ..Default::default()
+        };
+        assert!(benchmark_pattern(r"\d+", "abc", &options).is_err());
+    }
+
+    #[test]
+    fn test_time_lines_reports_slowest_first() {
+        let (compiled, _) = CompiledRegex::new(r"\w+\d").unwrap();
+        let long_line = "a".repeat(20_000);
+        let content = format!("short\n{}\nx1\ny2\n", long_line);
+        let options = BenchmarkOptions {
+            top_lines: 2,
+            ..Default::default()
+        };
+
+        let (slowest, catastrophic) = time_lines(&compiled, &content, &options);
+        assert!(!catastrophic);
+        assert_eq!(slowest.len(), 2);
+        assert_eq!(slowest[0].line, 2);
+        assert!(slowest[0].content.ends_with('…'));
+        assert!(slowest[0].time_us >= slowest[1].time_us);
+    }
+
+    #[test]
+    fn test_sweep_reports_growth() {
+        let options = BenchmarkOptions {
+            iterations: 5,
+            sweep: vec![1000, 100, 10_000],
+            ..Default::default()
+        };
+        let result = benchmark_pattern(r"\d+", "abc 123\n", &options).unwrap();
+        let sizes: Vec<usize> = result.growth.iter().map(|p| p.size).collect();
+        assert_eq!(sizes, vec![100, 1000, 10_000]);
+        assert!(result.growth_class.is_some());
+
+        let options = BenchmarkOptions {
+            sweep: vec![0],
+            ..Default::default()
+        };
+        assert!(benchmark_pattern(r"\d+", "abc", &options).is_err());
+    }
+
+    #[test]
+    fn test_scale_input_keeps_suffix() {
+        assert_eq!(scale_input("ab", 5), "aaaab");
+        assert_eq!(scale_input("xyz", 7), "xyxyxyz");
+        assert_eq!(scale_input("a", 3), "aaa");
+        assert_eq!(scale_input("", 3), "");
+    }
+
+    #[test]
+    fn test_classify_growth() {
+        let point = |size, avg_us| GrowthPoint { size, avg_us };
+        let linear = [point(100, 1.0), point(1000, 10.0), point(10_000, 110.0)];
+        assert_eq!(classify_growth(&linear, false).as_deref(), Some("linear"));
+        let quadratic = [point(1000, 10.0), point(10_000, 1000.0)];
+        assert_eq!(
+            classify_growth(&quadratic, false).as_deref(),
+            Some("superlinear")
+        );
+        assert_eq!(classify_growth(&linear[..1], false), None);
+        assert_eq!(
+            classify_growth(&linear[..1], true).as_deref(),
+            Some("superlinear")
+        );
+    }
+
+    #[test]
+    fn test_pattern_complexity() {
+        let c = pattern_complexity(r"(a|(b{2,50}))+c*").unwrap();
+        assert_eq!(c.alternation_count, 1);
+        assert_eq!(c.repetition_count, 3);
+        assert_eq!(c.max_quantifier_bound, Some(50));
+        assert_eq!(c.max_group_depth, 2);
+        assert!(pattern_complexity(r"foo(?=bar)").is_none());
+    }
+
     #[test]
     fn test_detect_redos() {
         assert!(detect_redos_vulnerability(r"(a+)+").is_some());
@@ -287,10 +1300,93 @@ mod tests {
         assert!(detect_redos_vulnerability(r"\d+").is_none());
     }
 
+    #[test]
+    fn test_detect_ambiguous_alternation() {
+        let ambiguous = ambiguous_alternation(r"^(a|aa)+$").unwrap();
+        assert_eq!(ambiguous.span, Span { start: 2, end: 6 });
+        assert_eq!(ambiguous.text, "aa");
+        assert_eq!(
+            ambiguous.suggestion(),
+            "Remove the branch `aa`: repeating the other branches already matches it"
+        );
+
+        let ambiguous = ambiguous_alternation(r"(x|x)*").unwrap();
+        assert_eq!(ambiguous.suggestion(), "Remove the duplicate branch `x`");
+        assert!(detect_redos_vulnerability(r"(x|x)*")
+            .unwrap()
+            .contains("at 1..4"));
+
+        // `aba` is `a`+`ba` and `ab`+`a`, but no branch is redundant
+        let ambiguous = ambiguous_alternation(r"id=(?:a|ab|ba)+;").unwrap();
+        assert_eq!(ambiguous.text, "aba");
+        assert!(ambiguous.suggestion().contains("(?>...)"));
+        assert!(generate_redos_input(r"id=(?:a|ab|ba)+;", &[]).starts_with("abaaba"));
+
+        // Shared prefixes that still split every text one way only cost
+        // polynomial time; so do ambiguities outside an unbounded quantifier
+        for pattern in [
+            r"(ab|cd)+",
+            r"^(a|ab)+$",
+            r"^(foo|foobar)+$",
+            r"id=(?:\.foo|\.foobar|baz)+;",
+            r"(a|aa)",
+            r"(a|aa){2}",
+            r"((a|aa)x)+",
+        ] {
+            assert!(ambiguous_alternation(pattern).is_none(), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_detect_only() {
+        let result = detect_only(r"^(a+)+$").unwrap();
+        assert!(result.catastrophic_backtracking);
+        assert!(result.warning.is_some());
+        assert!(result.suggestion.is_some());
+        assert_eq!(result.iterations, 0);
+        assert!(result.note.is_some());
+        assert!(result.vulnerable_span.is_none());
+
+        let result = detect_only(r"(foo|foofoo)+").unwrap();
+        assert!(result.catastrophic_backtracking);
+        assert_eq!(result.vulnerable_span, Some(Span { start: 1, end: 11 }));
+
+        let result = detect_only(r"\d+").unwrap();
+        assert!(!result.catastrophic_backtracking);
+        assert!(result.suggestion.is_none());
+    }
+
     #[test]
     fn test_generate_evil_input() {
-        let input = generate_redos_input(r"(a+)+");
+        let input = generate_redos_input(r"(a+)+", &[]);
         assert!(input.contains('a'));
         assert!(input.len() > 10);
     }
+
+    #[test]
+    fn test_redos_templates() {
+        let templates = parse_redos_templates(
+            r#"[{"fragment": "(a+)+", "input": "aaaa!"}, {"fragment": "(\\w+\\s?)+", "input": "word word word!"}]"#,
+        )
+        .unwrap();
+        assert_eq!(templates.len(), 2);
+        assert_eq!(templates[1].fragment, r"(\w+\s?)+");
+
+        // Templates take precedence over the builtin list
+        assert_eq!(generate_redos_input(r"^(a+)+$", &templates), "aaaa!");
+        assert_eq!(
+            generate_redos_input(r"^(\w+\s?)+$", &templates),
+            "word word word!"
+        );
+        assert_eq!(
+            generate_redos_input(r"(a|aa)+", &templates),
+            generate_redos_input(r"(a|aa)+", &[])
+        );
+
+        let err = parse_redos_templates(r#"[{"fragment": "(a+)+"}]"#).unwrap_err();
+        assert!(err.starts_with("Invalid ReDoS templates JSON: missing field `input`"));
+        let err = parse_redos_templates(r#"[{"fragment": "", "input": "x"}]"#).unwrap_err();
+        assert_eq!(err, "Invalid ReDoS template 1: fragment must not be empty");
+        assert!(parse_redos_templates(r#"{"fragment": "a", "input": "b"}"#).is_err());
+    }
 }
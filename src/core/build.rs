@@ -0,0 +1,411 @@
+//! Implementation of `re-x build` command
+//!
+//! Renders a pattern from a JSON description of its syntax tree, so tooling
+//! can construct patterns structurally instead of concatenating strings.
+//!
+//! Nodes are objects tagged by `type`:
+//! - `{"type": "literal", "value": "a.b"}` — text matched verbatim (escaped as needed)
+//! - `{"type": "dot"}` — any character
+//! - `{"type": "perl", "class": "digit" | "space" | "word", "negated": false}`
+//! - `{"type": "class", "items": [...], "negated": false}` where items are
+//!   `{"type": "char", "value": "x"}`, `{"type": "range", "from": "a", "to": "z"}`
+//!   or a `perl` node
+//! - `{"type": "anchor", "kind": "start_line" | "end_line" | "start_text" | "end_text" | "word_boundary" | "not_word_boundary"}`
+//! - `{"type": "group", "node": ..., "capture": true, "name": "year"}`
+//! - `{"type": "concat", "nodes": [...]}` and `{"type": "alternation", "nodes": [...]}`
+//! - `{"type": "repeat", "node": ..., "min": 1, "max": null, "greedy": true}`
+//!
+//! The tree is converted to a `regex_syntax` AST and printed with its
+//! `Display` impl. Non-capturing groups are added wherever precedence needs
+//! them, and capture groups are numbered in order of appearance. A `name`
+//! requires a capturing group.
+
+use regex_syntax::ast::{self, Ast, Position, Span};
+use serde::Deserialize;
+
+use super::engine::EngineType;
+use crate::output::BuildResult;
+
+/// A node of the JSON syntax tree accepted by `re-x build`
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AstNode {
+    Literal {
+        value: String,
+    },
+    Dot,
+    Perl {
+        class: PerlClass,
+        #[serde(default)]
+        negated: bool,
+    },
+    Class {
+        items: Vec<ClassItem>,
+        #[serde(default)]
+        negated: bool,
+    },
+    Anchor {
+        kind: AnchorKind,
+    },
+    Group {
+        node: Box<AstNode>,
+        #[serde(default = "default_true")]
+        capture: bool,
+        #[serde(default)]
+        name: Option<String>,
+    },
+    Concat {
+        nodes: Vec<AstNode>,
+    },
+    Alternation {
+        nodes: Vec<AstNode>,
+    },
+    Repeat {
+        node: Box<AstNode>,
+        #[serde(default)]
+        min: u32,
+        #[serde(default)]
+        max: Option<u32>,
+        #[serde(default = "default_true")]
+        greedy: bool,
+    },
+}
+
+/// A member of a bracketed class
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClassItem {
+    Char {
+        value: char,
+    },
+    Range {
+        from: char,
+        to: char,
+    },
+    Perl {
+        class: PerlClass,
+        #[serde(default)]
+        negated: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PerlClass {
+    Digit,
+    Space,
+    Word,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnchorKind {
+    StartLine,
+    EndLine,
+    StartText,
+    EndText,
+    WordBoundary,
+    NotWordBoundary,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Build a pattern from a JSON syntax tree and check that it compiles.
+///
+/// The tree only describes standard syntax, so the pattern must compile with
+/// the standard engine; there is no fancy-regex fallback.
+pub fn build_pattern(json: &str) -> Result<BuildResult, String> {
+    let node: AstNode =
+        serde_json::from_str(json).map_err(|e| format!("Invalid AST JSON: {}", e))?;
+
+    let ast = Builder::default().build(&node)?;
+    let pattern = ast.to_string();
+
+    regex::Regex::new(&pattern)
+        .map_err(|e| format!("Built pattern '{}' does not compile: {}", pattern, e))?;
+
+    Ok(BuildResult {
+        pattern,
+        engine: EngineType::Regex.to_string(),
+    })
+}
+
+/// Converts JSON nodes to `regex_syntax` AST nodes, numbering capture groups
+#[derive(Default)]
+struct Builder {
+    captures: u32,
+}
+
+impl Builder {
+    fn build(&mut self, node: &AstNode) -> Result<Ast, String> {
+        Ok(match node {
+            AstNode::Literal { value } => {
+                let mut chars: Vec<Ast> = value.chars().map(literal).collect();
+                match chars.len() {
+                    0 => Ast::empty(span()),
+                    1 => chars.remove(0),
+                    _ => Ast::concat(ast::Concat {
+                        span: span(),
+                        asts: chars,
+                    }),
+                }
+            }
+            AstNode::Dot => Ast::dot(span()),
+            AstNode::Perl { class, negated } => Ast::class_perl(perl(*class, *negated)),
+            AstNode::Class { items, negated } => {
+                if items.is_empty() {
+                    return Err("A class needs at least one item".to_string());
+                }
+                let items = items.iter().map(class_item).collect::<Result<_, _>>()?;
+                Ast::class_bracketed(ast::ClassBracketed {
+                    span: span(),
+                    negated: *negated,
+                    kind: ast::ClassSet::union(ast::ClassSetUnion {
+                        span: span(),
+                        items,
+                    }),
+                })
+            }
+            AstNode::Anchor { kind } => Ast::assertion(ast::Assertion {
+                span: span(),
+                kind: match kind {
+                    AnchorKind::StartLine => ast::AssertionKind::StartLine,
+                    AnchorKind::EndLine => ast::AssertionKind::EndLine,
+                    AnchorKind::StartText => ast::AssertionKind::StartText,
+                    AnchorKind::EndText => ast::AssertionKind::EndText,
+                    AnchorKind::WordBoundary => ast::AssertionKind::WordBoundary,
+                    AnchorKind::NotWordBoundary => ast::AssertionKind::NotWordBoundary,
+                },
+            }),
+            AstNode::Group {
+                node,
+                capture,
+                name,
+            } => {
+                // Number the group before its children, in order of its open paren
+                let kind = match name {
+                    Some(name) if !*capture => {
+                        return Err(format!(
+                            "Group '{}' has a name but is not capturing; drop the name or set capture to true",
+                            name
+                        ))
+                    }
+                    Some(name) => {
+                        self.captures += 1;
+                        ast::GroupKind::CaptureName {
+                            starts_with_p: false,
+                            name: ast::CaptureName {
+                                span: span(),
+                                name: name.clone(),
+                                index: self.captures,
+                            },
+                        }
+                    }
+                    None if *capture => {
+                        self.captures += 1;
+                        ast::GroupKind::CaptureIndex(self.captures)
+                    }
+                    None => return Ok(non_capturing(self.build(node)?)),
+                };
+                Ast::group(ast::Group {
+                    span: span(),
+                    kind,
+                    ast: Box::new(self.build(node)?),
+                })
+            }
+            AstNode::Concat { nodes } => {
+                let asts = nodes
+                    .iter()
+                    .map(|node| {
+                        let ast = self.build(node)?;
+                        Ok(if matches!(ast, Ast::Alternation(_)) {
+                            non_capturing(ast)
+                        } else {
+                            ast
+                        })
+                    })
+                    .collect::<Result<Vec<_>, String>>()?;
+                Ast::concat(ast::Concat { span: span(), asts })
+            }
+            AstNode::Alternation { nodes } => {
+                if nodes.len() < 2 {
+                    return Err("An alternation needs at least two nodes".to_string());
+                }
+                let asts = nodes
+                    .iter()
+                    .map(|node| self.build(node))
+                    .collect::<Result<_, _>>()?;
+                Ast::alternation(ast::Alternation { span: span(), asts })
+            }
+            AstNode::Repeat {
+                node,
+                min,
+                max,
+                greedy,
+            } => {
+                let kind = repetition_kind(*min, *max)?;
+                let inner = self.build(node)?;
+                let inner = match inner {
+                    Ast::Empty(_)
+                    | Ast::Concat(_)
+                    | Ast::Alternation(_)
+                    | Ast::Repetition(_)
+                    | Ast::Flags(_) => non_capturing(inner),
+                    _ => inner,
+                };
+                Ast::repetition(ast::Repetition {
+                    span: span(),
+                    op: ast::RepetitionOp { span: span(), kind },
+                    greedy: *greedy,
+                    ast: Box::new(inner),
+                })
+            }
+        })
+    }
+}
+
+fn repetition_kind(min: u32, max: Option<u32>) -> Result<ast::RepetitionKind, String> {
+    use ast::{RepetitionKind, RepetitionRange};
+
+    Ok(match (min, max) {
+        (_, Some(max)) if max < min => {
+            return Err(format!("Repeat max ({}) is less than min ({})", max, min))
+        }
+        (0, None) => RepetitionKind::ZeroOrMore,
+        (1, None) => RepetitionKind::OneOrMore,
+        (0, Some(1)) => RepetitionKind::ZeroOrOne,
+        (min, None) => RepetitionKind::Range(RepetitionRange::AtLeast(min)),
+        (min, Some(max)) if min == max => RepetitionKind::Range(RepetitionRange::Exactly(min)),
+        (min, Some(max)) => RepetitionKind::Range(RepetitionRange::Bounded(min, max)),
+    })
+}
+
+fn class_item(item: &ClassItem) -> Result<ast::ClassSetItem, String> {
+    Ok(match item {
+        ClassItem::Char { value } => ast::ClassSetItem::Literal(class_literal(*value)),
+        ClassItem::Range { from, to } => {
+            if from > to {
+                return Err(format!("Class range {}-{} is out of order", from, to));
+            }
+            ast::ClassSetItem::Range(ast::ClassSetRange {
+                span: span(),
+                start: class_literal(*from),
+                end: class_literal(*to),
+            })
+        }
+        ClassItem::Perl { class, negated } => ast::ClassSetItem::Perl(perl(*class, *negated)),
+    })
+}
+
+/// A literal, escaped if the character is special outside a class
+fn literal(c: char) -> Ast {
+    Ast::literal(class_literal(c))
+}
+
+/// A literal, escaped if the character is special anywhere (also covers
+/// `-`, `&` and `~` inside a class)
+fn class_literal(c: char) -> ast::Literal {
+    ast::Literal {
+        span: span(),
+        kind: if regex_syntax::is_meta_character(c) {
+            ast::LiteralKind::Meta
+        } else {
+            ast::LiteralKind::Verbatim
+        },
+        c,
+    }
+}
+
+fn perl(class: PerlClass, negated: bool) -> ast::ClassPerl {
+    ast::ClassPerl {
+        span: span(),
+        kind: match class {
+            PerlClass::Digit => ast::ClassPerlKind::Digit,
+            PerlClass::Space => ast::ClassPerlKind::Space,
+            PerlClass::Word => ast::ClassPerlKind::Word,
+        },
+        negated,
+    }
+}
+
+fn non_capturing(inner: Ast) -> Ast {
+    Ast::group(ast::Group {
+        span: span(),
+        kind: ast::GroupKind::NonCapturing(ast::Flags {
+            span: span(),
+            items: Vec::new(),
+        }),
+        ast: Box::new(inner),
+    })
+}
+
+/// Built nodes have no source text, so every span is empty
+fn span() -> Span {
+    Span::splat(Position::new(0, 1, 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(json: &str) -> String {
+        build_pattern(json).unwrap().pattern
+    }
+
+    #[test]
+    fn test_build_escapes_literals() {
+        assert_eq!(
+            build(r#"{"type": "literal", "value": "1.5+x"}"#),
+            r"1\.5\+x"
+        );
+        assert_eq!(
+            build(
+                r#"{"type": "class", "negated": true, "items": [
+                    {"type": "range", "from": "a", "to": "z"},
+                    {"type": "char", "value": "-"},
+                    {"type": "perl", "class": "digit"}
+                ]}"#
+            ),
+            r"[^a-z\-\d]"
+        );
+    }
+
+    #[test]
+    fn test_build_adds_groups_for_precedence() {
+        let json = r#"{"type": "concat", "nodes": [
+            {"type": "anchor", "kind": "start_line"},
+            {"type": "group", "name": "year", "node": {"type": "repeat", "min": 4, "max": 4, "node": {"type": "perl", "class": "digit"}}},
+            {"type": "repeat", "min": 0, "node": {"type": "literal", "value": "ab"}},
+            {"type": "alternation", "nodes": [{"type": "literal", "value": "x"}, {"type": "dot"}]},
+            {"type": "group", "node": {"type": "repeat", "min": 1, "greedy": false, "node": {"type": "dot"}}}
+        ]}"#;
+        let result = build_pattern(json).unwrap();
+        assert_eq!(result.pattern, r"^(?<year>\d{4})(?:ab)*(?:x|.)(.+?)");
+        assert_eq!(result.engine, "regex");
+    }
+
+    #[test]
+    fn test_build_rejects_invalid_trees() {
+        assert!(build_pattern(r#"{"type": "bogus"}"#).is_err());
+        assert!(build_pattern(r#"{"type": "class", "items": []}"#).is_err());
+        assert!(build_pattern(
+            r#"{"type": "repeat", "min": 3, "max": 1, "node": {"type": "dot"}}"#
+        )
+        .is_err());
+        // Parses, but the group name is not valid for either engine
+        let err = build_pattern(r#"{"type": "group", "name": "a b", "node": {"type": "dot"}}"#)
+            .unwrap_err();
+        assert!(err.contains("does not compile"), "{}", err);
+        // Accepted by fancy-regex only
+        let err = build_pattern(r#"{"type": "group", "name": "1bad", "node": {"type": "dot"}}"#)
+            .unwrap_err();
+        assert!(err.contains("does not compile"), "{}", err);
+        let err = build_pattern(
+            r#"{"type": "group", "capture": false, "name": "x", "node": {"type": "dot"}}"#,
+        )
+        .unwrap_err();
+        assert!(err.contains("not capturing"), "{}", err);
+    }
+}
@@ -0,0 +1,299 @@
+//! Implementation of `re-x generate-corpus` command
+//!
+//! Walks the pattern's HIR to build strings it matches, then perturbs exactly
+//! one character of a match to fall outside its class to build near misses.
+//! Generation uses a fixed seed, so the same pattern yields the same corpus.
+//!
+//! Only patterns the standard `regex` engine accepts are supported.
+
+use std::collections::HashSet;
+
+use regex_syntax::hir::{Class, Hir, HirKind};
+
+use super::engine::{CompiledRegex, CoreError};
+use crate::output::CorpusResult;
+
+/// Extra repetitions generated beyond a quantifier's minimum
+const MAX_EXTRA_REPEATS: u32 = 3;
+
+/// Attempts per requested string before giving up on finding distinct ones
+const ATTEMPTS_PER_STRING: usize = 20;
+
+/// Options for corpus generation
+pub struct CorpusOptions {
+    /// Number of matching strings (and near misses) to generate
+    pub count: usize,
+    /// Also generate non-matching strings one character away from a match
+    pub include_near_misses: bool,
+}
+
+impl Default for CorpusOptions {
+    fn default() -> Self {
+        Self {
+            count: 10,
+            include_near_misses: false,
+        }
+    }
+}
+
+/// Generate strings the pattern matches, and optionally near misses it rejects
+pub fn generate_corpus(pattern: &str, options: &CorpusOptions) -> Result<CorpusResult, CoreError> {
+    let (compiled, _) = CompiledRegex::new(pattern).map_err(CoreError::invalid_pattern)?;
+    let hir = regex_syntax::parse(pattern).map_err(|_| {
+        "generate-corpus only supports patterns the standard regex engine accepts (no lookaround or backreferences)"
+            .to_string()
+    })?;
+
+    let mut rng = Rng::new();
+    let mut positives = Vec::new();
+    let mut negatives = Vec::new();
+    let mut seen = HashSet::new();
+    let mut generated = Vec::new();
+
+    for _ in 0..options.count * ATTEMPTS_PER_STRING {
+        if positives.len() >= options.count {
+            break;
+        }
+        let mut sample = Sample::default();
+        if !sample.generate(&hir, &mut rng) {
+            continue;
+        }
+        let text: String = sample.chars.iter().collect();
+        if compiled.is_match(&text).unwrap_or(false) && seen.insert(text.clone()) {
+            positives.push(text);
+            generated.push(sample);
+        }
+    }
+
+    if options.include_near_misses && !generated.is_empty() {
+        for attempt in 0..options.count * ATTEMPTS_PER_STRING {
+            if negatives.len() >= options.count {
+                break;
+            }
+            let sample = &generated[attempt % generated.len()];
+            let Some(text) = sample.mutate(&mut rng) else {
+                continue;
+            };
+            if !compiled.is_match(&text).unwrap_or(true) && seen.insert(text.clone()) {
+                negatives.push(text);
+            }
+        }
+    }
+
+    let mut shortfalls = Vec::new();
+    if positives.len() < options.count {
+        shortfalls.push(format!("{} distinct matching strings", positives.len()));
+    }
+    if options.include_near_misses && negatives.len() < options.count {
+        shortfalls.push(format!("{} distinct near misses", negatives.len()));
+    }
+    let note = (!shortfalls.is_empty())
+        .then(|| format!("Only {} could be generated", shortfalls.join(" and ")));
+
+    Ok(CorpusResult {
+        pattern: pattern.to_string(),
+        positives,
+        negatives,
+        note,
+    })
+}
+
+/// A generated string, with the characters each position may take
+#[derive(Default)]
+struct Sample {
+    chars: Vec<char>,
+    /// Accepted ranges for each character in `chars`
+    slots: Vec<Vec<(char, char)>>,
+}
+
+impl Sample {
+    /// Append a string matching `hir`; false if some class has no usable character
+    fn generate(&mut self, hir: &Hir, rng: &mut Rng) -> bool {
+        match hir.kind() {
+            HirKind::Empty | HirKind::Look(_) => true,
+            HirKind::Literal(literal) => match std::str::from_utf8(&literal.0) {
+                Ok(text) => {
+                    for c in text.chars() {
+                        self.push(c, vec![(c, c)]);
+                    }
+                    true
+                }
+                Err(_) => false,
+            },
+            HirKind::Class(class) => {
+                let ranges = class_ranges(class);
+                match pick_printable(&ranges, rng) {
+                    Some(c) => {
+                        self.push(c, ranges);
+                        true
+                    }
+                    None => false,
+                }
+            }
+            HirKind::Repetition(rep) => {
+                let max = rep.max.unwrap_or(u32::MAX);
+                let extra = max.saturating_sub(rep.min).min(MAX_EXTRA_REPEATS);
+                let times = rep.min + rng.below(extra as usize + 1) as u32;
+                (0..times).all(|_| self.generate(&rep.sub, rng))
+            }
+            HirKind::Capture(capture) => self.generate(&capture.sub, rng),
+            HirKind::Concat(parts) => parts.iter().all(|part| self.generate(part, rng)),
+            HirKind::Alternation(branches) => {
+                self.generate(&branches[rng.below(branches.len())], rng)
+            }
+        }
+    }
+
+    fn push(&mut self, c: char, ranges: Vec<(char, char)>) {
+        self.chars.push(c);
+        self.slots.push(ranges);
+    }
+
+    /// Replace one character with one its position does not accept
+    fn mutate(&self, rng: &mut Rng) -> Option<String> {
+        if self.chars.is_empty() {
+            return None;
+        }
+        let start = rng.below(self.chars.len());
+        (0..self.chars.len())
+            .map(|offset| (start + offset) % self.chars.len())
+            .find_map(|index| {
+                let replacement = pick_outside(&self.slots[index], rng)?;
+                let mut chars = self.chars.clone();
+                chars[index] = replacement;
+                Some(chars.into_iter().collect())
+            })
+    }
+}
+
+/// The ranges of a class as chars (byte classes keep their ASCII part)
+fn class_ranges(class: &Class) -> Vec<(char, char)> {
+    match class {
+        Class::Unicode(class) => class
+            .ranges()
+            .iter()
+            .map(|range| (range.start(), range.end()))
+            .collect(),
+        Class::Bytes(class) => class
+            .ranges()
+            .iter()
+            .filter(|range| range.start().is_ascii())
+            .map(|range| (range.start() as char, range.end().min(0x7F) as char))
+            .collect(),
+    }
+}
+
+fn in_ranges(ranges: &[(char, char)], c: char) -> bool {
+    ranges.iter().any(|&(start, end)| start <= c && c <= end)
+}
+
+/// Printable ASCII characters, which keep generated strings readable
+fn printable() -> impl Iterator<Item = char> {
+    ' '..='~'
+}
+
+/// Pick a character from the class, preferring printable ASCII
+fn pick_printable(ranges: &[(char, char)], rng: &mut Rng) -> Option<char> {
+    let candidates: Vec<char> = printable().filter(|&c| in_ranges(ranges, c)).collect();
+    if candidates.is_empty() {
+        ranges.first().map(|&(start, _)| start)
+    } else {
+        Some(candidates[rng.below(candidates.len())])
+    }
+}
+
+/// Pick a character outside the class, preferring printable ASCII
+fn pick_outside(ranges: &[(char, char)], rng: &mut Rng) -> Option<char> {
+    let candidates: Vec<char> = printable()
+        .chain(['\n'])
+        .filter(|&c| !in_ranges(ranges, c))
+        .collect();
+    if candidates.is_empty() {
+        None
+    } else {
+        Some(candidates[rng.below(candidates.len())])
+    }
+}
+
+/// Small deterministic xorshift generator, so corpora (and benchmark
+/// samples) are reproducible
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    const DEFAULT_STATE: u64 = 0x2545_F491_4F6C_DD1D;
+
+    pub(crate) fn new() -> Self {
+        Self(Self::DEFAULT_STATE)
+    }
+
+    /// A generator whose sequence is determined by `seed` (0 gives the
+    /// same sequence as [`Rng::new`])
+    pub(crate) fn with_seed(seed: u64) -> Self {
+        match Self::DEFAULT_STATE ^ seed {
+            // xorshift never leaves the all-zero state
+            0 => Self::new(),
+            state => Self(state),
+        }
+    }
+
+    /// A value in `0..n` (`n` must be non-zero)
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % n as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_positives_match() {
+        let options = CorpusOptions {
+            count: 20,
+            ..Default::default()
+        };
+        let result = generate_corpus(r"\d+-\w+", &options).unwrap();
+        let re = regex::Regex::new(r"^\d+-\w+$").unwrap();
+        assert_eq!(result.positives.len(), 20);
+        assert!(result.positives.iter().all(|s| re.is_match(s)));
+        assert!(result.negatives.is_empty());
+    }
+
+    #[test]
+    fn test_near_misses_rejected() {
+        let options = CorpusOptions {
+            count: 10,
+            include_near_misses: true,
+        };
+        let result = generate_corpus(r"^[a-f]{3}\d$", &options).unwrap();
+        let re = regex::Regex::new(r"^[a-f]{3}\d$").unwrap();
+        assert_eq!(result.negatives.len(), 10);
+        for negative in &result.negatives {
+            assert!(!re.is_match(negative), "{}", negative);
+            assert_eq!(negative.chars().count(), 4, "one character changed");
+        }
+    }
+
+    #[test]
+    fn test_small_language_reports_shortfall() {
+        let result = generate_corpus("^(?:yes|no)$", &CorpusOptions::default()).unwrap();
+        assert_eq!(result.positives.len(), 2);
+        assert!(result.note.is_some());
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let options = CorpusOptions::default();
+        let first = generate_corpus(r"[a-z]+\d{2}", &options).unwrap();
+        let second = generate_corpus(r"[a-z]+\d{2}", &options).unwrap();
+        assert_eq!(first.positives, second.positives);
+    }
+
+    #[test]
+    fn test_rejects_fancy_pattern() {
+        assert!(generate_corpus(r"(\w)\1", &CorpusOptions::default()).is_err());
+    }
+}
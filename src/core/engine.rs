@@ -7,10 +7,28 @@ use std::sync::LazyLock;
 
 use thiserror::Error;
 
+use crate::output::CaptureInfo;
+
 static BACKREFERENCE_RE: LazyLock<regex::Regex> = LazyLock::new(|| {
     regex::Regex::new(r"\\[1-9]").expect("BUG: backreference detection pattern is invalid")
 });
 
+/// Default limit on pattern nesting depth (groups, repetitions, classes).
+///
+/// Patterns nested deeper than this are rejected by [`parse_ast`], which every
+/// recursive AST walk (explain, validate, portability) parses through, so
+/// untrusted input cannot exhaust the stack. This is deliberately stricter
+/// than regex-syntax's own default of 250: the walkers here recurse several
+/// frames per level and also run on MCP worker threads with smaller stacks.
+/// Raise it with `--max-depth` when a legitimate pattern needs more.
+pub const DEFAULT_MAX_DEPTH: u32 = 128;
+
+/// Default limit on the number of capture groups in a pattern.
+///
+/// Every match carries one entry per participating group, so a pattern with
+/// thousands of groups makes each match expensive to collect.
+pub const DEFAULT_MAX_GROUPS: usize = 1_000;
+
 /// Engine types
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EngineType {
@@ -30,7 +48,7 @@ impl std::fmt::Display for EngineType {
 }
 
 /// Errors that can occur during engine operations
-#[allow(dead_code, clippy::result_large_err)]
+#[allow(clippy::result_large_err)]
 #[derive(Error, Debug)]
 pub enum EngineError {
     #[error("Invalid regex pattern: {0}")]
@@ -46,6 +64,50 @@ pub enum EngineError {
     FancyRegexError(#[from] fancy_regex::Error),
 }
 
+/// Failure of a core operation, split by cause so the CLI can choose an exit
+/// code without re-inspecting the pattern or matching on the message
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum CoreError {
+    /// The pattern does not parse or compile, or exceeds a structural limit
+    #[error("{0}")]
+    InvalidPattern(String),
+
+    /// The pattern can only match the empty string (see `validate_runnable_pattern`)
+    #[error("empty pattern matches everywhere; refusing (pass --allow-empty to run it anyway)")]
+    EmptyPattern,
+
+    /// The pattern declares more capture groups than allowed (see `check_group_limit`)
+    #[error("pattern has too many capture groups: {groups} (limit: {limit}, see --max-groups)")]
+    TooManyGroups { groups: usize, limit: usize },
+
+    /// The work overran its wall-clock budget and was abandoned
+    #[error("Timed out: no result within {millis}ms; the work was abandoned")]
+    Timeout { millis: u128 },
+
+    /// Anything else: I/O, bad arguments, conflicting options
+    #[error("{0}")]
+    Failed(String),
+}
+
+impl CoreError {
+    /// Wrap a compilation or parse error of the pattern
+    pub fn invalid_pattern(err: impl std::fmt::Display) -> Self {
+        CoreError::InvalidPattern(err.to_string())
+    }
+}
+
+impl From<String> for CoreError {
+    fn from(message: String) -> Self {
+        CoreError::Failed(message)
+    }
+}
+
+impl From<&str> for CoreError {
+    fn from(message: &str) -> Self {
+        CoreError::Failed(message.to_string())
+    }
+}
+
 /// Features detected in a pattern that require fancy-regex
 #[derive(Debug, Default)]
 pub struct FancyFeatures {
@@ -127,32 +189,84 @@ pub enum CompiledRegex {
     FancyRegex(fancy_regex::Regex),
 }
 
-#[allow(dead_code, clippy::result_large_err)]
+/// A pattern compiled by [`CompiledRegex::compile`], with how its engine was chosen
+pub struct Compilation {
+    pub regex: CompiledRegex,
+    pub engine: EngineType,
+    /// Set when the pattern uses no fancy-regex features but the `regex`
+    /// crate rejected it anyway, so it silently fell back to fancy-regex:
+    /// why the `regex` crate refused it
+    pub fallback: Option<String>,
+}
+
+/// Describe why a pattern that needs no fancy-regex features still fell
+/// back to it, from the `regex` crate's compile error
+pub fn fallback_reason(err: &regex::Error) -> String {
+    let cause = match err {
+        // Syntax errors span several lines (pattern, caret); keep the last
+        regex::Error::Syntax(message) => message
+            .lines()
+            .rev()
+            .find_map(|line| line.strip_prefix("error: "))
+            .unwrap_or(message)
+            .to_string(),
+        other => other.to_string(),
+    };
+    format!(
+        "Fell back to fancy-regex because the regex crate rejected the pattern ({}); \
+         matching is no longer guaranteed linear time",
+        cause
+    )
+}
+
+#[allow(clippy::result_large_err)]
 impl CompiledRegex {
-    /// Compile a pattern with automatic engine selection
+    /// Compile a pattern with automatic engine selection.
+    ///
+    /// The pattern is normalized first (see [`normalize_pattern`]).
     pub fn new(pattern: &str) -> Result<(Self, EngineType), EngineError> {
+        Self::compile(pattern).map(|compiled| (compiled.regex, compiled.engine))
+    }
+
+    /// Like [`CompiledRegex::new`], but also reports whether the pattern
+    /// fell back to fancy-regex after the `regex` crate rejected it
+    pub fn compile(pattern: &str) -> Result<Compilation, EngineError> {
+        let normalized = normalize_pattern(pattern);
+        let pattern = normalized.as_deref().unwrap_or(pattern);
         let (engine, _features) = select_engine(pattern);
 
         match engine {
-            EngineType::Regex => {
-                match regex::Regex::new(pattern) {
-                    Ok(re) => Ok((CompiledRegex::Regex(re), EngineType::Regex)),
-                    Err(_) => {
-                        // Fall back to fancy-regex if standard regex fails
-                        let re = fancy_regex::Regex::new(pattern)?;
-                        Ok((CompiledRegex::FancyRegex(re), EngineType::FancyRegex))
-                    }
+            EngineType::Regex => match regex::Regex::new(pattern) {
+                Ok(re) => Ok(Compilation {
+                    regex: CompiledRegex::Regex(re),
+                    engine: EngineType::Regex,
+                    fallback: None,
+                }),
+                Err(err) => {
+                    // Fall back to fancy-regex if standard regex fails
+                    let re = fancy_regex::Regex::new(pattern)?;
+                    Ok(Compilation {
+                        regex: CompiledRegex::FancyRegex(re),
+                        engine: EngineType::FancyRegex,
+                        fallback: Some(fallback_reason(&err)),
+                    })
                 }
-            }
+            },
             EngineType::FancyRegex => {
                 let re = fancy_regex::Regex::new(pattern)?;
-                Ok((CompiledRegex::FancyRegex(re), EngineType::FancyRegex))
+                Ok(Compilation {
+                    regex: CompiledRegex::FancyRegex(re),
+                    engine: EngineType::FancyRegex,
+                    fallback: None,
+                })
             }
         }
     }
 
     /// Compile with a specific engine
     pub fn with_engine(pattern: &str, engine: EngineType) -> Result<Self, EngineError> {
+        let normalized = normalize_pattern(pattern);
+        let pattern = normalized.as_deref().unwrap_or(pattern);
         match engine {
             EngineType::Regex => {
                 let re = regex::Regex::new(pattern)?;
@@ -165,6 +279,19 @@ impl CompiledRegex {
         }
     }
 
+    /// Compile for matching raw bytes (`--byte-mode`) with the `regex` crate's
+    /// byte API. fancy-regex cannot search bytes, so patterns that need it
+    /// are rejected.
+    pub fn bytes(pattern: &str) -> Result<regex::bytes::Regex, EngineError> {
+        let normalized = normalize_pattern(pattern);
+        let pattern = normalized.as_deref().unwrap_or(pattern);
+        let (_, features) = select_engine(pattern);
+        if let Some(reason) = features.reason() {
+            return Err(EngineError::RequiresFancy(reason));
+        }
+        Ok(regex::bytes::Regex::new(pattern)?)
+    }
+
     /// Check if the pattern matches anywhere in the text
     pub fn is_match(&self, text: &str) -> Result<bool, EngineError> {
         match self {
@@ -193,47 +320,378 @@ impl CompiledRegex {
     }
 }
 
-/// Detect whether a pattern contains any capturing groups by walking the regex AST.
-/// Falls back to `true` (conservative — always collect captures) for patterns that
-/// `regex_syntax` cannot parse (e.g., fancy-regex-only features like lookahead).
-pub fn has_capturing_groups(pattern: &str) -> bool {
+/// Rewrite foreign syntax into something both engines compile: `\Q...\E`
+/// blocks are expanded, then `(?#...)` comments stripped.
+///
+/// Returns `None` if nothing changed, so callers can report the rewrite as
+/// `normalized_pattern`.
+pub fn normalize_pattern(pattern: &str) -> Option<String> {
+    let expanded = expand_quoted_literals(pattern);
+    strip_inline_comments(expanded.as_deref().unwrap_or(pattern)).or(expanded)
+}
+
+/// Expand Java/PCRE `\Q...\E` literal blocks into escaped literals.
+///
+/// Everything between `\Q` and the next `\E` (or the end of the pattern) is
+/// matched literally; neither engine supports the syntax natively. Whitespace
+/// is written as `\x{..}` so the literal survives free-spacing mode. Inside a
+/// character class `\Q` is left for the engine to report, so `[\Q]` is not
+/// mangled. Returns `None` if the pattern has no `\Q`, so callers can report
+/// the rewrite.
+pub fn expand_quoted_literals(pattern: &str) -> Option<String> {
+    if !pattern.contains("\\Q") {
+        return None;
+    }
+
+    let mut expanded = String::with_capacity(pattern.len());
+    let mut rest = pattern;
+    let mut class_depth = 0usize;
+    let mut changed = false;
+    while let Some(c) = rest.chars().next() {
+        if c == '\\' {
+            if let Some(quoted) = rest.strip_prefix("\\Q").filter(|_| class_depth == 0) {
+                let (literal, after) = quoted.split_once("\\E").unwrap_or((quoted, ""));
+                for c in literal.chars() {
+                    if c.is_whitespace() {
+                        expanded.push_str(&format!("\\x{{{:X}}}", c as u32));
+                    } else {
+                        expanded.push_str(&regex::escape(c.encode_utf8(&mut [0; 4])));
+                    }
+                }
+                rest = after;
+                changed = true;
+                continue;
+            }
+            // Copy the escape whole so `\\Q` stays an escaped backslash
+            let len = 1 + rest[1..].chars().next().map_or(0, char::len_utf8);
+            expanded.push_str(&rest[..len]);
+            rest = &rest[len..];
+            continue;
+        }
+
+        expanded.push(c);
+        rest = &rest[c.len_utf8()..];
+        match c {
+            '[' => {
+                class_depth += 1;
+                let len = class_prefix_len(rest);
+                expanded.push_str(&rest[..len]);
+                rest = &rest[len..];
+            }
+            ']' if class_depth > 0 => class_depth -= 1,
+            _ => {}
+        }
+    }
+
+    changed.then_some(expanded)
+}
+
+/// Remove PCRE/Perl inline comments, `(?#...)`.
+///
+/// fancy-regex understands them but the `regex` crate does not, which would
+/// force otherwise simple patterns onto the backtracking engine. A comment
+/// ends at the first unescaped `)`. Escapes and character classes are copied
+/// untouched, so `\(?#` and `[(?#]` are not comments; an unterminated comment
+/// is left in place for the engine to report. Returns `None` if the pattern
+/// has no comments.
+pub fn strip_inline_comments(pattern: &str) -> Option<String> {
+    if !pattern.contains("(?#") {
+        return None;
+    }
+
+    let mut stripped = String::with_capacity(pattern.len());
+    let mut rest = pattern;
+    let mut class_depth = 0usize;
+    let mut changed = false;
+    while let Some(c) = rest.chars().next() {
+        if c == '\\' {
+            // Copy the escape whole so `\(` stays a literal parenthesis
+            let len = 1 + rest[1..].chars().next().map_or(0, char::len_utf8);
+            stripped.push_str(&rest[..len]);
+            rest = &rest[len..];
+            continue;
+        }
+        if class_depth == 0 {
+            if let Some(end) = rest.strip_prefix("(?#").and_then(comment_end) {
+                rest = &rest[3 + end + 1..];
+                changed = true;
+                continue;
+            }
+        }
+
+        stripped.push(c);
+        rest = &rest[c.len_utf8()..];
+        match c {
+            '[' => {
+                class_depth += 1;
+                let len = class_prefix_len(rest);
+                stripped.push_str(&rest[..len]);
+                rest = &rest[len..];
+            }
+            ']' if class_depth > 0 => class_depth -= 1,
+            _ => {}
+        }
+    }
+
+    changed.then_some(stripped)
+}
+
+/// Length of the `^` and literal `]` that may open a character class body:
+/// a `]` right after the opening bracket (or `[^`) does not close the class
+fn class_prefix_len(body: &str) -> usize {
+    let negated = usize::from(body.starts_with('^'));
+    negated + usize::from(body[negated..].starts_with(']'))
+}
+
+/// Offset of the `)` closing a comment body, skipping escaped characters
+fn comment_end(body: &str) -> Option<usize> {
+    let mut escaped = false;
+    body.char_indices()
+        .find(|&(_, c)| {
+            let end = !escaped && c == ')';
+            escaped = !escaped && c == '\\';
+            end
+        })
+        .map(|(i, _)| i)
+}
+
+/// List the capture groups declared in a pattern, in group-number order.
+///
+/// Walks the regex AST; for patterns that `regex_syntax` cannot parse
+/// (fancy-regex features), falls back to the compiled fancy-regex group names.
+pub fn capture_group_info(pattern: &str) -> Vec<CaptureInfo> {
     use regex_syntax::ast::parse::Parser as AstParser;
     use regex_syntax::ast::{Ast, GroupKind};
 
-    fn walk(ast: &Ast) -> bool {
+    fn walk(ast: &Ast, groups: &mut Vec<CaptureInfo>) {
         match ast {
-            Ast::Group(group) => match &group.kind {
-                GroupKind::CaptureIndex(_) | GroupKind::CaptureName { .. } => true,
-                GroupKind::NonCapturing(_) => walk(&group.ast),
-            },
-            Ast::Concat(concat) => concat.asts.iter().any(walk),
-            Ast::Alternation(alt) => alt.asts.iter().any(walk),
-            Ast::Repetition(rep) => walk(&rep.ast),
-            _ => false,
+            Ast::Group(group) => {
+                match &group.kind {
+                    GroupKind::CaptureIndex(index) => groups.push(CaptureInfo {
+                        index: *index as usize,
+                        name: None,
+                    }),
+                    GroupKind::CaptureName { name, .. } => groups.push(CaptureInfo {
+                        index: name.index as usize,
+                        name: Some(name.name.clone()),
+                    }),
+                    GroupKind::NonCapturing(_) => {}
+                }
+                walk(&group.ast, groups);
+            }
+            Ast::Concat(concat) => concat.asts.iter().for_each(|a| walk(a, groups)),
+            Ast::Alternation(alt) => alt.asts.iter().for_each(|a| walk(a, groups)),
+            Ast::Repetition(rep) => walk(&rep.ast, groups),
+            _ => {}
         }
     }
 
     match AstParser::new().parse(pattern) {
-        Ok(ast) => walk(&ast),
-        Err(_) => true, // fancy-regex pattern — conservatively assume captures exist
+        Ok(ast) => {
+            let mut groups = Vec::new();
+            walk(&ast, &mut groups);
+            groups
+        }
+        Err(_) => match try_fancy_regex(pattern, None) {
+            Ok(re) => re
+                .capture_names()
+                .enumerate()
+                .skip(1)
+                .map(|(index, name)| CaptureInfo {
+                    index,
+                    name: name.map(|n| n.to_string()),
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        },
     }
 }
 
-/// Try to compile with standard regex crate
-pub fn try_regex_crate(pattern: &str) -> Result<regex::Regex, regex::Error> {
-    regex::Regex::new(pattern)
+/// Prepend inline flags such as `ms` or `ms-u` to a pattern.
+///
+/// A bare leading flag group like `(?x)` is merged with the new flags into a
+/// single group (`(?xms)`), unless both mention the same flag.
+pub fn prepend_flags(pattern: &str, flags: &str) -> String {
+    if flags.is_empty() {
+        return pattern.to_string();
+    }
+
+    let leading = pattern.strip_prefix("(?").and_then(|rest| {
+        let end = rest.find(')')?;
+        let existing = &rest[..end];
+        let bare = !existing.is_empty()
+            && existing
+                .chars()
+                .all(|c| c.is_ascii_alphabetic() || c == '-');
+        bare.then(|| (existing, &rest[end + 1..]))
+    });
+
+    if let Some((existing, rest)) = leading {
+        let overlaps = flags.chars().any(|c| c != '-' && existing.contains(c));
+        if !overlaps {
+            let (set_a, clear_a) = existing.split_once('-').unwrap_or((existing, ""));
+            let (set_b, clear_b) = flags.split_once('-').unwrap_or((flags, ""));
+            let merged = if clear_a.is_empty() && clear_b.is_empty() {
+                format!("{}{}", set_a, set_b)
+            } else {
+                format!("{}{}-{}{}", set_a, set_b, clear_a, clear_b)
+            };
+            return format!("(?{}){}", merged, rest);
+        }
+    }
+
+    format!("(?{}){}", flags, pattern)
+}
+
+/// Whether the pattern opens with a flag group enabling free-spacing mode, e.g. `(?x)`
+pub fn is_extended(pattern: &str) -> bool {
+    pattern
+        .strip_prefix("(?")
+        .and_then(|rest| rest.split_once(')'))
+        .is_some_and(|(flags, _)| {
+            flags.chars().all(|c| c.is_ascii_alphabetic() || c == '-')
+                && flags.split('-').next().is_some_and(|set| set.contains('x'))
+        })
 }
 
-/// Try to compile with fancy-regex
+/// Parse a pattern into an AST, rejecting nesting deeper than `max_depth`.
 #[allow(clippy::result_large_err)]
-pub fn try_fancy_regex(pattern: &str) -> Result<fancy_regex::Regex, fancy_regex::Error> {
-    fancy_regex::Regex::new(pattern)
+pub fn parse_ast(
+    pattern: &str,
+    max_depth: u32,
+) -> Result<regex_syntax::ast::Ast, regex_syntax::ast::Error> {
+    regex_syntax::ast::parse::ParserBuilder::new()
+        .nest_limit(max_depth)
+        .build()
+        .parse(pattern)
+}
+
+/// Error message for a pattern nested deeper than `max_depth`, shared by
+/// every command so the guard reads the same wherever it trips
+pub fn nesting_error_message(max_depth: u32) -> String {
+    format!(
+        "pattern nesting too deep (limit: {}, see --max-depth)",
+        max_depth
+    )
+}
+
+/// Whether an AST parse error was caused by exceeding the nesting limit
+pub fn is_nesting_error(err: &regex_syntax::ast::Error) -> bool {
+    matches!(
+        err.kind(),
+        regex_syntax::ast::ErrorKind::NestLimitExceeded(_)
+    )
+}
+
+/// Try to compile with standard regex crate, optionally overriding the
+/// compiled program size limit (default 10 MiB)
+pub fn try_regex_crate(
+    pattern: &str,
+    size_limit: Option<usize>,
+) -> Result<regex::Regex, regex::Error> {
+    let mut builder = regex::RegexBuilder::new(pattern);
+    if let Some(limit) = size_limit {
+        builder.size_limit(limit);
+    }
+    builder.build()
+}
+
+/// Try to compile with fancy-regex; `size_limit` applies to the regex
+/// programs it delegates to
+#[allow(clippy::result_large_err)]
+pub fn try_fancy_regex(
+    pattern: &str,
+    size_limit: Option<usize>,
+) -> Result<fancy_regex::Regex, fancy_regex::Error> {
+    let mut builder = fancy_regex::RegexBuilder::new(pattern);
+    if let Some(limit) = size_limit {
+        builder.delegate_size_limit(limit);
+    }
+    builder.build()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_prepend_flags_merges_leading_group() {
+        assert_eq!(prepend_flags(r"\d+", "ms"), r"(?ms)\d+");
+        assert_eq!(prepend_flags(r"(?x) \d+", "ms"), r"(?xms) \d+");
+        assert_eq!(prepend_flags(r"(?x)a", "ms-u"), r"(?xms-u)a");
+        assert_eq!(prepend_flags(r"(?i-x)a", "m"), r"(?im-x)a");
+        // Overlapping flags and non-flag groups are left as separate groups
+        assert_eq!(prepend_flags(r"(?m)a", "ms"), r"(?ms)(?m)a");
+        assert_eq!(prepend_flags(r"(?x:a)", "ms"), r"(?ms)(?x:a)");
+        assert_eq!(prepend_flags("a", ""), "a");
+    }
+
+    #[test]
+    fn test_expand_quoted_literals() {
+        assert_eq!(expand_quoted_literals(r"a.b"), None);
+        assert_eq!(
+            expand_quoted_literals(r"\Q1+1=2\E\d").as_deref(),
+            Some(r"1\+1=2\d")
+        );
+        // Unterminated block runs to the end; whitespace survives (?x)
+        assert_eq!(
+            expand_quoted_literals(r"(?x)\Qa (b)").as_deref(),
+            Some(r"(?x)a\x{20}\(b\)")
+        );
+        // An escaped backslash before Q is not a quote block
+        assert_eq!(expand_quoted_literals(r"\\Q."), None);
+        // Nor is one inside a character class, which also hides its `]`
+        assert_eq!(expand_quoted_literals(r"[\Q]"), None);
+        assert_eq!(
+            expand_quoted_literals(r"[]\Q]\Q]\E").as_deref(),
+            Some(r"[]\Q]\]")
+        );
+
+        let (re, engine) = CompiledRegex::new(r"\Q(?=x)\E").unwrap();
+        assert_eq!(engine, EngineType::Regex);
+        assert!(re.is_match("(?=x)").unwrap());
+        assert!(!re.is_match("x").unwrap());
+        let re = CompiledRegex::with_engine(r"(?<=\Q$\E)\d", EngineType::FancyRegex).unwrap();
+        assert!(re.is_match("$5").unwrap());
+    }
+
+    #[test]
+    fn test_strip_inline_comments() {
+        assert_eq!(strip_inline_comments(r"a(b)"), None);
+        assert_eq!(
+            strip_inline_comments(r"(?#year)\d{4}-(?#month)\d\d").as_deref(),
+            Some(r"\d{4}-\d\d")
+        );
+        // An escaped parenthesis does not end the comment
+        assert_eq!(strip_inline_comments(r"a(?# \) )b").as_deref(), Some("ab"));
+        // Escapes and character classes are not comments, even with `]` first
+        assert_eq!(strip_inline_comments(r"\(?#x)"), None);
+        assert_eq!(strip_inline_comments(r"[(?#]x)"), None);
+        assert_eq!(
+            strip_inline_comments(r"[]](?#c)[^]](?#c)").as_deref(),
+            Some(r"[]][^]]")
+        );
+        // Unterminated comments are left for the engine to reject
+        assert_eq!(strip_inline_comments(r"a(?#b"), None);
+
+        let (re, engine) = CompiledRegex::new(r"\d+(?# digits)").unwrap();
+        assert_eq!(engine, EngineType::Regex);
+        assert!(re.is_match("42").unwrap());
+        assert_eq!(
+            normalize_pattern(r"\Q(?#\E(?#x)").as_deref(),
+            Some(r"\(\?\#")
+        );
+    }
+
+    #[test]
+    fn test_is_extended() {
+        assert!(is_extended("(?x) a b"));
+        assert!(is_extended("(?xms)a"));
+        assert!(!is_extended("(?i-x)a"));
+        assert!(!is_extended("(?x:a)"));
+        assert!(!is_extended("a(?x)"));
+    }
+
     #[test]
     fn test_simple_pattern_uses_regex() {
         let (engine, _) = select_engine(r"\d+");
@@ -261,6 +719,29 @@ mod tests {
         assert!(features.backreference);
     }
 
+    #[test]
+    fn test_capture_group_info() {
+        let groups = capture_group_info(r"(\d+)-(?P<year>\d{4})(?:x)");
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].index, 1);
+        assert_eq!(groups[0].name, None);
+        assert_eq!(groups[1].index, 2);
+        assert_eq!(groups[1].name.as_deref(), Some("year"));
+
+        // Fancy-only pattern falls back to the compiled group names
+        let groups = capture_group_info(r"(?<=@)(?<word>\w+)");
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].name.as_deref(), Some("word"));
+    }
+
+    #[test]
+    fn test_parse_ast_nesting_limit() {
+        let deep = format!("{}a{}", "(".repeat(20), ")".repeat(20));
+        assert!(parse_ast(&deep, 32).is_ok());
+        let err = parse_ast(&deep, 10).unwrap_err();
+        assert!(is_nesting_error(&err));
+    }
+
     #[test]
     fn test_compile_simple() {
         let (re, engine) = CompiledRegex::new(r"\d+").unwrap();
@@ -275,4 +756,26 @@ mod tests {
         assert!(re.is_match("foobar").unwrap());
         assert!(!re.is_match("foobaz").unwrap());
     }
+
+    #[test]
+    fn test_compile_reports_fallback() {
+        let compiled = CompiledRegex::compile(r"\d+").unwrap();
+        assert!(compiled.fallback.is_none());
+
+        // Chosen up front for the lookahead: not a fallback
+        let compiled = CompiledRegex::compile(r"foo(?=bar)").unwrap();
+        assert_eq!(compiled.engine, EngineType::FancyRegex);
+        assert!(compiled.fallback.is_none());
+
+        // `\G` needs no fancy feature detection, but only fancy-regex knows it
+        let compiled = CompiledRegex::compile(r"\Ga").unwrap();
+        assert_eq!(compiled.engine, EngineType::FancyRegex);
+        assert_eq!(
+            compiled.fallback.as_deref(),
+            Some(
+                "Fell back to fancy-regex because the regex crate rejected the pattern \
+                 (unrecognized escape sequence); matching is no longer guaranteed linear time"
+            )
+        );
+    }
 }
@@ -2,69 +2,158 @@
 //!
 //! Breaks down a regex pattern into its component parts with descriptions.
 
-use regex_syntax::ast::parse::Parser as AstParser;
-use regex_syntax::ast::{self, Ast, ClassPerlKind, ClassUnicodeKind};
+use regex_syntax::ast::{self, Ast, ClassPerlKind, ClassSet, ClassSetItem, ClassUnicodeKind};
 
+use super::engine::{
+    capture_group_info, is_nesting_error, nesting_error_message, normalize_pattern, parse_ast,
+    try_fancy_regex, CoreError, DEFAULT_MAX_DEPTH,
+};
+use super::portability::{canonical_target, leftmost_longest_divergence, target_issues, TARGETS};
 use super::templates::recognize_pattern;
-use crate::output::{ExplainPart, ExplainResult};
+use crate::output::{ExplainPart, ExplainResult, GroupNamesResult, ReadabilityScore};
+
+/// Options for the explain command
+pub struct ExplainOptions {
+    /// Maximum pattern nesting depth accepted before walking the AST
+    pub max_depth: u32,
+    /// Annotate parts that are unsupported by this target language
+    pub target: Option<String>,
+}
+
+impl Default for ExplainOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_MAX_DEPTH,
+            target: None,
+        }
+    }
+}
+
+/// Explain a regex pattern.
+///
+/// The pattern is normalized first (see [`normalize_pattern`]); the parts
+/// explain `normalized_pattern` when it is set.
+pub fn explain_pattern(
+    pattern: &str,
+    options: &ExplainOptions,
+) -> Result<ExplainResult, CoreError> {
+    let normalized = normalize_pattern(pattern);
+    let mut result = explain_normalized(normalized.as_deref().unwrap_or(pattern), options)?;
+    result.pattern = pattern.to_string();
+    result.normalized_pattern = normalized;
+    Ok(result)
+}
+
+/// [`explain_pattern`] for a pattern that is already normalized
+fn explain_normalized(pattern: &str, options: &ExplainOptions) -> Result<ExplainResult, CoreError> {
+    let target = match options.target.as_deref() {
+        Some(name) => Some(canonical_target(name).ok_or_else(|| {
+            format!(
+                "Unknown target language '{}'. Valid options: {}",
+                name,
+                TARGETS.join(", ")
+            )
+        })?),
+        None => None,
+    };
 
-/// Explain a regex pattern
-pub fn explain_pattern(pattern: &str) -> Result<ExplainResult, String> {
     // Check for fancy-regex features first
     let fancy_features = super::engine::detect_fancy_features(pattern);
 
-    if fancy_features.needs_fancy() {
-        return explain_fancy_pattern(pattern, &fancy_features);
+    if fancy_features.needs_fancy() || has_conditional(pattern) {
+        // Sub-patterns are explained recursively, a level per nested group
+        if group_depth(pattern) > options.max_depth as usize {
+            return Err(CoreError::InvalidPattern(format!(
+                "Invalid pattern: {}",
+                nesting_error_message(options.max_depth)
+            )));
+        }
+        let mut result = explain_fancy_pattern(pattern, &fancy_features, options.max_depth)?;
+        if let Some(target) = target {
+            annotate_portability(&mut result.parts, target);
+        }
+        return Ok(result);
     }
 
-    let ast = AstParser::new()
-        .parse(pattern)
-        .map_err(|e| format!("Failed to parse pattern: {}", e))?;
+    let ast =
+        parse_ast(pattern, options.max_depth).map_err(|e| parse_error(&e, options.max_depth))?;
 
-    let parts = explain_ast(&ast);
+    let mut parts = explain_ast(&ast);
+    if let Some(target) = target {
+        annotate_portability(&mut parts, target);
+        if target.starts_with("posix") {
+            annotate_leftmost_longest(&mut parts, true);
+        }
+    }
     let summary = generate_summary(pattern, &parts);
 
     Ok(ExplainResult {
         pattern: pattern.to_string(),
+        normalized_pattern: None,
         parts,
         summary,
+        readability: readability_score(pattern),
+    })
+}
+
+/// List the named capture groups of a pattern with their indices
+/// (`explain --names-only`), without explaining the rest
+pub fn explain_group_names(
+    pattern: &str,
+    options: &ExplainOptions,
+) -> Result<GroupNamesResult, CoreError> {
+    let original = pattern;
+    let normalized = normalize_pattern(pattern);
+    let pattern = normalized.as_deref().unwrap_or(pattern);
+    // Patterns only fancy-regex can parse fail the AST parse; like
+    // `capture_group_info`, fall back to compiling them
+    if let Err(e) = parse_ast(pattern, options.max_depth) {
+        if is_nesting_error(&e) || try_fancy_regex(pattern, None).is_err() {
+            return Err(parse_error(&e, options.max_depth));
+        }
+    }
+
+    Ok(GroupNamesResult {
+        pattern: original.to_string(),
+        groups: capture_group_info(pattern)
+            .into_iter()
+            .filter(|group| group.name.is_some())
+            .collect(),
+    })
+}
+
+fn parse_error(err: &ast::Error, max_depth: u32) -> CoreError {
+    CoreError::InvalidPattern(if is_nesting_error(err) {
+        format!("Invalid pattern: {}", nesting_error_message(max_depth))
+    } else {
+        format!("Failed to parse pattern: {}", err)
     })
 }
 
 /// Explain a pattern that uses fancy-regex features (lookahead, lookbehind, etc.)
+///
+/// Each lookaround and conditional is explained with its own sub-patterns
+/// broken down as its children; other fancy features get a general description.
 fn explain_fancy_pattern(
     pattern: &str,
     features: &super::engine::FancyFeatures,
+    max_depth: u32,
 ) -> Result<ExplainResult, String> {
-    let mut parts = Vec::new();
+    let groups = find_fancy_groups(pattern);
+    let conditional = groups
+        .iter()
+        .any(|group| matches!(group, FancyGroup::Conditional { .. }));
+    let mut parts: Vec<ExplainPart> = groups
+        .iter()
+        .map(|group| explain_fancy_group(group, max_depth))
+        .collect();
 
-    if features.lookahead {
-        parts.push(ExplainPart {
-            token: "(?=...) / (?!...)".to_string(),
-            token_type: "lookahead".to_string(),
-            desc: "Lookahead assertion: checks what follows without consuming characters"
-                .to_string(),
-            quantifier: None,
-            group: None,
-            children: None,
-        });
-    }
-    if features.lookbehind {
-        parts.push(ExplainPart {
-            token: "(?<=...) / (?<!...)".to_string(),
-            token_type: "lookbehind".to_string(),
-            desc: "Lookbehind assertion: checks what precedes without consuming characters"
-                .to_string(),
-            quantifier: None,
-            group: None,
-            children: None,
-        });
-    }
     if features.backreference {
         parts.push(ExplainPart {
             token: r"\1, \2, ...".to_string(),
             token_type: "backreference".to_string(),
             desc: "Backreference: matches the same text as a previous capturing group".to_string(),
+            portability_note: None,
             quantifier: None,
             group: None,
             children: None,
@@ -75,13 +164,18 @@ fn explain_fancy_pattern(
             token: "(?>...)".to_string(),
             token_type: "atomic_group".to_string(),
             desc: "Atomic group: prevents backtracking into the group once matched".to_string(),
+            portability_note: None,
             quantifier: None,
             group: None,
             children: None,
         });
     }
 
-    let feature_desc = features.reason().unwrap_or_default();
+    let feature_desc = match (features.reason(), conditional) {
+        (Some(reason), true) => format!("{}, conditional", reason),
+        (Some(reason), false) => reason,
+        (None, _) => "Pattern uses conditional".to_string(),
+    };
     let summary = format!(
         "This pattern uses advanced features ({}) that require the fancy-regex engine",
         feature_desc
@@ -89,11 +183,415 @@ fn explain_fancy_pattern(
 
     Ok(ExplainResult {
         pattern: pattern.to_string(),
+        normalized_pattern: None,
         parts,
         summary,
+        readability: readability_score(pattern),
     })
 }
 
+/// A lookaround or conditional found by [`find_fancy_groups`]
+enum FancyGroup<'a> {
+    Lookaround {
+        /// The whole assertion, e.g. `(?<!\d)`
+        token: &'a str,
+        /// The sub-pattern it checks, e.g. `\d`
+        inner: &'a str,
+        behind: bool,
+        negative: bool,
+    },
+    Conditional {
+        /// The whole conditional, e.g. `(?(1)b|c)`
+        token: &'a str,
+        /// What is tested: a group (`1`, `<name>`, `'name'`) or a sub-pattern
+        condition: &'a str,
+        /// Matched when the condition holds
+        yes: &'a str,
+        /// Matched otherwise; `None` when there is no `|` branch
+        no: Option<&'a str>,
+    },
+}
+
+/// Whether a pattern has a `(?(...)then|else)` conditional, which only
+/// fancy-regex supports
+fn has_conditional(pattern: &str) -> bool {
+    find_fancy_groups(pattern)
+        .iter()
+        .any(|group| matches!(group, FancyGroup::Conditional { .. }))
+}
+
+/// Byte offsets of the `(`, `)` and `|` that are pattern syntax: not escaped
+/// and not inside a character class
+fn syntax_delimiters(pattern: &str) -> Vec<(usize, u8)> {
+    let bytes = pattern.as_bytes();
+    let mut delimiters = Vec::new();
+    let mut class_depth = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'[' => {
+                class_depth += 1;
+                // A `]` right after the opening bracket (or `[^`) is a literal
+                if bytes.get(i + 1) == Some(&b'^') {
+                    i += 1;
+                }
+                if bytes.get(i + 1) == Some(&b']') {
+                    i += 1;
+                }
+            }
+            b']' if class_depth > 0 => class_depth -= 1,
+            b @ (b'(' | b')' | b'|') if class_depth == 0 => delimiters.push((i, b)),
+            _ => {}
+        }
+        i += 1;
+    }
+    delimiters
+}
+
+/// Deepest group nesting in a pattern, by the same lexical scan (the
+/// parser's nesting limit does not apply to patterns it cannot parse)
+fn group_depth(pattern: &str) -> usize {
+    let mut depth = 0usize;
+    let mut deepest = 0;
+    for (_, b) in syntax_delimiters(pattern) {
+        match b {
+            b'(' => {
+                depth += 1;
+                deepest = deepest.max(depth);
+            }
+            b')' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    deepest
+}
+
+/// Offset of the `)` closing the group opened at `open`
+fn group_end(pattern: &str, open: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    syntax_delimiters(&pattern[open..])
+        .into_iter()
+        .find_map(|(pos, b)| {
+            match b {
+                b'(' => depth += 1,
+                b')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(open + pos);
+                    }
+                }
+                _ => {}
+            }
+            None
+        })
+}
+
+/// Split a conditional's body at its top-level `|` into then and else branches
+fn split_branches(body: &str) -> (&str, Option<&str>) {
+    let mut depth = 0usize;
+    for (pos, b) in syntax_delimiters(body) {
+        match b {
+            b'(' => depth += 1,
+            b')' => depth = depth.saturating_sub(1),
+            b'|' if depth == 0 => return (&body[..pos], Some(&body[pos + 1..])),
+            _ => {}
+        }
+    }
+    (body, None)
+}
+
+/// Find the outermost lookarounds and conditionals of a pattern, which
+/// `regex_syntax` cannot parse, with a lexical scan that skips escapes and
+/// character classes. Those nested inside another are left to the
+/// explanation of its sub-patterns; unclosed ones are ignored.
+fn find_fancy_groups(pattern: &str) -> Vec<FancyGroup<'_>> {
+    const LOOKAROUNDS: [(&str, bool, bool); 4] = [
+        ("(?=", false, false),
+        ("(?!", false, true),
+        ("(?<=", true, false),
+        ("(?<!", true, true),
+    ];
+
+    let mut groups = Vec::new();
+    let mut resume = 0;
+    for (open, b) in syntax_delimiters(pattern) {
+        if b != b'(' || open < resume {
+            continue;
+        }
+        let Some(end) = group_end(pattern, open) else {
+            continue;
+        };
+        let token = &pattern[open..=end];
+
+        let lookaround = LOOKAROUNDS
+            .iter()
+            .find(|(prefix, _, _)| token.starts_with(prefix));
+        let group = if let Some(&(prefix, behind, negative)) = lookaround {
+            FancyGroup::Lookaround {
+                token,
+                inner: &pattern[open + prefix.len()..end],
+                behind,
+                negative,
+            }
+        } else if token.starts_with("(?(") {
+            let Some(condition_end) = group_end(pattern, open + 2).filter(|&e| e < end) else {
+                continue;
+            };
+            let (yes, no) = split_branches(&pattern[condition_end + 1..end]);
+            FancyGroup::Conditional {
+                token,
+                condition: &pattern[open + 3..condition_end],
+                yes,
+                no,
+            }
+        } else {
+            continue;
+        };
+        groups.push(group);
+        resume = end + 1;
+    }
+    groups
+}
+
+/// Explain a sub-pattern of a fancy group as a list of child parts (none if
+/// it is empty or cannot be explained on its own)
+fn explain_sub_pattern(pattern: &str, max_depth: u32) -> Option<Vec<ExplainPart>> {
+    let options = ExplainOptions {
+        max_depth,
+        target: None,
+    };
+    explain_pattern(pattern, &options)
+        .ok()
+        .map(|result| result.parts)
+        .filter(|parts| !parts.is_empty())
+}
+
+/// Describe a lookaround or conditional, with its sub-patterns explained as
+/// the children
+fn explain_fancy_group(group: &FancyGroup, max_depth: u32) -> ExplainPart {
+    match *group {
+        FancyGroup::Lookaround {
+            token,
+            inner,
+            behind,
+            negative,
+        } => {
+            let (token_type, place) = if behind {
+                ("lookbehind", "the text just before")
+            } else {
+                ("lookahead", "the text that follows")
+            };
+            let (polarity, verb) = if negative {
+                ("Negative", "must not match")
+            } else {
+                ("Positive", "must match")
+            };
+            ExplainPart {
+                token: token.to_string(),
+                token_type: token_type.to_string(),
+                desc: format!(
+                    "{} {}: {} {} `{}`, checked without consuming characters",
+                    polarity, token_type, place, verb, inner
+                ),
+                portability_note: None,
+                quantifier: None,
+                group: None,
+                children: explain_sub_pattern(inner, max_depth),
+            }
+        }
+        FancyGroup::Conditional {
+            token,
+            condition,
+            yes,
+            no,
+        } => {
+            // The group tested: `1`, `<name>` or `'name'`; anything else is a
+            // sub-pattern that fancy-regex tries (and consumes) at this point
+            let is_number = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+            let tested_group = if is_number(condition) {
+                Some(condition)
+            } else {
+                condition
+                    .strip_prefix('<')
+                    .and_then(|c| c.strip_suffix('>'))
+                    .or_else(|| {
+                        condition
+                            .strip_prefix('\'')
+                            .and_then(|c| c.strip_suffix('\''))
+                    })
+            };
+            let test = match tested_group {
+                Some(group) if is_number(group) => format!("group {} captured something", group),
+                Some(name) => format!("group '{}' captured something", name),
+                None => format!("`{}` matches here (as part of the match)", condition),
+            };
+            let branch = |pattern: &str, desc: &str| ExplainPart {
+                token: pattern.to_string(),
+                token_type: "branch".to_string(),
+                desc: desc.to_string(),
+                portability_note: None,
+                quantifier: None,
+                group: None,
+                children: explain_sub_pattern(pattern, max_depth),
+            };
+
+            let mut children = Vec::new();
+            if tested_group.is_none() {
+                children.push(ExplainPart {
+                    token: condition.to_string(),
+                    token_type: "condition".to_string(),
+                    desc: "Condition: tried first; the branch taken depends on whether it matches"
+                        .to_string(),
+                    portability_note: None,
+                    quantifier: None,
+                    group: None,
+                    children: explain_sub_pattern(condition, max_depth),
+                });
+            }
+            children.push(branch(yes, "Then branch: matched if the condition holds"));
+            if let Some(no) = no {
+                children.push(branch(no, "Else branch: matched if it does not"));
+            }
+
+            ExplainPart {
+                token: token.to_string(),
+                token_type: "conditional".to_string(),
+                desc: format!(
+                    "Conditional: if {}, match `{}`, else match {}",
+                    test,
+                    yes,
+                    no.map_or("nothing".to_string(), |no| format!("`{}`", no))
+                ),
+                portability_note: None,
+                quantifier: None,
+                group: None,
+                children: Some(children),
+            }
+        }
+    }
+}
+
+/// Attach a portability note to each part that uses a construct the target
+/// does not support. Container parts only get notes for issues not already
+/// reported on one of their children, so each problem is flagged once.
+///
+/// For JavaScript, leaf parts also get a note when they could behave
+/// differently on astral-plane characters (see [`astral_hazards`]).
+///
+/// Returns every issue found in `parts` and their descendants.
+fn annotate_portability(parts: &mut [ExplainPart], target: &str) -> Vec<String> {
+    let mut all_issues = Vec::new();
+
+    for part in parts.iter_mut() {
+        let child_issues = part
+            .children
+            .as_mut()
+            .map(|children| annotate_portability(children, target))
+            .unwrap_or_default();
+
+        let mut own: Vec<String> = target_issues(&part.token, target)
+            .into_iter()
+            .filter(|issue| !child_issues.contains(issue))
+            .collect();
+        if target == "javascript" && part.children.is_none() {
+            own.extend(astral_hazards(&part.token));
+        }
+
+        if !own.is_empty() {
+            part.portability_note = Some(format!("{} in {}", own.join("; "), target));
+        }
+
+        all_issues.extend(child_issues);
+        all_issues.extend(own);
+    }
+
+    all_issues
+}
+
+/// Note alternations whose matches differ between leftmost-first and POSIX
+/// leftmost-longest semantics (see [`leftmost_longest_divergences`]).
+///
+/// `exposed`: `parts` end the pattern or make up a quantified group body,
+/// so an alternation last among them is not constrained by what follows.
+fn annotate_leftmost_longest(parts: &mut [ExplainPart], exposed: bool) {
+    let last = parts.len().saturating_sub(1);
+    for (i, part) in parts.iter_mut().enumerate() {
+        let exposed = (exposed && i == last) || part.quantifier.is_some();
+        if part.token_type == "alternation" && exposed {
+            if let Some(note) = leftmost_longest_divergence(&part.token) {
+                part.portability_note = Some(match part.portability_note.take() {
+                    Some(existing) => format!("{}; {}", existing, note),
+                    None => note,
+                });
+            }
+        }
+        let children_exposed = exposed && part.token_type != "alternation";
+        if let Some(children) = part.children.as_mut() {
+            annotate_leftmost_longest(children, children_exposed);
+        }
+    }
+}
+
+/// Ways a token may match differently on characters above U+FFFF in a
+/// JavaScript regex without the `u` flag, which works on UTF-16 code units:
+/// an astral character like an emoji is two units (a surrogate pair).
+fn astral_hazards(token: &str) -> Vec<String> {
+    fn walk(ast: &Ast, split: &mut bool, astral: &mut bool) {
+        match ast {
+            Ast::Dot(_) => *split = true,
+            Ast::ClassPerl(class) => *split |= class.negated,
+            Ast::ClassUnicode(class) => *split |= class.negated,
+            Ast::Literal(lit) => *astral |= lit.c > '\u{FFFF}',
+            Ast::ClassBracketed(class) => {
+                *split |= class.negated;
+                *astral |= set_has_astral(&class.kind);
+            }
+            Ast::Repetition(rep) => walk(&rep.ast, split, astral),
+            Ast::Group(group) => walk(&group.ast, split, astral),
+            Ast::Concat(concat) => concat.asts.iter().for_each(|a| walk(a, split, astral)),
+            Ast::Alternation(alt) => alt.asts.iter().for_each(|a| walk(a, split, astral)),
+            Ast::Empty(_) | Ast::Flags(_) | Ast::Assertion(_) => {}
+        }
+    }
+
+    fn set_has_astral(set: &ClassSet) -> bool {
+        match set {
+            ClassSet::Item(item) => item_has_astral(item),
+            ClassSet::BinaryOp(op) => set_has_astral(&op.lhs) || set_has_astral(&op.rhs),
+        }
+    }
+
+    fn item_has_astral(item: &ClassSetItem) -> bool {
+        match item {
+            ClassSetItem::Literal(lit) => lit.c > '\u{FFFF}',
+            ClassSetItem::Range(range) => range.end.c > '\u{FFFF}',
+            ClassSetItem::Bracketed(class) => set_has_astral(&class.kind),
+            ClassSetItem::Union(union) => union.items.iter().any(item_has_astral),
+            _ => false,
+        }
+    }
+
+    let Ok(ast) = ast::parse::Parser::new().parse(token) else {
+        return Vec::new();
+    };
+    let (mut split, mut astral) = (false, false);
+    walk(&ast, &mut split, &mut astral);
+
+    let mut hazards = Vec::new();
+    if split {
+        hazards.push(
+            "Without the u flag this matches a single UTF-16 code unit, so it can split an astral character (e.g. an emoji) into surrogates".to_string(),
+        );
+    }
+    if astral {
+        hazards.push(
+            "Code points above U+FFFF need the u flag (and \\u{...} escapes); without it they are two UTF-16 code units".to_string(),
+        );
+    }
+    hazards
+}
+
 /// Recursively explain an AST node
 fn explain_ast(ast: &Ast) -> Vec<ExplainPart> {
     match ast {
@@ -104,6 +602,7 @@ fn explain_ast(ast: &Ast) -> Vec<ExplainPart> {
                 token: format!("(?{})", flags_to_string(&flags.flags)),
                 token_type: "flags".to_string(),
                 desc: describe_flags(&flags.flags),
+                portability_note: None,
                 quantifier: None,
                 group: None,
                 children: None,
@@ -122,6 +621,7 @@ fn explain_ast(ast: &Ast) -> Vec<ExplainPart> {
                 token: c.to_string(),
                 token_type: "literal".to_string(),
                 desc,
+                portability_note: None,
                 quantifier: None,
                 group: None,
                 children: None,
@@ -133,6 +633,7 @@ fn explain_ast(ast: &Ast) -> Vec<ExplainPart> {
                 token: ".".to_string(),
                 token_type: "any_char".to_string(),
                 desc: "Matches any character (except newline by default)".to_string(),
+                portability_note: None,
                 quantifier: None,
                 group: None,
                 children: None,
@@ -161,6 +662,7 @@ fn explain_ast(ast: &Ast) -> Vec<ExplainPart> {
                 token: token.to_string(),
                 token_type: "anchor".to_string(),
                 desc: desc.to_string(),
+                portability_note: None,
                 quantifier: None,
                 group: None,
                 children: None,
@@ -191,6 +693,7 @@ fn explain_ast(ast: &Ast) -> Vec<ExplainPart> {
                 token,
                 token_type: "unicode_class".to_string(),
                 desc,
+                portability_note: None,
                 quantifier: None,
                 group: None,
                 children: None,
@@ -226,6 +729,7 @@ fn explain_ast(ast: &Ast) -> Vec<ExplainPart> {
                 token: token.to_string(),
                 token_type: "perl_class".to_string(),
                 desc: desc.to_string(),
+                portability_note: None,
                 quantifier: None,
                 group: None,
                 children: None,
@@ -244,6 +748,7 @@ fn explain_ast(ast: &Ast) -> Vec<ExplainPart> {
                     "Character class: matches {}one of the specified characters",
                     negated
                 ),
+                portability_note: None,
                 quantifier: None,
                 group: None,
                 children: None,
@@ -263,6 +768,7 @@ fn explain_ast(ast: &Ast) -> Vec<ExplainPart> {
                             token: format!("{}{{{}}}", rep.ast, n),
                             token_type: "repetition".to_string(),
                             desc: format!("Exactly {} of the preceding element", n),
+                            portability_note: None,
                             quantifier: Some(format!("{{{}}}", n)),
                             group: None,
                             children: if child_parts.len() > 1 {
@@ -277,6 +783,7 @@ fn explain_ast(ast: &Ast) -> Vec<ExplainPart> {
                             token: format!("{}{{{},}}", rep.ast, n),
                             token_type: "repetition".to_string(),
                             desc: format!("{} or more of the preceding element", n),
+                            portability_note: None,
                             quantifier: Some(format!("{{{},}}", n)),
                             group: None,
                             children: if child_parts.len() > 1 {
@@ -291,6 +798,7 @@ fn explain_ast(ast: &Ast) -> Vec<ExplainPart> {
                             token: format!("{}{{{},{}}}", rep.ast, m, n),
                             token_type: "repetition".to_string(),
                             desc: format!("Between {} and {} of the preceding element", m, n),
+                            portability_note: None,
                             quantifier: Some(format!("{{{},{}}}", m, n)),
                             group: None,
                             children: if child_parts.len() > 1 {
@@ -330,6 +838,7 @@ fn explain_ast(ast: &Ast) -> Vec<ExplainPart> {
                     token: format!("{}", ast),
                     token_type: "repetition".to_string(),
                     desc,
+                    portability_note: None,
                     quantifier: Some(quantifier.to_string()),
                     group: None,
                     children: Some(child_parts),
@@ -360,6 +869,7 @@ fn explain_ast(ast: &Ast) -> Vec<ExplainPart> {
                 token: format!("{}", ast),
                 token_type: token_type.to_string(),
                 desc: desc.to_string(),
+                portability_note: None,
                 quantifier: None,
                 group: group_num,
                 children: if children.is_empty() {
@@ -378,6 +888,7 @@ fn explain_ast(ast: &Ast) -> Vec<ExplainPart> {
                     token: format!("{}", a),
                     token_type: "branch".to_string(),
                     desc: "Alternative branch".to_string(),
+                    portability_note: None,
                     quantifier: None,
                     group: None,
                     children: Some(explain_ast(a)),
@@ -388,6 +899,7 @@ fn explain_ast(ast: &Ast) -> Vec<ExplainPart> {
                 token: format!("{}", ast),
                 token_type: "alternation".to_string(),
                 desc: format!("Match one of {} alternatives", alt.asts.len()),
+                portability_note: None,
                 quantifier: None,
                 group: None,
                 children: Some(branches),
@@ -420,6 +932,19 @@ fn flags_to_string(flags: &ast::Flags) -> String {
     s
 }
 
+/// Each flag a directive mentions, paired with whether it turns the flag on
+/// (it appears before the `-`) or off
+pub(crate) fn flag_settings(flags: &ast::Flags) -> impl Iterator<Item = (ast::Flag, bool)> + '_ {
+    let mut enabled = true;
+    flags.items.iter().filter_map(move |item| match item.kind {
+        ast::FlagsItemKind::Negation => {
+            enabled = false;
+            None
+        }
+        ast::FlagsItemKind::Flag(flag) => Some((flag, enabled)),
+    })
+}
+
 /// Describe flags
 fn describe_flags(flags: &ast::Flags) -> String {
     let mut descs = Vec::new();
@@ -453,6 +978,99 @@ fn describe_unicode_class(c: char) -> String {
     }
 }
 
+/// Score how hard a pattern is to read (0-100, higher is harder) from its
+/// length, group nesting, alternation branches, quantifiers and use of
+/// lookaround, backreferences and other backtracking-only constructs.
+///
+/// Nesting and alternations come from the pattern's syntax delimiters, and
+/// the rest from the fancy-regex parse tree, so both kinds of pattern are
+/// scored the same way.
+fn readability_score(pattern: &str) -> ReadabilityScore {
+    use fancy_regex::Expr;
+
+    fn walk(expr: &Expr, score: &mut ReadabilityScore) {
+        match expr {
+            Expr::Concat(children) | Expr::Alt(children) => {
+                children.iter().for_each(|child| walk(child, score))
+            }
+            Expr::Group(inner) => walk(inner, score),
+            Expr::Repeat { child, .. } => {
+                score.quantifiers += 1;
+                walk(child, score);
+            }
+            Expr::LookAround(inner, _) => {
+                score.lookarounds += 1;
+                walk(inner, score);
+            }
+            Expr::Backref { .. } | Expr::BackrefWithRelativeRecursionLevel { .. } => {
+                score.backreferences += 1
+            }
+            Expr::AtomicGroup(inner) => {
+                score.advanced += 1;
+                walk(inner, score);
+            }
+            Expr::Conditional {
+                condition,
+                true_branch,
+                false_branch,
+            } => {
+                score.advanced += 1;
+                walk(condition, score);
+                walk(true_branch, score);
+                walk(false_branch, score);
+            }
+            Expr::SubroutineCall(_)
+            | Expr::UnresolvedNamedSubroutineCall { .. }
+            | Expr::KeepOut
+            | Expr::ContinueFromPreviousMatchEnd => score.advanced += 1,
+            _ => {}
+        }
+    }
+
+    let mut score = ReadabilityScore {
+        score: 0,
+        label: String::new(),
+        length: pattern.chars().count(),
+        max_nesting: 0,
+        alternations: 0,
+        quantifiers: 0,
+        lookarounds: 0,
+        backreferences: 0,
+        advanced: 0,
+    };
+
+    let mut depth = 0usize;
+    for (_, delimiter) in syntax_delimiters(pattern) {
+        match delimiter {
+            b'(' => {
+                depth += 1;
+                score.max_nesting = score.max_nesting.max(depth);
+            }
+            b')' => depth = depth.saturating_sub(1),
+            _ => score.alternations += 1,
+        }
+    }
+    if let Ok(tree) = Expr::parse_tree(pattern) {
+        walk(&tree.expr, &mut score);
+    }
+
+    // Each feature's contribution is capped so no single one dominates
+    let points = (score.length / 4).min(25)
+        + (score.max_nesting * 6).min(24)
+        + (score.alternations * 3).min(15)
+        + (score.quantifiers * 2).min(16)
+        + (score.lookarounds * 6 + score.backreferences * 5 + score.advanced * 8).min(30);
+    score.score = points.min(100) as u32;
+    score.label = match score.score {
+        0..25 => "simple",
+        25..50 => "moderate",
+        50..75 => "complex",
+        _ => "very complex",
+    }
+    .to_string();
+    score
+}
+
 /// Generate a summary of the pattern
 fn generate_summary(pattern: &str, parts: &[ExplainPart]) -> String {
     if parts.is_empty() {
@@ -617,19 +1235,218 @@ mod tests {
 
     #[test]
     fn test_explain_simple() {
-        let result = explain_pattern(r"\d+").unwrap();
+        let result = explain_pattern(r"\d+", &ExplainOptions::default()).unwrap();
         assert!(!result.parts.is_empty());
     }
 
     #[test]
     fn test_explain_with_groups() {
-        let result = explain_pattern(r"(\d+)-(\d+)").unwrap();
+        let result = explain_pattern(r"(\d+)-(\d+)", &ExplainOptions::default()).unwrap();
         assert!(!result.parts.is_empty());
     }
 
+    #[test]
+    fn test_explain_group_names() {
+        let result = explain_group_names(
+            r"(?<year>\d{4})-(\d{2})-(?P<day>\d{2})",
+            &ExplainOptions::default(),
+        )
+        .unwrap();
+        let groups: Vec<_> = result
+            .groups
+            .iter()
+            .map(|g| (g.index, g.name.as_deref().unwrap()))
+            .collect();
+        assert_eq!(groups, [(1, "year"), (3, "day")]);
+
+        let fancy = explain_group_names(r"(?<w>\w+) \k<w>", &ExplainOptions::default()).unwrap();
+        assert_eq!(fancy.groups[0].name.as_deref(), Some("w"));
+
+        assert!(explain_group_names("(?<a>x", &ExplainOptions::default()).is_err());
+    }
+
     #[test]
     fn test_explain_alternation() {
-        let result = explain_pattern(r"cat|dog").unwrap();
+        let result = explain_pattern(r"cat|dog", &ExplainOptions::default()).unwrap();
         assert!(result.parts.iter().any(|p| p.token_type == "alternation"));
     }
+
+    #[test]
+    fn test_explain_lookaround_contents() {
+        let result =
+            explain_pattern(r"(?<!\d)[)(?=]+(?=\d{2}(?<=\d))", &Default::default()).unwrap();
+        let tokens: Vec<&str> = result.parts.iter().map(|p| p.token.as_str()).collect();
+        assert_eq!(tokens, vec![r"(?<!\d)", r"(?=\d{2}(?<=\d))"]);
+
+        let behind = &result.parts[0];
+        assert_eq!(behind.token_type, "lookbehind");
+        assert!(behind.desc.starts_with("Negative lookbehind"));
+        assert_eq!(behind.children.as_ref().unwrap()[0].token, r"\d");
+
+        // The nested lookbehind is explained inside the lookahead's sub-pattern
+        let ahead = &result.parts[1];
+        assert!(ahead.desc.starts_with("Positive lookahead"));
+        let nested = &ahead.children.as_ref().unwrap()[0];
+        assert_eq!(nested.token, r"(?<=\d)");
+        assert!(nested.desc.starts_with("Positive lookbehind"));
+    }
+
+    #[test]
+    fn test_explain_conditionals() {
+        let result = explain_pattern(r"(<)?\w+(?(1)>|;)", &Default::default()).unwrap();
+        let conditional = result
+            .parts
+            .iter()
+            .find(|p| p.token_type == "conditional")
+            .unwrap();
+        assert_eq!(conditional.token, "(?(1)>|;)");
+        assert_eq!(
+            conditional.desc,
+            "Conditional: if group 1 captured something, match `>`, else match `;`"
+        );
+        let branches: Vec<&str> = conditional
+            .children
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|p| p.token.as_str())
+            .collect();
+        assert_eq!(branches, vec![">", ";"]);
+
+        // A named group, no else branch, and a `|` inside a nested group
+        let result = explain_pattern(r"(?<q>')?x(?('q')(?:a|b))", &Default::default()).unwrap();
+        let conditional = &result.parts[0];
+        assert!(conditional.desc.contains("group 'q'"));
+        assert!(conditional.desc.ends_with("else match nothing"));
+        assert_eq!(conditional.children.as_ref().unwrap().len(), 1);
+
+        // A sub-pattern condition is explained as a child too
+        let result = explain_pattern(r"(?(\d)x|y)", &Default::default()).unwrap();
+        let children = result.parts[0].children.as_ref().unwrap();
+        assert_eq!(children[0].token_type, "condition");
+        assert_eq!(children[0].token, r"\d");
+
+        // An escaped paren before `?(` is not a conditional
+        assert!(!has_conditional(r"\(?(\d{3})"));
+    }
+
+    #[test]
+    fn test_readability_score() {
+        let simple = readability_score(r"\d+");
+        assert_eq!((simple.score, simple.label.as_str()), (2, "simple"));
+
+        let ipv4 = readability_score(
+            r"^(?:(?:25[0-5]|2[0-4]\d|1?\d?\d)\.){3}(?:25[0-5]|2[0-4]\d|1?\d?\d)$",
+        );
+        assert_eq!(ipv4.max_nesting, 2);
+        assert_eq!(ipv4.alternations, 4);
+        assert_eq!(ipv4.quantifiers, 5);
+        assert_eq!(ipv4.label, "complex");
+
+        // Fancy constructs are counted, and `|` inside a class is not an alternation
+        let fancy = readability_score(r"(?>a+)(?=[|x])(\w)\1");
+        assert_eq!(fancy.lookarounds, 1);
+        assert_eq!(fancy.backreferences, 1);
+        assert_eq!(fancy.advanced, 1);
+        assert_eq!(fancy.alternations, 0);
+        assert!(fancy.score > simple.score);
+
+        // Capped at 100
+        let huge = format!("(?=(?=(?=(?=x))))({})", "a+|".repeat(200));
+        assert_eq!(readability_score(&huge).score, 100);
+        assert_eq!(readability_score(&huge).label, "very complex");
+    }
+
+    #[test]
+    fn test_explain_target_notes() {
+        let options = ExplainOptions {
+            target: Some("go".to_string()),
+            ..Default::default()
+        };
+        let result = explain_pattern(r"foo(?=bar)", &options).unwrap();
+        let lookahead = result
+            .parts
+            .iter()
+            .find(|p| p.token_type == "lookahead")
+            .unwrap();
+        assert!(lookahead
+            .portability_note
+            .as_ref()
+            .unwrap()
+            .contains("go_regexp"));
+
+        let result = explain_pattern(r"(\d+)-[[:alpha:]]", &options).unwrap();
+        assert!(result.parts.iter().all(|p| p.portability_note.is_none()));
+
+        let options = ExplainOptions {
+            target: Some("javascript".to_string()),
+            ..Default::default()
+        };
+        let result = explain_pattern(r"(\d+)-[[:alpha:]]", &options).unwrap();
+        let noted: Vec<&str> = result
+            .parts
+            .iter()
+            .filter(|p| p.portability_note.is_some())
+            .map(|p| p.token.as_str())
+            .collect();
+        assert_eq!(noted, vec!["[[:alpha:]]"]);
+    }
+
+    #[test]
+    fn test_explain_javascript_astral_notes() {
+        let options = ExplainOptions {
+            target: Some("javascript".to_string()),
+            ..Default::default()
+        };
+        let result = explain_pattern(r"^.{1,3}[^,]\d\x{1F600}$", &options).unwrap();
+        let noted: Vec<&str> = result
+            .parts
+            .iter()
+            .filter(|p| p.portability_note.is_some())
+            .map(|p| p.token.as_str())
+            .collect();
+        assert_eq!(noted, vec![".{1,3}", "[^,]", "😀"]);
+        let note = result.parts[1].portability_note.as_ref().unwrap();
+        assert!(note.contains("UTF-16") && note.ends_with("in javascript"));
+
+        let options = ExplainOptions {
+            target: Some("python".to_string()),
+            ..Default::default()
+        };
+        let result = explain_pattern(r"^.{1,3}", &options).unwrap();
+        assert!(result.parts.iter().all(|p| p.portability_note.is_none()));
+    }
+
+    #[test]
+    fn test_explain_unknown_target() {
+        let options = ExplainOptions {
+            target: Some("cobol".to_string()),
+            ..Default::default()
+        };
+        assert!(explain_pattern(r"\d+", &options).is_err());
+    }
+
+    #[test]
+    fn test_explain_rejects_deep_nesting() {
+        let deep = format!("{}a{}", "(".repeat(40), ")".repeat(40));
+        let options = ExplainOptions {
+            max_depth: 16,
+            ..Default::default()
+        };
+        let err = explain_pattern(&deep, &options).unwrap_err();
+        assert!(matches!(err, CoreError::InvalidPattern(_)));
+        assert!(err.to_string().contains("nesting too deep"));
+        assert!(explain_pattern(&deep, &ExplainOptions::default()).is_ok());
+
+        // Lookarounds are explained recursively, so their nesting counts too
+        let deep = format!("{}{}", "(?=a".repeat(2000), ")".repeat(2000));
+        let err = explain_pattern(&deep, &ExplainOptions::default()).unwrap_err();
+        assert!(err.to_string().contains("nesting too deep"));
+        let nested = format!("{}{}", "(?=a".repeat(20), ")".repeat(20));
+        assert!(explain_pattern(&nested, &options).is_err());
+        // Up to the default limit fits a test thread's stack
+        let limit = DEFAULT_MAX_DEPTH as usize;
+        let nested = format!("{}{}", "(?=a".repeat(limit), ")".repeat(limit));
+        assert!(explain_pattern(&nested, &ExplainOptions::default()).is_ok());
+    }
 }
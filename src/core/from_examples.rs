@@ -2,13 +2,19 @@
 //!
 //! Infers regex patterns from example strings.
 
+use std::collections::{BTreeSet, HashSet};
+
 use super::templates::detect_known_formats;
 use crate::output::{FromExamplesResult, InferredPattern};
 
 /// Infer patterns from examples
+///
+/// With `format_only`, only known format templates are considered, and an
+/// empty candidate list comes with a note instead of heuristic guesses.
 pub fn infer_patterns(
     examples: &[String],
     negative_examples: Option<&[String]>,
+    format_only: bool,
 ) -> Result<FromExamplesResult, String> {
     if examples.is_empty() {
         return Err("At least one example is required".to_string());
@@ -23,31 +29,50 @@ pub fn infer_patterns(
     // Strategy 1: Known format templates (highest priority — precise patterns)
     // Template patterns are curated, so skip the generic specificity penalty.
     for (pattern, desc) in detect_known_formats(examples) {
-        let confidence = calculate_confidence(&pattern, examples, negative_examples, true);
+        let (confidence, unmatched_examples) =
+            calculate_confidence(&pattern, examples, negative_examples, true);
         candidates.push(InferredPattern {
             pattern,
             confidence,
             desc,
+            unmatched_examples,
+        });
+    }
+
+    if format_only {
+        sort_candidates(&mut candidates);
+        let note = candidates
+            .is_empty()
+            .then(|| "No known format matches all the examples".to_string());
+        return Ok(FromExamplesResult {
+            examples: examples.to_vec(),
+            negative_examples: negative_examples.map(|n| n.to_vec()),
+            inferred: candidates,
+            note,
         });
     }
 
     // Strategy 2: Character class based inference
     if let Some(pattern) = infer_character_classes(examples) {
-        let confidence = calculate_confidence(&pattern, examples, negative_examples, false);
+        let (confidence, unmatched_examples) =
+            calculate_confidence(&pattern, examples, negative_examples, false);
         candidates.push(InferredPattern {
             pattern,
             confidence,
             desc: "Character class based pattern".to_string(),
+            unmatched_examples,
         });
     }
 
     // Strategy 3: Common structure detection
     if let Some((pattern, desc)) = infer_common_structure(examples) {
-        let confidence = calculate_confidence(&pattern, examples, negative_examples, false);
+        let (confidence, unmatched_examples) =
+            calculate_confidence(&pattern, examples, negative_examples, false);
         candidates.push(InferredPattern {
             pattern,
             confidence,
             desc,
+            unmatched_examples,
         });
     }
 
@@ -58,37 +83,52 @@ pub fn infer_patterns(
             pattern: escaped,
             confidence: 1.0,
             desc: "Exact match (all examples identical)".to_string(),
+            unmatched_examples: Vec::new(),
         });
     }
 
     // Strategy 5: Literal prefix/suffix with wildcard
     if let Some((pattern, desc)) = infer_anchored_pattern(examples) {
-        let confidence = calculate_confidence(&pattern, examples, negative_examples, false);
+        let (confidence, unmatched_examples) =
+            calculate_confidence(&pattern, examples, negative_examples, false);
         candidates.push(InferredPattern {
             pattern,
             confidence,
             desc,
+            unmatched_examples,
         });
     }
 
-    // Sort by confidence (highest first) and deduplicate
-    candidates.sort_by(|a, b| {
-        b.confidence
-            .partial_cmp(&a.confidence)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
-    candidates.dedup_by(|a, b| a.pattern == b.pattern);
+    // Strategy 6: Tighten candidates that also match negative examples
+    if let Some(negatives) = negative_examples.filter(|n| !n.is_empty()) {
+        let refined: Vec<InferredPattern> = candidates
+            .iter()
+            .filter_map(|c| refine_candidate(c, examples, negatives))
+            .collect();
+        candidates.extend(refined);
+    }
 
-    // Keep top 5
-    candidates.truncate(5);
+    sort_candidates(&mut candidates);
 
     Ok(FromExamplesResult {
         examples: examples.to_vec(),
         negative_examples: negative_examples.map(|n| n.to_vec()),
         inferred: candidates,
+        note: None,
     })
 }
 
+/// Sort by confidence (highest first), deduplicate and keep the top 5
+fn sort_candidates(candidates: &mut Vec<InferredPattern>) {
+    candidates.sort_by(|a, b| {
+        b.confidence
+            .partial_cmp(&a.confidence)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    candidates.dedup_by(|a, b| a.pattern == b.pattern);
+    candidates.truncate(5);
+}
+
 /// Infer pattern based on character classes
 fn infer_character_classes(examples: &[String]) -> Option<String> {
     if examples.is_empty() {
@@ -268,7 +308,147 @@ fn infer_anchored_pattern(examples: &[String]) -> Option<(String, String)> {
     None
 }
 
-/// Calculate confidence score for a pattern.
+/// Tighten a candidate that also matches negative examples.
+///
+/// Tries anchoring it to the whole string and narrowing its `.` wildcards to
+/// exclude characters that only occur in negatives (and both together), and
+/// returns the variant that rejects the most negatives while still matching
+/// every positive — if it improves on the original.
+fn refine_candidate(
+    candidate: &InferredPattern,
+    examples: &[String],
+    negatives: &[String],
+) -> Option<InferredPattern> {
+    let negatives_matched = |pattern: &str| -> Option<usize> {
+        let re = regex::Regex::new(pattern).ok()?;
+        examples
+            .iter()
+            .all(|e| re.is_match(e))
+            .then(|| negatives.iter().filter(|n| re.is_match(n)).count())
+    };
+
+    let baseline = negatives_matched(&candidate.pattern)?;
+    if baseline == 0 {
+        return None;
+    }
+
+    let mut variants = vec![(
+        anchor(&candidate.pattern),
+        "anchored to the whole string".to_string(),
+    )];
+    if let Some(excluded) = distinguishing_class(examples, negatives) {
+        let restricted = restrict_wildcards(&candidate.pattern, &excluded);
+        if restricted != candidate.pattern {
+            variants.push((
+                anchor(&restricted),
+                format!("anchored, wildcards exclude [{}]", excluded),
+            ));
+            variants.push((restricted, format!("wildcards exclude [{}]", excluded)));
+        }
+    }
+
+    // Prefer the earliest variant among those rejecting the most negatives
+    let (pattern, how, remaining) = variants
+        .into_iter()
+        .filter(|(pattern, _)| *pattern != candidate.pattern)
+        .filter_map(|(pattern, how)| negatives_matched(&pattern).map(|n| (pattern, how, n)))
+        .reduce(|best, next| if next.2 < best.2 { next } else { best })?;
+
+    if remaining >= baseline {
+        return None;
+    }
+
+    let (confidence, unmatched_examples) =
+        calculate_confidence(&pattern, examples, Some(negatives), false);
+    Some(InferredPattern {
+        confidence,
+        desc: format!("{} (refined against negatives: {})", candidate.desc, how),
+        pattern,
+        unmatched_examples,
+    })
+}
+
+/// Anchor a pattern to the whole string
+fn anchor(pattern: &str) -> String {
+    if pattern.starts_with('^') && pattern.ends_with('$') {
+        pattern.to_string()
+    } else if pattern.contains('|') {
+        format!("^(?:{})$", pattern)
+    } else {
+        format!("^{}$", pattern)
+    }
+}
+
+/// Character class body covering characters that appear in negatives but
+/// never in positives, generalized to `a-zA-Z` / `0-9` when positives contain
+/// no letters / digits at all.
+fn distinguishing_class(examples: &[String], negatives: &[String]) -> Option<String> {
+    let positive: HashSet<char> = examples.iter().flat_map(|e| e.chars()).collect();
+    let positive_letters = positive.iter().any(|c| c.is_ascii_alphabetic());
+    let positive_digits = positive.iter().any(|c| c.is_ascii_digit());
+
+    let mut letters = false;
+    let mut digits = false;
+    let mut others = BTreeSet::new();
+    for c in negatives.iter().flat_map(|n| n.chars()) {
+        if positive.contains(&c) {
+            continue;
+        }
+        if c.is_ascii_alphabetic() && !positive_letters {
+            letters = true;
+        } else if c.is_ascii_digit() && !positive_digits {
+            digits = true;
+        } else {
+            others.insert(c);
+        }
+    }
+
+    let mut class = String::new();
+    if letters {
+        class.push_str("a-zA-Z");
+    }
+    if digits {
+        class.push_str("0-9");
+    }
+    for c in others {
+        class.push_str(&regex::escape(&c.to_string()));
+    }
+
+    (!class.is_empty()).then_some(class)
+}
+
+/// Replace every unescaped `.` outside a character class with `[^\n<excluded>]`
+fn restrict_wildcards(pattern: &str, excluded: &str) -> String {
+    let mut result = String::new();
+    let mut chars = pattern.chars();
+    let mut in_class = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                result.push(c);
+                if let Some(next) = chars.next() {
+                    result.push(next);
+                }
+            }
+            '[' => {
+                in_class = true;
+                result.push(c);
+            }
+            ']' => {
+                in_class = false;
+                result.push(c);
+            }
+            '.' if !in_class => result.push_str(&format!("[^\\n{}]", excluded)),
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
+
+/// Calculate confidence score for a pattern, along with the examples it
+/// fails to match.
 ///
 /// `is_template` — when true, the pattern comes from a curated template
 /// and the generic specificity penalty (dot-count) is skipped.
@@ -278,15 +458,20 @@ fn calculate_confidence(
     examples: &[String],
     negative_examples: Option<&[String]>,
     is_template: bool,
-) -> f64 {
+) -> (f64, Vec<String>) {
     let re = match regex::Regex::new(pattern) {
         Ok(r) => r,
-        Err(_) => return 0.0,
+        Err(_) => return (0.0, examples.to_vec()),
     };
 
     // Count how many examples match
-    let positive_matches = examples.iter().filter(|e| re.is_match(e)).count();
+    let unmatched: Vec<String> = examples
+        .iter()
+        .filter(|e| !re.is_match(e))
+        .cloned()
+        .collect();
     let positive_total = examples.len();
+    let positive_matches = positive_total - unmatched.len();
 
     let mut confidence = positive_matches as f64 / positive_total as f64;
 
@@ -306,19 +491,86 @@ fn calculate_confidence(
         // Cap at 0.95 to keep exact-match (1.0) ranked higher.
         confidence = confidence.min(0.95);
     } else {
-        // Generic patterns: penalize many wildcard dots (`.`)
-        let specificity = 1.0 - (pattern.matches('.').count() as f64 * 0.1).min(0.3);
-        confidence *= specificity;
+        // Generic patterns: penalize wildcards, unbounded ones the most
+        confidence *= 1.0 - wildcard_penalty(pattern);
         confidence = confidence.min(1.0);
     }
 
-    confidence
+    (confidence, unmatched)
+}
+
+/// Specificity penalty for a generic pattern's wildcards, from 0.0 to 0.4.
+///
+/// An unbounded `.*`, `.+` or `.{n,}` costs 0.15 and a single `.` 0.05;
+/// escaped dots and dots inside classes are not wildcards. Explicit
+/// quantifier bounds like `{4}` or `{2,5}` earn back 0.05.
+fn wildcard_penalty(pattern: &str) -> f64 {
+    let mut penalty: f64 = 0.0;
+    let mut bounded = false;
+    let mut chars = pattern.chars().peekable();
+    let mut in_class = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '[' if !in_class => in_class = true,
+            ']' if in_class => in_class = false,
+            _ if in_class => {}
+            '.' => {
+                let unbounded = match chars.peek() {
+                    Some('*' | '+') => true,
+                    Some('{') => !is_bounded_repeat(chars.clone()),
+                    _ => false,
+                };
+                penalty += if unbounded { 0.15 } else { 0.05 };
+            }
+            '{' => bounded |= is_bounded_repeat(std::iter::once('{').chain(chars.clone())),
+            _ => {}
+        }
+    }
+
+    if bounded {
+        penalty -= 0.05;
+    }
+    penalty.clamp(0.0, 0.4)
+}
+
+/// Whether `chars` starts with a repetition that has an upper bound: `{n}` or `{n,m}`
+fn is_bounded_repeat(chars: impl Iterator<Item = char>) -> bool {
+    let mut chars = chars;
+    if chars.next() != Some('{') {
+        return false;
+    }
+    let body: String = chars.take_while(|&c| c != '}').collect();
+    match body.split_once(',') {
+        Some((min, max)) => {
+            !min.is_empty()
+                && !max.is_empty()
+                && min.chars().all(|c| c.is_ascii_digit())
+                && max.chars().all(|c| c.is_ascii_digit())
+        }
+        None => !body.is_empty() && body.chars().all(|c| c.is_ascii_digit()),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_confidence_reports_unmatched_examples() {
+        let examples = vec!["12".to_string(), "ab".to_string(), "34".to_string()];
+        let (confidence, unmatched) = calculate_confidence(r"^\d+$", &examples, None, false);
+        assert!((confidence - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(unmatched, ["ab"]);
+
+        let (confidence, unmatched) = calculate_confidence(r"\w+", &examples, None, false);
+        assert_eq!(confidence, 1.0);
+        assert!(unmatched.is_empty());
+    }
+
     #[test]
     fn test_infer_date_pattern() {
         let examples = vec![
@@ -327,7 +579,7 @@ mod tests {
             "2023-06-01".to_string(),
         ];
 
-        let result = infer_patterns(&examples, None).unwrap();
+        let result = infer_patterns(&examples, None, false).unwrap();
         assert!(!result.inferred.is_empty());
 
         // Should contain ISO date pattern
@@ -337,15 +589,99 @@ mod tests {
             .any(|p| p.pattern.contains(r"\d{4}-\d{2}-\d{2}")));
     }
 
+    #[test]
+    fn test_format_only() {
+        let examples = vec!["2024-01-15".to_string(), "2025-12-31".to_string()];
+        let result = infer_patterns(&examples, None, true).unwrap();
+        assert_eq!(result.inferred[0].pattern, r"\d{4}-\d{2}-\d{2}");
+        assert!(result
+            .inferred
+            .iter()
+            .all(|p| !p.desc.starts_with("Character class")));
+        assert!(result.note.is_none());
+
+        let examples = vec!["abc12".to_string(), "xyz34".to_string()];
+        let result = infer_patterns(&examples, None, true).unwrap();
+        assert!(result.inferred.is_empty());
+        assert!(result.note.is_some());
+    }
+
+    #[test]
+    fn test_wildcard_penalty() {
+        assert_eq!(wildcard_penalty(r"\d\.\d[.]"), 0.0);
+        assert!((wildcard_penalty(r"a.b") - 0.05).abs() < 1e-9);
+        assert!((wildcard_penalty(r"a.*?b") - 0.15).abs() < 1e-9);
+        assert!((wildcard_penalty(r"a.{2,}b") - 0.15).abs() < 1e-9);
+        assert!((wildcard_penalty(r"a.{2,3}b") - 0.0).abs() < 1e-9);
+        assert_eq!(wildcard_penalty(r".+.+.+.+"), 0.4);
+        assert!(wildcard_penalty(r"\d{4}-.+") < wildcard_penalty(r"\d+-.+"));
+    }
+
+    #[test]
+    fn test_date_template_ranks_above_wildcard() {
+        let examples = vec![
+            "2024-01-15".to_string(),
+            "2025-12-31".to_string(),
+            "2023-06-01".to_string(),
+        ];
+
+        let result = infer_patterns(&examples, None, false).unwrap();
+        let position = |pred: &dyn Fn(&InferredPattern) -> bool| {
+            result.inferred.iter().position(pred).unwrap()
+        };
+        let date = position(&|p| p.pattern == r"\d{4}-\d{2}-\d{2}");
+        let wildcard = position(&|p| p.desc.starts_with("Common prefix"));
+        assert!(date < wildcard, "{:?}", result.inferred);
+        assert!(result.inferred[wildcard].confidence <= 0.85);
+    }
+
     #[test]
     fn test_infer_with_negatives() {
         let examples = vec!["abc123".to_string(), "def456".to_string()];
         let negatives = vec!["123abc".to_string(), "xyz".to_string()];
 
-        let result = infer_patterns(&examples, Some(&negatives)).unwrap();
+        let result = infer_patterns(&examples, Some(&negatives), false).unwrap();
         assert!(!result.inferred.is_empty());
     }
 
+    #[test]
+    fn test_negatives_force_anchor() {
+        let examples = vec!["ab1".to_string(), "ab2".to_string()];
+        let negatives = vec!["xab1".to_string()];
+
+        let result = infer_patterns(&examples, Some(&negatives), false).unwrap();
+        let refined = result
+            .inferred
+            .iter()
+            .find(|p| p.desc.contains("refined against negatives"))
+            .expect("a refined candidate");
+        assert_eq!(refined.pattern, r"^ab\d$");
+        assert_eq!(refined.confidence, 1.0);
+    }
+
+    #[test]
+    fn test_negatives_force_class_restriction() {
+        let examples = vec!["123".to_string(), "4567".to_string()];
+        let negatives = vec!["123abc".to_string()];
+
+        let result = infer_patterns(&examples, Some(&negatives), false).unwrap();
+        let naive = result
+            .inferred
+            .iter()
+            .find(|p| p.pattern == r"\d\d\d.*")
+            .expect("the naive candidate is still reported");
+        let refined = result
+            .inferred
+            .iter()
+            .find(|p| p.pattern == r"^\d\d\d[^\na-zA-Z]*$")
+            .expect("a refined candidate excluding letters");
+        assert!(refined.confidence > naive.confidence);
+        assert!(refined.desc.contains("exclude [a-zA-Z]"));
+
+        let re = regex::Regex::new(&refined.pattern).unwrap();
+        assert!(!re.is_match("123abc"));
+    }
+
     #[test]
     fn test_infer_email() {
         let examples = vec![
@@ -354,7 +690,7 @@ mod tests {
             "info@company.co.uk".to_string(),
         ];
 
-        let result = infer_patterns(&examples, None).unwrap();
+        let result = infer_patterns(&examples, None, false).unwrap();
         assert!(result.inferred.iter().any(|p| p.desc.contains("Email")));
     }
 
@@ -366,7 +702,7 @@ mod tests {
             "255.255.255.0".to_string(),
         ];
 
-        let result = infer_patterns(&examples, None).unwrap();
+        let result = infer_patterns(&examples, None, false).unwrap();
         assert!(result.inferred.iter().any(|p| p.desc.contains("IPv4")));
     }
 
@@ -377,7 +713,7 @@ mod tests {
             "123e4567-e89b-12d3-a456-426614174000".to_string(),
         ];
 
-        let result = infer_patterns(&examples, None).unwrap();
+        let result = infer_patterns(&examples, None, false).unwrap();
         assert!(result.inferred.iter().any(|p| p.desc.contains("UUID")));
     }
 
@@ -389,7 +725,7 @@ mod tests {
             "10.20.30".to_string(),
         ];
 
-        let result = infer_patterns(&examples, None).unwrap();
+        let result = infer_patterns(&examples, None, false).unwrap();
         assert!(result
             .inferred
             .iter()
@@ -404,7 +740,7 @@ mod tests {
             "#0000ff".to_string(),
         ];
 
-        let result = infer_patterns(&examples, None).unwrap();
+        let result = infer_patterns(&examples, None, false).unwrap();
         assert!(result.inferred.iter().any(|p| p.desc.contains("Hex color")));
     }
 
@@ -415,7 +751,7 @@ mod tests {
             "http://test.org/path".to_string(),
         ];
 
-        let result = infer_patterns(&examples, None).unwrap();
+        let result = infer_patterns(&examples, None, false).unwrap();
         assert!(result.inferred.iter().any(|p| p.desc.contains("URL")));
     }
 
@@ -427,7 +763,7 @@ mod tests {
             "255.255.255.0".to_string(),
         ];
 
-        let result = infer_patterns(&examples, None).unwrap();
+        let result = infer_patterns(&examples, None, false).unwrap();
         let ipv4_pos = result.inferred.iter().position(|p| p.desc.contains("IPv4"));
         let phone_pos = result
             .inferred
@@ -0,0 +1,216 @@
+//! Implementation of `re-x lint` command
+//!
+//! Style checks over a pattern's AST. Each rule reports issues with a span
+//! and a suggested replacement for the offending token.
+//!
+//! Rules:
+//! - `unnecessary-escape`: punctuation escaped where it has no special meaning
+//!   in the Rust regex dialect, e.g. `\/`, `\,` or `[\.\-]`, often carried
+//!   over from JavaScript. A suggestion is only made if the unescaped pattern
+//!   parses to the same HIR, so it never changes what the pattern matches.
+//!
+//! Only patterns the standard `regex` engine accepts are supported.
+
+use regex_syntax::ast::{self, Ast, ClassSet, ClassSetItem, Literal, LiteralKind};
+use regex_syntax::hir::Hir;
+
+use super::engine::{normalize_pattern, CompiledRegex, CoreError};
+use crate::output::{LintIssue, LintResult, Span};
+
+/// Lint a pattern.
+///
+/// The pattern is normalized first (see [`normalize_pattern`]); spans and
+/// the fix refer to `normalized_pattern` when it is set.
+pub fn lint_pattern(pattern: &str) -> Result<LintResult, CoreError> {
+    let normalized = normalize_pattern(pattern);
+    let mut result = lint_normalized(normalized.as_deref().unwrap_or(pattern))?;
+    result.pattern = pattern.to_string();
+    result.normalized_pattern = normalized;
+    Ok(result)
+}
+
+/// [`lint_pattern`] for a pattern that is already normalized
+fn lint_normalized(pattern: &str) -> Result<LintResult, CoreError> {
+    CompiledRegex::new(pattern).map_err(CoreError::invalid_pattern)?;
+    // The pattern compiles, so a parse failure here means it needs fancy-regex
+    let fancy_only = || {
+        CoreError::from(
+            "lint only supports patterns the standard regex engine accepts (no lookaround or backreferences)",
+        )
+    };
+    let parsed = ast::parse::Parser::new()
+        .parse(pattern)
+        .map_err(|_| fancy_only())?;
+    let hir = parse_hir(pattern).ok_or_else(fancy_only)?;
+
+    let mut escapes = Vec::new();
+    collect_escapes(&parsed, &mut escapes);
+
+    let issues: Vec<LintIssue> = escapes
+        .into_iter()
+        .filter(|literal| same_hir(&hir, &unescape(pattern, &[literal.span.start.offset])))
+        .map(|literal| LintIssue {
+            rule: "unnecessary-escape".to_string(),
+            message: format!(
+                "'{}' has no special meaning here, so the escape is unnecessary",
+                literal.c
+            ),
+            span: to_span(&literal.span),
+            token: pattern[literal.span.start.offset..literal.span.end.offset].to_string(),
+            suggestion: literal.c.to_string(),
+        })
+        .collect();
+
+    // Each fix is safe alone; check they are also safe together
+    let starts: Vec<usize> = issues.iter().map(|issue| issue.span.start).collect();
+    let fixed = (!issues.is_empty())
+        .then(|| unescape(pattern, &starts))
+        .filter(|fixed| same_hir(&hir, fixed));
+
+    Ok(LintResult {
+        pattern: pattern.to_string(),
+        normalized_pattern: None,
+        clean: issues.is_empty(),
+        issues,
+        fixed,
+    })
+}
+
+/// Escaped punctuation literals that are candidates for unescaping.
+///
+/// Backslashes and brackets are left alone: `\\`, `\[` and `\]` are either
+/// required or clearer escaped.
+fn collect_escapes(node: &Ast, out: &mut Vec<Literal>) {
+    match node {
+        Ast::Literal(literal) => push_candidate(literal, out),
+        Ast::ClassBracketed(class) => collect_class_escapes(&class.kind, out),
+        Ast::Repetition(rep) => collect_escapes(&rep.ast, out),
+        Ast::Group(group) => collect_escapes(&group.ast, out),
+        Ast::Alternation(alt) => alt.asts.iter().for_each(|a| collect_escapes(a, out)),
+        Ast::Concat(concat) => concat.asts.iter().for_each(|a| collect_escapes(a, out)),
+        Ast::Empty(_)
+        | Ast::Flags(_)
+        | Ast::Dot(_)
+        | Ast::Assertion(_)
+        | Ast::ClassUnicode(_)
+        | Ast::ClassPerl(_) => {}
+    }
+}
+
+fn collect_class_escapes(set: &ClassSet, out: &mut Vec<Literal>) {
+    match set {
+        ClassSet::Item(item) => collect_item_escapes(item, out),
+        ClassSet::BinaryOp(op) => {
+            collect_class_escapes(&op.lhs, out);
+            collect_class_escapes(&op.rhs, out);
+        }
+    }
+}
+
+fn collect_item_escapes(item: &ClassSetItem, out: &mut Vec<Literal>) {
+    match item {
+        ClassSetItem::Literal(literal) => push_candidate(literal, out),
+        ClassSetItem::Range(range) => {
+            push_candidate(&range.start, out);
+            push_candidate(&range.end, out);
+        }
+        ClassSetItem::Bracketed(class) => collect_class_escapes(&class.kind, out),
+        ClassSetItem::Union(union) => union
+            .items
+            .iter()
+            .for_each(|item| collect_item_escapes(item, out)),
+        ClassSetItem::Empty(_)
+        | ClassSetItem::Ascii(_)
+        | ClassSetItem::Unicode(_)
+        | ClassSetItem::Perl(_) => {}
+    }
+}
+
+fn push_candidate(literal: &Literal, out: &mut Vec<Literal>) {
+    let escaped = matches!(literal.kind, LiteralKind::Meta | LiteralKind::Superfluous);
+    if escaped && !matches!(literal.c, '\\' | '[' | ']') {
+        out.push(literal.clone());
+    }
+}
+
+/// The pattern without the backslashes at `starts` (in ascending order)
+fn unescape(pattern: &str, starts: &[usize]) -> String {
+    let mut result = String::with_capacity(pattern.len());
+    let mut last = 0;
+    for &start in starts {
+        result.push_str(&pattern[last..start]);
+        last = start + 1; // skip the backslash
+    }
+    result.push_str(&pattern[last..]);
+    result
+}
+
+fn parse_hir(pattern: &str) -> Option<Hir> {
+    regex_syntax::parse(pattern).ok()
+}
+
+fn same_hir(hir: &Hir, candidate: &str) -> bool {
+    parse_hir(candidate).is_some_and(|other| &other == hir)
+}
+
+fn to_span(span: &ast::Span) -> Span {
+    Span {
+        start: span.start.offset,
+        end: span.end.offset,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_pattern() {
+        let result = lint_pattern(r"^\d+\.\d+$").unwrap();
+        assert!(result.clean);
+        assert!(result.fixed.is_none());
+    }
+
+    #[test]
+    fn test_superfluous_escapes() {
+        let result = lint_pattern(r"https?:\/\/[a-z]+\,").unwrap();
+        let tokens: Vec<&str> = result.issues.iter().map(|i| i.token.as_str()).collect();
+        assert_eq!(tokens, [r"\/", r"\/", r"\,"]);
+        assert_eq!(result.issues[0].span, Span { start: 7, end: 9 });
+        assert_eq!(result.issues[0].suggestion, "/");
+        assert_eq!(result.fixed.as_deref(), Some("https?://[a-z]+,"));
+    }
+
+    #[test]
+    fn test_class_escapes_depend_on_position() {
+        // `-` only needs escaping between two class members; `.` never in a class
+        let result = lint_pattern(r"[\-a\.]x[a\-z]").unwrap();
+        let tokens: Vec<&str> = result.issues.iter().map(|i| i.token.as_str()).collect();
+        assert_eq!(tokens, [r"\-", r"\."]);
+        assert_eq!(result.fixed.as_deref(), Some(r"[-a.]x[a\-z]"));
+    }
+
+    #[test]
+    fn test_required_escapes_kept() {
+        let result = lint_pattern(r"\(\d\)\*\[\]\\").unwrap();
+        assert!(result.clean, "{:?}", result.issues);
+    }
+
+    #[test]
+    fn test_rejects_fancy_pattern() {
+        assert!(matches!(
+            lint_pattern(r"a(?=\/)"),
+            Err(CoreError::Failed(_))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_pattern_error() {
+        for pattern in ["(", r"[a-\-]"] {
+            assert!(
+                matches!(lint_pattern(pattern), Err(CoreError::InvalidPattern(_))),
+                "{pattern}"
+            );
+        }
+    }
+}
@@ -2,21 +2,44 @@
 //!
 //! This module contains all the business logic for re-x commands.
 
+pub mod ast_diff;
 pub mod benchmark;
+pub mod build;
+pub mod corpus;
 pub mod engine;
 pub mod explain;
 pub mod from_examples;
+pub mod lint;
+pub mod normalize;
+pub mod nullable;
 pub mod portability;
 pub mod replace;
+pub mod simplify;
 pub mod templates;
 pub mod test;
 pub mod validate;
 
 // Re-export commonly used types
+pub use ast_diff::ast_diff;
 pub use benchmark::{benchmark_file, benchmark_pattern, BenchmarkOptions};
-pub use engine::EngineType;
-pub use explain::explain_pattern;
+pub use build::build_pattern;
+pub use corpus::{generate_corpus, CorpusOptions};
+pub use engine::{CoreError, EngineType};
+pub use explain::{explain_group_names, explain_pattern, ExplainOptions};
 pub use from_examples::infer_patterns;
-pub use replace::{apply_file, replace_file_preview, replace_with_captures};
-pub use test::{test_file, test_stdin, test_string, TestOptions};
-pub use validate::{validate_for_language, validate_pattern};
+pub use lint::lint_pattern;
+pub use normalize::{canonicalize, dedupe_patterns};
+pub use replace::{
+    apply_file, apply_file_selective, apply_files, apply_hunks, replace_file_content,
+    replace_file_preview, replace_with_captures, truncate_result, ApplyHunk,
+};
+pub use simplify::simplify;
+pub use test::{
+    alternation_from_lines, dedup_matches, group_rows, is_match_file, is_match_string,
+    pattern_coverage, sort_matches, stream_file_json, test_file, test_many, test_stdin,
+    test_string, TestOptions,
+};
+pub use validate::{
+    check_group_limit, compare_languages, detail_portability, validate_all_targets,
+    validate_for_language, validate_pattern, validate_runnable_pattern, ValidateOptions,
+};
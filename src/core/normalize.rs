@@ -0,0 +1,406 @@
+//! Implementation of `re-x normalize` command
+//!
+//! Rewrites a pattern into a canonical spelling so that patterns written
+//! differently but meaning the same thing compare equal:
+//! - quantifiers use the shortest syntax (`x{1,}` → `x+`, `x{0,1}` → `x?`,
+//!   `x{2,2}` → `x{2}`) and `x{1}` loses its quantifier
+//! - bracketed classes list their members sorted, with overlapping and
+//!   adjacent ranges merged (`[cba0-9]` → `[0-9a-c]`); a class of one member
+//!   becomes that member (`[.]` → `\.`, `[^\d]` → `\D`)
+//! - literals drop superfluous and numeric escapes (`\/` → `/`, `\x41` → `A`)
+//! - redundant non-capturing groups are removed, as in `simplify`
+//! - `(?P<name>...)` is written `(?<name>...)`
+//!
+//! Alternation branches keep their order, since the leftmost branch wins.
+//! The rewrite is cross-checked on sample inputs and dropped if it behaves
+//! differently. Patterns that need fancy-regex are returned unchanged.
+//!
+//! `re-x dedupe-patterns` groups the patterns of a file by canonical form.
+
+use std::collections::HashMap;
+
+use regex_syntax::ast::{
+    self, Ast, ClassBracketed, ClassSet, ClassSetItem, ClassSetRange, ClassSetUnion,
+    ClassUnicodeKind, GroupKind, HexLiteralKind, LiteralKind, RepetitionKind, RepetitionRange,
+    SpecialLiteralKind,
+};
+
+use super::engine::{CompiledRegex, CoreError};
+use super::simplify::{
+    behaves_identically, is_atom, is_plain_group, print, spliceable, unwrap_top_level,
+};
+use crate::output::{
+    DedupePatternsResult, InvalidPattern, NormalizeResult, PatternGroup, PatternSource,
+};
+
+/// Canonicalize a pattern without changing what it matches
+pub fn canonicalize(pattern: &str) -> Result<NormalizeResult, CoreError> {
+    CompiledRegex::new(pattern).map_err(CoreError::invalid_pattern)?;
+
+    let normalized = match ast::parse::Parser::new().parse(pattern) {
+        Ok(mut parsed) => {
+            let extended = uses_free_spacing(&parsed);
+            canonicalize_ast(&mut parsed, extended);
+            let candidate = print(&unwrap_top_level(parsed));
+            if candidate != pattern && behaves_identically(pattern, &candidate) {
+                candidate
+            } else {
+                pattern.to_string()
+            }
+        }
+        // Backtracking-only syntax: leave it alone
+        Err(_) => pattern.to_string(),
+    };
+
+    Ok(NormalizeResult {
+        original: pattern.to_string(),
+        changed: normalized != pattern,
+        normalized,
+    })
+}
+
+/// Group the patterns of a file (one per line, blank lines skipped) that
+/// canonicalize to the same form. Only groups with more than one member are
+/// reported, in order of first appearance.
+pub fn dedupe_patterns(patterns: &str) -> DedupePatternsResult {
+    let mut groups: Vec<PatternGroup> = Vec::new();
+    let mut group_of: HashMap<String, usize> = HashMap::new();
+    let mut invalid = Vec::new();
+    let mut total = 0;
+
+    for (index, pattern) in patterns.lines().enumerate() {
+        if pattern.trim().is_empty() {
+            continue;
+        }
+        total += 1;
+        let source = PatternSource {
+            line: index + 1,
+            pattern: pattern.to_string(),
+        };
+        match canonicalize(pattern) {
+            Ok(result) => match group_of.get(&result.normalized) {
+                Some(&i) => groups[i].members.push(source),
+                None => {
+                    group_of.insert(result.normalized.clone(), groups.len());
+                    groups.push(PatternGroup {
+                        canonical: result.normalized,
+                        members: vec![source],
+                    });
+                }
+            },
+            Err(error) => invalid.push(InvalidPattern {
+                line: source.line,
+                pattern: source.pattern,
+                error: error.to_string(),
+            }),
+        }
+    }
+
+    let distinct = groups.len();
+    groups.retain(|group| group.members.len() > 1);
+    DedupePatternsResult {
+        total,
+        distinct,
+        groups,
+        invalid,
+    }
+}
+
+/// Recursively canonicalize an AST bottom-up, in place
+fn canonicalize_ast(node: &mut Ast, extended: bool) {
+    match node {
+        Ast::Literal(literal) => **literal = canonical_literal(literal, false, extended),
+        Ast::ClassUnicode(class) => canonical_unicode_kind(&mut class.kind),
+        Ast::ClassBracketed(class) => {
+            if let Some(canonical) = canonical_class(class, extended) {
+                *node = canonical;
+            }
+        }
+        Ast::Repetition(rep) => {
+            canonicalize_ast(&mut rep.ast, extended);
+            rep.op.kind = match std::mem::replace(&mut rep.op.kind, RepetitionKind::ZeroOrMore) {
+                RepetitionKind::Range(RepetitionRange::AtLeast(0)) => RepetitionKind::ZeroOrMore,
+                RepetitionKind::Range(RepetitionRange::AtLeast(1)) => RepetitionKind::OneOrMore,
+                RepetitionKind::Range(RepetitionRange::Bounded(0, 1)) => RepetitionKind::ZeroOrOne,
+                RepetitionKind::Range(RepetitionRange::Bounded(m, n)) if m == n => {
+                    RepetitionKind::Range(RepetitionRange::Exactly(m))
+                }
+                kind => kind,
+            };
+
+            if let Ast::Group(g) = &*rep.ast {
+                if is_plain_group(&g.kind) && is_atom(&g.ast) {
+                    *rep.ast = (*g.ast).clone();
+                }
+            }
+            if rep.op.kind == RepetitionKind::Range(RepetitionRange::Exactly(1)) {
+                *node = (*rep.ast).clone();
+            }
+        }
+        Ast::Group(group) => {
+            if let GroupKind::CaptureName { starts_with_p, .. } = &mut group.kind {
+                *starts_with_p = false;
+            }
+            canonicalize_ast(&mut group.ast, extended);
+        }
+        Ast::Concat(concat) => {
+            let mut asts = Vec::with_capacity(concat.asts.len());
+            for mut child in std::mem::take(&mut concat.asts) {
+                canonicalize_ast(&mut child, extended);
+                match spliceable(&child) {
+                    Some(Ast::Concat(inner)) => asts.extend(inner.asts.iter().cloned()),
+                    Some(inner) => asts.push(inner.clone()),
+                    None => asts.push(child),
+                }
+            }
+            concat.asts = asts;
+        }
+        Ast::Alternation(alt) => {
+            for branch in alt.asts.iter_mut() {
+                canonicalize_ast(branch, extended);
+                if let Some(inner) = spliceable(branch) {
+                    *branch = inner.clone();
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Spell a literal one fixed way: named escapes for tab and line breaks,
+/// `\x{..}` for other control characters (and whitespace under `(?x)`, where
+/// a bare space would be ignored), a backslash only where the character is
+/// special, and verbatim otherwise.
+fn canonical_literal(literal: &ast::Literal, in_class: bool, extended: bool) -> ast::Literal {
+    let c = literal.c;
+    let special = if in_class {
+        "\\[]^-&~"
+    } else {
+        "\\.+*?()|[]{}^$"
+    };
+    let kind = match c {
+        '\t' => LiteralKind::Special(SpecialLiteralKind::Tab),
+        '\n' => LiteralKind::Special(SpecialLiteralKind::LineFeed),
+        '\r' => LiteralKind::Special(SpecialLiteralKind::CarriageReturn),
+        _ if c.is_control() || (c.is_whitespace() && (extended || c != ' ')) => {
+            LiteralKind::HexBrace(HexLiteralKind::X)
+        }
+        _ if special.contains(c) || (extended && c == '#') => LiteralKind::Meta,
+        _ => LiteralKind::Verbatim,
+    };
+    ast::Literal {
+        span: literal.span,
+        kind,
+        c,
+    }
+}
+
+/// `\pL` and `\p{L}` are the same class; always use braces
+fn canonical_unicode_kind(kind: &mut ClassUnicodeKind) {
+    if let ClassUnicodeKind::OneLetter(c) = kind {
+        *kind = ClassUnicodeKind::Named(c.to_string());
+    }
+}
+
+/// Sort and merge the members of a bracketed class, or `None` to leave it as
+/// written (set operations, nested classes)
+fn canonical_class(class: &ClassBracketed, extended: bool) -> Option<Ast> {
+    let items: Vec<&ClassSetItem> = match &class.kind {
+        ClassSet::Item(ClassSetItem::Union(union)) => union.items.iter().collect(),
+        ClassSet::Item(item) => vec![item],
+        ClassSet::BinaryOp(_) => return None,
+    };
+
+    let mut ranges = Vec::new();
+    let mut named = Vec::new();
+    for item in items {
+        match item {
+            ClassSetItem::Empty(_) => {}
+            ClassSetItem::Literal(l) => ranges.push((l.c, l.c)),
+            ClassSetItem::Range(r) => ranges.push((r.start.c, r.end.c)),
+            ClassSetItem::Unicode(u) => {
+                let mut u = u.clone();
+                canonical_unicode_kind(&mut u.kind);
+                named.push(ClassSetItem::Unicode(u));
+            }
+            ClassSetItem::Ascii(_) | ClassSetItem::Perl(_) => named.push(item.clone()),
+            ClassSetItem::Bracketed(_) | ClassSetItem::Union(_) => return None,
+        }
+    }
+
+    // Sorted, with overlapping or touching ranges merged
+    ranges.sort_unstable();
+    let mut merged: Vec<(char, char)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start as u32 <= last.1 as u32 + 1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    let key = |item: &ClassSetItem| print(&bracketed(class, vec![item.clone()], false));
+    named.sort_by_cached_key(key);
+    named.dedup_by(|a, b| key(a) == key(b));
+
+    // A lone member needs no brackets
+    let span = class.span;
+    match (merged.as_slice(), named.as_slice()) {
+        ([(c, end)], []) if c == end && !class.negated => {
+            let literal = ast::Literal {
+                span,
+                kind: LiteralKind::Verbatim,
+                c: *c,
+            };
+            return Some(Ast::literal(canonical_literal(&literal, false, extended)));
+        }
+        ([], [ClassSetItem::Perl(perl)]) => {
+            let mut perl = perl.clone();
+            perl.negated ^= class.negated;
+            return Some(Ast::class_perl(perl));
+        }
+        ([], [ClassSetItem::Unicode(unicode)]) => {
+            let mut unicode = unicode.clone();
+            unicode.negated ^= class.negated;
+            return Some(Ast::class_unicode(unicode));
+        }
+        _ => {}
+    }
+
+    let literal = |c: char| {
+        canonical_literal(
+            &ast::Literal {
+                span,
+                kind: LiteralKind::Verbatim,
+                c,
+            },
+            true,
+            extended,
+        )
+    };
+    let mut members = Vec::new();
+    for (start, end) in merged {
+        if start == end {
+            members.push(ClassSetItem::Literal(literal(start)));
+        } else if end as u32 == start as u32 + 1 {
+            members.push(ClassSetItem::Literal(literal(start)));
+            members.push(ClassSetItem::Literal(literal(end)));
+        } else {
+            members.push(ClassSetItem::Range(ClassSetRange {
+                span,
+                start: literal(start),
+                end: literal(end),
+            }));
+        }
+    }
+    members.extend(named);
+
+    Some(bracketed(class, members, class.negated))
+}
+
+fn bracketed(class: &ClassBracketed, items: Vec<ClassSetItem>, negated: bool) -> Ast {
+    Ast::class_bracketed(ClassBracketed {
+        span: class.span,
+        negated,
+        kind: ClassSet::union(ClassSetUnion {
+            span: class.span,
+            items,
+        }),
+    })
+}
+
+/// Whether `(?x)` is switched on anywhere, making whitespace insignificant
+fn uses_free_spacing(node: &Ast) -> bool {
+    let sets_x = |flags: &ast::Flags| {
+        flags
+            .items
+            .iter()
+            .any(|item| item.kind == ast::FlagsItemKind::Flag(ast::Flag::IgnoreWhitespace))
+    };
+    match node {
+        Ast::Flags(f) => sets_x(&f.flags),
+        Ast::Group(g) => {
+            matches!(&g.kind, GroupKind::NonCapturing(flags) if sets_x(flags))
+                || uses_free_spacing(&g.ast)
+        }
+        Ast::Repetition(r) => uses_free_spacing(&r.ast),
+        Ast::Concat(c) => c.asts.iter().any(uses_free_spacing),
+        Ast::Alternation(a) => a.asts.iter().any(uses_free_spacing),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalized(pattern: &str) -> String {
+        canonicalize(pattern).unwrap().normalized
+    }
+
+    #[test]
+    fn test_dedupe_patterns() {
+        let result = dedupe_patterns("a{1,}\n\n[ba]\nx\na+\n(?:[ab])\n(\na+\n");
+        assert_eq!(result.total, 7);
+        assert_eq!(result.distinct, 3);
+        let groups: Vec<(&str, Vec<usize>)> = result
+            .groups
+            .iter()
+            .map(|g| {
+                (
+                    g.canonical.as_str(),
+                    g.members.iter().map(|m| m.line).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(groups, vec![("a+", vec![1, 5, 8]), ("[ab]", vec![3, 6])]);
+        assert_eq!(result.invalid.len(), 1);
+        assert_eq!(result.invalid[0].line, 7);
+    }
+
+    #[test]
+    fn test_quantifier_syntax() {
+        assert_eq!(normalized("a{1,}b{0,}c{0,1}?"), "a+b*c??");
+        assert_eq!(normalized(r"\d{2,2}x{1}"), r"\d{2}x");
+        assert_eq!(normalized("(?:ab){1}c"), "abc");
+    }
+
+    #[test]
+    fn test_class_members_sorted_and_merged() {
+        assert_eq!(normalized("[cba]"), "[a-c]");
+        assert_eq!(normalized(r"[z\da-fb-k]"), r"[a-kz\d]");
+        assert_eq!(normalized("[^ba]"), "[^ab]");
+        assert_eq!(normalized(r"[\-.]"), r"[\-.]");
+        assert_eq!(normalized(r"[.]x[^\d]"), r"\.x\D");
+    }
+
+    #[test]
+    fn test_literal_escapes() {
+        assert_eq!(normalized(r"\/\x41\u{42}\%"), "/AB%");
+        assert_eq!(normalized(r"a\.b"), r"a\.b");
+        assert_eq!(normalized(r"(?x)a\ b\#"), r"(?x)a\x{20}b\#");
+    }
+
+    #[test]
+    fn test_equivalent_spellings_agree() {
+        let groups = [
+            [
+                r"(?P<y>[0-9]{4,4})-(?:[0-9]){2}",
+                r"(?<y>[0-9]{4})-[0-9]{2}",
+            ],
+            [r"(?:[cab])[x]{1,}", r"[a-c]x+"],
+            [r"\pL+", r"\p{L}+"],
+        ];
+        for [a, b] in groups {
+            assert_eq!(normalized(a), normalized(b), "{} vs {}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_unchanged_patterns() {
+        for pattern in ["a|b", "(?:a|b)c", r"[a-z&&[^aeiou]]", r"(\w+)\s(?=x)"] {
+            let result = canonicalize(pattern).unwrap();
+            assert_eq!(result.normalized, pattern);
+            assert!(!result.changed);
+        }
+        assert!(canonicalize("(abc").is_err());
+    }
+}
@@ -0,0 +1,190 @@
+//! Empty-match analysis
+//!
+//! Computes whether a pattern can match the empty string (it is "nullable")
+//! and whether it can consume any text at all. A pattern that is both, like
+//! `a*`, reports an empty match at every position where it finds nothing
+//! better — usually a mistake. Patterns that are only zero-width (`^`, `\b`)
+//! match nothing but positions, which is what they are for.
+//!
+//! Standard patterns are analysed on the `regex_syntax` AST, fancy ones on
+//! the fancy-regex expression tree.
+
+use regex_syntax::ast::{self, Ast};
+
+/// How a pattern relates to the empty string
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Nullability {
+    /// Some match of the pattern can be empty
+    pub nullable: bool,
+    /// Some match of the pattern can be non-empty
+    pub consumes: bool,
+}
+
+impl Nullability {
+    const ZERO_WIDTH: Self = Self {
+        nullable: true,
+        consumes: false,
+    };
+    const CONSUMING: Self = Self {
+        nullable: false,
+        consumes: true,
+    };
+    /// Used where the answer depends on what a group captured at match time
+    const EITHER: Self = Self {
+        nullable: true,
+        consumes: true,
+    };
+
+    /// Can match the empty string and non-empty text alike, e.g. `a*`
+    pub fn floods_empty_matches(&self) -> bool {
+        self.nullable && self.consumes
+    }
+
+    /// All parts in sequence
+    fn concat(parts: impl IntoIterator<Item = Self>) -> Self {
+        parts.into_iter().fold(Self::ZERO_WIDTH, |acc, part| Self {
+            nullable: acc.nullable && part.nullable,
+            consumes: acc.consumes || part.consumes,
+        })
+    }
+
+    /// Any one of the branches
+    fn alternation(branches: impl IntoIterator<Item = Self>) -> Self {
+        branches.into_iter().fold(
+            Self {
+                nullable: false,
+                consumes: false,
+            },
+            |acc, branch| Self {
+                nullable: acc.nullable || branch.nullable,
+                consumes: acc.consumes || branch.consumes,
+            },
+        )
+    }
+
+    /// `inner` repeated between `min` and `max` times
+    fn repeat(inner: Self, min: u32, max: Option<u32>) -> Self {
+        Self {
+            nullable: min == 0 || inner.nullable,
+            consumes: max != Some(0) && inner.consumes,
+        }
+    }
+}
+
+/// Analyse a pattern, or `None` if neither engine can parse it
+pub fn nullability(pattern: &str) -> Option<Nullability> {
+    if let Ok(ast) = ast::parse::Parser::new().parse(pattern) {
+        return Some(ast_nullability(&ast));
+    }
+    fancy_regex::Expr::parse_tree(pattern)
+        .ok()
+        .map(|tree| expr_nullability(&tree.expr))
+}
+
+fn ast_nullability(ast: &Ast) -> Nullability {
+    match ast {
+        Ast::Empty(_) | Ast::Flags(_) | Ast::Assertion(_) => Nullability::ZERO_WIDTH,
+        Ast::Literal(_)
+        | Ast::Dot(_)
+        | Ast::ClassUnicode(_)
+        | Ast::ClassPerl(_)
+        | Ast::ClassBracketed(_) => Nullability::CONSUMING,
+        Ast::Repetition(r) => {
+            let (min, max) = match &r.op.kind {
+                ast::RepetitionKind::ZeroOrOne => (0, Some(1)),
+                ast::RepetitionKind::ZeroOrMore => (0, None),
+                ast::RepetitionKind::OneOrMore => (1, None),
+                ast::RepetitionKind::Range(ast::RepetitionRange::Exactly(n)) => (*n, Some(*n)),
+                ast::RepetitionKind::Range(ast::RepetitionRange::AtLeast(n)) => (*n, None),
+                ast::RepetitionKind::Range(ast::RepetitionRange::Bounded(m, n)) => (*m, Some(*n)),
+            };
+            Nullability::repeat(ast_nullability(&r.ast), min, max)
+        }
+        Ast::Group(g) => ast_nullability(&g.ast),
+        Ast::Concat(c) => Nullability::concat(c.asts.iter().map(ast_nullability)),
+        Ast::Alternation(a) => Nullability::alternation(a.asts.iter().map(ast_nullability)),
+    }
+}
+
+fn expr_nullability(expr: &fancy_regex::Expr) -> Nullability {
+    use fancy_regex::Expr;
+
+    match expr {
+        Expr::Empty
+        | Expr::Assertion(_)
+        | Expr::LookAround(..)
+        | Expr::KeepOut
+        | Expr::ContinueFromPreviousMatchEnd
+        | Expr::BackrefExistsCondition(_) => Nullability::ZERO_WIDTH,
+        Expr::Any { .. } => Nullability::CONSUMING,
+        Expr::Literal { val, .. } => {
+            if val.is_empty() {
+                Nullability::ZERO_WIDTH
+            } else {
+                Nullability::CONSUMING
+            }
+        }
+        Expr::Delegate { size, .. } => {
+            if *size == 0 {
+                Nullability::ZERO_WIDTH
+            } else {
+                Nullability::CONSUMING
+            }
+        }
+        Expr::Concat(children) => Nullability::concat(children.iter().map(expr_nullability)),
+        Expr::Alt(children) => Nullability::alternation(children.iter().map(expr_nullability)),
+        Expr::Group(inner) | Expr::AtomicGroup(inner) => expr_nullability(inner),
+        Expr::Repeat { child, lo, hi, .. } => {
+            let min = u32::try_from(*lo).unwrap_or(u32::MAX);
+            let max = (*hi != usize::MAX).then(|| u32::try_from(*hi).unwrap_or(u32::MAX));
+            Nullability::repeat(expr_nullability(child), min, max)
+        }
+        Expr::Conditional {
+            true_branch,
+            false_branch,
+            ..
+        } => Nullability::alternation([
+            expr_nullability(true_branch),
+            expr_nullability(false_branch),
+        ]),
+        Expr::Backref { .. }
+        | Expr::BackrefWithRelativeRecursionLevel { .. }
+        | Expr::SubroutineCall(_)
+        | Expr::UnresolvedNamedSubroutineCall { .. } => Nullability::EITHER,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn floods(pattern: &str) -> bool {
+        nullability(pattern).unwrap().floods_empty_matches()
+    }
+
+    #[test]
+    fn test_nullable_patterns() {
+        for pattern in [
+            "a*",
+            "x?",
+            "(?:ab)*c?",
+            "a|",
+            "[0-9]{0,3}",
+            r"\d*(?=x)",
+            r"(\w)*\1",
+        ] {
+            assert!(floods(pattern), "{}", pattern);
+        }
+        for pattern in ["a+", "a*b", "(a|b)c?", r"\d{2,}", "a{0}b", r"(\w)\1*"] {
+            assert!(!floods(pattern), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_zero_width_patterns_are_not_floods() {
+        for pattern in ["^", r"\b", "(?:)", "(?m)^$", "a{0}", "(?=x)", r"(?<!\d)"] {
+            let n = nullability(pattern).unwrap();
+            assert!(n.nullable && !n.consumes, "{}", pattern);
+        }
+    }
+}
@@ -5,7 +5,8 @@
 
 use std::sync::LazyLock;
 
-use crate::output::Portability;
+use super::engine::{parse_ast, DEFAULT_MAX_DEPTH};
+use crate::output::{DetailedPortability, EngineCompatibility, Portability};
 
 static LOOKBEHIND_RE: LazyLock<regex::Regex> = LazyLock::new(|| {
     regex::Regex::new(r"\(\?<[=!][^)]*[+*?][^)]*\)")
@@ -20,6 +21,50 @@ static INLINE_FLAGS_RE: LazyLock<regex::Regex> = LazyLock::new(|| {
     regex::Regex::new(r"\(\?[imsx]+\)").expect("BUG: inline flags detection pattern is invalid")
 });
 
+static HEX_DIGIT_ESCAPE_RE: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(r"(?:^|[^\\])(?:\\\\)*\\[hH]")
+        .expect("BUG: hex digit escape detection pattern is invalid")
+});
+
+static LINEBREAK_ESCAPE_RE: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(r"(?:^|[^\\])(?:\\\\)*\\R")
+        .expect("BUG: line break escape detection pattern is invalid")
+});
+
+static PERL_CLASS_RE: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(r"(?:^|[^\\])(?:\\\\)*\\[dDwWsS]")
+        .expect("BUG: Perl class detection pattern is invalid")
+});
+
+static LAZY_QUANTIFIER_RE: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(r"(?:^|[^\\])(?:\\\\)*[*+?}]\?")
+        .expect("BUG: lazy quantifier detection pattern is invalid")
+});
+
+static PLUS_OR_OPTIONAL_RE: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(r"(?:^|[^\\(])(?:\\\\)*[+?]")
+        .expect("BUG: + / ? quantifier detection pattern is invalid")
+});
+
+static GROUP_RE: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(r"(?:^|[^\\])(?:\\\\)*\(").expect("BUG: group detection pattern is invalid")
+});
+
+static COUNTED_REPETITION_RE: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(r"(?:^|[^\\])(?:\\\\)*\{\d")
+        .expect("BUG: counted repetition detection pattern is invalid")
+});
+
+static ALTERNATION_RE: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(r"(?:^|[^\\])(?:\\\\)*\|")
+        .expect("BUG: alternation detection pattern is invalid")
+});
+
+static UNICODE_PROPERTY_VALUE_RE: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(r"\\[pP]\{[^}]*[=:]")
+        .expect("BUG: Unicode property value detection pattern is invalid")
+});
+
 static SUBROUTINE_RE: LazyLock<regex::Regex> = LazyLock::new(|| {
     regex::Regex::new(r"\\g<[^>]+>").expect("BUG: subroutine detection pattern is invalid")
 });
@@ -34,9 +79,13 @@ pub struct PatternFeatures {
     pub non_word_boundary: bool, // \B
 
     // Character classes
-    pub unicode_classes: bool, // \p{...}
-    pub negated_unicode: bool, // \P{...}
-    pub posix_classes: bool,   // [:alpha:]
+    pub unicode_classes: bool,        // \p{...}
+    pub negated_unicode: bool,        // \P{...}
+    pub unicode_property_value: bool, // \p{Script=Greek}
+    pub posix_classes: bool,          // [:alpha:]
+    pub hex_digit_escape: bool,       // \h, \H (Oniguruma hex digit)
+    pub linebreak_escape: bool,       // \R (any line break)
+    pub perl_classes: bool,           // \d, \w, \s and their negations
 
     // Assertions
     pub lookahead: bool,           // (?=...) (?!...)
@@ -44,13 +93,18 @@ pub struct PatternFeatures {
     pub variable_lookbehind: bool, // (?<=a+) - variable length
 
     // Groups
+    pub group: bool,         // any (...) group (POSIX BRE spells them \(...\))
     pub named_capture: bool, // (?P<name>...) or (?<name>...)
     pub non_capturing: bool, // (?:...)
     pub atomic_group: bool,  // (?>...)
     pub backreference: bool, // \1, \2
+    pub alternation: bool,   // a|b
 
     // Quantifiers
-    pub possessive: bool, // a++, a*+
+    pub possessive: bool,         // a++, a*+
+    pub lazy: bool,               // a*?, a+?
+    pub plus_or_optional: bool,   // a+, a? (POSIX BRE only has * and \{n,m\})
+    pub counted_repetition: bool, // a{2}, a{1,3} (POSIX BRE spells them \{n,m\})
 
     // Flags
     pub inline_flags: bool, // (?i), (?m), etc.
@@ -67,9 +121,9 @@ impl PatternFeatures {
     /// Uses AST-based analysis when `regex_syntax` can parse the pattern (standard regex),
     /// and falls back to string-based heuristics for fancy-regex patterns.
     pub fn analyze(pattern: &str) -> Self {
-        use regex_syntax::ast::parse::Parser as AstParser;
-
-        match AstParser::new().parse(pattern) {
+        // Parse under the same nesting guard as explain/validate so the
+        // recursive `walk_ast` never descends past it
+        match parse_ast(pattern, DEFAULT_MAX_DEPTH) {
             Ok(ast) => Self::analyze_from_ast(&ast),
             Err(_) => Self::analyze_from_string(pattern),
         }
@@ -81,7 +135,8 @@ impl PatternFeatures {
         walk_ast(ast, &mut features);
         // Fancy-only features are always false in AST path:
         // lookahead, lookbehind, backreference, atomic_group,
-        // possessive, conditional, recursion, subroutine
+        // possessive, conditional, recursion, subroutine.
+        // \h, \H and \R are rejected by the AST parser, so they are too.
         features
     }
 
@@ -99,10 +154,15 @@ impl PatternFeatures {
             non_word_boundary: pattern.contains(r"\B"),
             unicode_classes: pattern.contains(r"\p{") || pattern.contains(r"\P{"),
             negated_unicode: pattern.contains(r"\P{"),
+            unicode_property_value: UNICODE_PROPERTY_VALUE_RE.is_match(pattern),
             posix_classes: pattern.contains("[:") && pattern.contains(":]"),
+            hex_digit_escape: HEX_DIGIT_ESCAPE_RE.is_match(pattern),
+            linebreak_escape: LINEBREAK_ESCAPE_RE.is_match(pattern),
+            perl_classes: PERL_CLASS_RE.is_match(pattern),
             lookahead: pattern.contains("(?=") || pattern.contains("(?!"),
             lookbehind,
             variable_lookbehind: lookbehind && LOOKBEHIND_RE.is_match(pattern),
+            group: GROUP_RE.is_match(pattern),
             named_capture: pattern.contains("(?P<")
                 || (pattern.contains("(?<")
                     && !pattern.contains("(?<=")
@@ -110,10 +170,14 @@ impl PatternFeatures {
             non_capturing: pattern.contains("(?:"),
             atomic_group: pattern.contains("(?>"),
             backreference: BACKREF_RE.is_match(pattern),
+            alternation: ALTERNATION_RE.is_match(pattern),
             possessive: pattern.contains("++")
                 || pattern.contains("*+")
                 || pattern.contains("?+")
                 || pattern.contains("}+"),
+            lazy: LAZY_QUANTIFIER_RE.is_match(pattern),
+            plus_or_optional: PLUS_OR_OPTIONAL_RE.is_match(pattern),
+            counted_repetition: COUNTED_REPETITION_RE.is_match(pattern),
             inline_flags: INLINE_FLAGS_RE.is_match(pattern),
             conditional: pattern.contains("(?("),
             recursion: pattern.contains("(?R)") || pattern.contains("(?0)"),
@@ -124,7 +188,7 @@ impl PatternFeatures {
 
 /// Recursively walk the AST to detect features
 fn walk_ast(ast: &regex_syntax::ast::Ast, features: &mut PatternFeatures) {
-    use regex_syntax::ast::{AssertionKind, Ast, GroupKind};
+    use regex_syntax::ast::{AssertionKind, Ast, GroupKind, RepetitionKind};
 
     match ast {
         Ast::Assertion(a) => match a.kind {
@@ -134,16 +198,15 @@ fn walk_ast(ast: &regex_syntax::ast::Ast, features: &mut PatternFeatures) {
             AssertionKind::NotWordBoundary => features.non_word_boundary = true,
             _ => {}
         },
-        Ast::ClassUnicode(c) => {
-            features.unicode_classes = true;
-            if c.negated {
-                features.negated_unicode = true;
-            }
+        Ast::ClassUnicode(c) => walk_unicode_class(c, features),
+        Ast::ClassPerl(_) => {
+            features.perl_classes = true;
         }
         Ast::ClassBracketed(c) => {
             walk_class_set(&c.kind, features);
         }
         Ast::Group(g) => {
+            features.group = true;
             match &g.kind {
                 GroupKind::CaptureName { .. } => features.named_capture = true,
                 GroupKind::NonCapturing(_) => features.non_capturing = true,
@@ -160,11 +223,22 @@ fn walk_ast(ast: &regex_syntax::ast::Ast, features: &mut PatternFeatures) {
             }
         }
         Ast::Alternation(a) => {
+            features.alternation = true;
             for child in &a.asts {
                 walk_ast(child, features);
             }
         }
         Ast::Repetition(r) => {
+            if !r.greedy {
+                features.lazy = true;
+            }
+            match r.op.kind {
+                RepetitionKind::OneOrMore | RepetitionKind::ZeroOrOne => {
+                    features.plus_or_optional = true
+                }
+                RepetitionKind::Range(_) => features.counted_repetition = true,
+                RepetitionKind::ZeroOrMore => {}
+            }
             walk_ast(&r.ast, features);
         }
         _ => {}
@@ -192,11 +266,9 @@ fn walk_class_set_item(item: &regex_syntax::ast::ClassSetItem, features: &mut Pa
         ClassSetItem::Ascii(_) => {
             features.posix_classes = true;
         }
-        ClassSetItem::Unicode(c) => {
-            features.unicode_classes = true;
-            if c.negated {
-                features.negated_unicode = true;
-            }
+        ClassSetItem::Unicode(c) => walk_unicode_class(c, features),
+        ClassSetItem::Perl(_) => {
+            features.perl_classes = true;
         }
         ClassSetItem::Bracketed(b) => {
             walk_class_set(&b.kind, features);
@@ -210,102 +282,397 @@ fn walk_class_set_item(item: &regex_syntax::ast::ClassSetItem, features: &mut Pa
     }
 }
 
+/// Record a `\p{...}` / `\P{...}` class, noting the `name=value` form
+fn walk_unicode_class(class: &regex_syntax::ast::ClassUnicode, features: &mut PatternFeatures) {
+    features.unicode_classes = true;
+    if class.negated {
+        features.negated_unicode = true;
+    }
+    if matches!(
+        class.kind,
+        regex_syntax::ast::ClassUnicodeKind::NamedValue { .. }
+    ) {
+        features.unicode_property_value = true;
+    }
+}
+
 /// Check portability to various languages/engines
 pub fn check_portability(pattern: &str) -> Portability {
     let features = PatternFeatures::analyze(pattern);
+    let compatible = |target: &str| unsupported_features(&features, target).next().is_none();
 
     Portability {
-        rust_regex: is_rust_regex_compatible(&features),
-        pcre2: is_pcre2_compatible(&features),
-        javascript: is_javascript_compatible(&features),
-        python_re: is_python_re_compatible(&features),
-        python_regex: is_python_regex_compatible(&features),
-        go_regexp: is_go_regexp_compatible(&features),
-        java: Some(is_java_compatible(&features)),
-        dotnet: is_dotnet_compatible(&features),
-        ruby: is_ruby_compatible(&features),
+        rust_regex: compatible("rust_regex"),
+        pcre2: compatible("pcre2"),
+        javascript: compatible("javascript"),
+        python_re: compatible("python_re"),
+        python_regex: compatible("python_regex"),
+        go_regexp: compatible("go_regexp"),
+        java: Some(compatible("java")),
+        dotnet: compatible("dotnet"),
+        ruby: compatible("ruby"),
+        posix_ere: compatible("posix_ere"),
+        posix_bre: compatible("posix_bre"),
     }
 }
 
-/// Rust regex crate compatibility
-fn is_rust_regex_compatible(features: &PatternFeatures) -> bool {
-    !features.lookahead
-        && !features.lookbehind
-        && !features.backreference
-        && !features.atomic_group
-        && !features.possessive
-        && !features.conditional
-        && !features.recursion
-        && !features.subroutine
+const POSIX: &[&str] = &["posix_ere", "posix_bre"];
+const NO_LOOKAROUND: &[&str] = &["rust_regex", "go_regexp", "posix_ere", "posix_bre"];
+/// Everything except PCRE2, Java and Ruby (Oniguruma) lacks `\h` and `\R`
+const NO_ONIG_ESCAPES: &[&str] = &[
+    "rust_regex",
+    "go_regexp",
+    "javascript",
+    "python_re",
+    "python_regex",
+    "dotnet",
+    "posix_ere",
+    "posix_bre",
+];
+
+/// Feature test, description, and the targets that reject the feature
+type FeatureSupport = (
+    fn(&PatternFeatures) -> bool,
+    &'static str,
+    &'static [&'static str],
+);
+
+/// One entry per pattern feature that some target lacks: how to detect it,
+/// how to describe it, and the targets (canonical names) that reject it.
+///
+/// This is the single source of truth for both `check_portability` and
+/// `target_issues`. Notes per engine:
+/// - Rust regex and Go (RE2): no lookaround, backreferences or other
+///   backtracking-only constructs; Go also only knows bare `\p{Greek}` names.
+/// - PCRE2: supports almost everything, but spells POSIX classes differently.
+/// - JavaScript: lookbehind must be fixed-length; no atomic/possessive.
+/// - .NET: variable-length lookbehind and conditionals, but no recursion,
+///   possessive quantifiers (pre-.NET 7) or `\p{Script=...}`.
+/// - Ruby (Oniguruma/Onigmo): subroutines and POSIX classes, but no PCRE-style
+///   recursion `(?R)` or conditionals.
+/// - POSIX ERE (`grep -E`) and BRE (`grep`, `sed`): no Perl classes, lazy
+///   quantifiers, lookaround, special groups, inline flags, `\p{...}` or word
+///   boundaries; BRE additionally has no alternation and no `+` or `?`, and
+///   spells groups and intervals `\(...\)` and `\{n,m\}`, so only `*` is
+///   written the same way.
+const FEATURE_SUPPORT: &[FeatureSupport] = &[
+    (
+        |f| f.lookahead,
+        "Lookahead assertions ((?=...) (?!...))",
+        NO_LOOKAROUND,
+    ),
+    (
+        |f| f.lookbehind,
+        "Lookbehind assertions ((?<=...) (?<!...))",
+        NO_LOOKAROUND,
+    ),
+    (
+        |f| f.variable_lookbehind,
+        "Variable-length lookbehind",
+        &["javascript"],
+    ),
+    (
+        |f| f.backreference,
+        "Backreferences (\\1, \\2)",
+        &["rust_regex", "go_regexp", "posix_ere"],
+    ),
+    (
+        |f| f.atomic_group,
+        "Atomic groups (?>...)",
+        &[
+            "rust_regex",
+            "go_regexp",
+            "javascript",
+            "python_re",
+            "posix_ere",
+            "posix_bre",
+        ],
+    ),
+    (
+        |f| f.possessive,
+        "Possessive quantifiers (++, *+)",
+        &[
+            "rust_regex",
+            "go_regexp",
+            "javascript",
+            "python_re",
+            "dotnet",
+            "posix_ere",
+            "posix_bre",
+        ],
+    ),
+    (
+        |f| f.conditional,
+        "Conditional patterns (?(1)...)",
+        &[
+            "rust_regex",
+            "go_regexp",
+            "javascript",
+            "ruby",
+            "posix_ere",
+            "posix_bre",
+        ],
+    ),
+    (
+        |f| f.recursion,
+        "Recursion (?R)",
+        &[
+            "rust_regex",
+            "go_regexp",
+            "javascript",
+            "python_re",
+            "java",
+            "dotnet",
+            "ruby",
+            "posix_ere",
+            "posix_bre",
+        ],
+    ),
+    (
+        |f| f.subroutine,
+        "Subroutine calls (\\g<name>)",
+        &[
+            "rust_regex",
+            "go_regexp",
+            "javascript",
+            "python_re",
+            "java",
+            "dotnet",
+            "posix_ere",
+            "posix_bre",
+        ],
+    ),
+    (
+        |f| f.posix_classes,
+        "POSIX bracket classes ([:alpha:])",
+        &[
+            "pcre2",
+            "javascript",
+            "python_re",
+            "python_regex",
+            "java",
+            "dotnet",
+        ],
+    ),
+    (
+        |f| f.hex_digit_escape,
+        "Hex digit escapes (\\h, \\H)",
+        NO_ONIG_ESCAPES,
+    ),
+    (
+        |f| f.linebreak_escape,
+        "Line break escapes (\\R)",
+        NO_ONIG_ESCAPES,
+    ),
+    (
+        |f| f.unicode_property_value,
+        "Unicode properties in name=value form (\\p{Script=Greek})",
+        &["go_regexp", "dotnet"],
+    ),
+    (|f| f.perl_classes, "Perl classes (\\d, \\w, \\s)", POSIX),
+    (|f| f.lazy, "Lazy quantifiers (*?, +?)", POSIX),
+    (|f| f.non_capturing, "Non-capturing groups (?:...)", POSIX),
+    (|f| f.named_capture, "Named groups (?<name>...)", POSIX),
+    (|f| f.inline_flags, "Inline flags ((?i), (?m))", POSIX),
+    (|f| f.unicode_classes, "Unicode classes (\\p{...})", POSIX),
+    (
+        |f| f.word_boundary || f.non_word_boundary,
+        "Word boundaries (\\b, \\B)",
+        POSIX,
+    ),
+    (|f| f.alternation, "Alternations (a|b)", &["posix_bre"]),
+    (
+        |f| f.plus_or_optional,
+        "The + and ? quantifiers",
+        &["posix_bre"],
+    ),
+    (|f| f.group, "Groups written (...)", &["posix_bre"]),
+    (
+        |f| f.counted_repetition,
+        "Counted repetition written {n,m}",
+        &["posix_bre"],
+    ),
+];
+
+/// Descriptions of the features present in `features` that `target` rejects
+fn unsupported_features<'a>(
+    features: &'a PatternFeatures,
+    target: &'a str,
+) -> impl Iterator<Item = &'static str> + 'a {
+    FEATURE_SUPPORT
+        .iter()
+        .filter(move |(present, _, targets)| targets.contains(&target) && present(features))
+        .map(|(_, feature, _)| *feature)
 }
 
-/// PCRE2 compatibility (most features supported)
-fn is_pcre2_compatible(features: &PatternFeatures) -> bool {
-    // PCRE2 supports almost everything
-    !features.posix_classes // PCRE uses different syntax for POSIX
+/// Canonical target names, in the same order as the `Portability` fields
+pub const TARGETS: &[&str] = &[
+    "rust_regex",
+    "pcre2",
+    "javascript",
+    "python_re",
+    "python_regex",
+    "go_regexp",
+    "java",
+    "dotnet",
+    "ruby",
+    "posix_ere",
+    "posix_bre",
+];
+
+/// Resolve a target language name or alias (e.g. `go`, `js`, `c#`) to its canonical name
+pub fn canonical_target(name: &str) -> Option<&'static str> {
+    let canonical = match name.to_lowercase().as_str() {
+        "rust" | "rust_regex" => "rust_regex",
+        "pcre" | "pcre2" => "pcre2",
+        "js" | "javascript" => "javascript",
+        "python" | "python_re" => "python_re",
+        "python_regex" | "regex" => "python_regex",
+        "go" | "go_regexp" | "golang" => "go_regexp",
+        "java" => "java",
+        "dotnet" | "csharp" | "c#" | ".net" => "dotnet",
+        "ruby" | "rb" => "ruby",
+        "posix" | "ere" | "posix_ere" => "posix_ere",
+        "bre" | "posix_bre" => "posix_bre",
+        _ => return None,
+    };
+    Some(canonical)
 }
 
-/// JavaScript RegExp compatibility
-/// Note: Unicode property classes (\p{...}) are supported with /u flag since ES2018
-fn is_javascript_compatible(features: &PatternFeatures) -> bool {
-    !features.variable_lookbehind // JS lookbehind must be fixed-length
-        && !features.atomic_group
-        && !features.possessive
-        && !features.conditional
-        && !features.recursion
-        && !features.subroutine
-        && !features.posix_classes
-}
-
-/// Python re module compatibility
-fn is_python_re_compatible(features: &PatternFeatures) -> bool {
-    !features.atomic_group
-        && !features.possessive
-        && !features.recursion
-        && !features.subroutine
-        && !features.posix_classes
+/// List the features of a pattern that a single target (canonical name) does not support.
+///
+/// Reads the same table as `check_portability`, so the list is empty exactly
+/// when the corresponding `Portability` field is true.
+pub fn target_issues(pattern: &str, target: &str) -> Vec<String> {
+    let features = PatternFeatures::analyze(pattern);
+    unsupported_features(&features, target)
+        .map(|feature| format!("{} are not supported", feature))
+        .collect()
 }
 
-/// Python regex module compatibility (third-party, more features)
-fn is_python_regex_compatible(features: &PatternFeatures) -> bool {
-    // Python regex module supports nearly everything except POSIX bracket classes
-    !features.posix_classes
+/// Per-engine compatibility with the reasons each engine rejects the pattern
+pub fn detailed_portability(pattern: &str) -> DetailedPortability {
+    let engine = |target: &str| {
+        let issues = target_issues(pattern, target);
+        EngineCompatibility {
+            compatible: issues.is_empty(),
+            issues,
+        }
+    };
+
+    DetailedPortability {
+        rust_regex: engine("rust_regex"),
+        pcre2: engine("pcre2"),
+        javascript: engine("javascript"),
+        python_re: engine("python_re"),
+        python_regex: engine("python_regex"),
+        go_regexp: engine("go_regexp"),
+        java: engine("java"),
+        dotnet: engine("dotnet"),
+        ruby: engine("ruby"),
+    }
 }
 
-/// Go regexp package compatibility (RE2-based)
-fn is_go_regexp_compatible(features: &PatternFeatures) -> bool {
-    // Go uses RE2, similar to Rust regex
-    !features.lookahead
-        && !features.lookbehind
-        && !features.backreference
-        && !features.atomic_group
-        && !features.possessive
-        && !features.conditional
-        && !features.recursion
-        && !features.subroutine
-}
+/// Alternations whose result depends on leftmost-first vs leftmost-longest
+/// semantics, one warning each.
+///
+/// Rust regex (like Perl) takes the first branch that matches, while POSIX
+/// engines take whichever branch makes the overall match longest. The two
+/// disagree when an earlier branch matches a prefix of what a later branch
+/// matches, as in `a|ab`, and nothing after the alternation forces the
+/// choice: when it ends the pattern, as in `x(a|ab)`, or when it is repeated.
+pub fn leftmost_longest_divergences(pattern: &str) -> Vec<String> {
+    use regex_syntax::ast::Ast;
+
+    /// `exposed`: the node is the whole pattern, the body of a quantifier or
+    /// the last item of an exposed concatenation, looking through groups
+    fn walk(ast: &Ast, pattern: &str, exposed: bool, out: &mut Vec<String>) {
+        match ast {
+            Ast::Repetition(rep) => walk(&rep.ast, pattern, true, out),
+            Ast::Group(group) => walk(&group.ast, pattern, exposed, out),
+            Ast::Alternation(alt) => {
+                if exposed {
+                    let span = alt.span.start.offset..alt.span.end.offset;
+                    out.extend(leftmost_longest_divergence(&pattern[span]));
+                }
+                for branch in &alt.asts {
+                    walk(branch, pattern, false, out);
+                }
+            }
+            Ast::Concat(concat) => {
+                let last = concat.asts.len().saturating_sub(1);
+                for (i, item) in concat.asts.iter().enumerate() {
+                    walk(item, pattern, exposed && i == last, out);
+                }
+            }
+            _ => {}
+        }
+    }
 
-/// Java java.util.regex compatibility
-fn is_java_compatible(features: &PatternFeatures) -> bool {
-    !features.recursion && !features.subroutine && !features.posix_classes
+    let mut divergences = Vec::new();
+    if let Ok(ast) = regex_syntax::ast::parse::Parser::new().parse(pattern) {
+        walk(&ast, pattern, true, &mut divergences);
+    }
+    divergences
 }
 
-/// .NET System.Text.RegularExpressions compatibility
-/// Supports: lookahead, lookbehind (variable-length), backreferences, atomic groups, conditionals
-/// Does NOT support: recursion, subroutines, possessive quantifiers (pre-.NET 7), POSIX classes
-fn is_dotnet_compatible(features: &PatternFeatures) -> bool {
-    !features.recursion && !features.subroutine && !features.possessive && !features.posix_classes
-}
+/// Check a single alternation, given as its own source text (e.g. `a|ab`),
+/// for an earlier branch that is a literal prefix of a later, longer branch.
+/// The caller decides whether the alternation is in a position where that
+/// changes the match (see [`leftmost_longest_divergences`]).
+pub fn leftmost_longest_divergence(alternation: &str) -> Option<String> {
+    use regex_syntax::ast::Ast;
+
+    /// The branch's leading literal characters, and whether that is all of it
+    fn literal_prefix(branch: &Ast) -> (String, bool) {
+        let items = match branch {
+            Ast::Concat(concat) => concat.asts.as_slice(),
+            Ast::Empty(_) => &[],
+            other => std::slice::from_ref(other),
+        };
+        let text: String = items
+            .iter()
+            .map_while(|item| match item {
+                Ast::Literal(lit) => Some(lit.c),
+                _ => None,
+            })
+            .collect();
+        let whole = text.chars().count() == items.len();
+        (text, whole)
+    }
 
-/// Ruby (Oniguruma/Onigmo) compatibility
-/// Supports: lookahead, lookbehind, backreferences, atomic groups, possessive, POSIX classes, subroutines
-/// Does NOT support: PCRE-style recursion (?R), conditionals (?(1)...|...)
-fn is_ruby_compatible(features: &PatternFeatures) -> bool {
-    !features.conditional && !features.recursion
+    let ast = regex_syntax::ast::parse::Parser::new()
+        .parse(alternation)
+        .ok()?;
+    let Ast::Alternation(ref alt) = ast else {
+        return None;
+    };
+
+    let source = |branch: &Ast| {
+        let span = branch.span();
+        &alternation[span.start.offset..span.end.offset]
+    };
+    let prefixes: Vec<_> = alt.asts.iter().map(literal_prefix).collect();
+
+    for (i, (short, short_whole)) in prefixes.iter().enumerate() {
+        if !short_whole || short.is_empty() {
+            continue;
+        }
+        for (j, (long, long_whole)) in prefixes.iter().enumerate().skip(i + 1) {
+            let longer = long.len() > short.len() || (long == short && !long_whole);
+            if long.starts_with(short.as_str()) && longer {
+                let (first, later) = (source(&alt.asts[i]), source(&alt.asts[j]));
+                return Some(format!(
+                    "Alternation `{}` depends on match semantics: POSIX engines (leftmost-longest) prefer `{}` wherever it matches, while leftmost-first engines like Rust regex stop at the earlier branch `{}`; put `{}` first to get the same matches in both",
+                    alternation, later, first, later
+                ));
+            }
+        }
+    }
+    None
 }
 
 /// Get a human-readable explanation of compatibility issues
-#[allow(dead_code)]
 pub fn explain_compatibility(pattern: &str) -> Vec<String> {
     let features = PatternFeatures::analyze(pattern);
     let mut issues = Vec::new();
@@ -356,6 +723,66 @@ pub fn explain_compatibility(pattern: &str) -> Vec<String> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_detailed_portability() {
+        let detailed = detailed_portability(r"(?<=a+)b");
+        assert!(!detailed.javascript.compatible);
+        assert_eq!(
+            detailed.javascript.issues,
+            vec!["Variable-length lookbehind are not supported"]
+        );
+        assert!(!detailed.rust_regex.compatible);
+        assert!(detailed.pcre2.compatible);
+        assert!(detailed.pcre2.issues.is_empty());
+    }
+
+    #[test]
+    fn test_target_issues_agree_with_portability() {
+        for pattern in [
+            r"\d+",
+            r"foo(?=bar)",
+            r"(?<=a+)b",
+            r"(\w)\1",
+            r"(?>a+)b",
+            r"a++b",
+            r"[[:alpha:]]+",
+            r"\h+",
+            r"a\Rb",
+            r"a+?",
+            r"(?:ab)*",
+            r"(a)\1",
+            r"cat|dog",
+            r"[[:digit:]]{3}",
+            r"\bword\b",
+            r"\p{Script=Greek}+",
+            r"(?=x)[\p{sc:Latin}]",
+        ] {
+            let p = check_portability(pattern);
+            let flags = [
+                p.rust_regex,
+                p.pcre2,
+                p.javascript,
+                p.python_re,
+                p.python_regex,
+                p.go_regexp,
+                p.java.unwrap_or(true),
+                p.dotnet,
+                p.ruby,
+                p.posix_ere,
+                p.posix_bre,
+            ];
+            for (target, compatible) in TARGETS.iter().zip(flags) {
+                assert_eq!(
+                    target_issues(pattern, target).is_empty(),
+                    compatible,
+                    "{} on {}",
+                    pattern,
+                    target
+                );
+            }
+        }
+    }
+
     #[test]
     fn test_simple_pattern_portable() {
         let portability = check_portability(r"\d+");
@@ -403,6 +830,86 @@ mod tests {
         assert!(portability.dotnet); // .NET supports conditionals
     }
 
+    #[test]
+    fn test_oniguruma_escapes_detected() {
+        for pattern in [r"\h+", r"[\H]", r"a\Rb"] {
+            let portability = check_portability(pattern);
+            assert!(!portability.rust_regex, "{}", pattern);
+            assert!(!portability.go_regexp, "{}", pattern);
+            assert!(portability.pcre2, "{}", pattern);
+            assert!(portability.ruby, "{}", pattern);
+        }
+
+        // An escaped backslash followed by a literal h is not \h
+        let features = PatternFeatures::analyze(r"(?=x)\\h");
+        assert!(!features.hex_digit_escape);
+        let features = PatternFeatures::analyze(r"(?=x)\\\h");
+        assert!(features.hex_digit_escape);
+    }
+
+    #[test]
+    fn test_unicode_property_value_form() {
+        for pattern in [r"\p{Script=Greek}", r"[^\P{gc:Lu}]", r"(?=x)\p{sc=Latin}"] {
+            let portability = check_portability(pattern);
+            assert!(!portability.go_regexp, "{}", pattern);
+            assert!(!portability.dotnet, "{}", pattern);
+            assert!(portability.javascript, "{}", pattern);
+            assert!(portability.java.unwrap(), "{}", pattern);
+        }
+
+        // The bare forms work in RE2
+        let portability = check_portability(r"\p{Greek}\pL");
+        assert!(portability.go_regexp);
+    }
+
+    #[test]
+    fn test_posix_targets() {
+        let portability = check_portability(r"^[[:alpha:]_][[:alnum:]_]*$");
+        assert!(portability.posix_ere);
+        assert!(portability.posix_bre);
+
+        // ERE has + ? and alternation, BRE doesn't
+        let portability = check_portability(r"^(cat|dog)s?$");
+        assert!(portability.posix_ere);
+        assert!(!portability.posix_bre);
+
+        // BRE has backreferences, ERE doesn't, but BRE writes the group
+        // \(a*\), which no engine here parses
+        let portability = check_portability(r"(a*)b\1");
+        assert!(!portability.posix_ere);
+        assert!(!portability.posix_bre);
+
+        // Only `*` repeats in BRE without backslashes; groups and intervals
+        // need them
+        for pattern in [r"(ab){2}", r"(ab)*", r"a{2,3}", r"(a)"] {
+            let portability = check_portability(pattern);
+            assert!(portability.posix_ere, "{}", pattern);
+            assert!(!portability.posix_bre, "{}", pattern);
+        }
+        assert!(check_portability(r"a*b[0-9]*").posix_bre);
+        let features = PatternFeatures::analyze(r"(?=x)(ab){2}");
+        assert!(features.group && features.counted_repetition);
+
+        for pattern in [
+            r"\d{3}",
+            r"[\w.]*",
+            r"a.*?b",
+            r"(?:ab)*",
+            r"(?i)abc",
+            r"foo(?=bar)",
+        ] {
+            let portability = check_portability(pattern);
+            assert!(!portability.posix_ere, "{}", pattern);
+            assert!(!portability.posix_bre, "{}", pattern);
+        }
+
+        // An escaped backslash followed by d is not \d
+        let features = PatternFeatures::analyze(r"(?=x)\\d");
+        assert!(!features.perl_classes);
+        let features = PatternFeatures::analyze(r"(?=x)a+?");
+        assert!(features.lazy);
+    }
+
     // --- AST accuracy tests (false-positive prevention) ---
 
     #[test]
@@ -449,4 +956,37 @@ mod tests {
         let features = PatternFeatures::analyze(r"\bword\b");
         assert!(features.word_boundary);
     }
+
+    #[test]
+    fn test_leftmost_longest_divergences() {
+        for pattern in [
+            "a|ab",
+            "(a|ab)",
+            "(?:x|y|xy)+",
+            r"a|a\d",
+            "(cat|category){2}",
+            "x(a|ab)",
+            "x(?:y(a|ab))",
+        ] {
+            assert_eq!(
+                leftmost_longest_divergences(pattern).len(),
+                1,
+                "{}",
+                pattern
+            );
+        }
+        // Longest branch first, unrelated or duplicate branches, or an
+        // alternation followed by more pattern all match the same either way
+        for pattern in ["ab|a", "cat|dog", "a|a", "x(a|ab)y", "(x(a|ab))y"] {
+            assert!(
+                leftmost_longest_divergences(pattern).is_empty(),
+                "{}",
+                pattern
+            );
+        }
+
+        let warning = leftmost_longest_divergence("cat|category").unwrap();
+        assert!(warning.contains("prefer `category`"));
+        assert!(warning.contains("put `category` first"));
+    }
 }
@@ -2,45 +2,49 @@
 //!
 //! Tests regex replacement without modifying files.
 
+use std::borrow::Cow;
+use std::collections::HashSet;
 use std::fs::{self, File};
 use std::io::{BufRead, BufReader, Read as _};
-use std::path::Path;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
-use super::engine::CompiledRegex;
-use crate::output::{ApplyResult, ReplaceFileResult, ReplacePreview, ReplaceResult};
+use super::engine::{normalize_pattern, prepend_flags, CompiledRegex, CoreError};
+use crate::output::{ApplyResult, ReplaceFileResult, ReplacePreview, ReplaceResult, Span};
 
 /// Apply multiline flags to pattern if needed
 fn apply_multiline(pattern: &str, multiline: bool) -> String {
     if multiline {
-        format!("(?ms){}", pattern)
+        prepend_flags(pattern, "ms")
     } else {
         pattern.to_string()
     }
 }
 
 /// Replace all occurrences in a string
-#[allow(dead_code)]
 pub fn replace_string(
     pattern: &str,
     replacement: &str,
     input: &str,
-) -> Result<ReplaceResult, String> {
-    let (compiled, _engine) = CompiledRegex::new(pattern).map_err(|e| e.to_string())?;
+) -> Result<ReplaceResult, CoreError> {
+    let (compiled, _engine) = CompiledRegex::new(pattern).map_err(CoreError::invalid_pattern)?;
 
-    let (result, count) = match &compiled {
+    let (result, spans) = match &compiled {
         CompiledRegex::Regex(re) => {
-            let mut count = 0;
-            let _count_only = re.replace_all(input, |_caps: &regex::Captures| {
-                count += 1;
-                replacement.to_string()
-            });
+            let spans = re
+                .find_iter(input)
+                .map(|m| Span {
+                    start: m.start(),
+                    end: m.end(),
+                })
+                .collect();
 
             // Re-do with actual replacement to handle backreferences
-            let result = re.replace_all(input, replacement);
-            (result.into_owned(), count)
+            let result = re.replace_all(input, normalize_replacement(replacement).as_ref());
+            (result.into_owned(), spans)
         }
         CompiledRegex::FancyRegex(re) => {
-            let mut count = 0;
+            let mut spans = Vec::new();
             let mut last_end = 0;
             let mut result = String::new();
 
@@ -52,7 +56,10 @@ pub fn replace_string(
                             let expanded = expand_replacement(replacement, &caps);
                             result.push_str(&expanded);
                             last_end = full_match.end();
-                            count += 1;
+                            spans.push(Span {
+                                start: full_match.start(),
+                                end: full_match.end(),
+                            });
 
                             if full_match.start() == full_match.end() {
                                 if last_end < input.len() {
@@ -74,35 +81,112 @@ pub fn replace_string(
                         result.push_str(&input[last_end..]);
                         break;
                     }
-                    Err(e) => return Err(e.to_string()),
+                    Err(e) => return Err(e.to_string().into()),
                 }
             }
 
-            (result, count)
+            (result, spans)
         }
     };
 
     Ok(ReplaceResult {
         pattern: pattern.to_string(),
+        normalized_pattern: normalize_pattern(pattern),
         replacement: replacement.to_string(),
         original: input.to_string(),
         result,
-        replacements_made: count,
+        replacements_made: spans.len(),
+        spans,
+        truncated: false,
+        remaining_matches: None,
     })
 }
 
+/// The capture groups of one match, from whichever engine compiled the
+/// pattern, as handed to a [`replace_with_fn`] callback
+pub enum Captures<'c, 'h> {
+    Regex(&'c regex::Captures<'h>),
+    FancyRegex(&'c fancy_regex::Captures<'h>),
+}
+
+impl<'h> Captures<'_, 'h> {
+    /// Text of group `i` (0 is the whole match), if it participated
+    pub fn get(&self, i: usize) -> Option<&'h str> {
+        match self {
+            Captures::Regex(caps) => caps.get(i).map(|m| m.as_str()),
+            Captures::FancyRegex(caps) => caps.get(i).map(|m| m.as_str()),
+        }
+    }
+
+    /// Text of the group called `name`, if it exists and participated
+    pub fn name(&self, name: &str) -> Option<&'h str> {
+        match self {
+            Captures::Regex(caps) => caps.name(name).map(|m| m.as_str()),
+            Captures::FancyRegex(caps) => caps.name(name).map(|m| m.as_str()),
+        }
+    }
+
+    /// Byte range of the whole match in the input
+    pub fn range(&self) -> Range<usize> {
+        match self {
+            Captures::Regex(caps) => caps.get_match().range(),
+            Captures::FancyRegex(caps) => caps.get(0).map_or(0..0, |m| m.range()),
+        }
+    }
+}
+
+/// Replace every match of `pattern` in `input` with the text `f` computes
+/// from its captures, e.g. to increment each matched number. Works with
+/// either engine; returns the new text and the number of replacements.
+///
+/// The pattern is normalized first (see [`normalize_pattern`]).
+pub fn replace_with_fn(
+    pattern: &str,
+    input: &str,
+    f: impl FnMut(&Captures) -> String,
+) -> Result<(String, usize), CoreError> {
+    let (compiled, _engine) = CompiledRegex::new(pattern).map_err(CoreError::invalid_pattern)?;
+    Ok(replace_each(&compiled, input, f)?)
+}
+
 /// Replace all occurrences in a content string, returning (new_content, count).
 /// Handles capture expansion for both regex and fancy-regex engines.
 fn replace_content(
     compiled: &CompiledRegex,
     content: &str,
     replacement: &str,
+    literal: bool,
+) -> Result<(String, usize), String> {
+    let normalized = normalize_replacement(replacement);
+    replace_each(compiled, content, |caps| match caps {
+        _ if literal => replacement.to_string(),
+        Captures::Regex(caps) => {
+            let mut expanded = String::new();
+            caps.expand(&normalized, &mut expanded);
+            expanded
+        }
+        Captures::FancyRegex(caps) => expand_replacement(replacement, caps),
+    })
+}
+
+/// Replace every match with the result of `f`, returning (new_content, count).
+///
+/// fancy-regex has no `replace_all` taking a closure, so matches are walked
+/// by hand: after an empty match the next character is copied unchanged
+/// and the search resumes past it, as the regex crate does.
+fn replace_each(
+    compiled: &CompiledRegex,
+    content: &str,
+    mut f: impl FnMut(&Captures) -> String,
 ) -> Result<(String, usize), String> {
     match compiled {
         CompiledRegex::Regex(re) => {
-            let count = re.find_iter(content).count();
-            let result = re.replace_all(content, replacement).into_owned();
-            Ok((result, count))
+            let mut count = 0;
+            let result = re.replace_all(content, |caps: &regex::Captures| {
+                count += 1;
+                f(&Captures::Regex(caps))
+            });
+            Ok((result.into_owned(), count))
         }
         CompiledRegex::FancyRegex(re) => {
             let mut result = String::new();
@@ -114,8 +198,7 @@ fn replace_content(
                     Ok(Some(caps)) => {
                         if let Some(full_match) = caps.get(0) {
                             result.push_str(&content[last_end..full_match.start()]);
-                            let expanded = expand_replacement(replacement, &caps);
-                            result.push_str(&expanded);
+                            result.push_str(&f(&Captures::FancyRegex(&caps)));
                             last_end = full_match.end();
                             count += 1;
 
@@ -179,18 +262,191 @@ fn diff_preview(original: &str, new_content: &str, max_preview: usize) -> Vec<Re
     preview
 }
 
-/// Preview replacements in a file (dry-run, never modifies the file)
+/// Lines of unchanged context around each change in [`unified_diff`]
+const DIFF_CONTEXT: usize = 3;
+
+/// How a line fares between the original and new content
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineChange {
+    Kept,
+    Removed,
+    Added,
+}
+
+/// Line-level edit script from `original` to `new_content`, lines keeping
+/// their endings. Like [`diff_preview`], equal line counts pair lines up by
+/// position; otherwise everything between the common prefix and suffix is
+/// one removed block followed by one added block.
+fn line_changes<'a>(original: &'a str, new_content: &'a str) -> Vec<(LineChange, &'a str)> {
+    let old: Vec<&str> = original.split_inclusive('\n').collect();
+    let new: Vec<&str> = new_content.split_inclusive('\n').collect();
+    let mut changes = Vec::new();
+
+    if old.len() == new.len() {
+        let mut i = 0;
+        while i < old.len() {
+            if old[i] == new[i] {
+                changes.push((LineChange::Kept, old[i]));
+                i += 1;
+                continue;
+            }
+            // Group a run of changed lines as all removals, then all additions
+            let end = (i..old.len())
+                .find(|&j| old[j] == new[j])
+                .unwrap_or(old.len());
+            changes.extend(old[i..end].iter().map(|&line| (LineChange::Removed, line)));
+            changes.extend(new[i..end].iter().map(|&line| (LineChange::Added, line)));
+            i = end;
+        }
+    } else {
+        let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        changes.extend(old[..prefix].iter().map(|&line| (LineChange::Kept, line)));
+        changes.extend(
+            old[prefix..old.len() - suffix]
+                .iter()
+                .map(|&line| (LineChange::Removed, line)),
+        );
+        changes.extend(
+            new[prefix..new.len() - suffix]
+                .iter()
+                .map(|&line| (LineChange::Added, line)),
+        );
+        changes.extend(
+            old[old.len() - suffix..]
+                .iter()
+                .map(|&line| (LineChange::Kept, line)),
+        );
+    }
+    changes
+}
+
+/// A hunk header range: `start,len` (1-based), just `start` for one line
+fn hunk_range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, len),
+    }
+}
+
+/// Render the change from `original` to `new_content` as a unified diff
+/// (`diff -u` style) labelled with `path`, or an empty string if nothing
+/// changed. Line endings are dropped from the output, and a last line without
+/// one is followed by `\ No newline at end of file`.
+pub fn unified_diff(path: &str, original: &str, new_content: &str) -> String {
+    let changes = line_changes(original, new_content);
+    let is_change = |i: &usize| changes[*i].0 != LineChange::Kept;
+
+    // (old, new) line counts before each entry, plus the totals at the end
+    let mut positions = Vec::with_capacity(changes.len() + 1);
+    let (mut old_line, mut new_line) = (0, 0);
+    for (change, _) in &changes {
+        positions.push((old_line, new_line));
+        match change {
+            LineChange::Kept => {
+                old_line += 1;
+                new_line += 1;
+            }
+            LineChange::Removed => old_line += 1,
+            LineChange::Added => new_line += 1,
+        }
+    }
+    positions.push((old_line, new_line));
+
+    let mut output = String::new();
+    let mut next = 0;
+    while let Some(first) = (next..changes.len()).find(is_change) {
+        // Merge later changes into this hunk while their contexts would overlap
+        let mut end = first;
+        loop {
+            end = (end..changes.len())
+                .find(|i| !is_change(i))
+                .unwrap_or(changes.len());
+            match (end..changes.len()).find(is_change) {
+                Some(later) if later - end <= 2 * DIFF_CONTEXT => end = later,
+                _ => break,
+            }
+        }
+
+        let start = first.saturating_sub(DIFF_CONTEXT).max(next);
+        let stop = (end + DIFF_CONTEXT).min(changes.len());
+        let (old_start, new_start) = positions[start];
+        let (old_stop, new_stop) = positions[stop];
+
+        if output.is_empty() {
+            output.push_str(&format!("--- {}\n+++ {}\n", path, path));
+        }
+        output.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(old_start, old_stop - old_start),
+            hunk_range(new_start, new_stop - new_start)
+        ));
+        for (change, line) in &changes[start..stop] {
+            let marker = match change {
+                LineChange::Kept => ' ',
+                LineChange::Removed => '-',
+                LineChange::Added => '+',
+            };
+            let text = match line.strip_suffix('\n') {
+                Some(text) => text.strip_suffix('\r').unwrap_or(text),
+                None => line,
+            };
+            output.push(marker);
+            output.push_str(text);
+            output.push('\n');
+            if !line.ends_with('\n') {
+                output.push_str("\\ No newline at end of file\n");
+            }
+        }
+        next = stop;
+    }
+    output
+}
+
+/// Preview replacements in a file (dry-run, never modifies the file).
+/// With `limit`, only the first `limit` matches are replaced (`--count`).
 pub fn replace_file_preview(
     pattern: &str,
     replacement: &str,
     file_path: &Path,
     max_preview: Option<usize>,
     multiline: bool,
-) -> Result<ReplaceFileResult, String> {
+    literal: bool,
+    limit: Option<usize>,
+) -> Result<ReplaceFileResult, CoreError> {
     let effective_pattern = apply_multiline(pattern, multiline);
-    let (compiled, _engine) = CompiledRegex::new(&effective_pattern).map_err(|e| e.to_string())?;
+    let (compiled, _engine) =
+        CompiledRegex::new(&effective_pattern).map_err(CoreError::invalid_pattern)?;
     let max_preview = max_preview.unwrap_or(20);
 
+    if limit.is_some() {
+        let content =
+            fs::read_to_string(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+        let replaced = replace_whole_file(
+            &compiled,
+            &content,
+            replacement,
+            multiline,
+            literal,
+            max_preview,
+            limit,
+            None,
+        )?;
+        return Ok(ReplaceFileResult {
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+            replacements_made: replaced.count,
+            remaining_matches: replaced.remaining,
+            preview: replaced.preview,
+        });
+    }
+
     if multiline {
         // Multiline: process entire content as one string for cross-line matches
         let mut content = String::new();
@@ -201,13 +457,15 @@ pub fn replace_file_preview(
             })
             .map_err(|e| format!("Failed to read file: {}", e))?;
 
-        let (new_content, total_replacements) = replace_content(&compiled, &content, replacement)?;
+        let (new_content, total_replacements) =
+            replace_content(&compiled, &content, replacement, literal)?;
         let preview = diff_preview(&content, &new_content, max_preview);
 
         Ok(ReplaceFileResult {
             pattern: pattern.to_string(),
             replacement: replacement.to_string(),
             replacements_made: total_replacements,
+            remaining_matches: None,
             preview,
         })
     } else {
@@ -219,7 +477,7 @@ pub fn replace_file_preview(
 
         for (line_num, line_result) in reader.lines().enumerate() {
             let line = line_result.map_err(|e| format!("Failed to read line: {}", e))?;
-            let (new_line, count) = replace_line(&compiled, &line, replacement)?;
+            let (new_line, count) = replace_line(&compiled, &line, replacement, literal)?;
             if count > 0 {
                 total_replacements += count;
                 if preview.len() < max_preview {
@@ -236,6 +494,7 @@ pub fn replace_file_preview(
             pattern: pattern.to_string(),
             replacement: replacement.to_string(),
             replacements_made: total_replacements,
+            remaining_matches: None,
             preview,
         })
     }
@@ -246,6 +505,7 @@ fn replace_line(
     compiled: &CompiledRegex,
     line: &str,
     replacement: &str,
+    literal: bool,
 ) -> Result<(String, usize), String> {
     match compiled {
         CompiledRegex::Regex(re) => {
@@ -256,8 +516,8 @@ fn replace_line(
             });
 
             // Re-do with actual replacement
-            let result = re.replace_all(line, replacement);
-            Ok((result.into_owned(), count))
+            let result = regex_replace_all(re, line, replacement, literal);
+            Ok((result, count))
         }
         CompiledRegex::FancyRegex(re) => {
             let mut count = 0;
@@ -269,7 +529,7 @@ fn replace_line(
                     Ok(Some(caps)) => {
                         if let Some(full_match) = caps.get(0) {
                             result.push_str(&line[last_end..full_match.start()]);
-                            let expanded = expand_replacement(replacement, &caps);
+                            let expanded = fancy_replacement(replacement, &caps, literal);
                             result.push_str(&expanded);
                             last_end = full_match.end();
                             count += 1;
@@ -302,27 +562,60 @@ fn replace_line(
 }
 
 /// Replace all occurrences in a string with capture group references
-/// Supports $1, $2, etc. and ${name} syntax
+/// Supports $1, $2, etc., ${name}, \1 and \g<name> syntax, unless `literal` is set.
+/// With `limit`, only the first `limit` matches are replaced (`--count`).
 pub fn replace_with_captures(
     pattern: &str,
     replacement: &str,
     input: &str,
     multiline: bool,
-) -> Result<ReplaceResult, String> {
+    literal: bool,
+    limit: Option<usize>,
+) -> Result<ReplaceResult, CoreError> {
     let effective_pattern = apply_multiline(pattern, multiline);
-    let (compiled, _engine) = CompiledRegex::new(&effective_pattern).map_err(|e| e.to_string())?;
+    let (compiled, _engine) =
+        CompiledRegex::new(&effective_pattern).map_err(CoreError::invalid_pattern)?;
 
-    let (result, count) = match &compiled {
+    if let Some(limit) = limit {
+        // The input is matched as one segment, as without a limit
+        let (result, ranges, remaining) =
+            replace_first(&compiled, input, replacement, true, literal, limit, None)?;
+        return Ok(ReplaceResult {
+            pattern: pattern.to_string(),
+            normalized_pattern: normalize_pattern(pattern),
+            replacement: replacement.to_string(),
+            original: input.to_string(),
+            result,
+            replacements_made: ranges.len(),
+            spans: ranges
+                .into_iter()
+                .map(|r| Span {
+                    start: r.start,
+                    end: r.end,
+                })
+                .collect(),
+            truncated: false,
+            remaining_matches: Some(remaining),
+        });
+    }
+
+    let (result, spans) = match &compiled {
         CompiledRegex::Regex(re) => {
-            let count = re.find_iter(input).count();
-            let result = re.replace_all(input, replacement).into_owned();
-            (result, count)
+            let spans = re
+                .find_iter(input)
+                .map(|m| Span {
+                    start: m.start(),
+                    end: m.end(),
+                })
+                .collect();
+            let result = regex_replace_all(re, input, replacement, literal);
+            (result, spans)
         }
         CompiledRegex::FancyRegex(re) => {
             // For fancy-regex, we need to handle captures manually
             let mut result = String::new();
             let mut last_end = 0;
-            let mut count = 0;
+            let mut spans = Vec::new();
 
             loop {
                 match re.captures_from_pos(input, last_end) {
@@ -331,11 +624,14 @@ pub fn replace_with_captures(
                             result.push_str(&input[last_end..full_match.start()]);
 
                             // Expand replacement with captures
-                            let expanded = expand_replacement(replacement, &caps);
+                            let expanded = fancy_replacement(replacement, &caps, literal);
                             result.push_str(&expanded);
 
                             last_end = full_match.end();
-                            count += 1;
+                            spans.push(Span {
+                                start: full_match.start(),
+                                end: full_match.end(),
+                            });
 
                             if full_match.start() == full_match.end() {
                                 if last_end < input.len() {
@@ -357,28 +653,216 @@ pub fn replace_with_captures(
                         result.push_str(&input[last_end..]);
                         break;
                     }
-                    Err(e) => return Err(e.to_string()),
+                    Err(e) => return Err(e.to_string().into()),
                 }
             }
 
-            (result, count)
+            (result, spans)
         }
     };
 
     Ok(ReplaceResult {
         pattern: pattern.to_string(),
+        normalized_pattern: normalize_pattern(pattern),
         replacement: replacement.to_string(),
         original: input.to_string(),
         result,
-        replacements_made: count,
+        replacements_made: spans.len(),
+        spans,
+        truncated: false,
+        remaining_matches: None,
     })
 }
 
+/// Cut `original` and `result` down to at most `max_bytes` each (on a char boundary),
+/// and keep only the `spans` that start within the kept part of `original`.
+///
+/// `replacements_made` keeps describing the full input.
+pub fn truncate_result(result: &mut ReplaceResult, max_bytes: usize) {
+    fn truncate(s: &mut String, max_bytes: usize) -> bool {
+        if s.len() <= max_bytes {
+            return false;
+        }
+        let mut end = max_bytes;
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        s.truncate(end);
+        true
+    }
+
+    let original_cut = truncate(&mut result.original, max_bytes);
+    let result_cut = truncate(&mut result.result, max_bytes);
+    if original_cut {
+        let kept = result.original.len();
+        result.spans.retain(|span| span.start < kept);
+        if let Some(last) = result.spans.last_mut() {
+            last.end = last.end.min(kept);
+        }
+    }
+    result.truncated |= original_cut || result_cut;
+}
+
+/// Replace in a file's full content without writing it back, returning the
+/// transformed content (`replace --raw --file`)
+pub fn replace_file_content(
+    pattern: &str,
+    replacement: &str,
+    file_path: &Path,
+    multiline: bool,
+    literal: bool,
+    limit: Option<usize>,
+) -> Result<String, CoreError> {
+    let effective_pattern = apply_multiline(pattern, multiline);
+    let (compiled, _engine) =
+        CompiledRegex::new(&effective_pattern).map_err(CoreError::invalid_pattern)?;
+
+    let content =
+        fs::read_to_string(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let replaced = replace_whole_file(
+        &compiled,
+        &content,
+        replacement,
+        multiline,
+        literal,
+        0,
+        limit,
+        None,
+    )?;
+    Ok(replaced.content)
+}
+
+/// A file's content after replacement, from [`replace_whole_file`]
+struct WholeFileReplacement {
+    content: String,
+    count: usize,
+    /// Matches left untouched by the limit, if there was one
+    remaining: Option<usize>,
+    preview: Vec<ReplacePreview>,
+}
+
+/// Replace in a whole file's content: across lines in multiline mode, else
+/// line by line, stopping after `limit` replacements if given. With a
+/// `guard`, only lines it matches are touched (line mode only). Previews up
+/// to `max_preview` changed lines.
+#[allow(clippy::too_many_arguments)]
+fn replace_whole_file(
+    compiled: &CompiledRegex,
+    content: &str,
+    replacement: &str,
+    multiline: bool,
+    literal: bool,
+    max_preview: usize,
+    limit: Option<usize>,
+    guard: Option<&CompiledRegex>,
+) -> Result<WholeFileReplacement, String> {
+    if let Some(limit) = limit {
+        let (new_content, ranges, remaining) = replace_first(
+            compiled,
+            content,
+            replacement,
+            multiline,
+            literal,
+            limit,
+            guard,
+        )?;
+        let preview = diff_preview(content, &new_content, max_preview);
+        return Ok(WholeFileReplacement {
+            content: new_content,
+            count: ranges.len(),
+            remaining: Some(remaining),
+            preview,
+        });
+    }
+
+    if multiline {
+        // Multiline: replace on full content, then diff for preview
+        let (new_content, count) = replace_content(compiled, content, replacement, literal)?;
+        let preview = diff_preview(content, &new_content, max_preview);
+        return Ok(WholeFileReplacement {
+            content: new_content,
+            count,
+            remaining: None,
+            preview,
+        });
+    }
+
+    // Line-by-line processing, keeping each line's own ending (\n or \r\n)
+    let mut total = 0;
+    let mut preview = Vec::new();
+    let mut new_content = String::with_capacity(content.len());
+
+    for (line_num, raw) in content.split_inclusive('\n').enumerate() {
+        let line = match raw.strip_suffix('\n') {
+            Some(line) => line.strip_suffix('\r').unwrap_or(line),
+            None => raw,
+        };
+        let ending = &raw[line.len()..];
+        if !guard_matches(guard, line)? {
+            new_content.push_str(raw);
+            continue;
+        }
+        let (new_line, count) = replace_line(compiled, line, replacement, literal)?;
+        if count > 0 {
+            total += count;
+            if preview.len() < max_preview {
+                preview.push(ReplacePreview {
+                    line: line_num + 1,
+                    before: line.to_string(),
+                    after: new_line.clone(),
+                });
+            }
+        }
+        new_content.push_str(&new_line);
+        new_content.push_str(ending);
+    }
+
+    Ok(WholeFileReplacement {
+        content: new_content,
+        count: total,
+        remaining: None,
+        preview,
+    })
+}
+
+/// Whether a line passes the `--only-lines-matching` guard (always, without one)
+fn guard_matches(guard: Option<&CompiledRegex>, line: &str) -> Result<bool, String> {
+    match guard {
+        Some(guard) => guard.is_match(line).map_err(|e| e.to_string()),
+        None => Ok(true),
+    }
+}
+
+/// Replace only the first `limit` matches of `content`, found per line or
+/// across lines like `apply` does. Returns the new content, the ranges that
+/// were replaced and how many later matches were left untouched.
+fn replace_first(
+    compiled: &CompiledRegex,
+    content: &str,
+    replacement: &str,
+    multiline: bool,
+    literal: bool,
+    limit: usize,
+    guard: Option<&CompiledRegex>,
+) -> Result<(String, Vec<Range<usize>>, usize), String> {
+    let hunks = collect_hunks(compiled, content, replacement, multiline, literal, guard)?;
+    let remaining = hunks.len().saturating_sub(limit);
+    let applied = &hunks[..hunks.len() - remaining];
+    let ranges = applied.iter().map(|hunk| hunk.range.clone()).collect();
+    Ok((splice_hunks(content, applied), ranges, remaining))
+}
+
 /// Apply regex replacements to a file, optionally creating a backup.
 ///
 /// * `dry_run` — if true, previews changes without writing.
 /// * `backup` — if true, copies the original file to `<path>.bak` before writing.
 /// * `multiline` — if true, enables cross-line matching with `(?ms)` flags.
+/// * `literal` — if true, inserts `replacement` verbatim (no `$1`/`${name}` expansion).
+/// * `limit` — if set, replaces only the first `limit` matches (`--count`).
+/// * `guard` — if set, replaces only on lines this pattern also matches
+///   (`--only-lines-matching`); not available in multiline mode.
+/// * `diff` — if true, also returns the change as a unified diff (`--diff`).
+#[allow(clippy::too_many_arguments)]
 pub fn apply_file(
     pattern: &str,
     replacement: &str,
@@ -387,9 +871,15 @@ pub fn apply_file(
     backup: bool,
     max_preview: Option<usize>,
     multiline: bool,
-) -> Result<ApplyResult, String> {
+    literal: bool,
+    limit: Option<usize>,
+    guard: Option<&str>,
+    diff: bool,
+) -> Result<ApplyResult, CoreError> {
     let effective_pattern = apply_multiline(pattern, multiline);
-    let (compiled, _engine) = CompiledRegex::new(&effective_pattern).map_err(|e| e.to_string())?;
+    let (compiled, _engine) =
+        CompiledRegex::new(&effective_pattern).map_err(CoreError::invalid_pattern)?;
+    let guard = compile_guard(guard, multiline)?;
 
     // Read entire file
     let file = File::open(file_path).map_err(|e| format!("Failed to open file: {}", e))?;
@@ -400,66 +890,427 @@ pub fn apply_file(
 
     let max_preview = max_preview.unwrap_or(20);
 
-    let (new_content, total_replacements, preview) = if multiline {
-        // Multiline: replace on full content, then diff for preview
-        let (new_content, count) = replace_content(&compiled, &content, replacement)?;
-        let preview = diff_preview(&content, &new_content, max_preview);
-        (new_content, count, preview)
-    } else {
-        // Line-by-line processing
-        let mut total = 0;
-        let mut preview = Vec::new();
-        let mut new_lines = Vec::new();
+    let replaced = replace_whole_file(
+        &compiled,
+        &content,
+        replacement,
+        multiline,
+        literal,
+        max_preview,
+        limit,
+        guard.as_ref(),
+    )?;
 
-        for (line_num, line) in content.lines().enumerate() {
-            let (new_line, count) = replace_line(&compiled, line, replacement)?;
-            if count > 0 {
-                total += count;
-                if preview.len() < max_preview {
-                    preview.push(ReplacePreview {
-                        line: line_num + 1,
-                        before: line.to_string(),
-                        after: new_line.clone(),
-                    });
-                }
-            }
-            new_lines.push(new_line);
-        }
+    let backup_path = if !dry_run && replaced.count > 0 {
+        write_with_backup(file_path, &replaced.content, backup)?
+    } else {
+        None
+    };
+    let diff =
+        diff.then(|| unified_diff(&file_path.to_string_lossy(), &content, &replaced.content));
 
-        let new_content = if content.ends_with('\n') {
-            new_lines.join("\n") + "\n"
-        } else {
-            new_lines.join("\n")
-        };
+    Ok(ApplyResult {
+        pattern: pattern.to_string(),
+        replacement: replacement.to_string(),
+        file_path: file_path.to_string_lossy().into_owned(),
+        backup_path,
+        replacements_made: replaced.count,
+        remaining_matches: replaced.remaining,
+        applied: !dry_run && replaced.count > 0,
+        preview: replaced.preview,
+        diff,
+        error: None,
+    })
+}
 
-        (new_content, total, preview)
+/// Compile the `--only-lines-matching` guard pattern, if there is one
+fn compile_guard(guard: Option<&str>, multiline: bool) -> Result<Option<CompiledRegex>, String> {
+    let Some(guard) = guard else {
+        return Ok(None);
     };
+    if multiline {
+        return Err(
+            "--only-lines-matching works line by line and cannot be used with --multiline"
+                .to_string(),
+        );
+    }
+    let (compiled, _engine) = CompiledRegex::new(guard)
+        .map_err(|e| format!("Invalid --only-lines-matching pattern: {}", e))?;
+    Ok(Some(compiled))
+}
 
-    let mut backup_path = None;
+/// Apply regex replacements to several files, continuing past failures: a
+/// file that cannot be read or written gets an entry with `error` set.
+///
+/// Fails up front only if the pattern or guard does not compile.
+#[allow(clippy::too_many_arguments)]
+pub fn apply_files(
+    pattern: &str,
+    replacement: &str,
+    file_paths: &[PathBuf],
+    dry_run: bool,
+    backup: bool,
+    max_preview: Option<usize>,
+    multiline: bool,
+    literal: bool,
+    limit: Option<usize>,
+    guard: Option<&str>,
+    diff: bool,
+) -> Result<Vec<ApplyResult>, CoreError> {
+    CompiledRegex::new(&apply_multiline(pattern, multiline)).map_err(CoreError::invalid_pattern)?;
+    compile_guard(guard, multiline)?;
 
-    if !dry_run && total_replacements > 0 {
-        if backup {
-            let bak = std::path::PathBuf::from(format!("{}.bak", file_path.display()));
-            fs::copy(file_path, &bak).map_err(|e| format!("Failed to create backup: {}", e))?;
-            backup_path = Some(bak.to_string_lossy().into_owned());
-        }
+    let results = file_paths
+        .iter()
+        .map(|file_path| {
+            apply_file(
+                pattern,
+                replacement,
+                file_path,
+                dry_run,
+                backup,
+                max_preview,
+                multiline,
+                literal,
+                limit,
+                guard,
+                diff,
+            )
+            .unwrap_or_else(|error| ApplyResult {
+                pattern: pattern.to_string(),
+                replacement: replacement.to_string(),
+                file_path: file_path.to_string_lossy().into_owned(),
+                backup_path: None,
+                replacements_made: 0,
+                remaining_matches: None,
+                applied: false,
+                preview: Vec::new(),
+                diff: None,
+                error: Some(error.to_string()),
+            })
+        })
+        .collect();
+    Ok(results)
+}
 
-        fs::write(file_path, &new_content).map_err(|e| format!("Failed to write file: {}", e))?;
-    }
+/// One replacement `apply` would make, for reviewing changes one at a time
+#[derive(Debug, Clone)]
+pub struct ApplyHunk {
+    /// Line number (1-indexed) where the match starts
+    pub line: usize,
+    /// The line(s) containing the match
+    pub before: String,
+    /// The same line(s) with only this match replaced
+    pub after: String,
+    range: Range<usize>,
+    replacement: String,
+}
+
+/// Every replacement `apply_file` would make in one file, in file order,
+/// along with the content they were found in
+#[derive(Debug, Clone)]
+pub struct FileHunks {
+    /// Replacements in file order
+    pub hunks: Vec<ApplyHunk>,
+    content: String,
+}
+
+/// List every replacement `apply_file` would make, in file order.
+///
+/// Hunk indices into the returned list are what `apply_file_selective` takes.
+pub fn apply_hunks(
+    pattern: &str,
+    replacement: &str,
+    file_path: &Path,
+    multiline: bool,
+    literal: bool,
+) -> Result<FileHunks, CoreError> {
+    let effective_pattern = apply_multiline(pattern, multiline);
+    let (compiled, _engine) =
+        CompiledRegex::new(&effective_pattern).map_err(CoreError::invalid_pattern)?;
+    let content =
+        fs::read_to_string(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let hunks = collect_hunks(&compiled, &content, replacement, multiline, literal, None)?;
+    Ok(FileHunks { hunks, content })
+}
+
+/// Apply only the hunks whose indices (into `file_hunks.hunks`) are listed
+/// in `matches_to_apply`, leaving every other match in place. The file is
+/// not read again: the hunks are spliced into the content they came from.
+///
+/// Options match [`apply_file`]. The preview lists the applied hunks.
+#[allow(clippy::too_many_arguments)]
+pub fn apply_file_selective(
+    pattern: &str,
+    replacement: &str,
+    file_path: &Path,
+    file_hunks: FileHunks,
+    dry_run: bool,
+    backup: bool,
+    max_preview: Option<usize>,
+    matches_to_apply: &[usize],
+) -> Result<ApplyResult, CoreError> {
+    let selected: HashSet<usize> = matches_to_apply.iter().copied().collect();
+    let FileHunks { hunks, content } = file_hunks;
+    let hunks: Vec<ApplyHunk> = hunks
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| selected.contains(index))
+        .map(|(_, hunk)| hunk)
+        .collect();
+    let new_content = splice_hunks(&content, &hunks);
+
+    let backup_path = if !dry_run && !hunks.is_empty() {
+        write_with_backup(file_path, &new_content, backup)?
+    } else {
+        None
+    };
 
     Ok(ApplyResult {
         pattern: pattern.to_string(),
         replacement: replacement.to_string(),
         file_path: file_path.to_string_lossy().into_owned(),
         backup_path,
-        replacements_made: total_replacements,
-        applied: !dry_run && total_replacements > 0,
-        preview,
+        replacements_made: hunks.len(),
+        remaining_matches: None,
+        applied: !dry_run && !hunks.is_empty(),
+        preview: hunks
+            .into_iter()
+            .take(max_preview.unwrap_or(20))
+            .map(|hunk| ReplacePreview {
+                line: hunk.line,
+                before: hunk.before,
+                after: hunk.after,
+            })
+            .collect(),
+        diff: None,
+        error: None,
     })
 }
 
-/// Expand replacement string with capture groups
+/// Find every match the way `apply_file` does (per line, or across the whole
+/// content in multiline mode) and record what it would be replaced with.
+/// Lines the `guard` does not match are skipped.
+fn collect_hunks(
+    compiled: &CompiledRegex,
+    content: &str,
+    replacement: &str,
+    multiline: bool,
+    literal: bool,
+    guard: Option<&CompiledRegex>,
+) -> Result<Vec<ApplyHunk>, String> {
+    // (offset of the segment in content, segment text)
+    let segments: Vec<(usize, &str)> = if multiline {
+        vec![(0, content)]
+    } else {
+        let mut offset = 0;
+        content
+            .split_inclusive('\n')
+            .map(|raw| {
+                let start = offset;
+                offset += raw.len();
+                let line = raw.strip_suffix('\n').unwrap_or(raw);
+                (start, line.strip_suffix('\r').unwrap_or(line))
+            })
+            .collect()
+    };
+
+    let mut hunks = Vec::new();
+    for (offset, segment) in segments {
+        if !guard_matches(guard, segment)? {
+            continue;
+        }
+        for (range, text) in segment_replacements(compiled, segment, replacement, literal)? {
+            let start = offset + range.start;
+            let end = offset + range.end;
+            let line_start = content[..start].rfind('\n').map_or(0, |i| i + 1);
+            let line_end = content[end..].find('\n').map_or(content.len(), |i| end + i);
+            let line_end = if content[..line_end].ends_with('\r') && line_end > end {
+                line_end - 1
+            } else {
+                line_end
+            };
+
+            hunks.push(ApplyHunk {
+                line: content[..start].matches('\n').count() + 1,
+                before: content[line_start..line_end].to_string(),
+                after: format!(
+                    "{}{}{}",
+                    &content[line_start..start],
+                    text,
+                    &content[end..line_end]
+                ),
+                range: start..end,
+                replacement: text,
+            });
+        }
+    }
+    Ok(hunks)
+}
+
+/// Each match in `text` with the string that replaces it
+fn segment_replacements(
+    compiled: &CompiledRegex,
+    text: &str,
+    replacement: &str,
+    literal: bool,
+) -> Result<Vec<(Range<usize>, String)>, String> {
+    let mut found = Vec::new();
+    match compiled {
+        CompiledRegex::Regex(re) => {
+            for caps in re.captures_iter(text) {
+                let Some(m) = caps.get(0) else { continue };
+                let mut expanded = String::new();
+                if literal {
+                    expanded.push_str(replacement);
+                } else {
+                    caps.expand(&normalize_replacement(replacement), &mut expanded);
+                }
+                found.push((m.range(), expanded));
+            }
+        }
+        CompiledRegex::FancyRegex(re) => {
+            let mut pos = 0;
+            while pos <= text.len() {
+                let caps = match re.captures_from_pos(text, pos) {
+                    Ok(Some(caps)) => caps,
+                    Ok(None) => break,
+                    Err(e) => return Err(format!("Regex error: {}", e)),
+                };
+                let Some(m) = caps.get(0) else { break };
+                found.push((m.range(), fancy_replacement(replacement, &caps, literal)));
+                pos = if m.start() == m.end() {
+                    match text[m.end()..].chars().next() {
+                        Some(c) => m.end() + c.len_utf8(),
+                        None => break,
+                    }
+                } else {
+                    m.end()
+                };
+            }
+        }
+    }
+    Ok(found)
+}
+
+/// Rebuild `content` with the given hunks (in file order) replaced
+fn splice_hunks(content: &str, hunks: &[ApplyHunk]) -> String {
+    let mut result = String::with_capacity(content.len());
+    let mut last_end = 0;
+    for hunk in hunks {
+        result.push_str(&content[last_end..hunk.range.start]);
+        result.push_str(&hunk.replacement);
+        last_end = hunk.range.end;
+    }
+    result.push_str(&content[last_end..]);
+    result
+}
+
+/// Write `content` to `file_path`, first copying the original to `<path>.bak`
+/// when `backup` is set. Returns the backup path, if one was made.
+fn write_with_backup(
+    file_path: &Path,
+    content: &str,
+    backup: bool,
+) -> Result<Option<String>, String> {
+    let mut backup_path = None;
+    if backup {
+        let bak = std::path::PathBuf::from(format!("{}.bak", file_path.display()));
+        fs::copy(file_path, &bak).map_err(|e| format!("Failed to create backup: {}", e))?;
+        backup_path = Some(bak.to_string_lossy().into_owned());
+    }
+
+    fs::write(file_path, content).map_err(|e| format!("Failed to write file: {}", e))?;
+    Ok(backup_path)
+}
+
+/// Run `replace_all` on the standard engine, inserting `replacement` verbatim when `literal`
+fn regex_replace_all(re: &regex::Regex, input: &str, replacement: &str, literal: bool) -> String {
+    if literal {
+        re.replace_all(input, regex::NoExpand(replacement))
+            .into_owned()
+    } else {
+        re.replace_all(input, normalize_replacement(replacement).as_ref())
+            .into_owned()
+    }
+}
+
+/// The text to insert for one fancy-regex match
+fn fancy_replacement(replacement: &str, caps: &fancy_regex::Captures, literal: bool) -> String {
+    if literal {
+        replacement.to_string()
+    } else {
+        expand_replacement(replacement, caps)
+    }
+}
+
+/// Rewrite Python/sed-style references into `$` syntax: `\1`..`\9` and
+/// `\g<N>` become `${N}`, `\g<name>` becomes `${name}` and `\\` a single
+/// backslash. Any other backslash is kept as is. The Perl whole-match alias
+/// `$&` becomes `${0}` (`$$&` stays a literal `$&`).
+fn normalize_replacement(replacement: &str) -> Cow<'_, str> {
+    if !replacement.contains('\\') && !replacement.contains("$&") {
+        return Cow::Borrowed(replacement);
+    }
+
+    let mut result = String::with_capacity(replacement.len());
+    let mut rest = replacement;
+    while let Some(pos) = rest.find(['\\', '$']) {
+        result.push_str(&rest[..pos]);
+        let after = &rest[pos + 1..];
+
+        if rest[pos..].starts_with('$') {
+            match after.chars().next() {
+                Some('&') => {
+                    result.push_str("${0}");
+                    rest = &after[1..];
+                }
+                Some('$') => {
+                    result.push_str("$$");
+                    rest = &after[1..];
+                }
+                _ => {
+                    result.push('$');
+                    rest = after;
+                }
+            }
+            continue;
+        }
+
+        match after.chars().next() {
+            Some(d @ '1'..='9') => {
+                result.push_str(&format!("${{{}}}", d));
+                rest = &after[1..];
+            }
+            Some('\\') => {
+                result.push('\\');
+                rest = &after[1..];
+            }
+            Some('g') if after[1..].starts_with('<') => match after.find('>') {
+                Some(close) if close > 2 => {
+                    result.push_str(&format!("${{{}}}", &after[2..close]));
+                    rest = &after[close + 1..];
+                }
+                _ => {
+                    result.push('\\');
+                    rest = after;
+                }
+            },
+            _ => {
+                result.push('\\');
+                rest = after;
+            }
+        }
+    }
+    result.push_str(rest);
+
+    Cow::Owned(result)
+}
+
+/// Expand replacement string with capture groups ($0 for the whole match,
+/// $1, ${name}, $$, and the forms accepted by `normalize_replacement`)
 fn expand_replacement(replacement: &str, caps: &fancy_regex::Captures) -> String {
+    let replacement = normalize_replacement(replacement);
     let mut result = String::new();
     let mut chars = replacement.chars().peekable();
 
@@ -524,14 +1375,281 @@ mod tests {
 
     #[test]
     fn test_replace_with_captures() {
-        let result = replace_with_captures(r"(\d+)-(\d+)", "$2-$1", "Call 123-456", false).unwrap();
+        let result =
+            replace_with_captures(r"(\d+)-(\d+)", "$2-$1", "Call 123-456", false, false, None)
+                .unwrap();
         assert_eq!(result.result, "Call 456-123");
+        assert_eq!(result.spans, vec![Span { start: 5, end: 12 }]);
     }
 
     #[test]
-    fn test_replace_multiline() {
+    fn test_replace_with_fn() {
+        let increment = |caps: &Captures| {
+            let n: u32 = caps.get(1).unwrap().parse().unwrap();
+            format!("v{}", n + 1)
+        };
+        // Standard engine, fancy-regex (lookbehind), then a normalized pattern
+        for pattern in [r"v(\d+)", r"(?<=\s|^)v(\d+)", r"(?#version)\Qv\E(\d+)"] {
+            let (result, count) = replace_with_fn(pattern, "v1 v9 v41", increment).unwrap();
+            assert_eq!(result, "v2 v10 v42", "{}", pattern);
+            assert_eq!(count, 3);
+        }
+
+        // Empty matches advance one character at a time on both engines
+        for pattern in [r"x*", r"x*(?=.|$)"] {
+            let (result, count) =
+                replace_with_fn(pattern, "aé", |caps| format!("[{}]", caps.range().start)).unwrap();
+            assert_eq!(result, "[0]a[1]é[3]", "{}", pattern);
+            assert_eq!(count, 3);
+        }
+    }
+
+    #[test]
+    fn test_normalize_replacement() {
+        assert_eq!(normalize_replacement("$1-$2"), "$1-$2");
+        assert_eq!(normalize_replacement(r"\2-\1x"), "${2}-${1}x");
+        assert_eq!(normalize_replacement(r"\g<year>/\g<10>"), "${year}/${10}");
+        assert_eq!(normalize_replacement(r"a\\1 \n \g<"), r"a\1 \n \g<");
+        assert_eq!(normalize_replacement("[$&] $$& $1"), "[${0}] $$& $1");
+    }
+
+    #[test]
+    fn test_replace_whole_match_references() {
+        // Standard engine, then fancy-regex (lookahead)
+        for pattern in [r"\d+", r"\d+(?=\b)"] {
+            for replacement in ["[$0]", "[${0}]", "[$&]"] {
+                let result =
+                    replace_with_captures(pattern, replacement, "a 12 b 3", false, false, None)
+                        .unwrap();
+                assert_eq!(result.result, "a [12] b [3]", "{} {}", pattern, replacement);
+            }
+            let result = replace_with_captures(pattern, "$$&", "a 12", false, false, None).unwrap();
+            assert_eq!(result.result, "a $&", "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_replace_backslash_references() {
+        let result =
+            replace_with_captures(r"(\d+)-(\d+)", r"$1:\2", "Call 123-456", false, false, None)
+                .unwrap();
+        assert_eq!(result.result, "Call 123:456");
+
+        let result = replace_with_captures(
+            r"(?<y>\d{4})-(?<m>\d\d)",
+            r"\g<m>/\g<y> \\ \g<1>",
+            "2024-05",
+            false,
+            false,
+            None,
+        )
+        .unwrap();
+        assert_eq!(result.result, r"05/2024 \ 2024");
+
+        // Fancy-regex path
         let result =
-            replace_with_captures(r"hello.world", "REPLACED", "hello\nworld", true).unwrap();
+            replace_with_captures(r"(\w)(?=\d)(\d)", r"\2$1", "a1 b2", false, false, None).unwrap();
+        assert_eq!(result.result, "1a 2b");
+
+        // Literal mode leaves backslash references alone
+        let result = replace_with_captures(r"(\d+)", r"\1", "n 7", false, true, None).unwrap();
+        assert_eq!(result.result, r"n \1");
+    }
+
+    #[test]
+    fn test_replace_spans_fancy() {
+        let result =
+            replace_with_captures(r"(?<=\$)\d+", "N", "$1 and $23", false, false, None).unwrap();
+        assert_eq!(result.result, "$N and $N");
+        assert_eq!(
+            result.spans,
+            vec![Span { start: 1, end: 2 }, Span { start: 8, end: 10 }]
+        );
+    }
+
+    #[test]
+    fn test_literal_replacement() {
+        let result =
+            replace_with_captures(r"price", "$1.99", "price: price", false, true, None).unwrap();
+        assert_eq!(result.result, "$1.99: $1.99");
+
+        // Fancy engine (lookbehind) must not expand either
+        let result =
+            replace_with_captures(r"(?<=: )(\w+)", "${1}$$", "a: b", false, true, None).unwrap();
+        assert_eq!(result.result, "a: ${1}$$");
+    }
+
+    #[test]
+    fn test_collect_and_splice_hunks() {
+        let content = "a1 b2\r\nc3\n";
+        let (compiled, _) = CompiledRegex::new(r"(\w)(\d)").unwrap();
+        let hunks = collect_hunks(&compiled, content, "$2$1", false, false, None).unwrap();
+        assert_eq!(hunks.len(), 3);
+        assert_eq!(hunks[1].line, 1);
+        assert_eq!(hunks[1].before, "a1 b2");
+        assert_eq!(hunks[1].after, "a1 2b");
+        assert_eq!(hunks[2].line, 2);
+
+        // Only the selected hunks change; line endings are kept as-is
+        let selected = [hunks[0].clone(), hunks[2].clone()];
+        assert_eq!(splice_hunks(content, &selected), "1a b2\r\n3c\n");
+
+        let (compiled, _) = CompiledRegex::new(r"(?<=a)\d").unwrap();
+        let hunks = collect_hunks(&compiled, content, "#", false, true, None).unwrap();
+        assert_eq!(hunks.len(), 1);
+        assert_eq!(hunks[0].after, "a# b2");
+    }
+
+    #[test]
+    fn test_replace_count_limit() {
+        // Both engines stop after the limit and count what is left
+        for pattern in [r"(\d)", r"(?<=\w)(\d)"] {
+            let result =
+                replace_with_captures(pattern, "<$1>", "a1 b2 c3", false, false, Some(2)).unwrap();
+            assert_eq!(result.result, "a<1> b<2> c3", "{}", pattern);
+            assert_eq!(result.replacements_made, 2);
+            assert_eq!(result.remaining_matches, Some(1));
+            assert_eq!(result.spans.len(), 2);
+        }
+
+        let result = replace_with_captures(r"\d", "N", "a1", false, false, Some(5)).unwrap();
+        assert_eq!(
+            (result.replacements_made, result.remaining_matches),
+            (1, Some(0))
+        );
+
+        // In files the limit runs across lines, in file order
+        let (compiled, _) = CompiledRegex::new(r"\d").unwrap();
+        let replaced = replace_whole_file(
+            &compiled,
+            "1 2\n3 4\n",
+            "N",
+            false,
+            false,
+            20,
+            Some(3),
+            None,
+        )
+        .unwrap();
+        assert_eq!(replaced.content, "N N\nN 4\n");
+        assert_eq!(replaced.count, 3);
+        assert_eq!(replaced.remaining, Some(1));
+        assert_eq!(replaced.preview.len(), 2);
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        assert_eq!(unified_diff("f", "a\nb\n", "a\nb\n"), "");
+
+        // Changes far apart get separate hunks with three lines of context
+        let before: String = (1..=20).map(|i| format!("{}\n", i)).collect();
+        let after = before
+            .replacen("\n2\n", "\ntwo\n", 1)
+            .replacen("\n19\n", "\nnineteen\n", 1);
+        let diff = unified_diff("f", &before, &after);
+        assert!(diff.starts_with("--- f\n+++ f\n@@ -1,5 +1,5 @@\n 1\n-2\n+two\n 3\n"));
+        assert!(diff.contains("@@ -16,5 +16,5 @@\n 16\n 17\n 18\n-19\n+nineteen\n 20\n"));
+
+        // CRLF endings are not shown; a missing final newline is marked
+        let diff = unified_diff("f", "a\r\nfoo", "a\r\nbar");
+        assert_eq!(
+            diff,
+            "--- f\n+++ f\n@@ -1,2 +1,2 @@\n a\n-foo\n\\ No newline at end of file\n+bar\n\\ No newline at end of file\n"
+        );
+
+        // Multiline replacements that change the line count
+        let diff = unified_diff("f", "a\nb\nc\n", "a\nbc\n");
+        assert!(diff.ends_with("@@ -1,3 +1,2 @@\n a\n-b\n-c\n+bc\n"));
+        let diff = unified_diff("f", "", "a\n");
+        assert!(diff.ends_with("@@ -0,0 +1 @@\n+a\n"));
+    }
+
+    #[test]
+    fn test_crlf_line_endings_kept() {
+        let (compiled, _) = CompiledRegex::new(r"o").unwrap();
+        let replaced = replace_whole_file(
+            &compiled,
+            "foo\r\nbar\r\n",
+            "0",
+            false,
+            false,
+            20,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(replaced.content, "f00\r\nbar\r\n");
+        assert_eq!(replaced.preview[0].before, "foo");
+    }
+
+    #[test]
+    fn test_guarded_replacement() {
+        let content = "let x = 1;\n// x stays\nlet y = x;";
+        let (compiled, _) = CompiledRegex::new(r"\bx\b").unwrap();
+        let (guard, _) = CompiledRegex::new(r"^let ").unwrap();
+        let replaced = replace_whole_file(
+            &compiled,
+            content,
+            "z",
+            false,
+            false,
+            20,
+            None,
+            Some(&guard),
+        )
+        .unwrap();
+        assert_eq!(replaced.content, "let z = 1;\n// x stays\nlet y = z;");
+        assert_eq!(replaced.count, 2);
+        assert_eq!(replaced.preview[1].line, 3);
+
+        // The limit counts only guarded matches
+        let replaced = replace_whole_file(
+            &compiled,
+            content,
+            "z",
+            false,
+            false,
+            20,
+            Some(1),
+            Some(&guard),
+        )
+        .unwrap();
+        assert_eq!(replaced.content, "let z = 1;\n// x stays\nlet y = x;");
+        assert_eq!(replaced.remaining, Some(1));
+
+        assert!(compile_guard(Some("let"), true).is_err());
+        assert!(compile_guard(Some("("), false).is_err());
+    }
+
+    #[test]
+    fn test_truncate_result() {
+        let input = "é1 ".repeat(100);
+        let mut result = replace_with_captures(r"\d", "N", &input, false, false, None).unwrap();
+        truncate_result(&mut result, 10);
+        assert!(result.truncated);
+        assert!(result.original.len() <= 10);
+        assert!(result.result.starts_with("éN éN"));
+        assert_eq!(result.replacements_made, 100);
+        assert_eq!(result.spans.len(), 2);
+        assert!(result.spans.iter().all(|span| span.end <= 10));
+
+        let mut result = replace_with_captures(r"\d", "N", "a1", false, false, None).unwrap();
+        truncate_result(&mut result, 10);
+        assert!(!result.truncated);
+        assert_eq!(result.result, "aN");
+    }
+
+    #[test]
+    fn test_replace_multiline() {
+        let result = replace_with_captures(
+            r"hello.world",
+            "REPLACED",
+            "hello\nworld",
+            true,
+            false,
+            None,
+        )
+        .unwrap();
         assert_eq!(result.result, "REPLACED");
         assert_eq!(result.replacements_made, 1);
     }
@@ -546,7 +1664,7 @@ mod tests {
     #[test]
     fn test_replace_multibyte_utf8() {
         // Zero-length match on multi-byte characters must not panic
-        let result = replace_with_captures(r"(?=.)", "|", "あいう", false).unwrap();
+        let result = replace_with_captures(r"(?=.)", "|", "あいう", false, false, None).unwrap();
         assert_eq!(result.result, "|あ|い|う");
     }
 }
@@ -0,0 +1,299 @@
+//! Implementation of `re-x simplify` command
+//!
+//! Applies small, provably safe AST rewrites to shorten a pattern:
+//! - `xxxx` / `[0-9][0-9][0-9]` / `\d\d{2}` collapse into `x{n}`
+//! - redundant non-capturing groups like `(?:abc)d` or `(?:a)+` are removed
+//!
+//! Capture groups are never touched, so group numbering is preserved.
+//! Patterns that need fancy-regex are returned unchanged.
+
+use regex_syntax::ast::{
+    self, print::Printer, Ast, GroupKind, Repetition, RepetitionKind, RepetitionOp, RepetitionRange,
+};
+
+use super::engine::{CompiledRegex, CoreError};
+use crate::output::SimplifyResult;
+
+/// Simplify a regex pattern without changing what it matches
+pub fn simplify(pattern: &str) -> Result<SimplifyResult, CoreError> {
+    CompiledRegex::new(pattern).map_err(CoreError::invalid_pattern)?;
+
+    let simplified = match ast::parse::Parser::new().parse(pattern) {
+        Ok(mut parsed) => {
+            simplify_ast(&mut parsed);
+            let candidate = print(&unwrap_top_level(parsed));
+            if candidate.len() < pattern.len() && behaves_identically(pattern, &candidate) {
+                candidate
+            } else {
+                pattern.to_string()
+            }
+        }
+        // Backtracking-only syntax: leave it alone
+        Err(_) => pattern.to_string(),
+    };
+
+    Ok(SimplifyResult {
+        original: pattern.to_string(),
+        changed: simplified != pattern,
+        simplified,
+    })
+}
+
+/// Recursively simplify an AST bottom-up, in place
+fn simplify_ast(node: &mut Ast) {
+    match node {
+        Ast::Concat(concat) => {
+            let mut asts = Vec::with_capacity(concat.asts.len());
+            for mut child in std::mem::take(&mut concat.asts) {
+                simplify_ast(&mut child);
+                match spliceable(&child) {
+                    Some(Ast::Concat(inner)) => asts.extend(inner.asts.iter().cloned()),
+                    Some(inner) => asts.push(inner.clone()),
+                    None => asts.push(child),
+                }
+            }
+            concat.asts = collapse_runs(asts);
+        }
+        Ast::Alternation(alt) => {
+            for branch in alt.asts.iter_mut() {
+                simplify_ast(branch);
+                if let Some(inner) = spliceable(branch) {
+                    *branch = inner.clone();
+                }
+            }
+        }
+        Ast::Repetition(rep) => {
+            simplify_ast(&mut rep.ast);
+            let unwrapped = match &*rep.ast {
+                Ast::Group(g) if is_plain_group(&g.kind) && is_atom(&g.ast) => {
+                    Some((*g.ast).clone())
+                }
+                _ => None,
+            };
+            if let Some(inner) = unwrapped {
+                *rep.ast = inner;
+            }
+        }
+        Ast::Group(group) => simplify_ast(&mut group.ast),
+        _ => {}
+    }
+}
+
+/// A whole-pattern `(?:...)` wrapper is always redundant unless it scopes flags
+pub(super) fn unwrap_top_level(node: Ast) -> Ast {
+    match &node {
+        Ast::Group(g) if is_plain_group(&g.kind) && !sets_flags(&g.ast) => (*g.ast).clone(),
+        _ => node,
+    }
+}
+
+/// The contents of a non-capturing group that can be inlined into a
+/// surrounding sequence, or `None` if the node must stay as it is.
+pub(super) fn spliceable(node: &Ast) -> Option<&Ast> {
+    match node {
+        Ast::Group(g)
+            if is_plain_group(&g.kind)
+                && !matches!(*g.ast, Ast::Alternation(_))
+                && !sets_flags(&g.ast) =>
+        {
+            Some(&g.ast)
+        }
+        _ => None,
+    }
+}
+
+/// `(?:...)` with no flags
+pub(super) fn is_plain_group(kind: &GroupKind) -> bool {
+    matches!(kind, GroupKind::NonCapturing(flags) if flags.items.is_empty())
+}
+
+/// Whether inline flags like `(?i)` appear directly in this node, where
+/// removing the enclosing group would widen their scope.
+fn sets_flags(node: &Ast) -> bool {
+    match node {
+        Ast::Flags(_) => true,
+        Ast::Concat(c) => c.asts.iter().any(|a| matches!(a, Ast::Flags(_))),
+        _ => false,
+    }
+}
+
+/// A single unit that a repetition operator can be applied to directly
+pub(super) fn is_atom(node: &Ast) -> bool {
+    match node {
+        Ast::Literal(_)
+        | Ast::Dot(_)
+        | Ast::ClassUnicode(_)
+        | Ast::ClassPerl(_)
+        | Ast::ClassBracketed(_) => true,
+        Ast::Group(g) => !matches!(
+            g.kind,
+            GroupKind::CaptureIndex(_) | GroupKind::CaptureName { .. }
+        ),
+        _ => false,
+    }
+}
+
+/// Describe a node as `atom{count}` if it is a plain atom or an exact repetition of one
+fn repeat_unit(node: &Ast) -> Option<(&Ast, u32)> {
+    match node {
+        Ast::Repetition(rep) if rep.greedy && is_atom(&rep.ast) => match rep.op.kind {
+            RepetitionKind::Range(RepetitionRange::Exactly(n)) => Some((&rep.ast, n)),
+            _ => None,
+        },
+        n if is_atom(n) => Some((n, 1)),
+        _ => None,
+    }
+}
+
+/// Merge consecutive copies of the same atom into a counted repetition when shorter
+fn collapse_runs(asts: Vec<Ast>) -> Vec<Ast> {
+    let mut out = Vec::with_capacity(asts.len());
+    let mut i = 0;
+
+    while i < asts.len() {
+        let Some((atom, first)) = repeat_unit(&asts[i]) else {
+            out.push(asts[i].clone());
+            i += 1;
+            continue;
+        };
+        let key = print(atom);
+
+        let mut total = first;
+        let mut j = i + 1;
+        while let Some((next, n)) = asts.get(j).and_then(repeat_unit) {
+            if print(next) != key {
+                break;
+            }
+            total += n;
+            j += 1;
+        }
+
+        let run_len: usize = asts[i..j].iter().map(|a| print(a).len()).sum();
+        let collapsed = format!("{}{{{}}}", key, total);
+
+        if j - i > 1 && collapsed.len() < run_len {
+            let span = *asts[i].span();
+            out.push(Ast::repetition(Repetition {
+                span,
+                op: RepetitionOp {
+                    span,
+                    kind: RepetitionKind::Range(RepetitionRange::Exactly(total)),
+                },
+                greedy: true,
+                ast: Box::new(atom.clone()),
+            }));
+        } else {
+            out.extend(asts[i..j].iter().cloned());
+        }
+        i = j;
+    }
+
+    out
+}
+
+pub(super) fn print(node: &Ast) -> String {
+    let mut out = String::new();
+    // Writing into a String cannot fail
+    let _ = Printer::new().print(node, &mut out);
+    out
+}
+
+/// Cross-check a rewrite by comparing match and capture spans on sample inputs
+/// built from the pattern's own characters.
+pub(super) fn behaves_identically(a: &str, b: &str) -> bool {
+    let (Ok(ra), Ok(rb)) = (regex::Regex::new(a), regex::Regex::new(b)) else {
+        return false;
+    };
+    if ra.captures_len() != rb.captures_len() {
+        return false;
+    }
+
+    let mut alphabet: Vec<char> = a
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .chain("aZ09 _-.\n".chars())
+        .collect();
+    alphabet.sort_unstable();
+    alphabet.dedup();
+    alphabet.truncate(24);
+
+    let spans = |re: &regex::Regex, s: &str| -> Vec<Option<(usize, usize)>> {
+        re.captures_iter(s)
+            .flat_map(|caps| {
+                caps.iter()
+                    .map(|m| m.map(|m| (m.start(), m.end())))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    };
+
+    let identical = samples(&alphabet).all(|s| spans(&ra, &s) == spans(&rb, &s));
+    identical
+}
+
+/// All strings up to length 3 over the alphabet, plus longer runs of each character
+fn samples(alphabet: &[char]) -> impl Iterator<Item = String> + '_ {
+    let short = (0..=3u32).flat_map(move |len| {
+        let total = alphabet.len().pow(len);
+        (0..total).map(move |mut idx| {
+            let mut s = String::new();
+            for _ in 0..len {
+                s.push(alphabet[idx % alphabet.len()]);
+                idx /= alphabet.len();
+            }
+            s
+        })
+    });
+    let runs = alphabet
+        .iter()
+        .flat_map(|&c| (4..=10).map(move |n| c.to_string().repeat(n)));
+    let mixed = std::iter::once(alphabet.iter().collect::<String>());
+
+    short.chain(runs).chain(mixed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collapse_repeated_classes() {
+        let result = simplify("[0-9][0-9][0-9]-[0-9][0-9][0-9][0-9]").unwrap();
+        assert_eq!(result.simplified, "[0-9]{3}-[0-9]{4}");
+        assert!(result.changed);
+    }
+
+    #[test]
+    fn test_merge_with_existing_count() {
+        let result = simplify(r"\d\d{2}").unwrap();
+        assert_eq!(result.simplified, r"\d{3}");
+    }
+
+    #[test]
+    fn test_remove_redundant_groups() {
+        assert_eq!(simplify("(?:abc)d").unwrap().simplified, "abcd");
+        assert_eq!(simplify("(?:a)+b").unwrap().simplified, "a+b");
+        assert_eq!(simplify("(?:a|b)").unwrap().simplified, "a|b");
+    }
+
+    #[test]
+    fn test_keeps_meaningful_groups() {
+        // Alternation inside a sequence, capture groups and flag scopes must stay
+        for pattern in ["(?:a|b)c", "(a)(a)(a)(a)", "(?:(?i)a)b", "(?:ab)+"] {
+            let result = simplify(pattern).unwrap();
+            assert_eq!(result.simplified, pattern);
+            assert!(!result.changed);
+        }
+    }
+
+    #[test]
+    fn test_fancy_pattern_unchanged() {
+        let result = simplify(r"(?:aaaa)(?=b)").unwrap();
+        assert!(!result.changed);
+    }
+
+    #[test]
+    fn test_invalid_pattern_errors() {
+        assert!(simplify("(abc").is_err());
+    }
+}
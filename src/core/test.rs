@@ -3,13 +3,27 @@
 //! Tests a regex pattern against input text or a file, returning all matches
 //! with positions and capture groups.
 
+use std::collections::HashMap;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader, Read};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::ops::Range;
 use std::path::Path;
-use std::time::Instant;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 
-use super::engine::{CompiledRegex, EngineType};
-use crate::output::{Capture, Match, TestResult};
+use super::engine::{
+    capture_group_info, is_extended, normalize_pattern, prepend_flags, select_engine, Compilation,
+    CompiledRegex, CoreError, EngineError, EngineType,
+};
+use super::nullable::nullability;
+use super::validate::validate_runnable_pattern;
+use crate::output::{
+    Capture, CaptureCount, CoverageResult, FileLocation, GroupRowsResult, LineCount, Match,
+    NamedTestResult, Span, TestManyResult, TestResult,
+};
 
 /// Options for the test command
 pub struct TestOptions {
@@ -19,6 +33,39 @@ pub struct TestOptions {
     pub engine: Option<EngineType>,
     /// Enable multiline mode ((?ms) — dot matches newline, ^/$ match line boundaries)
     pub multiline: bool,
+    /// Treat NUL (`\0`) as the record separator instead of newline
+    pub null_data: bool,
+    /// Report only this capture group (number or name) in place of the full match
+    pub group: Option<String>,
+    /// Disable Unicode mode ((?-u) — `\w`, `\d`, `\b` are ASCII-only)
+    pub no_unicode: bool,
+    /// Require the pattern to match the entire input (`\A(?:pattern)\z`)
+    pub full_match: bool,
+    /// Tally distinct values of this capture group (number or name) over all matches
+    pub by_capture: Option<String>,
+    /// Keep only this many of the most frequent values when tallying
+    pub top: Option<usize>,
+    /// Count matches per line instead of reporting them (file input only)
+    pub per_line_count: bool,
+    /// Attach this many characters of surrounding text to each match
+    pub context_chars: Option<usize>,
+    /// Report the empty matches of a pattern like `a*` instead of leaving them out
+    pub allow_empty_matches: bool,
+    /// Stop at the first match (existence check)
+    pub first: bool,
+    /// Give each line of a file this long to match; the scan stops at the
+    /// first line that takes longer and reports it in `timed_out_lines`
+    pub timeout_per_line_ms: Option<u64>,
+    /// Match raw bytes rather than UTF-8 text (input need not be valid UTF-8)
+    pub byte_mode: bool,
+    /// Give each match its line and column in the input (`--context-json`,
+    /// `--vimgrep`)
+    pub context_json: bool,
+    /// Keep only matches that start exactly where the previous one ended, like
+    /// a JavaScript `/gy` regex (`--sticky`); whole-input matching only
+    pub sticky: bool,
+    /// Report the pattern exactly as handed to the engine (`effective_pattern`)
+    pub effective_pattern: bool,
 }
 
 impl Default for TestOptions {
@@ -27,20 +74,98 @@ impl Default for TestOptions {
             max_matches: Some(100),
             engine: None,
             multiline: false,
+            null_data: false,
+            group: None,
+            no_unicode: false,
+            full_match: false,
+            by_capture: None,
+            top: None,
+            per_line_count: false,
+            context_chars: None,
+            allow_empty_matches: false,
+            first: false,
+            timeout_per_line_ms: None,
+            byte_mode: false,
+            context_json: false,
+            sticky: false,
+            effective_pattern: false,
         }
     }
 }
 
-/// Apply multiline flags to pattern if needed
-fn apply_multiline(pattern: &str, multiline: bool) -> String {
-    if multiline && !pattern.starts_with("(?") {
-        format!("(?ms){}", pattern)
-    } else if multiline {
-        // Pattern already has flags — inject m and s
-        format!("(?ms){}", pattern)
+/// Prepend the inline flags requested by the options as a single group, e.g. `(?ms-u)`,
+/// and anchor the pattern to the whole input for full-match mode.
+///
+/// The pattern is normalized first so an unterminated `\Q...\E` block cannot
+/// swallow the full-match anchor.
+fn apply_flags(pattern: &str, options: &TestOptions) -> String {
+    let normalized = normalize_pattern(pattern);
+    let pattern = normalized.as_deref().unwrap_or(pattern);
+    let pattern = if options.full_match {
+        // \A and \z rather than ^/$ so multiline mode cannot weaken the anchoring.
+        // In free-spacing mode a trailing `# comment` would swallow the closing
+        // parenthesis, so end the comment with a newline first.
+        let end = if is_extended(pattern) { "\n" } else { "" };
+        format!("\\A(?:{}{})\\z", pattern, end)
     } else {
         pattern.to_string()
+    };
+
+    let mut flags = String::new();
+    if options.multiline {
+        flags.push_str("ms");
+    }
+    if options.no_unicode {
+        flags.push_str("-u");
+    }
+
+    prepend_flags(&pattern, &flags)
+}
+
+/// Compile the effective pattern, honoring a forced engine
+fn compile(pattern: &str, options: &TestOptions) -> Result<Compilation, CoreError> {
+    let compiled = match options.engine {
+        Some(engine) => CompiledRegex::with_engine(pattern, engine).map(|regex| Compilation {
+            regex,
+            engine,
+            fallback: None,
+        }),
+        None => CompiledRegex::compile(pattern),
+    };
+
+    compiled.map_err(|e| {
+        CoreError::InvalidPattern(if options.no_unicode {
+            format!(
+                "{} (with --no-unicode, `.` and negated classes must not match non-ASCII bytes, \
+                 e.g. use [\\x00-\\x7F] instead of `.`; lookaround and backreferences are unavailable)",
+                e
+            )
+        } else {
+            e.to_string()
+        })
+    })
+}
+
+/// Why the backtracking engine is in use, or `None` for the linear-time engine.
+/// `fallback` is the reason the `regex` crate rejected the pattern, if it did.
+fn engine_reason(
+    pattern: &str,
+    engine: EngineType,
+    fallback: Option<&str>,
+    options: &TestOptions,
+) -> Option<String> {
+    if engine != EngineType::FancyRegex {
+        return None;
     }
+
+    let (_, features) = select_engine(pattern);
+    features.reason().or_else(|| {
+        Some(match fallback {
+            Some(reason) => reason.to_string(),
+            None if options.engine.is_some() => "Forced with --engine fancy-regex".to_string(),
+            None => "Pattern uses syntax only fancy-regex supports".to_string(),
+        })
+    })
 }
 
 /// Test a pattern against a string
@@ -48,35 +173,87 @@ pub fn test_string(
     pattern: &str,
     input: &str,
     options: &TestOptions,
-) -> Result<TestResult, String> {
+) -> Result<TestResult, CoreError> {
     let start = Instant::now();
+    if options.byte_mode {
+        return test_bytes(pattern, input.as_bytes(), options, start);
+    }
 
-    let effective_pattern = apply_multiline(pattern, options.multiline);
+    let effective_pattern = apply_flags(pattern, options);
     let pattern_ref = effective_pattern.as_str();
+    let compile_start = Instant::now();
+    let Compilation {
+        regex: compiled,
+        engine: engine_type,
+        fallback,
+    } = compile(pattern_ref, options)?;
+    let compile_us = compile_start.elapsed().as_micros() as u64;
+    let group = resolve_group(pattern_ref, options.group.as_deref())?;
+    let by_capture = resolve_group(pattern_ref, options.by_capture.as_deref())?;
+    let context = options
+        .context_chars
+        .map(|chars| ContextWindow { chars, group });
+    let matches_empty = floods_empty_matches(pattern_ref);
+    let skip_empty = matches_empty && !options.allow_empty_matches;
 
-    // Compile the regex
-    let (compiled, engine_type) = match options.engine {
-        Some(engine) => {
-            let compiled =
-                CompiledRegex::with_engine(pattern_ref, engine).map_err(|e| e.to_string())?;
-            (compiled, engine)
+    let max_matches = match_limit(options);
+    let match_start = Instant::now();
+    let matches = if options.null_data {
+        collect_matches_streaming(
+            &compiled,
+            input.as_bytes(),
+            b'\0',
+            max_matches,
+            context,
+            skip_empty,
+        )?
+    } else {
+        let mut matches = collect_text_matches(
+            &compiled,
+            pattern_ref,
+            input,
+            max_matches,
+            context,
+            skip_empty,
+        )?;
+        if options.sticky {
+            keep_sticky_run(&mut matches, input);
         }
-        None => CompiledRegex::new(pattern_ref).map_err(|e| e.to_string())?,
+        matches
     };
 
-    let max_matches = options.max_matches.unwrap_or(usize::MAX);
-    let matches = collect_matches(&compiled, input, pattern_ref, max_matches)?;
-
+    let capture_counts = match by_capture {
+        Some(by_capture) => tally_text(&compiled, input, by_capture, skip_empty, options)?,
+        None => Vec::new(),
+    };
+    let mut matches = select_group(matches, group);
+    if options.context_json {
+        locate_matches(input.as_bytes(), &mut matches)?;
+    }
+    let match_us = match_start.elapsed().as_micros() as u64;
     let elapsed = start.elapsed();
 
     Ok(TestResult {
         pattern: pattern.to_string(),
+        normalized_pattern: normalize_pattern(pattern),
+        effective_pattern: options.effective_pattern.then(|| effective_pattern.clone()),
         engine: engine_type.to_string(),
+        engine_reason: engine_reason(pattern_ref, engine_type, fallback.as_deref(), options),
+        fallback_occurred: fallback.is_some(),
         input_length: input.len(),
         matched: !matches.is_empty(),
         match_count: matches.len(),
+        full_match: options.full_match,
+        matches_empty,
+        capture_counts,
+        lines: Vec::new(),
+        timed_out_lines: Vec::new(),
+        warning: multiline_span_warning(options, input.len(), &matches)
+            .or_else(|| skip_empty.then(empty_matches_warning)),
         matches,
         elapsed_us: elapsed.as_micros() as u64,
+        compile_us,
+        match_us,
     })
 }
 
@@ -85,21 +262,29 @@ pub fn test_file(
     pattern: &str,
     file_path: &Path,
     options: &TestOptions,
-) -> Result<TestResult, String> {
+) -> Result<TestResult, CoreError> {
     let start = Instant::now();
+    if options.byte_mode {
+        let input = std::fs::read(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+        return test_bytes(pattern, &input, options, start);
+    }
 
-    let effective_pattern = apply_multiline(pattern, options.multiline);
+    let effective_pattern = apply_flags(pattern, options);
     let pattern_ref = effective_pattern.as_str();
-
-    // Compile the regex
-    let (compiled, engine_type) = match options.engine {
-        Some(engine) => {
-            let compiled =
-                CompiledRegex::with_engine(pattern_ref, engine).map_err(|e| e.to_string())?;
-            (compiled, engine)
-        }
-        None => CompiledRegex::new(pattern_ref).map_err(|e| e.to_string())?,
-    };
+    let compile_start = Instant::now();
+    let Compilation {
+        regex: compiled,
+        engine: engine_type,
+        fallback,
+    } = compile(pattern_ref, options)?;
+    let compile_us = compile_start.elapsed().as_micros() as u64;
+    let group = resolve_group(pattern_ref, options.group.as_deref())?;
+    let by_capture = resolve_group(pattern_ref, options.by_capture.as_deref())?;
+    let context = options
+        .context_chars
+        .map(|chars| ContextWindow { chars, group });
+    let matches_empty = floods_empty_matches(pattern_ref);
+    let skip_empty = matches_empty && !options.allow_empty_matches;
 
     // Open file
     let file = File::open(file_path).map_err(|e| format!("Failed to open file: {}", e))?;
@@ -109,296 +294,1817 @@ pub fn test_file(
         .map_err(|e| format!("Failed to read file metadata: {}", e))?;
 
     let file_size = metadata.len() as usize;
-    let max_matches = options.max_matches.unwrap_or(usize::MAX);
+    let max_matches = match_limit(options);
+
+    let match_start = Instant::now();
+    if options.per_line_count {
+        let lines = count_matches_per_line(&compiled, file, skip_empty)?;
+        let match_count = lines.iter().map(|l| l.count).sum();
+
+        return Ok(TestResult {
+            pattern: pattern.to_string(),
+            normalized_pattern: normalize_pattern(pattern),
+            effective_pattern: options.effective_pattern.then(|| effective_pattern.clone()),
+            engine: engine_type.to_string(),
+            engine_reason: engine_reason(pattern_ref, engine_type, fallback.as_deref(), options),
+            fallback_occurred: fallback.is_some(),
+            input_length: file_size,
+            matched: match_count > 0,
+            match_count,
+            full_match: false,
+            matches_empty,
+            capture_counts: Vec::new(),
+            lines,
+            timed_out_lines: Vec::new(),
+            warning: skip_empty.then(empty_matches_warning),
+            matches: Vec::new(),
+            elapsed_us: start.elapsed().as_micros() as u64,
+            compile_us,
+            match_us: match_start.elapsed().as_micros() as u64,
+        });
+    }
 
+    // NUL-separated records are always streamed, one record at a time.
     // Multiline mode requires full content (pattern spans across lines).
-    // For small files, also read entirely into memory.
+    // For small files, also read entirely into memory — unless --first only
+    // needs the first match and the pattern finds the same one line by line.
     // For large files without multiline, process line by line.
-    let matches = if options.multiline || file_size < 10 * 1024 * 1024 {
+    let read_whole = options.multiline
+        || options.full_match
+        || options.sticky
+        || (file_size < 10 * 1024 * 1024 && !(options.first && is_line_local(pattern_ref)));
+    let mut timed_out_lines = Vec::new();
+    // Streamed input is read a second time to tally --by-capture, so that
+    // only the reported matches are ever held
+    let compiled = Arc::new(compiled);
+    let tally_file = |delimiter: u8, stop_at: Option<usize>| match by_capture {
+        Some(by_capture) => {
+            let file = File::open(file_path).map_err(|e| format!("Failed to open file: {}", e))?;
+            tally_records(
+                &compiled, file, delimiter, by_capture, skip_empty, stop_at, options,
+            )
+        }
+        None => Ok(Vec::new()),
+    };
+    let (matches, capture_counts) = if let Some(timeout_ms) = options.timeout_per_line_ms {
+        // Always line by line (or record by record with -z), so the scan
+        // can stop at a pathological line with the matches found before it
+        if options.multiline || options.full_match {
+            return Err(
+                "a per-line timeout needs line-by-line matching; it cannot be combined with \
+                 multiline or full-match mode"
+                    .into(),
+            );
+        }
+        let delimiter = if options.null_data { b'\0' } else { b'\n' };
+        let (matches, timed_out) = stream_records_with_deadline(
+            &compiled,
+            file,
+            delimiter,
+            max_matches,
+            context,
+            skip_empty,
+            Duration::from_millis(timeout_ms),
+        )?;
+        let capture_counts = tally_file(delimiter, timed_out)?;
+        timed_out_lines.extend(timed_out);
+        (matches, capture_counts)
+    } else if options.null_data {
+        let matches =
+            collect_matches_streaming(&compiled, file, b'\0', max_matches, context, skip_empty)?;
+        (matches, tally_file(b'\0', None)?)
+    } else if read_whole {
         let mut content = String::new();
         BufReader::new(file)
             .read_to_string(&mut content)
-            .map_err(|e| format!("Failed to read file: {}", e))?;
+            .map_err(|e| read_error("Failed to read file", &e))?;
 
-        collect_matches(&compiled, &content, pattern_ref, max_matches)?
+        let mut matches = collect_text_matches(
+            &compiled,
+            pattern_ref,
+            &content,
+            max_matches,
+            context,
+            skip_empty,
+        )?;
+        if options.sticky {
+            keep_sticky_run(&mut matches, &content);
+        }
+        let capture_counts = match by_capture {
+            Some(by_capture) => tally_text(&compiled, &content, by_capture, skip_empty, options)?,
+            None => Vec::new(),
+        };
+        (matches, capture_counts)
     } else {
         // Large file without multiline - process line by line
-        collect_matches_streaming(&compiled, file, pattern_ref, max_matches)?
+        let matches =
+            collect_matches_streaming(&compiled, file, b'\n', max_matches, context, skip_empty)?;
+        (matches, tally_file(b'\n', None)?)
     };
 
+    let mut matches = select_group(matches, group);
+    if options.context_json {
+        let file = File::open(file_path).map_err(|e| format!("Failed to open file: {}", e))?;
+        locate_matches(file, &mut matches)?;
+    }
+    let match_us = match_start.elapsed().as_micros() as u64;
     let elapsed = start.elapsed();
 
     Ok(TestResult {
         pattern: pattern.to_string(),
+        normalized_pattern: normalize_pattern(pattern),
+        effective_pattern: options.effective_pattern.then(|| effective_pattern.clone()),
         engine: engine_type.to_string(),
+        engine_reason: engine_reason(pattern_ref, engine_type, fallback.as_deref(), options),
+        fallback_occurred: fallback.is_some(),
         input_length: file_size,
         matched: !matches.is_empty(),
         match_count: matches.len(),
+        full_match: options.full_match,
+        matches_empty,
+        capture_counts,
+        lines: Vec::new(),
+        warning: timed_out_warning(&timed_out_lines)
+            .or_else(|| multiline_span_warning(options, file_size, &matches))
+            .or_else(|| skip_empty.then(empty_matches_warning)),
+        timed_out_lines,
         matches,
         elapsed_us: elapsed.as_micros() as u64,
+        compile_us,
+        match_us,
     })
 }
 
-/// Test a pattern against stdin
-pub fn test_stdin(pattern: &str, options: &TestOptions) -> Result<TestResult, String> {
+/// Combine the patterns in `patterns` (one per line; blank lines are skipped)
+/// into a single alternation for `test --patterns-from`, like `grep -f`.
+///
+/// Each alternative is wrapped in a named group `alt<N>`, N being its line in
+/// the file, so the captures of a match show which line it came from. With
+/// `fixed_strings` every line is escaped and matched literally. A pattern
+/// that names a group `alt<N>` itself is rejected, as its captures would be
+/// mistaken for (or clash with) a line's tag.
+pub fn alternation_from_lines(patterns: &str, fixed_strings: bool) -> Result<String, String> {
+    let is_tag = |name: &str| {
+        name.strip_prefix("alt")
+            .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+    };
+    let alternatives: Vec<String> = patterns
+        .lines()
+        .enumerate()
+        .filter(|(_, pattern)| !pattern.trim().is_empty())
+        .map(|(index, pattern)| {
+            let pattern = if fixed_strings {
+                regex::escape(pattern)
+            } else {
+                pattern.to_string()
+            };
+            if let Some(name) = capture_group_info(&pattern)
+                .into_iter()
+                .filter_map(|group| group.name)
+                .find(|name| is_tag(name))
+            {
+                return Err(format!(
+                    "Line {} of the patterns file names a group `{}`; names of the form \
+                     alt<N> are reserved for tagging each line's matches, so rename it",
+                    index + 1,
+                    name
+                ));
+            }
+            Ok(format!("(?P<alt{}>{})", index + 1, pattern))
+        })
+        .collect::<Result<_, _>>()?;
+
+    if alternatives.is_empty() {
+        return Err("Patterns file contains no patterns".to_string());
+    }
+    Ok(alternatives.join("|"))
+}
+
+/// Test each pattern in `patterns` (one per line; blank lines are skipped)
+/// against the same input (`re-x test-many`). Each pattern is compiled once;
+/// one that fails to compile or match, or is empty without `allow_empty`,
+/// gets an entry with `error` set instead of failing the whole run.
+pub fn test_many(
+    patterns: &str,
+    input: &str,
+    allow_empty: bool,
+    options: &TestOptions,
+) -> TestManyResult {
+    let start = Instant::now();
+
+    let results: Vec<NamedTestResult> = patterns
+        .lines()
+        .enumerate()
+        .filter(|(_, pattern)| !pattern.trim().is_empty())
+        .map(|(index, pattern)| {
+            let outcome = validate_runnable_pattern(pattern, allow_empty).and_then(|_| {
+                let effective_pattern = apply_flags(pattern, options);
+                let compiled = compile(&effective_pattern, options)?;
+                let skip_empty =
+                    floods_empty_matches(&effective_pattern) && !options.allow_empty_matches;
+                let mut count = 0;
+                for_each_match(&compiled.regex, input, usize::MAX, |m| {
+                    if !(skip_empty && m.start == m.end) {
+                        count += 1;
+                    }
+                    true
+                })?;
+                Ok((count, compiled.engine))
+            });
+
+            let (match_count, engine, error) = match outcome {
+                Ok((count, engine)) => (count, Some(engine.to_string()), None),
+                Err(e) => (0, None, Some(e.to_string())),
+            };
+            NamedTestResult {
+                line: index + 1,
+                pattern: pattern.to_string(),
+                matched: match_count > 0,
+                match_count,
+                engine,
+                error,
+            }
+        })
+        .collect();
+
+    TestManyResult {
+        input_length: input.len(),
+        matched_patterns: results.iter().filter(|r| r.matched).count(),
+        failed_patterns: results.iter().filter(|r| r.error.is_some()).count(),
+        results,
+        elapsed_us: start.elapsed().as_micros() as u64,
+    }
+}
+
+/// Find the longest prefix of `pattern` that still matches somewhere in
+/// `input` (`test --coverage`), pinpointing where the pattern stops fitting
+/// the input.
+///
+/// Each prefix is compiled with its unclosed groups closed. Prefixes that do
+/// not compile are skipped, as are those ending in `(` or `|`, whose empty
+/// branch would match anywhere.
+pub fn pattern_coverage(
+    pattern: &str,
+    input: &str,
+    options: &TestOptions,
+) -> Result<CoverageResult, CoreError> {
     let start = Instant::now();
+    let first_match = |compiled: &CompiledRegex| {
+        let mut found = None;
+        for_each_match(compiled, input, 1, |m| {
+            found = Some(m.text.clone());
+            false
+        })
+        .map(|_| found)
+    };
+
+    // Only the whole pattern has to compile; prefixes are just probes
+    let compiled = compile(&apply_flags(pattern, options), options)?.regex;
+    let (matched_prefix, prefix_match) = match first_match(&compiled)? {
+        Some(text) => (pattern.len(), Some(text)),
+        None => pattern
+            .char_indices()
+            .rev()
+            .filter(|&(i, c)| !matches!(c, '(' | '|') || ends_in_escape(&pattern[..i]))
+            .map(|(i, c)| i + c.len_utf8())
+            .filter(|&end| !ends_in_escape(&pattern[..end]))
+            .find_map(|end| {
+                let prefix = &pattern[..end];
+                let probe = format!("{}{}", prefix, ")".repeat(unclosed_groups(prefix)));
+                let compiled = compile(&apply_flags(&probe, options), options).ok()?;
+                first_match(&compiled.regex)
+                    .ok()
+                    .flatten()
+                    .map(|text| (end, Some(text)))
+            })
+            .unwrap_or((0, None)),
+    };
+
+    let matched = matched_prefix == pattern.len() && prefix_match.is_some();
+    Ok(CoverageResult {
+        pattern: pattern.to_string(),
+        matched,
+        matched_prefix: pattern[..matched_prefix].to_string(),
+        failed_at: (!matched).then_some(matched_prefix),
+        prefix_match,
+        elapsed_us: start.elapsed().as_micros() as u64,
+    })
+}
 
-    let effective_pattern = apply_multiline(pattern, options.multiline);
+/// Whether `prefix` stops inside an escape (an odd run of trailing `\`)
+fn ends_in_escape(prefix: &str) -> bool {
+    prefix.bytes().rev().take_while(|&b| b == b'\\').count() % 2 == 1
+}
+
+/// Number of groups opened in `prefix` and not yet closed, ignoring escaped
+/// parentheses and those inside character classes
+fn unclosed_groups(prefix: &str) -> usize {
+    let mut depth = 0usize;
+    let mut in_class = false;
+    let mut bytes = prefix.bytes();
+    while let Some(b) = bytes.next() {
+        match b {
+            b'\\' => {
+                bytes.next();
+            }
+            b'[' => in_class = true,
+            b']' => in_class = false,
+            b'(' if !in_class => depth += 1,
+            b')' if !in_class => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    depth
+}
+
+/// Whether the pattern matches anywhere in `input`, stopping at the first
+/// match without collecting any (MCP `regex_is_match`)
+pub fn is_match_string(
+    pattern: &str,
+    input: &str,
+    options: &TestOptions,
+) -> Result<bool, CoreError> {
+    let effective_pattern = apply_flags(pattern, options);
+    let compiled = compile(&effective_pattern, options)?.regex;
+    let skip_empty = floods_empty_matches(&effective_pattern) && !options.allow_empty_matches;
+
+    Ok(text_is_match(&compiled, input, skip_empty)?)
+}
+
+/// Whether the pattern matches anywhere in a file. Like `--first`, line-local
+/// patterns are checked line by line so the rest of the file is never read.
+pub fn is_match_file(
+    pattern: &str,
+    file_path: &Path,
+    options: &TestOptions,
+) -> Result<bool, CoreError> {
+    let effective_pattern = apply_flags(pattern, options);
     let pattern_ref = effective_pattern.as_str();
+    let compiled = compile(pattern_ref, options)?.regex;
+    let skip_empty = floods_empty_matches(pattern_ref) && !options.allow_empty_matches;
 
-    // Compile the regex
-    let (compiled, engine_type) = match options.engine {
-        Some(engine) => {
-            let compiled =
-                CompiledRegex::with_engine(pattern_ref, engine).map_err(|e| e.to_string())?;
-            (compiled, engine)
+    let file = File::open(file_path).map_err(|e| format!("Failed to open file: {}", e))?;
+
+    if options.multiline || options.full_match || !is_line_local(pattern_ref) {
+        let mut content = String::new();
+        BufReader::new(file)
+            .read_to_string(&mut content)
+            .map_err(|e| read_error("Failed to read file", &e))?;
+        return Ok(text_is_match(&compiled, &content, skip_empty)?);
+    }
+
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| format!("Failed to read line: {}", e))?;
+        if text_is_match(&compiled, line.trim_end_matches('\r'), skip_empty)? {
+            return Ok(true);
         }
-        None => CompiledRegex::new(pattern_ref).map_err(|e| e.to_string())?,
-    };
+    }
+    Ok(false)
+}
+
+/// Existence check; with `skip_empty`, an empty match does not count, as in
+/// the match list (so `a*` only "matches" text containing an `a`)
+fn text_is_match(compiled: &CompiledRegex, text: &str, skip_empty: bool) -> Result<bool, String> {
+    if skip_empty {
+        return Ok(!collect_matches(compiled, text, 1, None, true)?.is_empty());
+    }
+    compiled.is_match(text).map_err(|e| e.to_string())
+}
+
+/// Test a pattern against stdin
+pub fn test_stdin(pattern: &str, options: &TestOptions) -> Result<TestResult, CoreError> {
+    let start = Instant::now();
+    if options.byte_mode {
+        let mut input = Vec::new();
+        io::stdin()
+            .read_to_end(&mut input)
+            .map_err(|e| format!("Failed to read stdin: {}", e))?;
+        return test_bytes(pattern, &input, options, start);
+    }
+
+    let effective_pattern = apply_flags(pattern, options);
+    let pattern_ref = effective_pattern.as_str();
+    let compile_start = Instant::now();
+    let Compilation {
+        regex: compiled,
+        engine: engine_type,
+        fallback,
+    } = compile(pattern_ref, options)?;
+    let compile_us = compile_start.elapsed().as_micros() as u64;
+    let group = resolve_group(pattern_ref, options.group.as_deref())?;
+    let by_capture = resolve_group(pattern_ref, options.by_capture.as_deref())?;
+    let context = options
+        .context_chars
+        .map(|chars| ContextWindow { chars, group });
+    let matches_empty = floods_empty_matches(pattern_ref);
+    let skip_empty = matches_empty && !options.allow_empty_matches;
 
     // Read stdin
     let mut input = String::new();
     io::stdin()
         .read_to_string(&mut input)
-        .map_err(|e| format!("Failed to read stdin: {}", e))?;
+        .map_err(|e| read_error("Failed to read stdin", &e))?;
 
-    let max_matches = options.max_matches.unwrap_or(usize::MAX);
-    let matches = collect_matches(&compiled, &input, pattern_ref, max_matches)?;
+    let max_matches = match_limit(options);
+    let match_start = Instant::now();
+    let matches = if options.null_data {
+        collect_matches_streaming(
+            &compiled,
+            input.as_bytes(),
+            b'\0',
+            max_matches,
+            context,
+            skip_empty,
+        )?
+    } else {
+        let mut matches = collect_text_matches(
+            &compiled,
+            pattern_ref,
+            &input,
+            max_matches,
+            context,
+            skip_empty,
+        )?;
+        if options.sticky {
+            keep_sticky_run(&mut matches, &input);
+        }
+        matches
+    };
 
+    let capture_counts = match by_capture {
+        Some(by_capture) => tally_text(&compiled, &input, by_capture, skip_empty, options)?,
+        None => Vec::new(),
+    };
+    let mut matches = select_group(matches, group);
+    if options.context_json {
+        locate_matches(input.as_bytes(), &mut matches)?;
+    }
+    let match_us = match_start.elapsed().as_micros() as u64;
     let elapsed = start.elapsed();
 
     Ok(TestResult {
         pattern: pattern.to_string(),
+        normalized_pattern: normalize_pattern(pattern),
+        effective_pattern: options.effective_pattern.then(|| effective_pattern.clone()),
         engine: engine_type.to_string(),
+        engine_reason: engine_reason(pattern_ref, engine_type, fallback.as_deref(), options),
+        fallback_occurred: fallback.is_some(),
         input_length: input.len(),
         matched: !matches.is_empty(),
         match_count: matches.len(),
+        full_match: options.full_match,
+        matches_empty,
+        capture_counts,
+        lines: Vec::new(),
+        timed_out_lines: Vec::new(),
+        warning: multiline_span_warning(options, input.len(), &matches)
+            .or_else(|| skip_empty.then(empty_matches_warning)),
         matches,
         elapsed_us: elapsed.as_micros() as u64,
+        compile_us,
+        match_us,
     })
 }
 
-/// Collect all matches from text
-fn collect_matches(
-    compiled: &CompiledRegex,
-    text: &str,
+/// Test a pattern against raw bytes (`--byte-mode`), for input that is not
+/// valid UTF-8. Uses the `regex` crate's byte API on the whole input; offsets
+/// are byte offsets and match text is decoded lossily (invalid bytes → U+FFFD).
+fn test_bytes(
     pattern: &str,
-    max_matches: usize,
-) -> Result<Vec<Match>, String> {
-    let mut matches = Vec::new();
+    input: &[u8],
+    options: &TestOptions,
+    start: Instant,
+) -> Result<TestResult, CoreError> {
+    if options.engine == Some(EngineType::FancyRegex) {
+        return Err("--byte-mode needs the regex engine; fancy-regex cannot match bytes".into());
+    }
+    if options.null_data
+        || options.per_line_count
+        || options.context_chars.is_some()
+        || options.timeout_per_line_ms.is_some()
+    {
+        return Err(
+            "--byte-mode matches the whole input at once; it cannot be combined with -z, \
+             --per-line-count, --context-chars or --timeout-per-line-ms"
+                .into(),
+        );
+    }
 
-    match compiled {
-        CompiledRegex::Regex(re) => {
-            // Try to use captures if the pattern has capture groups
-            let has_captures = super::engine::has_capturing_groups(pattern);
+    let effective_pattern = apply_flags(pattern, options);
+    let pattern_ref = effective_pattern.as_str();
+    let compile_start = Instant::now();
+    let compiled = CompiledRegex::bytes(pattern_ref).map_err(|e| match e {
+        EngineError::RequiresFancy(_) => CoreError::InvalidPattern(format!(
+            "{} (--byte-mode only supports the regex engine: no lookaround or backreferences)",
+            e
+        )),
+        e => CoreError::invalid_pattern(e),
+    })?;
+    let compile_us = compile_start.elapsed().as_micros() as u64;
+    let group = resolve_group(pattern_ref, options.group.as_deref())?;
+    let by_capture = resolve_group(pattern_ref, options.by_capture.as_deref())?;
+    let matches_empty = floods_empty_matches(pattern_ref);
+    let skip_empty = matches_empty && !options.allow_empty_matches;
 
-            if has_captures {
-                for caps in re.captures_iter(text) {
-                    if matches.len() >= max_matches {
-                        break;
-                    }
+    let max_matches = match_limit(options);
+    let match_start = Instant::now();
+    let matches = collect_byte_matches(&compiled, input, max_matches, skip_empty);
 
-                    if let Some(full_match) = caps.get(0) {
-                        let mut captures = Vec::new();
+    let capture_counts = match by_capture {
+        Some(by_capture) => tally_bytes(&compiled, input, by_capture, skip_empty, options),
+        None => Vec::new(),
+    };
+    let mut matches = select_group(matches, group);
+    if options.context_json {
+        locate_matches(input, &mut matches)?;
+    }
+    let match_us = match_start.elapsed().as_micros() as u64;
+    let elapsed = start.elapsed();
 
-                        // Collect capture groups (skip group 0 which is the full match)
-                        for (i, cap) in caps.iter().enumerate().skip(1) {
-                            if let Some(c) = cap {
-                                captures.push(Capture {
-                                    group: i,
-                                    name: re
-                                        .capture_names()
-                                        .nth(i)
-                                        .flatten()
-                                        .map(|s| s.to_string()),
-                                    text: c.as_str().to_string(),
-                                    start: c.start(),
-                                    end: c.end(),
-                                });
-                            }
-                        }
+    Ok(TestResult {
+        pattern: pattern.to_string(),
+        normalized_pattern: normalize_pattern(pattern),
+        effective_pattern: options.effective_pattern.then(|| effective_pattern.clone()),
+        engine: EngineType::Regex.to_string(),
+        engine_reason: None,
+        fallback_occurred: false,
+        input_length: input.len(),
+        matched: !matches.is_empty(),
+        match_count: matches.len(),
+        full_match: options.full_match,
+        matches_empty,
+        capture_counts,
+        lines: Vec::new(),
+        timed_out_lines: Vec::new(),
+        warning: multiline_span_warning(options, input.len(), &matches)
+            .or_else(|| skip_empty.then(empty_matches_warning)),
+        matches,
+        elapsed_us: elapsed.as_micros() as u64,
+        compile_us,
+        match_us,
+    })
+}
 
-                        matches.push(Match {
-                            text: full_match.as_str().to_string(),
-                            start: full_match.start(),
-                            end: full_match.end(),
-                            captures,
-                        });
-                    }
-                }
-            } else {
-                for m in re.find_iter(text) {
-                    if matches.len() >= max_matches {
-                        break;
-                    }
+/// Collect matches from raw bytes, decoding their text lossily
+fn collect_byte_matches(
+    re: &regex::bytes::Regex,
+    input: &[u8],
+    max_matches: usize,
+    skip_empty: bool,
+) -> Vec<Match> {
+    let names: Vec<Option<String>> = re.capture_names().map(|n| n.map(String::from)).collect();
 
-                    matches.push(Match {
-                        text: m.as_str().to_string(),
-                        start: m.start(),
-                        end: m.end(),
-                        captures: Vec::new(),
-                    });
-                }
+    re.captures_iter(input)
+        .filter_map(|caps| {
+            let whole = caps.get(0)?;
+            if skip_empty && whole.is_empty() {
+                return None;
             }
-        }
+            let captures = caps
+                .iter()
+                .enumerate()
+                .skip(1)
+                .filter_map(|(i, cap)| {
+                    cap.map(|c| Capture {
+                        group: i,
+                        name: names[i].clone(),
+                        text: String::from_utf8_lossy(c.as_bytes()).into_owned(),
+                        start: c.start(),
+                        end: c.end(),
+                    })
+                })
+                .collect();
+            Some(Match {
+                text: String::from_utf8_lossy(whole.as_bytes()).into_owned(),
+                start: whole.start(),
+                end: whole.end(),
+                captures,
+                index: None,
+                occurrences: None,
+                positions: Vec::new(),
+                context: None,
+                location: None,
+            })
+        })
+        .take(max_matches)
+        .collect()
+}
 
-        CompiledRegex::FancyRegex(re) => {
-            let has_captures = super::engine::has_capturing_groups(pattern);
+/// Appended to errors caused by input that is not valid UTF-8
+const NOT_UTF8_HINT: &str = " (use --byte-mode to match input that is not valid UTF-8)";
 
-            if has_captures {
-                let mut search_start = 0;
-                while search_start < text.len() && matches.len() < max_matches {
-                    let result = re
-                        .captures_from_pos(text, search_start)
-                        .map_err(|e| e.to_string())?;
-
-                    match result {
-                        Some(caps) => {
-                            if let Some(full_match) = caps.get(0) {
-                                let mut captures = Vec::new();
-
-                                for i in 1..caps.len() {
-                                    if let Some(c) = caps.get(i) {
-                                        captures.push(Capture {
-                                            group: i,
-                                            name: re
-                                                .capture_names()
-                                                .nth(i)
-                                                .flatten()
-                                                .map(|s| s.to_string()),
-                                            text: c.as_str().to_string(),
-                                            start: c.start(),
-                                            end: c.end(),
-                                        });
-                                    }
-                                }
-
-                                search_start = full_match.end().max(search_start + 1);
-
-                                matches.push(Match {
-                                    text: full_match.as_str().to_string(),
-                                    start: full_match.start(),
-                                    end: full_match.end(),
-                                    captures,
-                                });
-                            } else {
-                                break;
-                            }
-                        }
-                        None => break,
-                    }
-                }
-            } else {
-                let mut search_start = 0;
-                while search_start < text.len() && matches.len() < max_matches {
-                    let result = re
-                        .find_from_pos(text, search_start)
-                        .map_err(|e| e.to_string())?;
-
-                    match result {
-                        Some(m) => {
-                            matches.push(Match {
-                                text: m.as_str().to_string(),
-                                start: m.start(),
-                                end: m.end(),
-                                captures: Vec::new(),
-                            });
-                            search_start = m.end().max(search_start + 1);
-                        }
-                        None => break,
-                    }
-                }
-            }
-        }
+/// Describe a failed read, pointing at `--byte-mode` when the input was not UTF-8
+fn read_error(what: &str, err: &io::Error) -> String {
+    let hint = if err.kind() == io::ErrorKind::InvalidData {
+        NOT_UTF8_HINT
+    } else {
+        ""
+    };
+    format!("{}: {}{}", what, err, hint)
+}
+
+/// Warn when multiline mode lets `.` swallow newlines so that a single match
+/// covers most of the input — almost always a surprise rather than intended.
+fn multiline_span_warning(
+    options: &TestOptions,
+    input_length: usize,
+    matches: &[Match],
+) -> Option<String> {
+    if !options.multiline || input_length == 0 {
+        return None;
     }
 
-    Ok(matches)
+    let swallowed = matches
+        .iter()
+        .any(|m| m.text.contains('\n') && (m.end - m.start) * 5 > input_length * 4);
+
+    swallowed.then(|| {
+        "A single match spans most of the input across newlines: --multiline makes `.` \
+         match newline. Use [^\\n] instead of `.`, or an inline (?m) flag alone if you only \
+         need ^/$ to match at line boundaries"
+            .to_string()
+    })
 }
 
-/// Collect matches from a file using streaming (line by line)
-fn collect_matches_streaming(
-    compiled: &CompiledRegex,
-    file: File,
-    pattern: &str,
-    max_matches: usize,
-) -> Result<Vec<Match>, String> {
-    let mut matches = Vec::new();
-    let mut reader = BufReader::new(file);
-    let mut byte_offset = 0usize;
-    let mut raw_line = String::new();
+/// Whether the pattern can match both the empty string and real text, like `a*`
+fn floods_empty_matches(pattern: &str) -> bool {
+    nullability(pattern).is_some_and(|n| n.floods_empty_matches())
+}
 
-    loop {
-        raw_line.clear();
-        let bytes_read = reader
-            .read_line(&mut raw_line)
-            .map_err(|e| format!("Failed to read line: {}", e))?;
+/// Point out the line the scan stopped at for running past `--timeout-per-line-ms`
+fn timed_out_warning(timed_out_lines: &[usize]) -> Option<String> {
+    timed_out_lines.first().map(|line| {
+        format!(
+            "Line {} ran past the per-line timeout and the scan stopped there (see \
+             timed_out_lines); matches on it and the lines after it are missing from the results",
+            line
+        )
+    })
+}
 
-        if bytes_read == 0 {
-            break; // EOF
-        }
+/// Explain why the empty matches of a pattern like `a*` were left out
+fn empty_matches_warning() -> String {
+    "The pattern can also match the empty string, which it does at every position where \
+     nothing longer matches; those empty matches are left out (pass --allow-empty-matches \
+     to include them). Use + instead of * if at least one character is required"
+        .to_string()
+}
 
-        if matches.len() >= max_matches {
-            break;
-        }
+/// Resolve a `--group` argument (number or name) to a capture group index
+fn resolve_group(pattern: &str, group: Option<&str>) -> Result<Option<usize>, String> {
+    let Some(group) = group else {
+        return Ok(None);
+    };
+    let groups = capture_group_info(pattern);
+
+    let index = match group.parse::<usize>() {
+        Ok(0) => Some(0),
+        Ok(n) => groups.iter().find(|g| g.index == n).map(|g| g.index),
+        Err(_) => groups
+            .iter()
+            .find(|g| g.name.as_deref() == Some(group))
+            .map(|g| g.index),
+    };
 
-        // Strip the line ending for matching, but use raw length for offset
-        let line = raw_line.trim_end_matches(&['\n', '\r'][..]);
+    index.map(Some).ok_or_else(|| {
+        format!(
+            "Capture group '{}' does not exist in pattern ({} group{})",
+            group,
+            groups.len(),
+            if groups.len() == 1 { "" } else { "s" }
+        )
+    })
+}
 
-        let line_matches = collect_matches(compiled, line, pattern, max_matches - matches.len())?;
+/// Whether searching line by line finds the same matches as searching the
+/// whole text: nothing in the pattern can match `\n` and it has no `^`, `$`,
+/// `\A` or `\z` anchors. Fancy-regex patterns are not analysed.
+fn is_line_local(pattern: &str) -> bool {
+    use regex_syntax::hir::{Class, Hir, HirKind};
 
-        // Adjust positions to account for byte offset
-        for mut m in line_matches {
-            m.start += byte_offset;
-            m.end += byte_offset;
-            for cap in &mut m.captures {
-                cap.start += byte_offset;
-                cap.end += byte_offset;
+    fn can_match_newline(hir: &Hir) -> bool {
+        match hir.kind() {
+            HirKind::Empty | HirKind::Look(_) => false,
+            HirKind::Literal(literal) => literal.0.contains(&b'\n'),
+            HirKind::Class(Class::Unicode(class)) => class
+                .ranges()
+                .iter()
+                .any(|r| r.start() <= '\n' && '\n' <= r.end()),
+            HirKind::Class(Class::Bytes(class)) => class
+                .ranges()
+                .iter()
+                .any(|r| r.start() <= b'\n' && b'\n' <= r.end()),
+            HirKind::Repetition(rep) => can_match_newline(&rep.sub),
+            HirKind::Capture(cap) => can_match_newline(&cap.sub),
+            HirKind::Concat(hirs) | HirKind::Alternation(hirs) => {
+                hirs.iter().any(can_match_newline)
             }
-            matches.push(m);
         }
-
-        byte_offset += raw_line.len(); // includes actual line ending (\n or \r\n)
     }
 
-    Ok(matches)
+    match regex_syntax::parse(pattern) {
+        Ok(hir) => !hir.properties().look_set().contains_anchor() && !can_match_newline(&hir),
+        Err(_) => false,
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Whether every match of the pattern must start at the beginning of the text:
+/// it opens with `\A`, or `^` outside multiline mode, in every alternative.
+/// Lookarounds and other zero-width items before the anchor don't move it.
+fn is_start_anchored(pattern: &str) -> bool {
+    use fancy_regex::{Assertion, Expr};
+    use regex_syntax::hir::Look;
 
-    #[test]
-    fn test_simple_match() {
-        let result = test_string(r"\d+", "hello 123 world 456", &TestOptions::default()).unwrap();
-        assert!(result.matched);
-        assert_eq!(result.match_count, 2);
-        assert_eq!(result.matches[0].text, "123");
-        assert_eq!(result.matches[1].text, "456");
+    fn expr_anchored(expr: &Expr) -> bool {
+        match expr {
+            Expr::Assertion(Assertion::StartText) => true,
+            // The anchor may follow zero-width items, as in `(?=\d)^`
+            Expr::Concat(children) => children
+                .iter()
+                .find(|child| {
+                    expr_anchored(child)
+                        || !matches!(
+                            child,
+                            Expr::Empty | Expr::Assertion(_) | Expr::LookAround(..)
+                        )
+                })
+                .is_some_and(expr_anchored),
+            Expr::Alt(children) => children.iter().all(expr_anchored),
+            Expr::Group(inner) | Expr::AtomicGroup(inner) => expr_anchored(inner),
+            Expr::Repeat { child, lo, .. } => *lo > 0 && expr_anchored(child),
+            _ => false,
+        }
     }
 
-    #[test]
-    fn test_no_match() {
-        let result = test_string(r"\d+", "hello world", &TestOptions::default()).unwrap();
-        assert!(!result.matched);
-        assert_eq!(result.match_count, 0);
+    match regex_syntax::parse(pattern) {
+        Ok(hir) => hir.properties().look_set_prefix().contains(Look::Start),
+        Err(_) => {
+            fancy_regex::Expr::parse_tree(pattern).is_ok_and(|tree| expr_anchored(&tree.expr))
+        }
     }
+}
 
-    #[test]
-    fn test_with_captures() {
-        let result = test_string(r"(\d+)-(\d+)", "123-456", &TestOptions::default()).unwrap();
-        assert!(result.matched);
-        assert_eq!(result.match_count, 1);
-        assert_eq!(result.matches[0].captures.len(), 2);
-        assert_eq!(result.matches[0].captures[0].text, "123");
-        assert_eq!(result.matches[0].captures[1].text, "456");
+/// How many matches to collect: one for --first, else --max-matches
+fn match_limit(options: &TestOptions) -> usize {
+    if options.first {
+        1
+    } else {
+        options.max_matches.unwrap_or(usize::MAX)
     }
+}
 
-    #[test]
-    fn test_max_matches() {
-        let options = TestOptions {
-            max_matches: Some(1),
-            engine: None,
-            multiline: false,
+/// Occurrences of each value of the --by-capture group, counted one match
+/// at a time so the matches themselves never have to be kept
+struct Tally {
+    group: usize,
+    counts: HashMap<String, usize>,
+}
+
+impl Tally {
+    fn new(group: usize) -> Self {
+        Self {
+            group,
+            counts: HashMap::new(),
+        }
+    }
+
+    /// Count one match; false once no more are wanted (--first)
+    fn add(&mut self, m: &Match, options: &TestOptions) -> bool {
+        let value = if self.group == 0 {
+            Some(m.text.as_str())
+        } else {
+            m.captures
+                .iter()
+                .find(|c| c.group == self.group)
+                .map(|c| c.text.as_str())
+        };
+        if let Some(value) = value {
+            self.add_value(value);
+        }
+        !options.first
+    }
+
+    fn add_value(&mut self, value: &str) {
+        match self.counts.get_mut(value) {
+            Some(count) => *count += 1,
+            None => {
+                self.counts.insert(value.to_string(), 1);
+            }
+        }
+    }
+
+    /// The counts, most frequent first (ties by value), cut to --top
+    fn into_counts(self, top: Option<usize>) -> Vec<CaptureCount> {
+        let mut counts: Vec<CaptureCount> = self
+            .counts
+            .into_iter()
+            .map(|(value, count)| CaptureCount { value, count })
+            .collect();
+        counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+        counts.truncate(top.unwrap_or(usize::MAX));
+        counts
+    }
+}
+
+/// Tally the --by-capture `group` over every match in `text`, not just the
+/// --max-matches that are reported, by walking them with [`for_each_match`].
+/// Follows the rules the reported matches were found by: each record on its
+/// own with -z, only the leading run with --sticky, one match with --first.
+fn tally_text(
+    compiled: &CompiledRegex,
+    text: &str,
+    group: usize,
+    skip_empty: bool,
+    options: &TestOptions,
+) -> Result<Vec<CaptureCount>, String> {
+    if options.null_data {
+        return tally_records(
+            compiled,
+            text.as_bytes(),
+            b'\0',
+            group,
+            skip_empty,
+            None,
+            options,
+        );
+    }
+
+    let mut tally = Tally::new(group);
+    let mut expected = 0;
+    for_each_match(compiled, text, usize::MAX, |m| {
+        if skip_empty && m.start == m.end {
+            return true;
+        }
+        if options.sticky {
+            if m.start != expected {
+                return false;
+            }
+            expected = sticky_next_start(text, m);
+        }
+        tally.add(m, options)
+    })?;
+    Ok(tally.into_counts(options.top))
+}
+
+/// [`tally_text`] over input read record by record, stopping before the
+/// 1-based record `stop_at` (the one that hit --timeout-per-line-ms)
+fn tally_records<R: Read>(
+    compiled: &CompiledRegex,
+    reader: R,
+    delimiter: u8,
+    group: usize,
+    skip_empty: bool,
+    stop_at: Option<usize>,
+    options: &TestOptions,
+) -> Result<Vec<CaptureCount>, String> {
+    let mut tally = Tally::new(group);
+    stream_records(
+        compiled,
+        reader,
+        delimiter,
+        usize::MAX,
+        None,
+        skip_empty,
+        |record_number| Ok(stop_at.is_none_or(|stop_at| record_number < stop_at)),
+        |m| tally.add(&m, options),
+    )?;
+    Ok(tally.into_counts(options.top))
+}
+
+/// [`tally_text`] for --byte-mode, decoding the group's values lossily
+fn tally_bytes(
+    re: &regex::bytes::Regex,
+    input: &[u8],
+    group: usize,
+    skip_empty: bool,
+    options: &TestOptions,
+) -> Vec<CaptureCount> {
+    let mut tally = Tally::new(group);
+    for caps in re.captures_iter(input) {
+        if skip_empty && caps.get(0).is_some_and(|m| m.is_empty()) {
+            continue;
+        }
+        if let Some(value) = caps.get(group) {
+            tally.add_value(&String::from_utf8_lossy(value.as_bytes()));
+        }
+        if options.first {
+            break;
+        }
+    }
+    tally.into_counts(options.top)
+}
+
+/// Fill in the line and column of each match (`--context-json`, `--vimgrep`) by scanning
+/// `reader`, the input the byte offsets refer to, once for newlines
+fn locate_matches<R: Read>(reader: R, matches: &mut [Match]) -> Result<(), String> {
+    let mut offsets: Vec<usize> = matches.iter().flat_map(|m| [m.start, m.end]).collect();
+    offsets.sort_unstable();
+    offsets.dedup();
+
+    let mut positions = HashMap::with_capacity(offsets.len());
+    let mut pending = offsets.into_iter().peekable();
+    let (mut line, mut column, mut line_start) = (1, 1, 0);
+    let mut bytes = BufReader::new(reader).bytes();
+    let mut offset = 0;
+    while let Some(&target) = pending.peek() {
+        if target == offset {
+            positions.insert(target, (line, column, offset - line_start + 1));
+            pending.next();
+            continue;
+        }
+        let Some(byte) = bytes.next() else {
+            break;
+        };
+        offset += 1;
+        match byte.map_err(|e| format!("Failed to read file: {}", e))? {
+            b'\n' => {
+                line += 1;
+                column = 1;
+                line_start = offset;
+            }
+            // Count characters, not the continuation bytes of multi-byte ones
+            b if b & 0xC0 != 0x80 => column += 1,
+            _ => {}
+        }
+    }
+
+    for m in matches.iter_mut() {
+        let (Some(&(line, column, byte_column)), Some(&(end_line, end_column, _))) =
+            (positions.get(&m.start), positions.get(&m.end))
+        else {
+            continue;
+        };
+        m.location = Some(FileLocation {
+            line,
+            column,
+            end_line,
+            end_column,
+            byte_column,
+        });
+    }
+    Ok(())
+}
+
+/// Collapse matches with identical text into one entry (at the first
+/// occurrence) carrying the number of occurrences and every span
+pub fn dedup_matches(matches: Vec<Match>) -> Vec<Match> {
+    let mut slots: HashMap<String, usize> = HashMap::new();
+    let mut deduped: Vec<Match> = Vec::new();
+
+    for m in matches {
+        let span = Span {
+            start: m.start,
+            end: m.end,
+        };
+        match slots.get(&m.text) {
+            Some(&slot) => {
+                let entry = &mut deduped[slot];
+                entry.occurrences = entry.occurrences.map(|n| n + 1);
+                entry.positions.push(span);
+            }
+            None => {
+                slots.insert(m.text.clone(), deduped.len());
+                deduped.push(Match {
+                    occurrences: Some(1),
+                    positions: vec![span],
+                    ..m
+                });
+            }
+        }
+    }
+
+    deduped
+}
+
+/// Project matches onto a table with one column per capture group, for
+/// loading into a CSV or dataframe
+pub fn group_rows(pattern: &str, matches: &[Match]) -> GroupRowsResult {
+    let normalized = normalize_pattern(pattern);
+    let groups = capture_group_info(normalized.as_deref().unwrap_or(pattern));
+
+    let rows = matches
+        .iter()
+        .map(|m| {
+            groups
+                .iter()
+                .map(|g| {
+                    m.captures
+                        .iter()
+                        .find(|c| c.group == g.index)
+                        .map(|c| c.text.clone())
+                })
+                .collect()
+        })
+        .collect();
+
+    GroupRowsResult {
+        pattern: pattern.to_string(),
+        columns: groups
+            .into_iter()
+            .map(|g| g.name.unwrap_or_else(|| g.index.to_string()))
+            .collect(),
+        rows,
+    }
+}
+
+/// Reorder matches for output: `position` (match order), `text` (so duplicates
+/// sit together) or `length` (longest first). Ties keep match order.
+///
+/// When the order changes, each match records its original `index`.
+pub fn sort_matches(matches: &mut [Match], by: &str) -> Result<(), String> {
+    match by {
+        "position" => return Ok(()),
+        "text" | "length" => {}
+        _ => {
+            return Err(format!(
+                "Unknown sort order '{}'. Valid options: position, text, length",
+                by
+            ))
+        }
+    }
+
+    for (index, m) in matches.iter_mut().enumerate() {
+        m.index = Some(index);
+    }
+    if by == "text" {
+        matches.sort_by(|a, b| a.text.cmp(&b.text));
+    } else {
+        matches.sort_by_key(|m| std::cmp::Reverse(m.text.len()));
+    }
+    Ok(())
+}
+
+/// Replace each match with the text and span of a single capture group.
+///
+/// Matches where the group did not participate are dropped.
+fn select_group(matches: Vec<Match>, group: Option<usize>) -> Vec<Match> {
+    match group {
+        None => matches,
+        Some(0) => matches
+            .into_iter()
+            .map(|m| Match {
+                captures: Vec::new(),
+                ..m
+            })
+            .collect(),
+        Some(index) => matches
+            .into_iter()
+            .filter_map(|m| {
+                let cap = m.captures.into_iter().find(|c| c.group == index)?;
+                Some(Match {
+                    text: cap.text,
+                    start: cap.start,
+                    end: cap.end,
+                    captures: Vec::new(),
+                    index: None,
+                    occurrences: None,
+                    positions: Vec::new(),
+                    context: m.context,
+                    location: None,
+                })
+            })
+            .collect(),
+    }
+}
+
+/// Collect all matches from text
+fn collect_matches(
+    compiled: &CompiledRegex,
+    text: &str,
+    max_matches: usize,
+    context: Option<ContextWindow>,
+    skip_empty: bool,
+) -> Result<Vec<Match>, String> {
+    let mut matches = Vec::new();
+    // Skipped empty matches don't count towards the limit
+    let limit = if skip_empty { usize::MAX } else { max_matches };
+    for_each_match(compiled, text, limit, |m| {
+        if skip_empty && m.start == m.end {
+            return true;
+        }
+        if matches.len() >= max_matches {
+            return false;
+        }
+        matches.push(m.clone());
+        true
+    })?;
+    if let Some(context) = context {
+        for m in &mut matches {
+            m.context = context.around(text, m);
+        }
+    }
+    Ok(matches)
+}
+
+/// [`collect_matches`] over a whole text. A pattern anchored to the start of
+/// the text (see [`is_start_anchored`]) has at most one match, at offset 0,
+/// so it is searched for once rather than iterating on to the end of the text.
+fn collect_text_matches(
+    compiled: &CompiledRegex,
+    pattern: &str,
+    text: &str,
+    max_matches: usize,
+    context: Option<ContextWindow>,
+    skip_empty: bool,
+) -> Result<Vec<Match>, String> {
+    if !is_start_anchored(pattern) {
+        return collect_matches(compiled, text, max_matches, context, skip_empty);
+    }
+    let mut matches = collect_matches(compiled, text, max_matches.min(1), context, false)?;
+    if skip_empty {
+        matches.retain(|m| m.start != m.end);
+    }
+    Ok(matches)
+}
+
+/// Cut `matches` (in input order) down to the leading run in which each one
+/// starts exactly where the previous ended, the first at offset 0: what a
+/// JavaScript `/gy` (sticky) regex yields when iterated over `text`.
+///
+/// A sticky match found by searching forward from the previous end must start
+/// there, so the run is a prefix of the find-all matches. As in JavaScript,
+/// an empty match moves the next expected start on by one character.
+fn keep_sticky_run(matches: &mut Vec<Match>, text: &str) {
+    let mut expected = 0;
+    let run = matches
+        .iter()
+        .take_while(|m| {
+            if m.start != expected {
+                return false;
+            }
+            expected = sticky_next_start(text, m);
+            true
+        })
+        .count();
+    matches.truncate(run);
+}
+
+/// Where the sticky match after `m` has to start
+fn sticky_next_start(text: &str, m: &Match) -> usize {
+    if m.start == m.end {
+        m.end + text[m.end..].chars().next().map_or(1, char::len_utf8)
+    } else {
+        m.end
+    }
+}
+
+/// Surrounding text to attach to each match (`--context-chars`)
+#[derive(Clone, Copy)]
+struct ContextWindow {
+    /// Characters to show on either side
+    chars: usize,
+    /// Capture group whose span is marked (`--group`), else the whole match
+    group: Option<usize>,
+}
+
+impl ContextWindow {
+    /// Up to `chars` characters either side of the marked span, cut on char
+    /// boundaries, with `…` where the text continues. `None` if the group
+    /// did not participate in the match.
+    fn around(&self, text: &str, m: &Match) -> Option<String> {
+        let (start, end) = match self.group {
+            None | Some(0) => (m.start, m.end),
+            Some(index) => {
+                let cap = m.captures.iter().find(|c| c.group == index)?;
+                (cap.start, cap.end)
+            }
+        };
+
+        let from = text[..start]
+            .char_indices()
+            .rev()
+            .take(self.chars)
+            .last()
+            .map_or(start, |(i, _)| i);
+        let to = text[end..]
+            .char_indices()
+            .nth(self.chars)
+            .map_or(text.len(), |(i, _)| end + i);
+
+        Some(format!(
+            "{}{}«{}»{}{}",
+            if from > 0 { "…" } else { "" },
+            &text[from..start],
+            &text[start..end],
+            &text[end..to],
+            if to < text.len() { "…" } else { "" }
+        ))
+    }
+}
+
+/// Invoke `f` for each match in `text`, in order, without buffering them.
+///
+/// Stops after `max_matches` matches or as soon as `f` returns `false`.
+/// Returns the number of matches passed to `f`.
+pub fn for_each_match(
+    compiled: &CompiledRegex,
+    text: &str,
+    max_matches: usize,
+    mut f: impl FnMut(&Match) -> bool,
+) -> Result<usize, String> {
+    let mut count = 0;
+
+    match compiled {
+        CompiledRegex::Regex(re) => {
+            let has_captures = re.captures_len() > 1;
+
+            if has_captures {
+                for caps in re.captures_iter(text) {
+                    if count >= max_matches {
+                        break;
+                    }
+
+                    if let Some(full_match) = caps.get(0) {
+                        let mut captures = Vec::new();
+
+                        // Collect capture groups (skip group 0 which is the full match)
+                        for (i, cap) in caps.iter().enumerate().skip(1) {
+                            if let Some(c) = cap {
+                                captures.push(Capture {
+                                    group: i,
+                                    name: re
+                                        .capture_names()
+                                        .nth(i)
+                                        .flatten()
+                                        .map(|s| s.to_string()),
+                                    text: c.as_str().to_string(),
+                                    start: c.start(),
+                                    end: c.end(),
+                                });
+                            }
+                        }
+
+                        count += 1;
+                        let keep_going = f(&Match {
+                            text: full_match.as_str().to_string(),
+                            start: full_match.start(),
+                            end: full_match.end(),
+                            captures,
+                            index: None,
+                            occurrences: None,
+                            positions: Vec::new(),
+                            context: None,
+                            location: None,
+                        });
+                        if !keep_going {
+                            break;
+                        }
+                    }
+                }
+            } else {
+                for m in re.find_iter(text) {
+                    if count >= max_matches {
+                        break;
+                    }
+
+                    count += 1;
+                    let keep_going = f(&Match {
+                        text: m.as_str().to_string(),
+                        start: m.start(),
+                        end: m.end(),
+                        captures: Vec::new(),
+                        index: None,
+                        occurrences: None,
+                        positions: Vec::new(),
+                        context: None,
+                        location: None,
+                    });
+                    if !keep_going {
+                        break;
+                    }
+                }
+            }
+        }
+
+        CompiledRegex::FancyRegex(re) => {
+            let has_captures = re.captures_len() > 1;
+            let mut cursor = FancyCursor::default();
+
+            if has_captures {
+                while count < max_matches {
+                    let found = cursor.next(text, |pos| {
+                        Ok(re.captures_from_pos(text, pos)?.and_then(|caps| {
+                            let range = caps.get(0)?.range();
+                            Some((caps, range))
+                        }))
+                    })?;
+                    let Some((caps, range)) = found else { break };
+
+                    let mut captures = Vec::new();
+                    for i in 1..caps.len() {
+                        if let Some(c) = caps.get(i) {
+                            captures.push(Capture {
+                                group: i,
+                                name: re.capture_names().nth(i).flatten().map(|s| s.to_string()),
+                                text: c.as_str().to_string(),
+                                start: c.start(),
+                                end: c.end(),
+                            });
+                        }
+                    }
+
+                    count += 1;
+                    let keep_going = f(&Match {
+                        text: text[range.clone()].to_string(),
+                        start: range.start,
+                        end: range.end,
+                        captures,
+                        index: None,
+                        occurrences: None,
+                        positions: Vec::new(),
+                        context: None,
+                        location: None,
+                    });
+                    if !keep_going {
+                        break;
+                    }
+                }
+            } else {
+                while count < max_matches {
+                    let found = cursor.next(text, |pos| {
+                        Ok(re.find_from_pos(text, pos)?.map(|m| (m, m.range())))
+                    })?;
+                    let Some((m, _)) = found else { break };
+
+                    count += 1;
+                    let keep_going = f(&Match {
+                        text: m.as_str().to_string(),
+                        start: m.start(),
+                        end: m.end(),
+                        captures: Vec::new(),
+                        index: None,
+                        occurrences: None,
+                        positions: Vec::new(),
+                        context: None,
+                        location: None,
+                    });
+                    if !keep_going {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(count)
+}
+
+/// Position of a fancy-regex search over successive matches.
+///
+/// Follows the `regex` crate's iteration rules so both engines report the
+/// same matches: each search resumes where the previous match ended, and an
+/// empty match touching that end is skipped by retrying one character later.
+/// Zero-width patterns like `\b` or `(?=x)` thus report each position once,
+/// including the end of the text.
+#[derive(Default)]
+struct FancyCursor {
+    pos: usize,
+    last_end: Option<usize>,
+}
+
+impl FancyCursor {
+    /// Run `search` from the cursor and return the next match with its span
+    fn next<T>(
+        &mut self,
+        text: &str,
+        mut search: impl FnMut(usize) -> Result<Option<(T, Range<usize>)>, fancy_regex::Error>,
+    ) -> Result<Option<(T, Range<usize>)>, String> {
+        while self.pos <= text.len() {
+            let Some((found, range)) = search(self.pos).map_err(|e| e.to_string())? else {
+                return Ok(None);
+            };
+            if range.is_empty() && self.last_end == Some(range.end) {
+                match text[range.end..].chars().next() {
+                    Some(c) => self.pos = range.end + c.len_utf8(),
+                    None => return Ok(None),
+                }
+                continue;
+            }
+            self.pos = range.end;
+            self.last_end = Some(range.end);
+            return Ok(Some((found, range)));
+        }
+        Ok(None)
+    }
+}
+
+/// Collect matches from a reader using streaming, one record at a time.
+///
+/// Records are split on `delimiter` (`\n` for lines, `\0` for `--null-data`).
+/// Reported offsets are relative to the raw input, including separators.
+fn collect_matches_streaming<R: Read>(
+    compiled: &CompiledRegex,
+    reader: R,
+    delimiter: u8,
+    max_matches: usize,
+    context: Option<ContextWindow>,
+    skip_empty: bool,
+) -> Result<Vec<Match>, String> {
+    let mut matches = Vec::new();
+    stream_records(
+        compiled,
+        reader,
+        delimiter,
+        max_matches,
+        context,
+        skip_empty,
+        |_| Ok(true),
+        |m| {
+            matches.push(m);
+            true
+        },
+    )?;
+    Ok(matches)
+}
+
+/// Match each record in turn and pass every match to `found`, shifted to
+/// raw-input offsets, until `max_matches` have been found or `found` returns
+/// false. Empty matches are left out when `skip_empty` is set.
+///
+/// `on_record` gets the 1-based number of each record before it is matched
+/// and returns false to stop the scan there.
+#[allow(clippy::too_many_arguments)]
+fn stream_records<R: Read>(
+    compiled: &CompiledRegex,
+    reader: R,
+    delimiter: u8,
+    max_matches: usize,
+    context: Option<ContextWindow>,
+    skip_empty: bool,
+    mut on_record: impl FnMut(usize) -> Result<bool, String>,
+    mut found: impl FnMut(Match) -> bool,
+) -> Result<(), String> {
+    let mut match_count = 0;
+    let mut wanted = true;
+    let mut record_number = 0;
+    let mut reader = BufReader::new(reader);
+    let mut byte_offset = 0usize;
+    let mut raw_record = Vec::new();
+
+    while wanted && match_count < max_matches {
+        raw_record.clear();
+        let bytes_read = reader
+            .read_until(delimiter, &mut raw_record)
+            .map_err(|e| format!("Failed to read line: {}", e))?;
+
+        if bytes_read == 0 {
+            break; // EOF
+        }
+
+        record_number += 1;
+        if !on_record(record_number)? {
+            break;
+        }
+
+        // Strip the separator for matching, but use raw length for offset
+        let record = record_text(&raw_record, delimiter)?;
+
+        for_each_match(compiled, record, usize::MAX, |m| {
+            if skip_empty && m.start == m.end {
+                return true;
+            }
+            let mut m = m.clone();
+            if let Some(context) = context {
+                m.context = context.around(record, &m);
+            }
+            shift_match(&mut m, byte_offset);
+            match_count += 1;
+            wanted = found(m);
+            wanted && match_count < max_matches
+        })?;
+
+        byte_offset += raw_record.len(); // includes the actual separator (\n, \r\n or \0)
+    }
+
+    Ok(())
+}
+
+/// Write the matches of `pattern` in a file to `out` as a JSON array, each
+/// element as soon as it is found (`test --stream-json`). The file is read
+/// record by record and only the current match is held, so memory stays flat
+/// however many matches there are. Returns the number of matches written.
+///
+/// An error after the first byte is written leaves the array unterminated.
+pub fn stream_file_json<W: Write>(
+    pattern: &str,
+    file_path: &Path,
+    options: &TestOptions,
+    out: W,
+) -> Result<usize, CoreError> {
+    let effective_pattern = apply_flags(pattern, options);
+    let compiled = compile(&effective_pattern, options)?.regex;
+    let context = options
+        .context_chars
+        .map(|chars| ContextWindow { chars, group: None });
+    let skip_empty = floods_empty_matches(&effective_pattern) && !options.allow_empty_matches;
+    let max_matches = match_limit(options);
+    let delimiter = if options.null_data { b'\0' } else { b'\n' };
+
+    let file = File::open(file_path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut out = BufWriter::new(out);
+    let write_error = |e: io::Error| format!("Failed to write output: {}", e);
+
+    out.write_all(b"[").map_err(write_error)?;
+    let mut written = 0;
+    let mut outcome = Ok(());
+    stream_records(
+        &compiled,
+        file,
+        delimiter,
+        max_matches,
+        context,
+        skip_empty,
+        |_| Ok(true),
+        |m| {
+            let separator: &[u8] = if written == 0 { b"\n  " } else { b",\n  " };
+            outcome = out.write_all(separator).map_err(write_error).and_then(|_| {
+                serde_json::to_writer(&mut out, &m)
+                    .map_err(|e| format!("Failed to write output: {}", e))
+            });
+            written += 1;
+            outcome.is_ok()
+        },
+    )?;
+    outcome?;
+
+    let close: &[u8] = if written == 0 { b"]\n" } else { b"\n]\n" };
+    out.write_all(close).map_err(write_error)?;
+    out.flush().map_err(write_error)?;
+    Ok(written)
+}
+
+/// A record read up to `delimiter`, as text without the separator
+/// (`\r\n` counts as one line ending)
+fn record_text(raw_record: &[u8], delimiter: u8) -> Result<&str, String> {
+    let record = std::str::from_utf8(raw_record)
+        .map_err(|e| format!("Failed to read line: {}{}", e, NOT_UTF8_HINT))?;
+    Ok(if delimiter == b'\n' {
+        record.trim_end_matches(&['\n', '\r'][..])
+    } else {
+        record.strip_suffix(delimiter as char).unwrap_or(record)
+    })
+}
+
+/// Move a match found in a record to its offset in the whole input
+fn shift_match(m: &mut Match, offset: usize) {
+    m.start += offset;
+    m.end += offset;
+    for cap in &mut m.captures {
+        cap.start += offset;
+        cap.end += offset;
+    }
+}
+
+/// [`collect_matches_streaming`] on a worker thread that reads the input
+/// itself, while this thread watches for a record that takes longer than
+/// `timeout` (`--timeout-per-line-ms`).
+///
+/// Threads cannot be interrupted, so the scan stops at the first record that
+/// overruns: its worker is left to finish that record in the background and
+/// quit, and the matches found before it are returned with its 1-based number.
+fn stream_records_with_deadline(
+    compiled: &Arc<CompiledRegex>,
+    file: File,
+    delimiter: u8,
+    max_matches: usize,
+    context: Option<ContextWindow>,
+    skip_empty: bool,
+    timeout: Duration,
+) -> Result<(Vec<Match>, Option<usize>), String> {
+    enum Event {
+        Found(Match),
+        Done(Result<(), String>),
+    }
+
+    // The record being matched and when it started
+    let progress = Arc::new(Mutex::new((0usize, Instant::now())));
+    let abandoned = Arc::new(AtomicBool::new(false));
+    let (sender, events) = mpsc::channel();
+    {
+        let compiled = Arc::clone(compiled);
+        let progress = Arc::clone(&progress);
+        let abandoned = Arc::clone(&abandoned);
+        thread::spawn(move || {
+            let result = stream_records(
+                &compiled,
+                file,
+                delimiter,
+                max_matches,
+                context,
+                skip_empty,
+                |record_number| {
+                    if abandoned.load(Ordering::Relaxed) {
+                        return Ok(false);
+                    }
+                    *progress.lock().unwrap() = (record_number, Instant::now());
+                    Ok(true)
+                },
+                |m| sender.send(Event::Found(m)).is_ok(),
+            );
+            let _ = sender.send(Event::Done(result));
+        });
+    }
+
+    let mut matches = Vec::new();
+    loop {
+        let (record_number, started) = *progress.lock().unwrap();
+        let remaining = timeout.saturating_sub(started.elapsed());
+        if record_number > 0 && remaining.is_zero() {
+            abandoned.store(true, Ordering::Relaxed);
+            return Ok((matches, Some(record_number)));
+        }
+        match events.recv_timeout(remaining) {
+            Ok(Event::Found(m)) => matches.push(m),
+            Ok(Event::Done(result)) => return result.map(|()| (matches, None)),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err("Match worker thread panicked".to_string())
+            }
+        }
+    }
+}
+
+/// Count the matches on each line, streaming so memory stays flat for big files.
+///
+/// Only lines with at least one match are returned. Empty matches are not
+/// counted when `skip_empty` is set, as in the default match listing.
+fn count_matches_per_line<R: Read>(
+    compiled: &CompiledRegex,
+    reader: R,
+    skip_empty: bool,
+) -> Result<Vec<LineCount>, String> {
+    let mut lines = Vec::new();
+    let mut reader = BufReader::new(reader);
+    let mut raw_line = Vec::new();
+    let mut line_number = 0;
+
+    loop {
+        raw_line.clear();
+        let bytes_read = reader
+            .read_until(b'\n', &mut raw_line)
+            .map_err(|e| format!("Failed to read line: {}", e))?;
+
+        if bytes_read == 0 {
+            break; // EOF
+        }
+        line_number += 1;
+
+        let line = std::str::from_utf8(&raw_line)
+            .map_err(|e| format!("Failed to read line: {}{}", e, NOT_UTF8_HINT))?;
+        let line = line.trim_end_matches(&['\n', '\r'][..]);
+
+        let mut count = 0;
+        for_each_match(compiled, line, usize::MAX, |m| {
+            if !(skip_empty && m.start == m.end) {
+                count += 1;
+            }
+            true
+        })?;
+        if count > 0 {
+            lines.push(LineCount { line_number, count });
+        }
+    }
+
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_matches_per_line() {
+        let (compiled, _) = CompiledRegex::new(r"\d+").unwrap();
+        let input = "a 1 2\nnone\r\n3\n\n45 6 7";
+        let lines = count_matches_per_line(&compiled, input.as_bytes(), false).unwrap();
+        assert_eq!(
+            lines,
+            [
+                LineCount {
+                    line_number: 1,
+                    count: 2
+                },
+                LineCount {
+                    line_number: 3,
+                    count: 1
+                },
+                LineCount {
+                    line_number: 5,
+                    count: 3
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_context_chars() {
+        let options = TestOptions {
+            context_chars: Some(3),
+            ..Default::default()
+        };
+        let result = test_string(r"\d+", "id 42 here, 7", &options).unwrap();
+        assert_eq!(result.matches[0].context.as_deref(), Some("id «42» he…"));
+        assert_eq!(result.matches[1].context.as_deref(), Some("…e, «7»"));
+
+        // Counts characters, not bytes, and never splits a UTF-8 sequence
+        let result = test_string("x", "ééééxüüüü", &options).unwrap();
+        assert_eq!(result.matches[0].context.as_deref(), Some("…ééé«x»üüü…"));
+
+        let options = TestOptions {
+            context_chars: Some(2),
+            group: Some("1".to_string()),
+            ..Default::default()
+        };
+        let result = test_string(r"k=(\w+)", "a k=val b", &options).unwrap();
+        assert_eq!(result.matches[0].context.as_deref(), Some("…k=«val» b"));
+    }
+
+    #[test]
+    fn test_dedup_matches() {
+        let result = test_string(r"\w+", "err ok err err", &TestOptions::default()).unwrap();
+        let deduped = dedup_matches(result.matches);
+        assert_eq!(deduped.len(), 2);
+        assert_eq!(deduped[0].text, "err");
+        assert_eq!(deduped[0].occurrences, Some(3));
+        let starts: Vec<usize> = deduped[0].positions.iter().map(|s| s.start).collect();
+        assert_eq!(starts, [0, 7, 11]);
+        assert_eq!(deduped[1].occurrences, Some(1));
+    }
+
+    #[test]
+    fn test_sort_matches() {
+        let mut result =
+            test_string(r"\w+", "pear fig apple fig", &TestOptions::default()).unwrap();
+        sort_matches(&mut result.matches, "text").unwrap();
+        let texts: Vec<&str> = result.matches.iter().map(|m| m.text.as_str()).collect();
+        assert_eq!(texts, ["apple", "fig", "fig", "pear"]);
+        let indices: Vec<_> = result.matches.iter().map(|m| m.index).collect();
+        assert_eq!(indices, [Some(2), Some(1), Some(3), Some(0)]);
+
+        sort_matches(&mut result.matches, "length").unwrap();
+        assert_eq!(result.matches[0].text, "apple");
+        assert_eq!(result.matches[3].text, "fig");
+
+        let mut result = test_string(r"\w+", "b a", &TestOptions::default()).unwrap();
+        sort_matches(&mut result.matches, "position").unwrap();
+        assert_eq!(result.matches[0].text, "b");
+        assert_eq!(result.matches[0].index, None);
+        assert!(sort_matches(&mut result.matches, "size").is_err());
+    }
+
+    #[test]
+    fn test_simple_match() {
+        let result = test_string(r"\d+", "hello 123 world 456", &TestOptions::default()).unwrap();
+        assert!(result.matched);
+        assert_eq!(result.match_count, 2);
+        assert_eq!(result.matches[0].text, "123");
+        assert_eq!(result.matches[1].text, "456");
+    }
+
+    #[test]
+    fn test_phase_timings() {
+        let pattern = r"\b\w{3,}@\w+\.(?:com|org)\b";
+        let input = "mail user@example.com or admin@example.org\n".repeat(10_000);
+        let check = |result: TestResult| {
+            assert_eq!(result.match_count, 20_000);
+            assert!(result.compile_us > 0);
+            assert!(result.match_us > 0);
+            assert!(result.compile_us + result.match_us <= result.elapsed_us);
+        };
+        let options = TestOptions {
+            max_matches: None,
+            ..Default::default()
+        };
+
+        check(test_string(pattern, &input, &options).unwrap());
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), &input).unwrap();
+        check(test_file(pattern, file.path(), &options).unwrap());
+    }
+
+    #[test]
+    fn test_no_match() {
+        let result = test_string(r"\d+", "hello world", &TestOptions::default()).unwrap();
+        assert!(!result.matched);
+        assert_eq!(result.match_count, 0);
+    }
+
+    #[test]
+    fn test_with_captures() {
+        let result = test_string(r"(\d+)-(\d+)", "123-456", &TestOptions::default()).unwrap();
+        assert!(result.matched);
+        assert_eq!(result.match_count, 1);
+        assert_eq!(result.matches[0].captures.len(), 2);
+        assert_eq!(result.matches[0].captures[0].text, "123");
+        assert_eq!(result.matches[0].captures[1].text, "456");
+    }
+
+    #[test]
+    fn test_max_matches() {
+        let options = TestOptions {
+            max_matches: Some(1),
+            ..Default::default()
         };
         let result = test_string(r"\d+", "1 2 3 4 5", &options).unwrap();
         assert_eq!(result.match_count, 1);
@@ -407,9 +2113,8 @@ mod tests {
     #[test]
     fn test_multiline_dot_matches_newline() {
         let options = TestOptions {
-            max_matches: Some(100),
-            engine: None,
             multiline: true,
+            ..Default::default()
         };
         let result = test_string(r"hello.world", "hello\nworld", &options).unwrap();
         assert!(result.matched);
@@ -419,11 +2124,590 @@ mod tests {
     #[test]
     fn test_multiline_anchors() {
         let options = TestOptions {
-            max_matches: Some(100),
-            engine: None,
             multiline: true,
+            ..Default::default()
+        };
+        let result = test_string(r"^\w+$", "foo\nbar\nbaz", &options).unwrap();
+        assert_eq!(result.match_count, 3);
+    }
+
+    #[test]
+    fn test_multiline_span_warning() {
+        let options = TestOptions {
+            multiline: true,
+            ..Default::default()
         };
+        let result = test_string(r"start.*end", "start\nfoo\nbar\nend", &options).unwrap();
+        assert!(result.warning.is_some());
+
         let result = test_string(r"^\w+$", "foo\nbar\nbaz", &options).unwrap();
+        assert!(result.warning.is_none());
+    }
+
+    #[test]
+    fn test_empty_matches_left_out() {
+        let result = test_string("a*", "baab", &TestOptions::default()).unwrap();
+        assert!(result.matches_empty);
+        assert!(result.warning.unwrap().contains("--allow-empty-matches"));
+        let texts: Vec<_> = result.matches.iter().map(|m| m.text.as_str()).collect();
+        assert_eq!(texts, ["aa"]);
+
+        // Skipped empty matches don't use up the limit
+        let options = TestOptions {
+            max_matches: Some(1),
+            ..Default::default()
+        };
+        let result = test_string(r"\d*", "ab 12", &options).unwrap();
+        assert_eq!(result.matches[0].text, "12");
+
+        let options = TestOptions {
+            allow_empty_matches: true,
+            ..Default::default()
+        };
+        let result = test_string("a*", "baab", &options).unwrap();
         assert_eq!(result.match_count, 3);
+        assert!(result.warning.is_none());
+
+        // Zero-width patterns keep their matches
+        let result = test_string(r"\b", "ab cd", &TestOptions::default()).unwrap();
+        assert!(!result.matches_empty);
+        assert_eq!(result.match_count, 4);
+    }
+
+    #[test]
+    fn test_is_match_string() {
+        let options = TestOptions::default();
+        assert!(is_match_string(r"\d+", "abc 123", &options).unwrap());
+        assert!(!is_match_string(r"\d+", "abc", &options).unwrap());
+        assert!(is_match_string(r"(?<=\$)\d", "cost $5", &options).unwrap());
+
+        // Empty matches don't count unless asked for, as in the match list
+        assert!(!is_match_string("x*", "abc", &options).unwrap());
+        let options = TestOptions {
+            allow_empty_matches: true,
+            ..Default::default()
+        };
+        assert!(is_match_string("x*", "abc", &options).unwrap());
+
+        let options = TestOptions {
+            multiline: true,
+            ..Default::default()
+        };
+        assert!(is_match_string("a.b", "a\nb", &options).unwrap());
+    }
+
+    #[test]
+    fn test_byte_mode() {
+        let options = TestOptions {
+            byte_mode: true,
+            ..Default::default()
+        };
+        let result = test_string(r"(?<n>\d+)é", "ab 12é 3é", &options).unwrap();
+        assert_eq!(result.engine, "regex");
+        assert_eq!(result.match_count, 2);
+        // Byte offsets, not character offsets
+        assert_eq!((result.matches[1].start, result.matches[1].end), (8, 11));
+        assert_eq!(result.matches[0].captures[0].name.as_deref(), Some("n"));
+
+        let matches = collect_byte_matches(
+            &CompiledRegex::bytes(r"(?-u)id=\xFF(\d)").unwrap(),
+            b"id=\xFF7 id=8",
+            10,
+            false,
+        );
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].text, "id=\u{FFFD}7");
+        assert_eq!(matches[0].captures[0].start, 4);
+
+        let err = test_string(r"(?<=a)b", "ab", &options).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("--byte-mode only supports the regex engine"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_line_local_patterns() {
+        assert!(is_line_local(r"\d+\.\w*"));
+        for pattern in ["^a", r"a$", r"\Aa", r"\s+", "[^x]", "a\nb", r"(?=a)"] {
+            assert!(!is_line_local(pattern), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_start_anchored_patterns() {
+        for pattern in [
+            "^a",
+            r"\Aa+",
+            "^a|^b",
+            "(?:^a)+b",
+            r"^(\w+)\1",
+            r"(?=\d)^\d+",
+            r"^(?:a|b(?=c))",
+        ] {
+            assert!(is_start_anchored(pattern), "{}", pattern);
+        }
+        for pattern in ["a^", "^a|b", "(?m)^a", "(?:^a)?b", r"(\w)\1^", r"(?<=x)|^a"] {
+            assert!(!is_start_anchored(pattern), "{}", pattern);
+        }
+
+        // A single search still finds the one match, and honours the options
+        let result = test_string("^a+", "aab aa", &TestOptions::default()).unwrap();
+        assert_eq!(result.match_count, 1);
+        assert_eq!(result.matches[0].text, "aa");
+        let result = test_string(r"^(?=b)\w*", "bob", &TestOptions::default()).unwrap();
+        assert_eq!(result.matches[0].text, "bob");
+        assert!(
+            !test_string("^a*", "bab", &TestOptions::default())
+                .unwrap()
+                .matched
+        );
+    }
+
+    #[test]
+    fn test_group_rows() {
+        let result = test_string(
+            r"(\d+)-(?<b>\d+)(x)?",
+            "123-456 789-012x",
+            &TestOptions::default(),
+        )
+        .unwrap();
+        let table = group_rows(r"(\d+)-(?<b>\d+)(x)?", &result.matches);
+        assert_eq!(table.columns, ["1", "b", "3"]);
+        assert_eq!(
+            table.rows,
+            [
+                vec![Some("123".to_string()), Some("456".to_string()), None],
+                vec![
+                    Some("789".to_string()),
+                    Some("012".to_string()),
+                    Some("x".to_string())
+                ],
+            ]
+        );
+
+        // No groups: an empty row per match
+        let table = group_rows(r"\d+", &result.matches);
+        assert!(table.columns.is_empty());
+        assert_eq!(table.rows, [vec![], vec![]]);
+    }
+
+    #[test]
+    fn test_group_selection() {
+        let options = TestOptions {
+            group: Some("2".to_string()),
+            ..Default::default()
+        };
+        let result = test_string(r"(\w+)=(\d+)", "a=1 b=22", &options).unwrap();
+        assert_eq!(result.match_count, 2);
+        assert_eq!(result.matches[1].text, "22");
+        assert_eq!(result.matches[1].start, 6);
+        assert!(result.matches[1].captures.is_empty());
+
+        // Named group with the backtracking engine
+        let options = TestOptions {
+            group: Some("num".to_string()),
+            ..Default::default()
+        };
+        let result = test_string(r"(?<=x)(?<num>\d+)", "x12 y34 x5", &options).unwrap();
+        assert_eq!(result.engine, "fancy-regex");
+        let texts: Vec<&str> = result.matches.iter().map(|m| m.text.as_str()).collect();
+        assert_eq!(texts, vec!["12", "5"]);
+    }
+
+    #[test]
+    fn test_many_patterns() {
+        let patterns = "\\d+\n\n(?<=x)\\d\n(unclosed\nzzz\n(?:)\n";
+        let result = test_many(patterns, "x1 22 333", false, &TestOptions::default());
+
+        let summary: Vec<(usize, bool, usize, bool)> = result
+            .results
+            .iter()
+            .map(|r| (r.line, r.matched, r.match_count, r.error.is_some()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (1, true, 3, false),
+                (3, true, 1, false),
+                (4, false, 0, true),
+                (5, false, 0, false),
+                (6, false, 0, true),
+            ]
+        );
+        assert_eq!(result.results[1].engine.as_deref(), Some("fancy-regex"));
+        assert_eq!(result.matched_patterns, 2);
+        assert_eq!(result.failed_patterns, 2);
+
+        let result = test_many("(?:)", "ab", true, &TestOptions::default());
+        assert!(result.results[0].error.is_none());
+    }
+
+    #[test]
+    fn test_locate_matches_multibyte_and_crlf() {
+        let input = "café foo\r\nbar foo\n€foo";
+        let options = TestOptions::default();
+        let mut matches = test_string("foo", input, &options).unwrap().matches;
+        locate_matches(input.as_bytes(), &mut matches).unwrap();
+
+        let locations: Vec<(usize, usize, usize, usize)> = matches
+            .iter()
+            .map(|m| {
+                let l = m.location.as_ref().unwrap();
+                (l.line, l.column, l.end_line, l.end_column)
+            })
+            .collect();
+        assert_eq!(locations, vec![(1, 6, 1, 9), (2, 5, 2, 8), (3, 2, 3, 5)]);
+
+        // A match spanning CRLF ends on the next line
+        let mut matches = test_string("o\r\nb", input, &options).unwrap().matches;
+        locate_matches(input.as_bytes(), &mut matches).unwrap();
+        let l = matches[0].location.as_ref().unwrap();
+        assert_eq!((l.line, l.column, l.end_line, l.end_column), (1, 8, 2, 2));
+    }
+
+    #[test]
+    fn test_alternation_from_lines() {
+        let combined = alternation_from_lines("foo\n\nb.r\n", false).unwrap();
+        assert_eq!(combined, "(?P<alt1>foo)|(?P<alt3>b.r)");
+        let result = test_string(&combined, "bar foo", &TestOptions::default()).unwrap();
+        let tags: Vec<_> = result
+            .matches
+            .iter()
+            .map(|m| m.captures[0].name.as_deref().unwrap())
+            .collect();
+        assert_eq!(tags, vec!["alt3", "alt1"]);
+
+        assert_eq!(
+            alternation_from_lines("a.b\n1+1\n", true).unwrap(),
+            r"(?P<alt1>a\.b)|(?P<alt2>1\+1)"
+        );
+        assert!(alternation_from_lines("\n  \n", false).is_err());
+
+        // A user group named like a tag would clash with (or fake) one
+        let err = alternation_from_lines("x\n(?P<alt1>y)\n", false).unwrap_err();
+        assert!(err.contains("Line 2"), "{}", err);
+        assert!(alternation_from_lines("(?P<alternative>y)\n(?P<alt>z)", false).is_ok());
+    }
+
+    #[test]
+    fn test_sticky_run() {
+        let sticky = TestOptions {
+            sticky: true,
+            ..Default::default()
+        };
+        let result = test_string(r"\d", "12a3", &sticky).unwrap();
+        assert_eq!(result.match_count, 2);
+        assert_eq!(result.matches[1].text, "2");
+
+        // The first match must start at offset 0
+        let result = test_string("a", "baa", &sticky).unwrap();
+        assert!(!result.matched);
+
+        // An empty match moves the expected start on by one character
+        let result = test_string(
+            "x?",
+            "éx",
+            &TestOptions {
+                allow_empty_matches: true,
+                ..sticky
+            },
+        )
+        .unwrap();
+        assert_eq!(result.match_count, 2);
+        assert_eq!(result.matches[1].start, 2);
+    }
+
+    #[test]
+    fn test_pattern_coverage() {
+        let options = TestOptions::default();
+        let coverage = |pattern: &str, input: &str| {
+            let result = pattern_coverage(pattern, input, &options).unwrap();
+            (result.matched_prefix, result.failed_at, result.prefix_match)
+        };
+
+        assert_eq!(
+            coverage(r"^foo\d+$", "foo12x"),
+            (r"^foo\d+".to_string(), Some(7), Some("foo12".to_string()))
+        );
+        // Prefixes inside a group are probed with the group closed
+        assert_eq!(coverage(r"id=(\d{4})", "id=12").1, Some(6));
+        // A trailing `(` or `|` would probe an empty branch; escaped ones are literal
+        assert_eq!(coverage(r"ab|cd", "xyz").1, Some(0));
+        assert_eq!(coverage(r"a\(b", "a(c").1, Some(3));
+        assert_eq!(coverage(r"(?<=a)b\d", "ab").1, Some(7));
+
+        let result = pattern_coverage("(foo|bar)baz", "barbaz", &options).unwrap();
+        assert!(result.matched);
+        assert_eq!(result.failed_at, None);
+        assert_eq!(result.matched_prefix, "(foo|bar)baz");
+
+        assert!(pattern_coverage("(unclosed", "x", &options).is_err());
+        assert_eq!(unclosed_groups(r"(a[(]\((b"), 2);
+    }
+
+    #[test]
+    fn test_engine_reason() {
+        let options = TestOptions::default();
+        let result = test_string(r"\d+", "a1", &options).unwrap();
+        assert!(result.engine_reason.is_none());
+
+        let result = test_string(r"(\w)\1", "aa", &options).unwrap();
+        assert_eq!(
+            result.engine_reason.as_deref(),
+            Some("Pattern uses backreference")
+        );
+        assert!(!result.fallback_occurred);
+
+        let result = test_string(r"\Ga", "ab", &options).unwrap();
+        assert!(result.fallback_occurred);
+        assert!(result
+            .engine_reason
+            .unwrap()
+            .starts_with("Fell back to fancy-regex"));
+
+        let options = TestOptions {
+            engine: Some(EngineType::FancyRegex),
+            ..Default::default()
+        };
+        let result = test_string(r"\d+", "a1", &options).unwrap();
+        assert_eq!(
+            result.engine_reason.as_deref(),
+            Some("Forced with --engine fancy-regex")
+        );
+    }
+
+    #[test]
+    fn test_by_capture_tally() {
+        let options = TestOptions {
+            max_matches: Some(1),
+            by_capture: Some("status".to_string()),
+            top: Some(2),
+            ..Default::default()
+        };
+        let input = "GET 200\nGET 404\nPOST 200\nGET 500\nPUT 200\nGET 404";
+        let result = test_string(r"(\w+) (?<status>\d{3})", input, &options).unwrap();
+
+        // The tally covers every match even though only one is reported
+        assert_eq!(result.match_count, 1);
+        let counts: Vec<(&str, usize)> = result
+            .capture_counts
+            .iter()
+            .map(|c| (c.value.as_str(), c.count))
+            .collect();
+        assert_eq!(counts, vec![("200", 3), ("404", 2)]);
+    }
+
+    #[test]
+    fn test_by_capture_tally_follows_sticky_run() {
+        let options = TestOptions {
+            sticky: true,
+            by_capture: Some("1".to_string()),
+            ..Default::default()
+        };
+        let result = test_string(r"a(\d)", "a1a2 a3", &options).unwrap();
+        let values: Vec<&str> = result
+            .capture_counts
+            .iter()
+            .map(|c| c.value.as_str())
+            .collect();
+        assert_eq!(values, vec!["1", "2"]);
+    }
+
+    #[test]
+    fn test_group_missing() {
+        let options = TestOptions {
+            group: Some("3".to_string()),
+            ..Default::default()
+        };
+        let err = test_string(r"(\w+)=(\d+)", "a=1", &options).unwrap_err();
+        assert!(err.to_string().contains("does not exist"));
+    }
+
+    #[test]
+    fn test_no_unicode_word_class() {
+        let result = test_string(r"^\w$", "é", &TestOptions::default()).unwrap();
+        assert!(result.matched);
+
+        let options = TestOptions {
+            no_unicode: true,
+            ..Default::default()
+        };
+        let result = test_string(r"^\w$", "é", &options).unwrap();
+        assert!(!result.matched);
+        assert!(test_string(r"^\w$", "e", &options).unwrap().matched);
+    }
+
+    #[test]
+    fn test_no_unicode_composes_with_multiline() {
+        let options = TestOptions {
+            multiline: true,
+            no_unicode: true,
+            ..Default::default()
+        };
+        let result = test_string(r"^\w+$", "ab\ncé\nde", &options).unwrap();
+        let texts: Vec<&str> = result.matches.iter().map(|m| m.text.as_str()).collect();
+        assert_eq!(texts, vec!["ab", "de"]);
+    }
+
+    #[test]
+    fn test_no_unicode_rejects_dot_with_hint() {
+        let options = TestOptions {
+            no_unicode: true,
+            ..Default::default()
+        };
+        let err = test_string(r"a.b", "axb", &options).unwrap_err();
+        assert!(matches!(err, CoreError::InvalidPattern(_)));
+        assert!(err.to_string().contains("--no-unicode"));
+    }
+
+    #[test]
+    fn test_full_match() {
+        let options = TestOptions {
+            full_match: true,
+            ..Default::default()
+        };
+        let result = test_string(r"(\d{3})-(\d{4})", "555-1234", &options).unwrap();
+        assert!(result.matched);
+        assert!(result.full_match);
+        assert_eq!(result.match_count, 1);
+        assert_eq!(result.matches[0].captures[1].text, "1234");
+
+        // A partial match, or an alternation that only covers a prefix, does not count
+        assert!(!test_string(r"\d+", "123abc", &options).unwrap().matched);
+        assert!(!test_string(r"a|ab", "ab x", &options).unwrap().matched);
+        assert!(test_string(r"a|ab", "ab", &options).unwrap().matched);
+    }
+
+    #[test]
+    fn test_effective_pattern() {
+        let options = TestOptions {
+            multiline: true,
+            no_unicode: true,
+            full_match: true,
+            effective_pattern: true,
+            ..Default::default()
+        };
+        let result = test_string(r"\Qa.\E\w", "a.b", &options).unwrap();
+        assert_eq!(
+            result.effective_pattern.as_deref(),
+            Some(r"(?ms-u)\A(?:a\.\w)\z")
+        );
+        assert!(result.matched);
+
+        let result = test_string("a", "a", &TestOptions::default()).unwrap();
+        assert!(result.effective_pattern.is_none());
+    }
+
+    #[test]
+    fn test_full_match_ignores_line_anchors_in_multiline() {
+        let options = TestOptions {
+            full_match: true,
+            multiline: true,
+            ..Default::default()
+        };
+        assert!(!test_string(r"\w+", "abc\ndef", &options).unwrap().matched);
+        assert!(
+            test_string(r"\w+\n\w+", "abc\ndef", &options)
+                .unwrap()
+                .matched
+        );
+    }
+
+    #[test]
+    fn test_extended_pattern_composes_with_flags() {
+        let options = TestOptions {
+            multiline: true,
+            full_match: true,
+            ..Default::default()
+        };
+        let pattern = "(?x)\n  \\w+ \\n  # first line\n  \\w+   # second line";
+        assert!(test_string(pattern, "abc\ndef", &options).unwrap().matched);
+
+        let options = TestOptions {
+            multiline: true,
+            ..Default::default()
+        };
+        assert_eq!(apply_flags("(?x) a b", &options), "(?xms) a b");
+    }
+
+    #[test]
+    fn test_for_each_match_early_stop() {
+        let (compiled, _) = CompiledRegex::new(r"\d").unwrap();
+        let mut seen = Vec::new();
+        let count = for_each_match(&compiled, "1 2 3 4 5", usize::MAX, |m| {
+            seen.push(m.text.clone());
+            seen.len() < 2
+        })
+        .unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(seen, vec!["1", "2"]);
+
+        let (compiled, _) = CompiledRegex::new(r"(?<=x)(\d)").unwrap();
+        let count = for_each_match(&compiled, "x1 x2 x3", 2, |m| {
+            assert_eq!(m.captures.len(), 1);
+            true
+        })
+        .unwrap();
+        assert_eq!(count, 2);
+    }
+
+    fn spans(compiled: &CompiledRegex, text: &str) -> Vec<(usize, usize)> {
+        let mut spans = Vec::new();
+        for_each_match(compiled, text, usize::MAX, |m| {
+            spans.push((m.start, m.end));
+            true
+        })
+        .unwrap();
+        spans
+    }
+
+    #[test]
+    fn test_zero_width_matches_agree_across_engines() {
+        let cases = [
+            (r"^", "abc"),
+            (r"$", "abc"),
+            (r"^", ""),
+            (r"\b", "ab cd"),
+            (r"", "aé"),
+            (r"a*", "baaa"),
+            (r"x*(y)?", "xxayy"),
+        ];
+        for (pattern, text) in cases {
+            let standard = CompiledRegex::Regex(regex::Regex::new(pattern).unwrap());
+            let fancy = CompiledRegex::FancyRegex(fancy_regex::Regex::new(pattern).unwrap());
+            assert_eq!(
+                spans(&standard, text),
+                spans(&fancy, text),
+                "{pattern:?} on {text:?}"
+            );
+        }
+
+        let (compiled, _) = CompiledRegex::new(r"\b").unwrap();
+        assert_eq!(spans(&compiled, "ab cd"), [(0, 0), (2, 2), (3, 3), (5, 5)]);
+        let (compiled, _) = CompiledRegex::new(r"(?=x)").unwrap();
+        assert_eq!(spans(&compiled, "axbx"), [(1, 1), (3, 3)]);
+        let (compiled, _) = CompiledRegex::new(r"(?<=a)").unwrap();
+        assert_eq!(spans(&compiled, "aab"), [(1, 1), (2, 2)]);
+    }
+
+    #[test]
+    fn test_null_data_records() {
+        let options = TestOptions {
+            null_data: true,
+            ..Default::default()
+        };
+        let input = "foo\nbar\0baz\0foo";
+        let result = test_string(r"^\w+$", input, &options).unwrap();
+        // "foo\nbar" is a single record, so `^\w+$` cannot span the newline
+        assert_eq!(result.match_count, 2);
+        assert_eq!(result.matches[0].text, "baz");
+        assert_eq!(
+            &input[result.matches[1].start..result.matches[1].end],
+            "foo"
+        );
+        assert_eq!(result.matches[1].start, 12);
     }
 }
@@ -3,25 +3,131 @@
 //! Validates regex syntax and checks cross-language portability.
 
 use regex_syntax::ast;
-use regex_syntax::ast::parse::Parser as AstParser;
 
-use super::engine::{select_engine, try_fancy_regex, try_regex_crate};
-use super::portability::check_portability;
-use crate::output::{ValidateResult, ValidationError};
+use super::engine::{
+    capture_group_info, fallback_reason, is_nesting_error, nesting_error_message,
+    normalize_pattern, parse_ast, select_engine, try_fancy_regex, try_regex_crate, CoreError,
+    DEFAULT_MAX_DEPTH,
+};
+use super::explain::flag_settings;
+use super::portability::{
+    canonical_target, check_portability, detailed_portability, leftmost_longest_divergences,
+    target_issues, PatternFeatures, TARGETS,
+};
+use crate::output::{
+    CompareLanguagesResult, FlagState, Portability, TargetReport, ValidateResult, ValidationError,
+};
 
-/// Validate a regex pattern
-pub fn validate_pattern(pattern: &str) -> ValidateResult {
+/// Options for the validate command
+pub struct ValidateOptions {
+    /// Maximum pattern nesting depth accepted before compiling or walking the AST
+    pub max_depth: u32,
+    /// Report the effective inline flags (`active_flags`)
+    pub flags: bool,
+    /// Compiled program size limit in bytes (`None` keeps the regex crate default)
+    pub size_limit: Option<usize>,
+    /// Report the pattern exactly as handed to the engines (`effective_pattern`)
+    pub effective_pattern: bool,
+}
+
+impl Default for ValidateOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_MAX_DEPTH,
+            flags: false,
+            size_limit: None,
+            effective_pattern: false,
+        }
+    }
+}
+
+/// Reject patterns that can only match the empty string (``, `(?:)`, `(?i)`),
+/// which would otherwise "match" at every position of the input.
+///
+/// Checked by the commands that run a pattern over input (test, replace,
+/// apply). `validate` and `explain` accept empty patterns: they are valid syntax.
+pub fn validate_runnable_pattern(pattern: &str, allow_empty: bool) -> Result<(), CoreError> {
+    if allow_empty {
+        return Ok(());
+    }
+
+    let empty = match regex_syntax::parse(pattern) {
+        Ok(hir) => matches!(hir.kind(), regex_syntax::hir::HirKind::Empty),
+        Err(_) => pattern.is_empty(),
+    };
+
+    if empty {
+        Err(CoreError::EmptyPattern)
+    } else {
+        Ok(())
+    }
+}
+
+/// Reject patterns declaring more than `max_groups` capture groups, counted
+/// on the AST (see [`capture_group_info`]). Checked by the MCP server, where
+/// patterns come from a client and are not trusted.
+pub fn check_group_limit(pattern: &str, max_groups: usize) -> Result<(), CoreError> {
+    let groups = capture_group_info(pattern).len();
+    if groups > max_groups {
+        Err(CoreError::TooManyGroups {
+            groups,
+            limit: max_groups,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Validate a regex pattern.
+///
+/// The pattern is normalized first (see [`normalize_pattern`]); error
+/// positions refer to `normalized_pattern` when it is set.
+pub fn validate_pattern(pattern: &str, options: &ValidateOptions) -> ValidateResult {
+    let normalized = normalize_pattern(pattern);
+    let mut result = validate_normalized(normalized.as_deref().unwrap_or(pattern), options);
+    result.normalized_pattern = normalized;
+    result
+}
+
+/// [`validate_pattern`] for a pattern that is already normalized
+fn validate_normalized(pattern: &str, options: &ValidateOptions) -> ValidateResult {
     // First, try to parse with regex-syntax for detailed error messages
-    let ast_result = AstParser::new().parse(pattern);
+    let ast_result = parse_ast(pattern, options.max_depth);
+
+    // Reject overly nested patterns before handing them to either engine
+    if let Err(ref err) = ast_result {
+        if is_nesting_error(err) {
+            return ValidateResult {
+                valid: false,
+                error: Some(ValidationError {
+                    kind: "syntax_error".to_string(),
+                    position: Some(err.span().start.offset),
+                    message: nesting_error_message(options.max_depth),
+                }),
+                engine_required: None,
+                reason: None,
+                fallback_occurred: false,
+                portability: None,
+                detailed_portability: None,
+                capture_groups: Vec::new(),
+                targets: Vec::new(),
+                warnings: Vec::new(),
+                active_flags: None,
+                normalized_pattern: None,
+                effective_pattern: options.effective_pattern.then(|| pattern.to_string()),
+                suggestion: Some("Flatten nested groups or remove redundant grouping".to_string()),
+            };
+        }
+    }
 
     // Check if it's valid with standard regex
-    let regex_result = try_regex_crate(pattern);
+    let regex_result = try_regex_crate(pattern, options.size_limit);
 
     // Check if it's valid with fancy-regex
-    let fancy_result = try_fancy_regex(pattern);
+    let fancy_result = try_fancy_regex(pattern, options.size_limit);
 
     // Determine validity and errors
-    match (&regex_result, &fancy_result) {
+    let mut result = match (&regex_result, &fancy_result) {
         (Ok(_), _) => {
             // Valid with standard regex
             let portability = check_portability(pattern);
@@ -31,27 +137,91 @@ pub fn validate_pattern(pattern: &str) -> ValidateResult {
                 error: None,
                 engine_required: Some("regex".to_string()),
                 reason: None,
+                fallback_occurred: false,
                 portability: Some(portability),
+                detailed_portability: None,
+                capture_groups: capture_group_info(pattern),
+                targets: Vec::new(),
+                warnings: ast_result
+                    .as_ref()
+                    .map(|ast| {
+                        let mut warnings = anchoring_warnings(pattern, ast);
+                        warnings.extend(dubious_construct_warnings(pattern, ast));
+                        warnings.extend(unicode_property_notes(pattern, ast));
+                        warnings
+                    })
+                    .unwrap_or_default(),
+                active_flags: ast_result
+                    .as_ref()
+                    .ok()
+                    .filter(|_| options.flags)
+                    .map(active_flags),
+                normalized_pattern: None,
+                effective_pattern: None,
                 suggestion: None,
             }
         }
-        (Err(_), Ok(_)) => {
+        (Err(regex_err), Ok(_)) => {
             // Only valid with fancy-regex
             let (_, features) = select_engine(pattern);
             let portability = check_portability(pattern);
+            let fallback = !features.needs_fancy();
+            let mut warnings = oniguruma_escape_hints(pattern);
+            // The engine accepted every reference, so only the suspicious ones remain
+            warnings.extend(
+                backreference_problems(pattern)
+                    .into_iter()
+                    .filter(|p| !p.invalid)
+                    .map(|p| p.message),
+            );
 
             ValidateResult {
                 valid: true,
                 error: None,
                 engine_required: Some("fancy-regex".to_string()),
-                reason: features.reason(),
+                reason: features
+                    .reason()
+                    .or_else(|| fallback.then(|| fallback_reason(regex_err))),
+                fallback_occurred: fallback,
                 portability: Some(portability),
+                detailed_portability: None,
+                capture_groups: capture_group_info(pattern),
+                targets: Vec::new(),
+                warnings,
+                active_flags: None,
+                normalized_pattern: None,
+                effective_pattern: None,
                 suggestion: None,
             }
         }
         (Err(regex_err), Err(fancy_err)) => {
             // Invalid with both engines
-            let (error, suggestion) = if let Err(ast_err) = ast_result {
+            let (error, suggestion) = if let Some(limit) = exceeded_size_limit(regex_err, fancy_err)
+            {
+                // Checked first: a pattern only fancy-regex can parse still
+                // fails the AST parse, which would hide the real cause
+                size_limit_error(regex_err, limit)
+            } else if let Some(problem) = backreference_problems(pattern)
+                .into_iter()
+                .find(|p| p.invalid)
+            {
+                // regex-syntax only says "backreferences are not supported"
+                (
+                    ValidationError {
+                        kind: "invalid_backreference".to_string(),
+                        position: Some(problem.position),
+                        message: problem.message,
+                    },
+                    problem.suggestion,
+                )
+            } else if let Some(problem) = ast_result
+                .as_ref()
+                .ok()
+                .and_then(|ast| unicode_property_error(pattern, ast))
+            {
+                // The AST parser accepts any property name; only translation checks it
+                problem
+            } else if let Err(ast_err) = ast_result {
                 // Use AST parser error for better messages
                 parse_ast_error(&ast_err)
             } else {
@@ -59,40 +229,1006 @@ pub fn validate_pattern(pattern: &str) -> ValidateResult {
                 parse_regex_error(regex_err, fancy_err)
             };
 
+            // Escapes copied from Ruby/PCRE deserve a more useful hint than
+            // "unrecognized escape sequence"
+            let hints = oniguruma_escape_hints(pattern);
+            let suggestion = if hints.is_empty() {
+                suggestion
+            } else {
+                Some(hints.join("; "))
+            };
+
             ValidateResult {
                 valid: false,
                 error: Some(error),
                 engine_required: None,
                 reason: None,
+                fallback_occurred: false,
                 portability: None,
+                detailed_portability: None,
+                capture_groups: Vec::new(),
+                targets: Vec::new(),
+                warnings: Vec::new(),
+                active_flags: None,
+                normalized_pattern: None,
+                effective_pattern: None,
                 suggestion,
             }
         }
+    };
+    // --extended and --pattern-file are applied before the pattern gets here,
+    // and the engines compile it as is
+    result.effective_pattern = options.effective_pattern.then(|| pattern.to_string());
+    result
+}
+
+/// Warn about a top-level alternation mixed with anchors, e.g. `^foo|bar$`,
+/// where the anchors bind to single branches rather than the whole alternation.
+fn anchoring_warnings(pattern: &str, ast: &ast::Ast) -> Vec<String> {
+    use ast::{AssertionKind, Ast};
+
+    let ast::Ast::Alternation(alt) = ast else {
+        return Vec::new();
+    };
+
+    fn items(branch: &Ast) -> &[Ast] {
+        match branch {
+            Ast::Concat(concat) => &concat.asts,
+            other => std::slice::from_ref(other),
+        }
+    }
+    let start_anchor = |branch: &Ast| {
+        items(branch)
+            .iter()
+            .find(|a| !matches!(a, Ast::Flags(_)))
+            .and_then(|a| match a {
+                Ast::Assertion(x)
+                    if matches!(x.kind, AssertionKind::StartLine | AssertionKind::StartText) =>
+                {
+                    Some(x.span)
+                }
+                _ => None,
+            })
+    };
+    let end_anchor = |branch: &Ast| {
+        items(branch).last().and_then(|a| match a {
+            Ast::Assertion(x)
+                if matches!(x.kind, AssertionKind::EndLine | AssertionKind::EndText) =>
+            {
+                Some(x.span)
+            }
+            _ => None,
+        })
+    };
+
+    let starts: Vec<_> = alt.asts.iter().map(start_anchor).collect();
+    let ends: Vec<_> = alt.asts.iter().map(end_anchor).collect();
+    let any_anchor = starts.iter().chain(&ends).any(Option::is_some);
+    let consistent = starts.iter().all(|s| s.is_some() == starts[0].is_some())
+        && ends.iter().all(|e| e.is_some() == ends[0].is_some());
+    if !any_anchor || consistent {
+        return Vec::new();
+    }
+
+    // Strip the anchors from each branch and re-apply them around a group
+    let start_text = starts
+        .iter()
+        .flatten()
+        .next()
+        .map(|s| &pattern[s.start.offset..s.end.offset]);
+    let end_text = ends
+        .iter()
+        .flatten()
+        .next()
+        .map(|e| &pattern[e.start.offset..e.end.offset]);
+    // Flags ahead of a start anchor stay ahead of it: those of the first
+    // branch lead the suggestion, later branches keep theirs inside the group
+    let flags_text = |branch: &Ast, start: &Option<ast::Span>| {
+        start.map_or("", |s| &pattern[branch.span().start.offset..s.start.offset])
+    };
+    let leading_flags = flags_text(&alt.asts[0], &starts[0]);
+    let branches: Vec<String> = alt
+        .asts
+        .iter()
+        .zip(starts.iter().zip(&ends))
+        .enumerate()
+        .map(|(i, (branch, (start, end)))| {
+            let span = branch.span();
+            let from = start.map_or(span.start.offset, |s| s.end.offset);
+            let to = end.map_or(span.end.offset, |e| e.start.offset);
+            let flags = if i == 0 {
+                ""
+            } else {
+                flags_text(branch, start)
+            };
+            format!("{}{}", flags, &pattern[from..to.max(from)])
+        })
+        .collect();
+    let suggestion = format!(
+        "{}{}(?:{}){}",
+        leading_flags,
+        start_text.unwrap_or(""),
+        branches.join("|"),
+        end_text.unwrap_or("")
+    );
+
+    vec![format!(
+        "Anchors apply per alternation branch: `{}` matches each branch with its own anchors, \
+         not the whole alternation. To anchor every branch use `{}`",
+        pattern, suggestion
+    )]
+}
+
+/// Flag constructs that compile but rarely do what the author meant: parts
+/// that can only match the empty string (`[a-z]{0}`, `(?:)`), alternation
+/// branches repeating an earlier one, and quantifiers that change nothing (`x{1,1}`)
+fn dubious_construct_warnings(pattern: &str, ast: &ast::Ast) -> Vec<String> {
+    use ast::{Ast, RepetitionKind, RepetitionRange};
+
+    fn walk(pattern: &str, ast: &Ast, warnings: &mut Vec<String>) {
+        let text = |span: &ast::Span| &pattern[span.start.offset..span.end.offset];
+        match ast {
+            Ast::Repetition(rep) => {
+                match rep.op.kind {
+                    RepetitionKind::Range(RepetitionRange::Exactly(0))
+                    | RepetitionKind::Range(RepetitionRange::Bounded(_, 0)) => {
+                        warnings.push(format!(
+                        "`{}` repeats zero times and always matches the empty string; remove it",
+                        text(&rep.span)
+                    ))
+                    }
+                    RepetitionKind::Range(RepetitionRange::Exactly(1))
+                    | RepetitionKind::Range(RepetitionRange::Bounded(1, 1)) => {
+                        warnings.push(format!(
+                        "`{}` is quantified to exactly one repetition; `{}` alone is equivalent",
+                        text(&rep.span),
+                        text(rep.ast.span())
+                    ))
+                    }
+                    _ => {}
+                }
+                walk(pattern, &rep.ast, warnings);
+            }
+            Ast::Group(group) => {
+                if matches!(*group.ast, Ast::Empty(_)) {
+                    warnings.push(format!(
+                        "Empty group `{}` only matches the empty string",
+                        text(&group.span)
+                    ));
+                }
+                walk(pattern, &group.ast, warnings);
+            }
+            Ast::Alternation(alt) => {
+                for (i, branch) in alt.asts.iter().enumerate() {
+                    let branch_text = text(branch.span());
+                    if alt.asts[..i].iter().any(|b| text(b.span()) == branch_text) {
+                        warnings.push(format!(
+                            "Alternation branch `{}` repeats an earlier branch and can never be \
+                             the one that matches; remove the duplicate",
+                            branch_text
+                        ));
+                    }
+                }
+                for branch in &alt.asts {
+                    walk(pattern, branch, warnings);
+                }
+            }
+            Ast::Concat(concat) => {
+                for item in &concat.asts {
+                    walk(pattern, item, warnings);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut warnings = Vec::new();
+    walk(pattern, ast, &mut warnings);
+    warnings
+}
+
+/// Unicode property names the regex crate accepts in `\p{name=value}`
+const UNICODE_PROPERTY_NAMES: &[&str] = &[
+    "General_Category",
+    "Script",
+    "Script_Extensions",
+    "Age",
+    "gc",
+    "sc",
+    "scx",
+];
+
+/// Long names of the Unicode general categories
+const UNICODE_CATEGORIES: &[&str] = &[
+    "Letter",
+    "Cased_Letter",
+    "Uppercase_Letter",
+    "Lowercase_Letter",
+    "Titlecase_Letter",
+    "Modifier_Letter",
+    "Other_Letter",
+    "Mark",
+    "Nonspacing_Mark",
+    "Spacing_Mark",
+    "Enclosing_Mark",
+    "Number",
+    "Decimal_Number",
+    "Letter_Number",
+    "Other_Number",
+    "Punctuation",
+    "Connector_Punctuation",
+    "Dash_Punctuation",
+    "Open_Punctuation",
+    "Close_Punctuation",
+    "Initial_Punctuation",
+    "Final_Punctuation",
+    "Other_Punctuation",
+    "Symbol",
+    "Math_Symbol",
+    "Currency_Symbol",
+    "Modifier_Symbol",
+    "Other_Symbol",
+    "Separator",
+    "Space_Separator",
+    "Line_Separator",
+    "Paragraph_Separator",
+    "Other",
+    "Control",
+    "Format",
+    "Private_Use",
+    "Unassigned",
+];
+
+/// Long names of the Unicode scripts
+const UNICODE_SCRIPTS: &[&str] = &[
+    "Adlam",
+    "Ahom",
+    "Anatolian_Hieroglyphs",
+    "Arabic",
+    "Armenian",
+    "Avestan",
+    "Balinese",
+    "Bamum",
+    "Bassa_Vah",
+    "Batak",
+    "Bengali",
+    "Bhaiksuki",
+    "Bopomofo",
+    "Brahmi",
+    "Braille",
+    "Buginese",
+    "Buhid",
+    "Canadian_Aboriginal",
+    "Carian",
+    "Caucasian_Albanian",
+    "Chakma",
+    "Cham",
+    "Cherokee",
+    "Chorasmian",
+    "Common",
+    "Coptic",
+    "Cuneiform",
+    "Cypriot",
+    "Cypro_Minoan",
+    "Cyrillic",
+    "Deseret",
+    "Devanagari",
+    "Dives_Akuru",
+    "Dogra",
+    "Duployan",
+    "Egyptian_Hieroglyphs",
+    "Elbasan",
+    "Elymaic",
+    "Ethiopic",
+    "Georgian",
+    "Glagolitic",
+    "Gothic",
+    "Grantha",
+    "Greek",
+    "Gujarati",
+    "Gunjala_Gondi",
+    "Gurmukhi",
+    "Han",
+    "Hangul",
+    "Hanifi_Rohingya",
+    "Hanunoo",
+    "Hatran",
+    "Hebrew",
+    "Hiragana",
+    "Imperial_Aramaic",
+    "Inherited",
+    "Inscriptional_Pahlavi",
+    "Inscriptional_Parthian",
+    "Javanese",
+    "Kaithi",
+    "Kannada",
+    "Katakana",
+    "Kawi",
+    "Kayah_Li",
+    "Kharoshthi",
+    "Khitan_Small_Script",
+    "Khmer",
+    "Khojki",
+    "Khudawadi",
+    "Lao",
+    "Latin",
+    "Lepcha",
+    "Limbu",
+    "Linear_A",
+    "Linear_B",
+    "Lisu",
+    "Lycian",
+    "Lydian",
+    "Mahajani",
+    "Makasar",
+    "Malayalam",
+    "Mandaic",
+    "Manichaean",
+    "Marchen",
+    "Masaram_Gondi",
+    "Medefaidrin",
+    "Meetei_Mayek",
+    "Mende_Kikakui",
+    "Meroitic_Cursive",
+    "Meroitic_Hieroglyphs",
+    "Miao",
+    "Modi",
+    "Mongolian",
+    "Mro",
+    "Multani",
+    "Myanmar",
+    "Nabataean",
+    "Nag_Mundari",
+    "Nandinagari",
+    "New_Tai_Lue",
+    "Newa",
+    "Nko",
+    "Nushu",
+    "Nyiakeng_Puachue_Hmong",
+    "Ogham",
+    "Ol_Chiki",
+    "Old_Hungarian",
+    "Old_Italic",
+    "Old_North_Arabian",
+    "Old_Permic",
+    "Old_Persian",
+    "Old_Sogdian",
+    "Old_South_Arabian",
+    "Old_Turkic",
+    "Old_Uyghur",
+    "Oriya",
+    "Osage",
+    "Osmanya",
+    "Pahawh_Hmong",
+    "Palmyrene",
+    "Pau_Cin_Hau",
+    "Phags_Pa",
+    "Phoenician",
+    "Psalter_Pahlavi",
+    "Rejang",
+    "Runic",
+    "Samaritan",
+    "Saurashtra",
+    "Sharada",
+    "Shavian",
+    "Siddham",
+    "SignWriting",
+    "Sinhala",
+    "Sogdian",
+    "Sora_Sompeng",
+    "Soyombo",
+    "Sundanese",
+    "Syloti_Nagri",
+    "Syriac",
+    "Tagalog",
+    "Tagbanwa",
+    "Tai_Le",
+    "Tai_Tham",
+    "Tai_Viet",
+    "Takri",
+    "Tamil",
+    "Tangsa",
+    "Tangut",
+    "Telugu",
+    "Thaana",
+    "Thai",
+    "Tibetan",
+    "Tifinagh",
+    "Tirhuta",
+    "Toto",
+    "Ugaritic",
+    "Vai",
+    "Vithkuqi",
+    "Wancho",
+    "Warang_Citi",
+    "Yezidi",
+    "Yi",
+    "Zanabazar_Square",
+];
+
+/// Common binary properties, offered for typos in a bare `\p{...}` name
+const UNICODE_BINARY_PROPERTIES: &[&str] = &[
+    "Alphabetic",
+    "Any",
+    "ASCII",
+    "ASCII_Hex_Digit",
+    "Assigned",
+    "Dash",
+    "Diacritic",
+    "Emoji",
+    "Emoji_Presentation",
+    "Extended_Pictographic",
+    "Hex_Digit",
+    "Ideographic",
+    "Lowercase",
+    "Math",
+    "Uppercase",
+    "White_Space",
+];
+
+/// Every `\p{...}` / `\P{...}` class in the AST, including those inside brackets
+fn unicode_classes(ast: &ast::Ast) -> Vec<&ast::ClassUnicode> {
+    use ast::{Ast, ClassSet, ClassSetItem};
+
+    fn walk_set<'a>(set: &'a ClassSet, out: &mut Vec<&'a ast::ClassUnicode>) {
+        match set {
+            ClassSet::Item(item) => walk_item(item, out),
+            ClassSet::BinaryOp(op) => {
+                walk_set(&op.lhs, out);
+                walk_set(&op.rhs, out);
+            }
+        }
+    }
+
+    fn walk_item<'a>(item: &'a ClassSetItem, out: &mut Vec<&'a ast::ClassUnicode>) {
+        match item {
+            ClassSetItem::Unicode(class) => out.push(class),
+            ClassSetItem::Bracketed(class) => walk_set(&class.kind, out),
+            ClassSetItem::Union(union) => union.items.iter().for_each(|i| walk_item(i, out)),
+            _ => {}
+        }
+    }
+
+    fn walk<'a>(ast: &'a Ast, out: &mut Vec<&'a ast::ClassUnicode>) {
+        match ast {
+            Ast::ClassUnicode(class) => out.push(class),
+            Ast::ClassBracketed(class) => walk_set(&class.kind, out),
+            Ast::Repetition(rep) => walk(&rep.ast, out),
+            Ast::Group(group) => walk(&group.ast, out),
+            Ast::Concat(concat) => concat.asts.iter().for_each(|a| walk(a, out)),
+            Ast::Alternation(alt) => alt.asts.iter().for_each(|a| walk(a, out)),
+            _ => {}
+        }
+    }
+
+    let mut out = Vec::new();
+    walk(ast, &mut out);
+    out
+}
+
+/// A property name or value the way the regex crate compares them:
+/// case-insensitive, ignoring spaces, `_` and `-`
+fn loose_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, ' ' | '_' | '-'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Levenshtein distance between two strings, counted in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitute = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitute.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// The candidate closest to `name` under loose matching, if it is close
+/// enough to be a likely typo
+fn closest_name<'a>(name: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    let name = loose_name(name);
+    let max_distance = ((name.chars().count() + 1) / 3).max(1);
+    candidates
+        .iter()
+        .map(|candidate| (edit_distance(&name, &loose_name(candidate)), *candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Whether a bare `\p{name}` is a script (as opposed to a general category
+/// or binary property), which JavaScript only accepts as `\p{Script=name}`
+fn is_script_name(name: &str) -> bool {
+    let parses = |class: String| regex_syntax::Parser::new().parse(&class).is_ok();
+    parses(format!(r"\p{{Script={}}}", name)) && !parses(format!(r"\p{{gc={}}}", name))
+}
+
+/// Check each `\p{...}` class against the regex crate's Unicode tables and
+/// report the first one it does not know, suggesting the closest known
+/// property or value (e.g. `\p{Script=Gerek}` -> `\p{Script=Greek}`)
+fn unicode_property_error(
+    pattern: &str,
+    ast: &ast::Ast,
+) -> Option<(ValidationError, Option<String>)> {
+    use ast::ClassUnicodeKind;
+    use regex_syntax::hir::ErrorKind;
+
+    unicode_classes(ast).into_iter().find_map(|class| {
+        let token = &pattern[class.span.start.offset..class.span.end.offset];
+        let kind = match regex_syntax::Parser::new().parse(token) {
+            Err(regex_syntax::Error::Translate(err)) => err.kind().clone(),
+            _ => return None,
+        };
+        // Swap the misspelled part of the token (starting at byte `at`) for
+        // the closest known name
+        let did_you_mean = |at: Option<usize>, wrong: &str, right: Option<&str>| {
+            let at = at?;
+            right.map(|right| {
+                format!(
+                    "Did you mean `{}{}{}`?",
+                    &token[..at],
+                    right,
+                    &token[at + wrong.len()..]
+                )
+            })
+        };
+
+        let (message, suggestion) = match (&kind, &class.kind) {
+            (ErrorKind::UnicodePropertyNotFound, ClassUnicodeKind::OneLetter(c)) => (
+                format!("Unknown one-letter Unicode category `{}` in `{}`", c, token),
+                Some(
+                    "One-letter categories are L (letter), M (mark), N (number), \
+                     P (punctuation), S (symbol), Z (separator) and C (other)"
+                        .to_string(),
+                ),
+            ),
+            (ErrorKind::UnicodePropertyNotFound, ClassUnicodeKind::Named(name)) => {
+                let candidates = [UNICODE_SCRIPTS, UNICODE_CATEGORIES, UNICODE_BINARY_PROPERTIES]
+                    .concat();
+                (
+                    format!("Unknown Unicode property `{}` in `{}`", name, token),
+                    did_you_mean(token.find(name.as_str()), name, closest_name(name, &candidates)),
+                )
+            }
+            (ErrorKind::UnicodePropertyNotFound, ClassUnicodeKind::NamedValue { name, .. }) => (
+                format!("Unknown Unicode property `{}` in `{}`", name, token),
+                did_you_mean(
+                    token.find(name.as_str()),
+                    name,
+                    closest_name(name, UNICODE_PROPERTY_NAMES),
+                ),
+            ),
+            (
+                ErrorKind::UnicodePropertyValueNotFound,
+                ClassUnicodeKind::NamedValue { name, value, .. },
+            ) => {
+                let at = token.rfind(value.as_str());
+                match loose_name(name).as_str() {
+                    // regex-syntax knows the property but has no tables for it
+                    "block" | "blk" => (
+                        format!(
+                            "The Rust regex crate does not support the Unicode Block property (`{}`)",
+                            token
+                        ),
+                        Some(
+                            "Match the block's code point range instead, e.g. \
+                             `[\\x{0370}-\\x{03FF}]`, or use `\\p{Script=...}`"
+                                .to_string(),
+                        ),
+                    ),
+                    loose => (
+                        format!(
+                            "Unknown value `{}` for Unicode property `{}` in `{}`",
+                            value, name, token
+                        ),
+                        match loose {
+                            "script" | "sc" | "scriptextensions" | "scx" => {
+                                did_you_mean(at, value, closest_name(value, UNICODE_SCRIPTS))
+                            }
+                            "generalcategory" | "gc" => {
+                                did_you_mean(at, value, closest_name(value, UNICODE_CATEGORIES))
+                            }
+                            "age" => Some(
+                                "Age values are Unicode versions, e.g. `\\p{Age=6.0}`".to_string(),
+                            ),
+                            _ => None,
+                        },
+                    ),
+                }
+            }
+            _ => return None,
+        };
+
+        Some((
+            ValidationError {
+                kind: "unknown_unicode_property".to_string(),
+                position: Some(class.span.start.offset),
+                message,
+            },
+            suggestion,
+        ))
+    })
+}
+
+/// Portability notes for Unicode property classes whose spelling differs
+/// between engines: the `\p{name=value}` form and bare script names like `\p{Greek}`
+fn unicode_property_notes(pattern: &str, ast: &ast::Ast) -> Vec<String> {
+    use ast::ClassUnicodeKind;
+
+    let mut notes: Vec<String> = Vec::new();
+    for class in unicode_classes(ast) {
+        let token = &pattern[class.span.start.offset..class.span.end.offset];
+        let note = match &class.kind {
+            ClassUnicodeKind::NamedValue { name, .. } => match loose_name(name).as_str() {
+                "generalcategory" | "gc" | "script" | "sc" | "scriptextensions" | "scx" => {
+                    format!(
+                        "`{}` uses the name=value form: JavaScript needs the `u` flag for it, \
+                         while Go's RE2 and .NET do not support it (RE2 only knows bare names \
+                         like `\\p{{Greek}}` and `\\p{{Lu}}`)",
+                        token
+                    )
+                }
+                _ => format!(
+                    "`{}` uses the `{}` property, which JavaScript, Go's RE2 and .NET do not support",
+                    token, name
+                ),
+            },
+            ClassUnicodeKind::Named(name) if is_script_name(name) => format!(
+                "`{}` is a script name: JavaScript requires the `\\p{{Script={}}}` form with the `u` flag",
+                token, name
+            ),
+            _ => continue,
+        };
+        if !notes.contains(&note) {
+            notes.push(note);
+        }
+    }
+    notes
+}
+
+/// Explain Ruby/PCRE escapes the Rust `regex` crate rejects, with a portable rewrite
+fn oniguruma_escape_hints(pattern: &str) -> Vec<String> {
+    let features = PatternFeatures::analyze(pattern);
+    let mut hints = Vec::new();
+    if features.hex_digit_escape {
+        hints.push(
+            "`\\h` means hex digit in Ruby (`\\H` is its negation) but horizontal whitespace in PCRE/Java; \
+             use `[0-9a-fA-F]` (or `[^0-9a-fA-F]`) for the Rust regex crate"
+                .to_string(),
+        );
+    }
+    if features.linebreak_escape {
+        hints.push(
+            "`\\R` means any line break; use `(?:\\r\\n|[\\n\\x0B\\x0C\\r\\x{85}\\x{2028}\\x{2029}])` for the Rust regex crate"
+                .to_string(),
+        );
+    }
+    hints
+}
+
+/// A backreference that cannot work as written
+struct BackrefProblem {
+    /// Byte offset of the reference in the pattern
+    position: usize,
+    message: String,
+    suggestion: Option<String>,
+    /// The referenced group does not exist, so no engine compiles the pattern
+    invalid: bool,
+}
+
+/// Cross-check the backreferences of a pattern (`\N`, `\k<name>`, `(?P=name)`)
+/// against its capture groups. A reference to a group that does not exist is
+/// invalid; one that comes before or inside its group is flagged because the
+/// group has not captured anything yet at that point.
+fn backreference_problems(pattern: &str) -> Vec<BackrefProblem> {
+    enum Target {
+        Number(usize),
+        Name(String),
+    }
+    struct Group {
+        name: Option<String>,
+        open: usize,
+        close: Option<usize>,
+    }
+
+    let bytes = pattern.as_bytes();
+    // Index of the byte that ends a group name starting at `from`
+    let name_end = |from: usize, close: u8| {
+        bytes[from..]
+            .iter()
+            .position(|&b| b == close)
+            .map(|n| from + n)
+    };
+
+    let mut groups: Vec<Group> = Vec::new();
+    let mut refs: Vec<(usize, usize, Target)> = Vec::new();
+    let mut open_groups: Vec<Option<usize>> = Vec::new();
+    let mut non_capturing = 0;
+    let mut class_depth = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if class_depth == 0 && matches!(bytes.get(i + 1), Some(b'1'..=b'9')) => {
+                let end = i
+                    + 1
+                    + bytes[i + 1..]
+                        .iter()
+                        .take_while(|b| b.is_ascii_digit())
+                        .count();
+                if let Ok(n) = pattern[i + 1..end].parse() {
+                    refs.push((i, end, Target::Number(n)));
+                }
+                i = end;
+                continue;
+            }
+            b'\\' if class_depth == 0 && bytes.get(i + 1) == Some(&b'k') => {
+                let close = match bytes.get(i + 2) {
+                    Some(b'<') => Some(b'>'),
+                    Some(b'\'') => Some(b'\''),
+                    Some(b'{') => Some(b'}'),
+                    _ => None,
+                };
+                if let Some(end) = close.and_then(|c| name_end(i + 3, c)) {
+                    refs.push((i, end + 1, Target::Name(pattern[i + 3..end].to_string())));
+                    i = end + 1;
+                    continue;
+                }
+                i += 2;
+                continue;
+            }
+            b'\\' => i += 1,
+            b'[' => {
+                class_depth += 1;
+                // A `]` right after the opening bracket (or `[^`) is a literal
+                if bytes.get(i + 1) == Some(&b'^') {
+                    i += 1;
+                }
+                if bytes.get(i + 1) == Some(&b']') {
+                    i += 1;
+                }
+            }
+            b']' if class_depth > 0 => class_depth -= 1,
+            b'(' if class_depth == 0 => {
+                let rest = &pattern[i + 1..];
+                let name = if let Some(after) = rest.strip_prefix("?P=") {
+                    if let Some(end) = name_end(i + 4, b')') {
+                        refs.push((i, end + 1, Target::Name(after[..end - i - 4].to_string())));
+                        i = end + 1;
+                        continue;
+                    }
+                    None
+                } else if rest.starts_with("?P<")
+                    || rest.starts_with("?'")
+                    || (rest.starts_with("?<")
+                        && !rest.starts_with("?<=")
+                        && !rest.starts_with("?<!"))
+                {
+                    let from = i + if rest.starts_with("?P<") { 4 } else { 3 };
+                    let close = if rest.starts_with("?'") { b'\'' } else { b'>' };
+                    name_end(from, close).map(|end| Some(pattern[from..end].to_string()))
+                } else if rest.starts_with('?') {
+                    if rest.starts_with("?:") {
+                        non_capturing += 1;
+                    }
+                    None
+                } else {
+                    Some(None)
+                };
+                open_groups.push(name.map(|name| {
+                    groups.push(Group {
+                        name,
+                        open: i,
+                        close: None,
+                    });
+                    groups.len() - 1
+                }));
+            }
+            b')' if class_depth == 0 => {
+                if let Some(Some(index)) = open_groups.pop() {
+                    groups[index].close = Some(i);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    let mut problems = Vec::new();
+    for (position, end, target) in refs {
+        let text = &pattern[position..end];
+        let found = match &target {
+            Target::Number(n) => groups.get(n - 1).map(|g| (g, n.to_string())),
+            Target::Name(name) => groups
+                .iter()
+                .find(|g| g.name.as_deref() == Some(name.as_str()))
+                .map(|g| (g, format!("`{}`", name))),
+        };
+
+        let Some((group, label)) = found else {
+            let (message, hints) = match target {
+                Target::Number(n) => {
+                    let have = match groups.len() {
+                        0 => "the pattern has no capture groups".to_string(),
+                        1 => "the pattern has only 1 capture group".to_string(),
+                        count => format!("the pattern has only {} capture groups", count),
+                    };
+                    let mut hints = Vec::new();
+                    let (first, rest) = text[1..].split_at(1);
+                    if !rest.is_empty() && first.parse().is_ok_and(|d: usize| d <= groups.len()) {
+                        hints.push(format!(
+                            "`{}` means group {}, so write `\\{}(?:{})` for group {} followed by `{}`",
+                            text, n, first, rest, first, rest
+                        ));
+                    }
+                    if non_capturing > 0 {
+                        hints.push(
+                            "non-capturing groups `(?:...)` are not numbered, so make the group you \
+                             meant capturing"
+                                .to_string(),
+                        );
+                    }
+                    hints.push(match groups.len() {
+                        0 => {
+                            "wrap the text to repeat in a capture group, e.g. `(ab)\\1`".to_string()
+                        }
+                        1 => "refer to the existing group as `\\1`".to_string(),
+                        count => format!("refer to an existing group, `\\1` to `\\{}`", count),
+                    });
+                    (
+                        format!(
+                            "Backreference `{}` refers to group {}, but {}",
+                            text, n, have
+                        ),
+                        hints,
+                    )
+                }
+                Target::Name(name) => {
+                    let names: Vec<String> = groups
+                        .iter()
+                        .filter_map(|g| g.name.as_ref())
+                        .map(|name| format!("`{}`", name))
+                        .collect();
+                    let hint = if names.is_empty() {
+                        "name the group to repeat, e.g. `(?<word>\\w+) \\k<word>`".to_string()
+                    } else {
+                        format!("use one of the defined names: {}", names.join(", "))
+                    };
+                    (
+                        format!(
+                            "Backreference `{}` refers to a group named `{}`, which the pattern \
+                             does not define",
+                            text, name
+                        ),
+                        vec![hint],
+                    )
+                }
+            };
+            let mut suggestion = hints.join("; ");
+            suggestion[..1].make_ascii_uppercase();
+            problems.push(BackrefProblem {
+                position,
+                message,
+                suggestion: Some(suggestion),
+                invalid: true,
+            });
+            continue;
+        };
+
+        let message = if position < group.open {
+            format!(
+                "Backreference `{}` comes before group {}, which has not captured anything \
+                 yet: most engines fail to match it (JavaScript matches it \
+                 as empty) unless a repetition sets the group on an earlier pass",
+                text, label
+            )
+        } else if group.close.is_none_or(|close| position < close) {
+            format!(
+                "Backreference `{}` is inside group {}, which has not finished capturing \
+                 yet: most engines fail to match it unless a repetition sets the \
+                 group on an earlier pass",
+                text, label
+            )
+        } else {
+            continue;
+        };
+        problems.push(BackrefProblem {
+            position,
+            message,
+            suggestion: None,
+            invalid: false,
+        });
+    }
+    problems
+}
+
+/// Fold every inline flag directive in the AST, in pattern order, into the
+/// flag state the pattern ends up with. Directives inside a group (and the
+/// flags of a `(?flags:...)` group) only last until the group closes, so
+/// they mark the state as `mid_pattern` without changing it.
+fn active_flags(ast: &ast::Ast) -> FlagState {
+    fn apply(state: &mut FlagState, flags: &ast::Flags) {
+        for (flag, enabled) in flag_settings(flags) {
+            let field = match flag {
+                ast::Flag::CaseInsensitive => &mut state.case_insensitive,
+                ast::Flag::MultiLine => &mut state.multiline,
+                ast::Flag::DotMatchesNewLine => &mut state.dot_matches_newline,
+                ast::Flag::SwapGreed => &mut state.swap_greed,
+                ast::Flag::Unicode => &mut state.unicode,
+                ast::Flag::IgnoreWhitespace => &mut state.ignore_whitespace,
+                ast::Flag::CRLF => &mut state.crlf,
+            };
+            *field = enabled;
+        }
+    }
+
+    fn walk(ast: &ast::Ast, state: &mut FlagState, depth: usize) {
+        use ast::{Ast, GroupKind};
+
+        match ast {
+            Ast::Flags(f) => {
+                if depth > 0 || f.span.start.offset > 0 {
+                    state.mid_pattern = true;
+                }
+                apply(state, &f.flags);
+            }
+            Ast::Group(g) => {
+                let mut inner = state.clone();
+                if let GroupKind::NonCapturing(flags) = &g.kind {
+                    if !flags.items.is_empty() {
+                        inner.mid_pattern = true;
+                        apply(&mut inner, flags);
+                    }
+                }
+                walk(&g.ast, &mut inner, depth + 1);
+                state.mid_pattern |= inner.mid_pattern;
+            }
+            Ast::Concat(c) => {
+                for child in &c.asts {
+                    walk(child, state, depth);
+                }
+            }
+            Ast::Alternation(a) => {
+                for child in &a.asts {
+                    walk(child, state, depth + 1);
+                }
+            }
+            Ast::Repetition(r) => walk(&r.ast, state, depth + 1),
+            _ => {}
+        }
     }
+
+    let mut state = FlagState::default();
+    walk(ast, &mut state, 0);
+    state
 }
 
 /// Validate a pattern for a specific target language
-pub fn validate_for_language(pattern: &str, target: &str) -> ValidateResult {
-    let mut result = validate_pattern(pattern);
+///
+/// For the POSIX targets this also warns about alternations that match
+/// differently under leftmost-longest semantics (e.g. `(a|ab)+`).
+pub fn validate_for_language(
+    pattern: &str,
+    target: &str,
+    options: &ValidateOptions,
+) -> ValidateResult {
+    let mut result = validate_pattern(pattern, options);
+    let normalized = result.normalized_pattern.clone();
+    let pattern = normalized.as_deref().unwrap_or(pattern);
 
     if result.valid {
         let Some(portability) = result.portability.as_ref() else {
             return result;
         };
-        let compatible = match target.to_lowercase().as_str() {
-            "rust" | "rust_regex" => portability.rust_regex,
-            "pcre" | "pcre2" => portability.pcre2,
-            "js" | "javascript" => portability.javascript,
-            "python" | "python_re" => portability.python_re,
-            "python_regex" | "regex" => portability.python_regex,
-            "go" | "go_regexp" | "golang" => portability.go_regexp,
-            "java" => portability.java.unwrap_or(true),
-            "dotnet" | "csharp" | "c#" | ".net" => portability.dotnet,
-            "ruby" | "rb" => portability.ruby,
-            _ => true,
-        };
 
-        if !compatible {
+        if !is_compatible(portability, target) {
             result.error = Some(ValidationError {
                 kind: "incompatible".to_string(),
                 position: None,
@@ -100,11 +1236,101 @@ pub fn validate_for_language(pattern: &str, target: &str) -> ValidateResult {
             });
             result.suggestion = suggest_compatible_alternative(pattern, target);
         }
+
+        if matches!(canonical_target(target), Some("posix_ere" | "posix_bre")) {
+            result
+                .warnings
+                .extend(leftmost_longest_divergences(pattern));
+        }
+    }
+
+    result
+}
+
+/// Validate a pattern and report compatibility with every known target language
+pub fn validate_all_targets(pattern: &str, options: &ValidateOptions) -> ValidateResult {
+    let mut result = validate_pattern(pattern, options);
+    let normalized = result.normalized_pattern.clone();
+    let pattern = normalized.as_deref().unwrap_or(pattern);
+
+    if let Some(portability) = result.portability.as_ref() {
+        result.targets = TARGETS
+            .iter()
+            .map(|&target| {
+                let compatible = is_compatible(portability, target);
+                TargetReport {
+                    target: target.to_string(),
+                    compatible,
+                    issues: target_issues(pattern, target),
+                    rewrite: if compatible {
+                        None
+                    } else {
+                        suggest_compatible_alternative(pattern, target)
+                    },
+                }
+            })
+            .collect();
     }
 
     result
 }
 
+/// Swap the flat `portability` booleans of a valid pattern's result for the
+/// per-engine breakdown with reasons (`--verbose-portability`)
+pub fn detail_portability(result: &mut ValidateResult, pattern: &str) {
+    let normalized = result.normalized_pattern.clone();
+    let pattern = normalized.as_deref().unwrap_or(pattern);
+    if result.portability.take().is_some() {
+        result.detailed_portability = Some(detailed_portability(pattern));
+    }
+}
+
+/// Portability matrix for `re-x compare-languages`: which targets accept the
+/// pattern and, for the rest, the features they reject
+pub fn compare_languages(
+    pattern: &str,
+    options: &ValidateOptions,
+) -> Result<CompareLanguagesResult, CoreError> {
+    let result = validate_all_targets(pattern, options);
+    if !result.valid {
+        let message = result.error.map(|e| e.message).unwrap_or_default();
+        return Err(CoreError::InvalidPattern(match result.suggestion {
+            Some(suggestion) => format!("Invalid pattern: {} ({})", message, suggestion),
+            None => format!("Invalid pattern: {}", message),
+        }));
+    }
+
+    Ok(CompareLanguagesResult {
+        pattern: pattern.to_string(),
+        engine_required: result.engine_required.unwrap_or_default(),
+        compatible: result
+            .targets
+            .iter()
+            .filter(|report| report.compatible)
+            .map(|report| report.target.clone())
+            .collect(),
+        targets: result.targets,
+    })
+}
+
+/// Look up a target language (or one of its aliases) in a portability report
+fn is_compatible(portability: &Portability, target: &str) -> bool {
+    match canonical_target(target) {
+        Some("rust_regex") => portability.rust_regex,
+        Some("pcre2") => portability.pcre2,
+        Some("javascript") => portability.javascript,
+        Some("python_re") => portability.python_re,
+        Some("python_regex") => portability.python_regex,
+        Some("go_regexp") => portability.go_regexp,
+        Some("java") => portability.java.unwrap_or(true),
+        Some("dotnet") => portability.dotnet,
+        Some("ruby") => portability.ruby,
+        Some("posix_ere") => portability.posix_ere,
+        Some("posix_bre") => portability.posix_bre,
+        _ => true,
+    }
+}
+
 /// Parse AST error into ValidationError
 fn parse_ast_error(err: &ast::Error) -> (ValidationError, Option<String>) {
     let kind = match err.kind() {
@@ -160,6 +1386,42 @@ fn parse_regex_error(
     )
 }
 
+/// The size limit a compile failed on, if the pattern is valid but its
+/// compiled program is too large (e.g. `a{1000000}`)
+fn exceeded_size_limit(regex_err: &regex::Error, fancy_err: &fancy_regex::Error) -> Option<usize> {
+    if let regex::Error::CompiledTooBig(limit) = regex_err {
+        return Some(*limit);
+    }
+    match fancy_err {
+        fancy_regex::Error::CompileError(err) => match err.as_ref() {
+            fancy_regex::CompileError::InnerError(inner) => inner.size_limit(),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Error for a pattern that exceeds the compiled size limit
+fn size_limit_error(regex_err: &regex::Error, limit: usize) -> (ValidationError, Option<String>) {
+    let message = match regex_err {
+        regex::Error::CompiledTooBig(_) => regex_err.to_string(),
+        _ => format!("Compiled regex exceeds size limit of {} bytes.", limit),
+    };
+
+    (
+        ValidationError {
+            kind: "size_limit".to_string(),
+            position: None,
+            message,
+        },
+        Some(
+            "pattern expands too large; reduce repetition bounds or alternation breadth \
+             (or raise the limit with --size-limit)"
+                .to_string(),
+        ),
+    )
+}
+
 /// Suggest a fix based on error type
 fn suggest_fix_for_error(kind: &str, message: &str) -> Option<String> {
     match kind {
@@ -207,38 +1469,389 @@ fn suggest_compatible_alternative(pattern: &str, target: &str) -> Option<String>
                 None
             }
         }
+        "posix" | "ere" | "posix_ere" => suggest_posix_alternative(pattern, false),
+        "bre" | "posix_bre" => suggest_posix_alternative(pattern, true),
         _ => None,
     }
 }
 
+/// Rewrites for the Perl-style constructs that grep/sed patterns lack
+fn suggest_posix_alternative(pattern: &str, bre: bool) -> Option<String> {
+    let f = PatternFeatures::analyze(pattern);
+    let mut hints = Vec::new();
+
+    if f.perl_classes {
+        hints.push("`\\d` → `[0-9]`, `[[:digit:]]`; `\\w` → `[[:alnum:]_]`; `\\s` → `[[:space:]]`");
+    }
+    if f.lazy {
+        hints.push("replace lazy quantifiers with a negated class, e.g. `\".*?\"` → `\"[^\"]*\"`");
+    }
+    if f.non_capturing || f.named_capture {
+        hints.push("use plain `(...)` groups");
+    }
+    if f.inline_flags {
+        hints.push("drop inline flags and pass them to the tool instead (e.g. `grep -i`)");
+    }
+    if f.word_boundary || f.non_word_boundary {
+        hints.push("`\\b` is a GNU extension; GNU tools also accept `\\<` and `\\>`");
+    }
+    if f.lookahead || f.lookbehind {
+        hints.push("lookaround has no POSIX equivalent; match the context and strip it afterwards");
+    }
+    if f.backreference && !bre {
+        hints.push("backreferences are only standard in BRE (`grep` without `-E`)");
+    }
+    if bre && f.alternation {
+        hints.push("BRE has no alternation; use ERE (`grep -E`) or one pattern per `-e`");
+    }
+    if bre && f.plus_or_optional {
+        hints.push("BRE has no `+` or `?`; use `\\{1,\\}` and `\\{0,1\\}`");
+    }
+    if bre && (f.group || f.counted_repetition) {
+        hints.push("in BRE, groups and intervals are written `\\(...\\)` and `\\{n,m\\}`");
+    }
+
+    if hints.is_empty() {
+        None
+    } else {
+        Some(hints.join("; "))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_backreference_problems() {
+        let invalid = |pattern: &str| {
+            let result = validate_pattern(pattern, &ValidateOptions::default());
+            assert!(!result.valid, "{}", pattern);
+            let error = result.error.unwrap();
+            assert_eq!(error.kind, "invalid_backreference", "{}", pattern);
+            (error.position, error.message, result.suggestion.unwrap())
+        };
+
+        let (position, message, suggestion) = invalid(r"(a)(b)\3");
+        assert_eq!(position, Some(6));
+        assert!(message.contains("refers to group 3, but the pattern has only 2 capture groups"));
+        assert!(suggestion.contains(r"`\1` to `\2`"));
+
+        let (_, message, suggestion) = invalid(r"\1");
+        assert!(message.contains("has no capture groups"));
+        assert!(suggestion.starts_with("Wrap the text"));
+
+        let (_, _, suggestion) = invalid(r"(a)\10");
+        assert!(suggestion.starts_with(r"`\10` means group 10, so write `\1(?:0)`"));
+
+        let (_, message, suggestion) = invalid(r"(?<x>a)\k<y>");
+        assert!(message.contains("group named `y`"));
+        assert_eq!(suggestion, "Use one of the defined names: `x`");
+
+        // Escaped parentheses and classes are not groups
+        let (_, message, _) = invalid(r"\(a\)[(]\1");
+        assert!(message.contains("no capture groups"));
+
+        let warnings =
+            |pattern: &str| validate_pattern(pattern, &ValidateOptions::default()).warnings;
+        assert!(warnings(r"\2(a)(b)")[0].contains("comes before group 2"));
+        assert!(warnings(r"(a\1)")[0].contains("inside group 1"));
+        for pattern in [r"(a)\1", r"(?<x>a)\k<x>", r"(?P<x>a)(?P=x)", r"(?:(a)|b)\1"] {
+            assert!(warnings(pattern).is_empty(), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_anchored_alternation_warning() {
+        let result = validate_pattern("^foo|bar$", &ValidateOptions::default());
+        assert!(result.valid);
+        assert_eq!(result.warnings.len(), 1);
+        assert!(result.warnings[0].contains("`^(?:foo|bar)$`"));
+
+        let result = validate_pattern(r"\Aab|cd|ef", &ValidateOptions::default());
+        assert!(result.warnings[0].contains(r"`\A(?:ab|cd|ef)`"));
+
+        // Flags before the anchor stay in front of it
+        let result = validate_pattern("(?i)^a|b", &ValidateOptions::default());
+        assert!(
+            result.warnings[0].contains("`(?i)^(?:a|b)`"),
+            "{:?}",
+            result.warnings
+        );
+        let result = validate_pattern("^a|(?i)^b|c", &ValidateOptions::default());
+        assert!(
+            result.warnings[0].contains("`^(?:a|(?i)b|c)`"),
+            "{:?}",
+            result.warnings
+        );
+
+        // Consistently anchored branches, grouped alternations and unanchored ones are fine
+        for pattern in ["^foo$|^bar$", "^(?:foo|bar)$", "foo|bar", "^a"] {
+            let result = validate_pattern(pattern, &ValidateOptions::default());
+            assert!(result.warnings.is_empty(), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_dubious_construct_warnings() {
+        let warnings = |pattern| validate_pattern(pattern, &ValidateOptions::default()).warnings;
+
+        let w = warnings("x[a-z]{0}y");
+        assert_eq!(w.len(), 1);
+        assert!(w[0].contains("`[a-z]{0}` repeats zero times"));
+        assert!(warnings("a(?:)b")[0].contains("Empty group `(?:)`"));
+        assert!(warnings("(?:ab){1,1}c")[0].contains("`(?:ab)` alone"));
+        assert!(warnings("(cat|dog|cat)s")[0].contains("branch `cat` repeats"));
+        // Nested constructs are found too
+        assert_eq!(warnings("(a{0}|b{1})+").len(), 2);
+
+        for pattern in ["a{0,1}", "a{1,2}", "(a|ab)c", "(?:ab)+", "x{2}"] {
+            assert!(warnings(pattern).is_empty(), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_compare_languages() {
+        let result = compare_languages(r"(?<=\$)\d+", &ValidateOptions::default()).unwrap();
+        assert_eq!(result.engine_required, "fancy-regex");
+        assert_eq!(result.targets.len(), TARGETS.len());
+        assert!(result.compatible.contains(&"pcre2".to_string()));
+        assert!(!result.compatible.contains(&"go_regexp".to_string()));
+
+        let go = result
+            .targets
+            .iter()
+            .find(|report| report.target == "go_regexp")
+            .unwrap();
+        assert!(go.issues[0].starts_with("Lookbehind assertions"));
+
+        let err = compare_languages("(a", &ValidateOptions::default()).unwrap_err();
+        assert!(matches!(err, CoreError::InvalidPattern(_)));
+        assert!(err.to_string().starts_with("Invalid pattern:"), "{}", err);
+    }
+
+    #[test]
+    fn test_oniguruma_escape_hints() {
+        let result = validate_pattern(r"a\Rb", &ValidateOptions::default());
+        assert!(!result.valid);
+        assert!(result.suggestion.unwrap().contains("any line break"));
+
+        // fancy-regex accepts \h, so it is valid but gets a portability warning
+        let result = validate_pattern(r"\h+", &ValidateOptions::default());
+        assert!(result.valid);
+        assert!(result.warnings[0].contains("[0-9a-fA-F]"));
+        assert!(!result.portability.unwrap().rust_regex);
+    }
+
+    #[test]
+    fn test_unicode_property_validation() {
+        for name in [
+            UNICODE_SCRIPTS,
+            UNICODE_CATEGORIES,
+            UNICODE_BINARY_PROPERTIES,
+        ]
+        .concat()
+        {
+            assert!(
+                regex::Regex::new(&format!(r"\p{{{}}}", name)).is_ok(),
+                "{}",
+                name
+            );
+        }
+
+        let invalid = |pattern: &str| {
+            let result = validate_pattern(pattern, &ValidateOptions::default());
+            let error = result.error.unwrap();
+            assert_eq!(error.kind, "unknown_unicode_property");
+            (error.position.unwrap(), result.suggestion)
+        };
+        assert_eq!(
+            invalid(r"a\p{Scrpt=Greek}"),
+            (1, Some(r"Did you mean `\p{Script=Greek}`?".to_string()))
+        );
+        assert_eq!(
+            invalid(r"[\P{sc:Gerek}]").1.unwrap(),
+            r"Did you mean `\P{sc:Greek}`?"
+        );
+        assert_eq!(
+            invalid(r"\p{Uppercase_Leter}").1.unwrap(),
+            r"Did you mean `\p{Uppercase_Letter}`?"
+        );
+        assert!(invalid(r"\p{Block=Greek}")
+            .1
+            .unwrap()
+            .contains("code point range"));
+        assert_eq!(invalid(r"\p{Xyzzy}").1, None);
+
+        let result = validate_pattern(r"\p{sc=Greek}\p{Greek}\p{Lu}", &ValidateOptions::default());
+        assert!(result.valid);
+        assert_eq!(result.warnings.len(), 2);
+        assert!(result.warnings[0].contains("name=value form"));
+        assert!(result.warnings[1].contains(r"`\p{Script=Greek}` form"));
+    }
+
+    #[test]
+    fn test_active_flags() {
+        let options = ValidateOptions {
+            flags: true,
+            ..Default::default()
+        };
+
+        let flags = validate_pattern("(?is)a.b", &options).active_flags.unwrap();
+        assert!(flags.case_insensitive && flags.dot_matches_newline && flags.unicode);
+        assert!(!flags.multiline && !flags.mid_pattern);
+
+        let flags = validate_pattern("a(?i)b(?-iu:c)", &options)
+            .active_flags
+            .unwrap();
+        assert!(flags.case_insensitive && flags.unicode && flags.mid_pattern);
+
+        // Directives inside a group end with it
+        let flags = validate_pattern("(a(?i)b)c(?s:d)", &options)
+            .active_flags
+            .unwrap();
+        assert!(!flags.case_insensitive && !flags.dot_matches_newline && flags.mid_pattern);
+
+        let flags = validate_pattern("abc", &options).active_flags.unwrap();
+        assert_eq!(flags, FlagState::default());
+
+        // Only reported when asked for
+        assert!(validate_pattern("(?i)a", &ValidateOptions::default())
+            .active_flags
+            .is_none());
+    }
+
+    #[test]
+    fn test_runnable_pattern_rejects_empty() {
+        for pattern in ["", "(?:)", "(?i)", "(?x)  "] {
+            assert_eq!(
+                validate_runnable_pattern(pattern, false).unwrap_err(),
+                CoreError::EmptyPattern
+            );
+            assert!(validate_runnable_pattern(pattern, true).is_ok());
+        }
+        for pattern in ["a", "^", r"\b", "a?"] {
+            assert!(validate_runnable_pattern(pattern, false).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_posix_suggestions() {
+        let result = validate_for_language(r"\d+\s*", "posix", &ValidateOptions::default());
+        assert_eq!(result.error.unwrap().kind, "incompatible");
+        assert!(result.suggestion.unwrap().contains("`[[:digit:]]`"));
+
+        let result = validate_for_language("a|b+", "bre", &ValidateOptions::default());
+        let suggestion = result.suggestion.unwrap();
+        assert!(suggestion.contains("no alternation"));
+        assert!(suggestion.contains(r"`\{1,\}`"));
+
+        let result = validate_for_language("[0-9]+", "ere", &ValidateOptions::default());
+        assert!(result.error.is_none());
+    }
+
+    #[test]
+    fn test_all_targets_report() {
+        let result = validate_all_targets(r"foo(?=bar)", &ValidateOptions::default());
+        assert!(result.valid);
+        assert_eq!(result.targets.len(), TARGETS.len());
+
+        let go = result
+            .targets
+            .iter()
+            .find(|t| t.target == "go_regexp")
+            .unwrap();
+        assert!(!go.compatible);
+        assert!(!go.issues.is_empty());
+        assert!(go.rewrite.is_some());
+
+        let pcre = result.targets.iter().find(|t| t.target == "pcre2").unwrap();
+        assert!(pcre.compatible);
+        assert!(pcre.issues.is_empty());
+        assert!(pcre.rewrite.is_none());
+    }
+
     #[test]
     fn test_valid_simple_pattern() {
-        let result = validate_pattern(r"\d+");
+        let result = validate_pattern(r"\d+", &ValidateOptions::default());
         assert!(result.valid);
         assert_eq!(result.engine_required, Some("regex".to_string()));
     }
 
     #[test]
     fn test_valid_fancy_pattern() {
-        let result = validate_pattern(r"foo(?=bar)");
+        let result = validate_pattern(r"foo(?=bar)", &ValidateOptions::default());
         assert!(result.valid);
         assert_eq!(result.engine_required, Some("fancy-regex".to_string()));
+        assert!(!result.fallback_occurred);
+    }
+
+    #[test]
+    fn test_fallback_pattern() {
+        let result = validate_pattern(r"a{,3}", &ValidateOptions::default());
+        assert!(result.valid);
+        assert!(result.fallback_occurred);
+        assert!(result
+            .reason
+            .unwrap()
+            .contains("repetition quantifier expects a valid decimal"));
+
+        assert!(!validate_pattern(r"a{0,3}", &ValidateOptions::default()).fallback_occurred);
     }
 
     #[test]
     fn test_invalid_pattern() {
-        let result = validate_pattern(r"(\d+");
+        let result = validate_pattern(r"(\d+", &ValidateOptions::default());
         assert!(!result.valid);
         assert!(result.error.is_some());
     }
 
+    #[test]
+    fn test_capture_groups_reported() {
+        let result = validate_pattern(r"(?P<user>\w+)@(\w+)", &ValidateOptions::default());
+        assert_eq!(result.capture_groups.len(), 2);
+        assert_eq!(result.capture_groups[0].name.as_deref(), Some("user"));
+        assert_eq!(result.capture_groups[1].index, 2);
+    }
+
+    #[test]
+    fn test_rejects_deep_nesting() {
+        let deep = format!("{}a{}", "(".repeat(40), ")".repeat(40));
+        let result = validate_pattern(
+            &deep,
+            &ValidateOptions {
+                max_depth: 16,
+                ..Default::default()
+            },
+        );
+        assert!(!result.valid);
+        let error = result.error.unwrap();
+        assert_eq!(error.kind, "syntax_error");
+        assert!(error.message.contains("nesting too deep (limit: 16"));
+    }
+
+    #[test]
+    fn test_size_limit_error() {
+        for pattern in ["a{1000000}", "(?=x)a{1000000}"] {
+            let result = validate_pattern(pattern, &ValidateOptions::default());
+            assert!(!result.valid, "{}", pattern);
+            assert_eq!(result.error.unwrap().kind, "size_limit", "{}", pattern);
+            assert!(result.suggestion.unwrap().contains("repetition bounds"));
+        }
+
+        let result = validate_pattern(
+            "a{1000}",
+            &ValidateOptions {
+                size_limit: Some(1000),
+                ..Default::default()
+            },
+        );
+        assert_eq!(result.error.unwrap().kind, "size_limit");
+    }
+
     #[test]
     fn test_portability_check() {
-        let result = validate_pattern(r"(\w+)\s+\1");
+        let result = validate_pattern(r"(\w+)\s+\1", &ValidateOptions::default());
         assert!(result.valid);
         let portability = result.portability.unwrap();
         assert!(!portability.rust_regex);
@@ -0,0 +1,7 @@
+//! re-x - AI-native regex CLI, as a library
+//!
+//! The engines and command implementations behind the `re-x` binary, for
+//! use from Rust code (e.g. [`core::replace::replace_with_fn`]).
+
+pub mod core;
+pub mod output;
@@ -2,8 +2,7 @@
 //!
 //! Test, validate, explain. Built for coding agents.
 
-mod core;
-mod output;
+use re_x::{core, output};
 
 #[cfg(feature = "cli")]
 mod cli;
@@ -16,14 +15,18 @@ use std::process::ExitCode;
 fn main() -> ExitCode {
     #[cfg(feature = "cli")]
     {
-        use cli::{parse, Commands};
+        use cli::{exit_code, parse, Commands, OutputFormat};
 
         let args = parse();
 
         // Check for MCP mode
         #[cfg(feature = "mcp")]
         if args.mcp {
-            return run_mcp_server();
+            return run_mcp_server(mcp::ServerConfig {
+                max_depth: args.max_depth,
+                timeout_ms: args.mcp_timeout_ms,
+                max_groups: args.max_groups,
+            });
         }
 
         // If no command and no MCP mode, show help
@@ -34,24 +37,53 @@ fn main() -> ExitCode {
             eprintln!();
             eprintln!("Commands:");
             eprintln!("  test          Test a regex pattern against input");
+            eprintln!("  test-many     Test every pattern in a file against one input");
+            eprintln!("  watch         Re-run a pattern each time a file changes");
             eprintln!("  replace       Test regex replacement");
             eprintln!("  validate      Validate regex syntax and check portability");
+            eprintln!("  compare-languages Show which target languages accept a pattern");
             eprintln!("  explain       Explain a regex pattern");
+            eprintln!("  simplify      Simplify a pattern into an equivalent shorter form");
+            eprintln!("  normalize     Rewrite a pattern into a canonical form");
+            eprintln!("  dedupe-patterns Group equivalent patterns from a file");
+            eprintln!("  lint          Flag style issues such as unnecessary escapes");
+            eprintln!("  ast-diff      Compare the parse structure of two patterns");
+            eprintln!("  build         Render a pattern from a JSON syntax tree");
+            eprintln!("  generate-corpus Generate matching and near-miss strings for a pattern");
             eprintln!("  from-examples Infer regex pattern from examples");
             eprintln!("  apply         Apply regex replacement to a file (with backup)");
             eprintln!("  benchmark     Benchmark regex performance and detect ReDoS");
             eprintln!();
             eprintln!("Options:");
-            eprintln!("  -f, --format <FORMAT>  Output format [json|text] (default: json)");
+            eprintln!(
+                "  -f, --format <FORMAT>  Output format [json|text|csv] (default: json, or $RE_X_FORMAT)"
+            );
+            eprintln!("  --delimiter <CHAR>     Field separator for --format csv ('tab' for TSV)");
             eprintln!("  --mcp                  Run as MCP server");
+            eprintln!("  --mcp-timeout-ms <MS>  Time budget per MCP tool call (default: 30000)");
+            eprintln!(
+                "  --max-groups <N>       Capture groups allowed in MCP patterns (default: 1000)"
+            );
+            eprintln!("  --max-depth <N>        Maximum pattern nesting depth (default: 128, below regex-syntax's 250)");
+            eprintln!(
+                "  -X, --extended         Free-spacing mode: ignore whitespace and # comments"
+            );
+            eprintln!("  --pattern-file         Read PATTERN from the file at that path");
+            eprintln!("  -q, --quiet            Print nothing; report the outcome via exit code");
             eprintln!("  -h, --help             Print help");
             eprintln!("  -V, --version          Print version");
             return ExitCode::SUCCESS;
         };
 
         let format = args.format;
+        let max_depth = args.max_depth;
+        let quiet = args.quiet;
 
-        let result = match command {
+        if format == OutputFormat::Csv && !quiet && !matches!(command, Commands::Test { .. }) {
+            eprintln!("re-x: CSV output is only available for test; printing JSON");
+        }
+
+        let result: Result<cli::CommandOutput, core::CoreError> = match command {
             Commands::Test {
                 pattern,
                 input,
@@ -59,6 +91,32 @@ fn main() -> ExitCode {
                 max_matches,
                 engine,
                 multiline,
+                null_data,
+                group,
+                no_unicode,
+                full_match,
+                by_capture,
+                top,
+                allow_empty,
+                allow_empty_matches,
+                sort,
+                dedup,
+                per_line_count,
+                context_chars,
+                highlight_captures,
+                color,
+                only_groups,
+                first,
+                timeout_per_line_ms,
+                byte_mode,
+                engine_fallback_warning,
+                context_json,
+                stream_json,
+                coverage,
+                sticky,
+                vimgrep,
+                show_flags_normalized,
+                ..
             } => cli::handle_test(
                 &pattern,
                 input.as_deref(),
@@ -66,6 +124,32 @@ fn main() -> ExitCode {
                 max_matches,
                 engine.as_deref(),
                 multiline,
+                null_data,
+                group.as_deref(),
+                no_unicode,
+                full_match,
+                by_capture.as_deref(),
+                top,
+                allow_empty,
+                allow_empty_matches,
+                &sort,
+                dedup,
+                per_line_count,
+                context_chars,
+                highlight_captures,
+                color,
+                only_groups,
+                first,
+                timeout_per_line_ms,
+                byte_mode,
+                engine_fallback_warning,
+                context_json,
+                stream_json,
+                coverage,
+                sticky,
+                vimgrep,
+                show_flags_normalized,
+                args.delimiter,
                 format,
             ),
 
@@ -76,6 +160,11 @@ fn main() -> ExitCode {
                 file,
                 max_preview,
                 multiline,
+                max_result_bytes,
+                literal_replacement,
+                allow_empty,
+                raw,
+                count,
             } => cli::handle_replace(
                 &pattern,
                 &replacement,
@@ -83,36 +172,162 @@ fn main() -> ExitCode {
                 file.as_ref(),
                 max_preview,
                 multiline,
+                max_result_bytes,
+                literal_replacement,
+                allow_empty,
+                raw,
+                count,
                 format,
             ),
 
             Commands::Validate {
                 pattern,
                 target_lang,
-            } => cli::handle_validate(&pattern, target_lang.as_deref(), format),
+                all_targets,
+                flags,
+                verbose_portability,
+                size_limit,
+                engine_fallback_warning,
+                show_flags_normalized,
+            } => cli::handle_validate(
+                &pattern,
+                target_lang.as_deref(),
+                all_targets,
+                flags,
+                verbose_portability,
+                size_limit,
+                engine_fallback_warning,
+                show_flags_normalized,
+                max_depth,
+                format,
+            ),
+
+            Commands::Explain {
+                pattern,
+                target,
+                tree,
+                names_only,
+            } => cli::handle_explain(
+                &pattern,
+                target.as_deref(),
+                tree,
+                names_only,
+                max_depth,
+                format,
+            )
+            .map(Into::into),
 
-            Commands::Explain { pattern } => cli::handle_explain(&pattern, format),
+            Commands::CompareLanguages { pattern } => {
+                cli::handle_compare_languages(&pattern, max_depth, format).map(Into::into)
+            }
+
+            Commands::Simplify { pattern } => {
+                cli::handle_simplify(&pattern, format).map(Into::into)
+            }
 
-            Commands::FromExamples { examples, negative } => {
-                cli::handle_from_examples(&examples, negative.as_deref(), format)
+            Commands::Normalize { pattern } => {
+                cli::handle_normalize(&pattern, format).map(Into::into)
             }
 
+            Commands::DedupePatterns { file } => {
+                cli::handle_dedupe_patterns(&file, format).map(Into::into)
+            }
+
+            Commands::Lint { pattern } => cli::handle_lint(&pattern, format).map(Into::into),
+
+            Commands::TestMany {
+                patterns_file,
+                input,
+                file,
+                multiline,
+                allow_empty,
+            } => cli::handle_test_many(
+                &patterns_file,
+                input.as_deref(),
+                file.as_ref(),
+                multiline,
+                allow_empty,
+                format,
+            ),
+
+            Commands::Watch {
+                pattern,
+                file,
+                max_matches,
+                engine,
+                multiline,
+                debounce_ms,
+                max_runs,
+            } => cli::handle_watch(
+                &pattern,
+                &file,
+                max_matches,
+                engine.as_deref(),
+                multiline,
+                debounce_ms,
+                max_runs,
+                format,
+            ),
+
+            Commands::Build { ast_file } => cli::handle_build(&ast_file, format).map(Into::into),
+
+            Commands::AstDiff { a, b } => cli::handle_ast_diff(&a, &b, format).map(Into::into),
+
+            Commands::GenerateCorpus {
+                pattern,
+                count,
+                include_near_misses,
+            } => cli::handle_generate_corpus(&pattern, count, include_near_misses, format)
+                .map(Into::into),
+
+            Commands::FromExamples {
+                examples,
+                negative,
+                show_unmatched,
+                format_only,
+            } => cli::handle_from_examples(
+                &examples,
+                negative.as_deref(),
+                show_unmatched,
+                format_only,
+                format,
+            )
+            .map(Into::into),
+
             Commands::Apply {
                 pattern,
                 replacement,
                 file,
+                stdin_list,
+                null_data,
                 dry_run,
                 no_backup,
                 max_preview,
                 multiline,
+                literal_replacement,
+                allow_empty,
+                interactive,
+                count,
+                only_lines_matching,
+                diff,
+                color,
             } => cli::handle_apply(
                 &pattern,
                 &replacement,
-                &file,
+                file.as_deref(),
+                stdin_list,
+                null_data,
                 dry_run,
                 no_backup,
                 max_preview,
                 multiline,
+                literal_replacement,
+                allow_empty,
+                interactive,
+                count,
+                only_lines_matching.as_deref(),
+                diff,
+                color,
                 format,
             ),
 
@@ -120,30 +335,78 @@ fn main() -> ExitCode {
                 pattern,
                 input,
                 file,
+                input_file,
                 timeout_ms,
                 iterations,
+                auto,
+                warmup,
+                repeat_input,
+                detect_only,
+                per_line,
+                top_lines,
+                sweep,
+                catastrophic_threshold_ms,
+                stddev_ratio,
+                sample,
+                seed,
+                redos_templates,
             } => cli::handle_benchmark(
                 &pattern,
                 input.as_deref(),
                 file.as_ref(),
+                input_file.as_deref(),
                 timeout_ms,
                 iterations,
+                auto,
+                warmup,
+                repeat_input,
+                detect_only,
+                per_line,
+                top_lines,
+                sweep,
+                catastrophic_threshold_ms,
+                stddev_ratio,
+                sample,
+                seed,
+                redos_templates.as_deref(),
                 format,
             ),
         };
 
         match result {
-            Ok(output) => {
-                println!("{}", output);
-                ExitCode::SUCCESS
+            Ok(result) => {
+                if !quiet {
+                    if result.trailing_newline {
+                        println!("{}", result.output);
+                    } else {
+                        print!("{}", result.output);
+                    }
+                }
+                ExitCode::from(result.exit_code)
             }
             Err(e) => {
                 // Output error as structured JSON for AI consumption
-                let error = crate::output::ErrorResponse::new("COMMAND_ERROR", &e);
+                let message = e.to_string();
+                let invalid_pattern = matches!(e, core::CoreError::InvalidPattern(_));
+                let code = if invalid_pattern {
+                    crate::output::error_codes::INVALID_PATTERN
+                } else if e == core::CoreError::EmptyPattern {
+                    crate::output::error_codes::INVALID_INPUT
+                } else {
+                    "COMMAND_ERROR"
+                };
+                let error = crate::output::ErrorResponse::new(code, &message);
                 let error_json = serde_json::to_string(&error)
-                    .unwrap_or_else(|_| format!(r#"{{"error":true,"message":"{}"}}"#, e));
+                    .unwrap_or_else(|_| format!(r#"{{"error":true,"message":"{}"}}"#, message));
                 eprintln!("{}", error_json);
-                ExitCode::FAILURE
+
+                // The handler reports whether the pattern itself was at fault,
+                // as opposed to I/O or argument errors
+                ExitCode::from(if invalid_pattern {
+                    exit_code::INVALID_PATTERN
+                } else {
+                    exit_code::ERROR
+                })
             }
         }
     }
@@ -156,8 +419,8 @@ fn main() -> ExitCode {
 }
 
 #[cfg(feature = "mcp")]
-fn run_mcp_server() -> ExitCode {
-    match mcp::run_server() {
+fn run_mcp_server(config: mcp::ServerConfig) -> ExitCode {
+    match mcp::run_server(&config) {
         Ok(_) => ExitCode::SUCCESS,
         Err(e) => {
             eprintln!("MCP server error: {}", e);
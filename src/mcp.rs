@@ -5,9 +5,41 @@
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::io::{self, BufRead, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
 
-use crate::core;
+use crate::core::{self, CoreError};
 use crate::output::json::format_json;
+use crate::output::{error_codes, ErrorResponse, IsMatchResult, ReplaceCountResult};
+
+/// Timed-out tool calls whose workers may still be running before new calls
+/// are refused
+const MAX_ABANDONED_WORKERS: usize = 4;
+
+/// Tool worker threads still running, including abandoned ones
+static LIVE_WORKERS: AtomicUsize = AtomicUsize::new(0);
+
+/// A place among the live workers, released when the worker finishes
+struct WorkerSlot;
+
+impl WorkerSlot {
+    fn claim() -> Option<Self> {
+        LIVE_WORKERS
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |live| {
+                (live < MAX_ABANDONED_WORKERS).then_some(live + 1)
+            })
+            .ok()
+            .map(|_| WorkerSlot)
+    }
+}
+
+impl Drop for WorkerSlot {
+    fn drop(&mut self) {
+        LIVE_WORKERS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
 
 /// MCP JSON-RPC request
 #[derive(Debug, Deserialize)]
@@ -39,6 +71,17 @@ struct JsonRpcError {
     data: Option<Value>,
 }
 
+/// Server-side limits applied to every tool call
+#[derive(Clone)]
+pub struct ServerConfig {
+    /// Maximum pattern nesting depth accepted by explain/validate
+    pub max_depth: u32,
+    /// Wall-clock budget for a single tool call, in milliseconds
+    pub timeout_ms: u64,
+    /// Maximum capture groups in the `pattern` of any tool call
+    pub max_groups: usize,
+}
+
 /// Tool definition for MCP
 #[derive(Debug, Serialize)]
 struct ToolDefinition {
@@ -58,7 +101,7 @@ fn send_response(stdout: &mut io::Stdout, response: &JsonRpcResponse) -> Result<
 }
 
 /// Run the MCP server
-pub fn run_server() -> Result<(), String> {
+pub fn run_server(config: &ServerConfig) -> Result<(), String> {
     let stdin = io::stdin();
     let mut stdout = io::stdout();
 
@@ -94,7 +137,7 @@ pub fn run_server() -> Result<(), String> {
             continue;
         }
 
-        let response = handle_request(&request);
+        let response = handle_request(&request, config);
         send_response(&mut stdout, &response)?;
     }
 
@@ -118,7 +161,7 @@ fn handle_notification(request: &JsonRpcRequest) {
 }
 
 /// Handle a request (always returns a response)
-fn handle_request(request: &JsonRpcRequest) -> JsonRpcResponse {
+fn handle_request(request: &JsonRpcRequest, config: &ServerConfig) -> JsonRpcResponse {
     let id = request.id.clone().unwrap_or(Value::Null);
 
     match request.method.as_str() {
@@ -182,7 +225,7 @@ fn handle_request(request: &JsonRpcRequest) -> JsonRpcResponse {
                 .cloned()
                 .unwrap_or(json!({}));
 
-            match call_tool(tool_name, &arguments) {
+            match call_tool(tool_name, &arguments, config) {
                 Ok(result) => JsonRpcResponse {
                     jsonrpc: "2.0".to_string(),
                     id,
@@ -195,7 +238,7 @@ fn handle_request(request: &JsonRpcRequest) -> JsonRpcResponse {
                     error: None,
                 },
                 Err(e) => {
-                    let error_response = crate::output::ErrorResponse::new("TOOL_ERROR", &e);
+                    let error_response = tool_error(&arguments, &e, config);
                     let error_msg = serde_json::to_string(&error_response)
                         .unwrap_or_else(|_| format!(r#"{{"error":true,"message":"{}"}}"#, e));
                     JsonRpcResponse {
@@ -252,6 +295,89 @@ fn get_tools() -> Vec<ToolDefinition> {
                         "type": "integer",
                         "description": "Maximum matches to return (default: 100)"
                     },
+                    "multiline": {
+                        "type": "boolean",
+                        "description": "Enable multiline mode: dot matches newline, ^/$ match line boundaries (default: false)"
+                    },
+                    "group": {
+                        "type": ["integer", "string"],
+                        "description": "Return only this capture group (number or name) as each match's text"
+                    },
+                    "no_unicode": {
+                        "type": "boolean",
+                        "description": "ASCII-only \\w, \\d, \\s and \\b via (?-u); `.` must then be replaced by an ASCII class (default: false)"
+                    },
+                    "full_match": {
+                        "type": "boolean",
+                        "description": "Check whether the entire input matches (as if wrapped in ^(?:...)$) instead of finding all matches (default: false)"
+                    },
+                    "by_capture": {
+                        "type": ["integer", "string"],
+                        "description": "Tally distinct values of this capture group (number or name) over all matches into capture_counts, most frequent first"
+                    },
+                    "top": {
+                        "type": "integer",
+                        "description": "With by_capture, keep only the N most frequent values"
+                    },
+                    "sort": {
+                        "type": "string",
+                        "enum": ["position", "text", "length"],
+                        "description": "Order of matches: position (default), text (groups duplicates) or length (longest first); reordered matches carry their original index"
+                    },
+                    "dedup": {
+                        "type": "boolean",
+                        "description": "Collapse matches with identical text into one entry with occurrences and positions (default: false)"
+                    },
+                    "context_chars": {
+                        "type": "integer",
+                        "description": "Attach this many characters of surrounding text to each match as context, with the match marked «like this» and … where the text continues"
+                    },
+                    "per_line_count": {
+                        "type": "boolean",
+                        "description": "With file_path, return only lines (line_number, count) for lines with at least one match instead of the matches themselves; streams the file (default: false)"
+                    },
+                    "allow_empty": {
+                        "type": "boolean",
+                        "description": "Run an empty pattern anyway; by default it is refused with INVALID_INPUT because it matches at every position (default: false)"
+                    },
+                    "allow_empty_matches": {
+                        "type": "boolean",
+                        "description": "Keep the empty matches of a pattern that can also match text, like a*; by default they are left out and matches_empty is set (default: false)"
+                    },
+                    "first": {
+                        "type": "boolean",
+                        "description": "Stop at the first match, for existence checks on large files (default: false)"
+                    },
+                    "timeout_per_line_ms": {
+                        "type": "integer",
+                        "description": "With file_path: give each line this many milliseconds to match; the scan stops at the first slower line, listed in timed_out_lines"
+                    },
+                    "byte_mode": {
+                        "type": "boolean",
+                        "description": "Match the raw bytes of file_path, which need not be valid UTF-8; offsets are byte offsets and match text is decoded lossily. Regex engine only (default: false)"
+                    }
+                },
+                "required": ["pattern"]
+            }),
+        },
+        ToolDefinition {
+            name: "regex_is_match".to_string(),
+            description: "Check whether a regex pattern matches anywhere in input text or a file. Returns only {matched: true|false} and stops at the first match, so it is far cheaper than regex_test when a yes/no answer is enough.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "pattern": {
+                        "type": "string",
+                        "description": "The regex pattern to check"
+                    },
+                    "input": {
+                        "type": "string",
+                        "description": "Text to check"
+                    },
+                    "file_path": {
+                        "type": "string",
+                        "description": "File path to check (alternative to input)"
+                    },
                     "multiline": {
                         "type": "boolean",
                         "description": "Enable multiline mode: dot matches newline, ^/$ match line boundaries (default: false)"
@@ -262,7 +388,7 @@ fn get_tools() -> Vec<ToolDefinition> {
         },
         ToolDefinition {
             name: "regex_replace".to_string(),
-            description: "Test regex replacement on input text. Shows before/after without modifying any files. Supports capture group references ($1, $2, etc.).".to_string(),
+            description: "Test regex replacement on input text. Shows before/after without modifying any files. Supports capture group references ($1, ${name}, \\1, \\g<name>).".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
@@ -272,7 +398,7 @@ fn get_tools() -> Vec<ToolDefinition> {
                     },
                     "replacement": {
                         "type": "string",
-                        "description": "Replacement string (supports $1, $2 for capture groups)"
+                        "description": "Replacement string (supports $1, ${name}, \\1 and \\g<name> for capture groups; \\\\ is a literal backslash)"
                     },
                     "input": {
                         "type": "string",
@@ -285,6 +411,26 @@ fn get_tools() -> Vec<ToolDefinition> {
                     "multiline": {
                         "type": "boolean",
                         "description": "Enable multiline mode: dot matches newline, ^/$ match line boundaries (default: false)"
+                    },
+                    "max_result_bytes": {
+                        "type": "integer",
+                        "description": "Truncate the echoed original/result strings (and the spans within them) to this many bytes (replacements_made stays exact)"
+                    },
+                    "literal_replacement": {
+                        "type": "boolean",
+                        "description": "Insert the replacement verbatim, without expanding $1, ${name} or $$ (default: false)"
+                    },
+                    "allow_empty": {
+                        "type": "boolean",
+                        "description": "Run an empty pattern anyway; by default it is refused with INVALID_INPUT because it matches at every position (default: false)"
+                    },
+                    "count_only": {
+                        "type": "boolean",
+                        "description": "Return only {replacements_made} without the original/result text (default: false)"
+                    },
+                    "count": {
+                        "type": "integer",
+                        "description": "Replace only the first N matches and leave the rest untouched; remaining_matches counts them"
                     }
                 },
                 "required": ["pattern", "replacement"]
@@ -302,7 +448,26 @@ fn get_tools() -> Vec<ToolDefinition> {
                     },
                     "target_lang": {
                         "type": "string",
-                        "description": "Check compatibility for specific language (rust|python|javascript|go|java|pcre|dotnet|ruby)"
+                        "description": "Check compatibility for specific language (rust|python|javascript|go|java|pcre|dotnet|ruby|ere|bre)"
+                    },
+                    "all_targets": {
+                        "type": "boolean",
+                        "description": "Report compatibility, blocking features and a suggested rewrite for every target language",
+                        "default": false
+                    },
+                    "flags": {
+                        "type": "boolean",
+                        "description": "Report the effective flags set by inline directives like (?i) or (?-u) as active_flags",
+                        "default": false
+                    },
+                    "verbose_portability": {
+                        "type": "boolean",
+                        "description": "Report portability as {engine: {compatible, issues}} with the reason for each incompatibility instead of flat booleans",
+                        "default": false
+                    },
+                    "size_limit": {
+                        "type": "integer",
+                        "description": "Compiled program size limit in bytes (default 10485760); patterns over it fail with kind size_limit"
                     }
                 },
                 "required": ["pattern"]
@@ -317,6 +482,98 @@ fn get_tools() -> Vec<ToolDefinition> {
                     "pattern": {
                         "type": "string",
                         "description": "The regex pattern to explain"
+                    },
+                    "target": {
+                        "type": "string",
+                        "description": "Annotate parts unsupported by this language (rust|python|javascript|go|java|pcre|dotnet|ruby|ere|bre)"
+                    },
+                    "names_only": {
+                        "type": "boolean",
+                        "description": "Only return the named capture groups as {groups: [{index, name}]}",
+                        "default": false
+                    }
+                },
+                "required": ["pattern"]
+            }),
+        },
+        ToolDefinition {
+            name: "regex_simplify".to_string(),
+            description: "Rewrite a regex pattern into an equivalent shorter form (e.g. [0-9][0-9][0-9] -> [0-9]{3}, redundant (?:...) removed). Capture groups are preserved.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "pattern": {
+                        "type": "string",
+                        "description": "The regex pattern to simplify"
+                    }
+                },
+                "required": ["pattern"]
+            }),
+        },
+        ToolDefinition {
+            name: "regex_normalize".to_string(),
+            description: "Rewrite a regex pattern into a canonical form (sorted class members, x{1,} -> x+, redundant (?:...) removed) so equivalent patterns written differently compare equal. Useful as a dedup/cache key.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "pattern": {
+                        "type": "string",
+                        "description": "The regex pattern to normalize"
+                    }
+                },
+                "required": ["pattern"]
+            }),
+        },
+        ToolDefinition {
+            name: "regex_lint".to_string(),
+            description: "Lint a regex for style issues. Flags escapes that are unnecessary in the Rust regex dialect (e.g. \\/ or \\, carried over from JavaScript) with spans, suggestions and a fixed pattern that matches exactly the same strings.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "pattern": {
+                        "type": "string",
+                        "description": "The regex pattern to lint"
+                    }
+                },
+                "required": ["pattern"]
+            }),
+        },
+        ToolDefinition {
+            name: "regex_ast_diff".to_string(),
+            description: "Structurally compare how two regex patterns parse: added, removed and changed nodes (groups, quantifier bounds, classes) with spans. Use when reviewing an edit to a pattern to confirm it changes only what was intended.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "a": {
+                        "type": "string",
+                        "description": "The first (old) pattern"
+                    },
+                    "b": {
+                        "type": "string",
+                        "description": "The second (new) pattern"
+                    }
+                },
+                "required": ["a", "b"]
+            }),
+        },
+        ToolDefinition {
+            name: "regex_generate_corpus".to_string(),
+            description: "Generate strings a regex matches, plus near misses (one character changed so it no longer matches). Use to build regression tests for a pattern or to round-trip through regex_from_examples.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "pattern": {
+                        "type": "string",
+                        "description": "The regex pattern to generate strings for"
+                    },
+                    "count": {
+                        "type": "integer",
+                        "description": "Number of strings to generate of each kind (default: 10)"
+                    },
+                    "include_near_misses": {
+                        "type": "boolean",
+                        "description": "Also generate non-matching near misses in negatives",
+                        "default": false
                     }
                 },
                 "required": ["pattern"]
@@ -337,6 +594,10 @@ fn get_tools() -> Vec<ToolDefinition> {
                         "type": "array",
                         "items": { "type": "string" },
                         "description": "Strings that should NOT match"
+                    },
+                    "format_only": {
+                        "type": "boolean",
+                        "description": "Only return known formats (dates, UUIDs, emails, ...), skipping heuristic guesses (default: false)"
                     }
                 },
                 "required": ["examples"]
@@ -354,7 +615,7 @@ fn get_tools() -> Vec<ToolDefinition> {
                     },
                     "replacement": {
                         "type": "string",
-                        "description": "Replacement string (supports $1, $2 for capture groups)"
+                        "description": "Replacement string (supports $1, ${name}, \\1 and \\g<name> for capture groups; \\\\ is a literal backslash)"
                     },
                     "file_path": {
                         "type": "string",
@@ -375,6 +636,30 @@ fn get_tools() -> Vec<ToolDefinition> {
                     "multiline": {
                         "type": "boolean",
                         "description": "Enable multiline mode: dot matches newline, ^/$ match line boundaries (default: false)"
+                    },
+                    "literal_replacement": {
+                        "type": "boolean",
+                        "description": "Insert the replacement verbatim, without expanding $1, ${name} or $$ (default: false)"
+                    },
+                    "allow_empty": {
+                        "type": "boolean",
+                        "description": "Run an empty pattern anyway; by default it is refused with INVALID_INPUT because it matches at every position (default: false)"
+                    },
+                    "count_only": {
+                        "type": "boolean",
+                        "description": "Return only {replacements_made} without the preview; the file is still written unless dry_run is set (default: false)"
+                    },
+                    "count": {
+                        "type": "integer",
+                        "description": "Replace only the first N matches and leave the rest untouched; remaining_matches counts them"
+                    },
+                    "only_lines_matching": {
+                        "type": "string",
+                        "description": "Guard pattern: replace only on lines it also matches (not with multiline)"
+                    },
+                    "diff": {
+                        "type": "boolean",
+                        "description": "Also return the changes as a unified diff in `diff`; implies dry_run (default: false)"
                     }
                 },
                 "required": ["pattern", "replacement", "file_path"]
@@ -400,7 +685,35 @@ fn get_tools() -> Vec<ToolDefinition> {
                     },
                     "timeout_ms": {
                         "type": "integer",
-                        "description": "Timeout in milliseconds (default: 5000)"
+                        "description": "Timeout in milliseconds (default: 5000); also a hard wall-clock cap on the whole call, after which any pass still running is cut short and the result is marked timeout"
+                    },
+                    "detect_only": {
+                        "type": "boolean",
+                        "description": "Only run static ReDoS detection without executing the pattern (instant, no timing)",
+                        "default": false
+                    },
+                    "per_line": {
+                        "type": "boolean",
+                        "description": "With file_path: also time each line separately and report the 5 slowest in slowest_lines",
+                        "default": false
+                    },
+                    "sweep": {
+                        "type": "array",
+                        "items": { "type": "integer" },
+                        "description": "Input sizes in bytes (e.g. [10, 100, 1000, 10000]); time the input stretched to each size and report the growth curve with a linear/superlinear verdict"
+                    },
+                    "sample": {
+                        "type": "integer",
+                        "description": "With file_path: benchmark this many randomly chosen lines instead of the whole file (quick estimate for huge files)"
+                    },
+                    "seed": {
+                        "type": "integer",
+                        "description": "Seed for choosing the sampled lines (same seed, same lines)",
+                        "default": 0
+                    },
+                    "redos_templates": {
+                        "type": "string",
+                        "description": "Without input or file_path: JSON file of extra [{\"fragment\", \"input\"}] pairs; a pattern containing a fragment is benchmarked against its input before the builtin templates are tried"
                     }
                 },
                 "required": ["pattern"]
@@ -409,8 +722,102 @@ fn get_tools() -> Vec<ToolDefinition> {
     ]
 }
 
-/// Call a specific tool
-fn call_tool(name: &str, arguments: &Value) -> Result<String, String> {
+/// Build the error payload for a failed tool call, with the error code chosen
+/// by the kind of failure. When the failure may come from an invalid `pattern`
+/// argument, the pattern is re-validated so the response carries the same
+/// position, context and suggestion as `regex_validate`.
+fn tool_error(arguments: &Value, error: &CoreError, config: &ServerConfig) -> ErrorResponse {
+    let message = &error.to_string();
+    let code = match error {
+        CoreError::EmptyPattern => Some(error_codes::INVALID_INPUT),
+        CoreError::Timeout { .. } => Some(error_codes::TIMEOUT),
+        CoreError::TooManyGroups { .. } => Some(error_codes::TOO_MANY_GROUPS),
+        CoreError::InvalidPattern(_) | CoreError::Failed(_) => None,
+    };
+    if let Some(code) = code {
+        return ErrorResponse::new(code, message);
+    }
+
+    let invalid = arguments
+        .get("pattern")
+        .and_then(|v| v.as_str())
+        .map(|pattern| {
+            let options = core::ValidateOptions {
+                max_depth: config.max_depth,
+                ..Default::default()
+            };
+            (pattern, core::validate_pattern(pattern, &options))
+        })
+        .filter(|(_, result)| !result.valid);
+
+    let Some((pattern, result)) = invalid else {
+        let code = match error {
+            CoreError::InvalidPattern(_) => error_codes::INVALID_PATTERN,
+            _ => "TOOL_ERROR",
+        };
+        return ErrorResponse::new(code, message);
+    };
+
+    let mut response = ErrorResponse::new(error_codes::INVALID_PATTERN, message);
+    if let Some(position) = result.error.and_then(|e| e.position) {
+        response = response
+            .with_position(position)
+            .with_context(error_context(pattern, position));
+    }
+    if let Some(suggestion) = result.suggestion {
+        response = response.with_suggestion(suggestion);
+    }
+    response
+}
+
+/// A short excerpt of the pattern around `position` with a caret under it
+fn error_context(pattern: &str, position: usize) -> String {
+    const RADIUS: usize = 15;
+
+    let mut split = position.min(pattern.len());
+    while !pattern.is_char_boundary(split) {
+        split -= 1;
+    }
+    let (before, after) = pattern.split_at(split);
+
+    let skip = before.chars().count().saturating_sub(RADIUS);
+    let before: String = before.chars().skip(skip).collect();
+    let after: String = after.chars().take(RADIUS).collect();
+
+    format!(
+        "{}{}\n{}^",
+        before,
+        after,
+        " ".repeat(before.chars().count())
+    )
+}
+
+/// Call a specific tool within the server's per-call time budget, so no
+/// single expensive operation can stall the stdio loop.
+///
+/// `regex_apply` writes files, and an abandoned worker would still write
+/// after the caller was told it timed out, so it runs to completion here.
+fn call_tool(name: &str, arguments: &Value, config: &ServerConfig) -> Result<String, CoreError> {
+    if name == "regex_apply" {
+        return dispatch_tool(name, arguments, config);
+    }
+
+    let (name, arguments, config) = (name.to_string(), arguments.clone(), config.clone());
+    run_with_deadline(Duration::from_millis(config.timeout_ms), move || {
+        dispatch_tool(&name, &arguments, &config)
+    })
+}
+
+/// Run a specific tool
+fn dispatch_tool(
+    name: &str,
+    arguments: &Value,
+    config: &ServerConfig,
+) -> Result<String, CoreError> {
+    if let Some(pattern) = arguments.get("pattern").and_then(|v| v.as_str()) {
+        core::check_group_limit(pattern, config.max_groups)?;
+    }
+
     match name {
         "regex_test" => {
             let pattern = arguments
@@ -418,6 +825,12 @@ fn call_tool(name: &str, arguments: &Value) -> Result<String, String> {
                 .and_then(|v| v.as_str())
                 .ok_or("pattern is required")?;
 
+            let allow_empty = arguments
+                .get("allow_empty")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            core::validate_runnable_pattern(pattern, allow_empty)?;
+
             let input = arguments.get("input").and_then(|v| v.as_str());
 
             let file_path = arguments.get("file_path").and_then(|v| v.as_str());
@@ -433,29 +846,155 @@ fn call_tool(name: &str, arguments: &Value) -> Result<String, String> {
                 .and_then(|v| v.as_bool())
                 .unwrap_or(false);
 
+            let group = arguments.get("group").and_then(|v| match v {
+                Value::String(s) => Some(s.clone()),
+                Value::Number(n) => Some(n.to_string()),
+                _ => None,
+            });
+
+            let no_unicode = arguments
+                .get("no_unicode")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+
+            let full_match = arguments
+                .get("full_match")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+
+            let by_capture = arguments.get("by_capture").and_then(|v| match v {
+                Value::String(s) => Some(s.clone()),
+                Value::Number(n) => Some(n.to_string()),
+                _ => None,
+            });
+
+            let top = arguments
+                .get("top")
+                .and_then(|v| v.as_u64())
+                .map(|v| v as usize);
+
+            let per_line_count = arguments
+                .get("per_line_count")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            if per_line_count && file_path.is_none() {
+                return Err("per_line_count requires file_path".into());
+            }
+
+            let context_chars = arguments
+                .get("context_chars")
+                .and_then(|v| v.as_u64())
+                .map(|v| v as usize);
+
+            let allow_empty_matches = arguments
+                .get("allow_empty_matches")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+
+            let first = arguments
+                .get("first")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+
+            let timeout_per_line_ms = arguments
+                .get("timeout_per_line_ms")
+                .and_then(|v| v.as_u64());
+            if timeout_per_line_ms.is_some() && file_path.is_none() {
+                return Err("timeout_per_line_ms requires file_path".into());
+            }
+
+            let byte_mode = arguments
+                .get("byte_mode")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+
             let options = core::TestOptions {
                 max_matches: Some(max_matches),
                 engine: None,
                 multiline,
+                group,
+                no_unicode,
+                full_match,
+                by_capture,
+                top,
+                per_line_count,
+                context_chars,
+                allow_empty_matches,
+                first,
+                timeout_per_line_ms,
+                byte_mode,
+                ..Default::default()
             };
 
-            let result = if let Some(fp) = file_path {
+            let sort = arguments
+                .get("sort")
+                .and_then(|v| v.as_str())
+                .unwrap_or("position");
+
+            let dedup = arguments
+                .get("dedup")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+
+            let mut result = if let Some(fp) = file_path {
                 core::test_file(pattern, std::path::Path::new(fp), &options)?
             } else if let Some(text) = input {
                 core::test_string(pattern, text, &options)?
             } else {
-                return Err("Either input or file_path is required".to_string());
+                return Err("Either input or file_path is required".into());
             };
+            if dedup {
+                result.matches = core::dedup_matches(std::mem::take(&mut result.matches));
+            }
+            core::sort_matches(&mut result.matches, sort)?;
 
             Ok(format_json(&result))
         }
 
+        "regex_is_match" => {
+            let pattern = arguments
+                .get("pattern")
+                .and_then(|v| v.as_str())
+                .ok_or("pattern is required")?;
+            core::validate_runnable_pattern(pattern, false)?;
+
+            let input = arguments.get("input").and_then(|v| v.as_str());
+
+            let file_path = arguments.get("file_path").and_then(|v| v.as_str());
+
+            let multiline = arguments
+                .get("multiline")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+
+            let options = core::TestOptions {
+                multiline,
+                ..Default::default()
+            };
+
+            let matched = if let Some(fp) = file_path {
+                core::is_match_file(pattern, std::path::Path::new(fp), &options)?
+            } else if let Some(text) = input {
+                core::is_match_string(pattern, text, &options)?
+            } else {
+                return Err("Either input or file_path is required".into());
+            };
+
+            Ok(format_json(&IsMatchResult { matched }))
+        }
+
         "regex_replace" => {
             let pattern = arguments
                 .get("pattern")
                 .and_then(|v| v.as_str())
                 .ok_or("pattern is required")?;
 
+            let allow_empty = arguments
+                .get("allow_empty")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            core::validate_runnable_pattern(pattern, allow_empty)?;
+
             let replacement = arguments
                 .get("replacement")
                 .and_then(|v| v.as_str())
@@ -470,20 +1009,62 @@ fn call_tool(name: &str, arguments: &Value) -> Result<String, String> {
                 .and_then(|v| v.as_bool())
                 .unwrap_or(false);
 
+            let max_result_bytes = arguments
+                .get("max_result_bytes")
+                .and_then(|v| v.as_u64())
+                .map(|v| v as usize);
+
+            let literal_replacement = arguments
+                .get("literal_replacement")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+
+            let count_only = arguments
+                .get("count_only")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+
+            let count = arguments
+                .get("count")
+                .and_then(|v| v.as_u64())
+                .map(|v| v as usize);
+
             if let Some(fp) = file_path {
                 let result = core::replace_file_preview(
                     pattern,
                     replacement,
                     std::path::Path::new(fp),
-                    Some(20),
+                    Some(if count_only { 0 } else { 20 }),
                     multiline,
+                    literal_replacement,
+                    count,
                 )?;
+                if count_only {
+                    return Ok(format_json(&ReplaceCountResult {
+                        replacements_made: result.replacements_made,
+                    }));
+                }
                 Ok(format_json(&result))
             } else if let Some(text) = input {
-                let result = core::replace_with_captures(pattern, replacement, text, multiline)?;
+                let mut result = core::replace_with_captures(
+                    pattern,
+                    replacement,
+                    text,
+                    multiline,
+                    literal_replacement,
+                    count,
+                )?;
+                if count_only {
+                    return Ok(format_json(&ReplaceCountResult {
+                        replacements_made: result.replacements_made,
+                    }));
+                }
+                if let Some(max_bytes) = max_result_bytes {
+                    core::truncate_result(&mut result, max_bytes);
+                }
                 Ok(format_json(&result))
             } else {
-                Err("Either input or file_path is required".to_string())
+                Err("Either input or file_path is required".into())
             }
         }
 
@@ -494,13 +1075,44 @@ fn call_tool(name: &str, arguments: &Value) -> Result<String, String> {
                 .ok_or("pattern is required")?;
 
             let target_lang = arguments.get("target_lang").and_then(|v| v.as_str());
+            let all_targets = arguments
+                .get("all_targets")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+
+            let flags = arguments
+                .get("flags")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+
+            let size_limit = arguments
+                .get("size_limit")
+                .and_then(|v| v.as_u64())
+                .map(|n| n as usize);
+
+            let options = core::ValidateOptions {
+                max_depth: config.max_depth,
+                flags,
+                size_limit,
+                effective_pattern: false,
+            };
 
-            let result = if let Some(lang) = target_lang {
-                core::validate_for_language(pattern, lang)
+            let mut result = if all_targets {
+                core::validate_all_targets(pattern, &options)
+            } else if let Some(lang) = target_lang {
+                core::validate_for_language(pattern, lang, &options)
             } else {
-                core::validate_pattern(pattern)
+                core::validate_pattern(pattern, &options)
             };
 
+            let verbose_portability = arguments
+                .get("verbose_portability")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            if verbose_portability {
+                core::detail_portability(&mut result, pattern);
+            }
+
             Ok(format_json(&result))
         }
 
@@ -510,7 +1122,94 @@ fn call_tool(name: &str, arguments: &Value) -> Result<String, String> {
                 .and_then(|v| v.as_str())
                 .ok_or("pattern is required")?;
 
-            let result = core::explain_pattern(pattern)?;
+            let options = core::ExplainOptions {
+                max_depth: config.max_depth,
+                target: arguments
+                    .get("target")
+                    .and_then(|v| v.as_str())
+                    .map(String::from),
+            };
+
+            let names_only = arguments
+                .get("names_only")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+
+            if names_only {
+                let result = core::explain_group_names(pattern, &options)?;
+                return Ok(format_json(&result));
+            }
+
+            let result = core::explain_pattern(pattern, &options)?;
+            Ok(format_json(&result))
+        }
+
+        "regex_simplify" => {
+            let pattern = arguments
+                .get("pattern")
+                .and_then(|v| v.as_str())
+                .ok_or("pattern is required")?;
+
+            let result = core::simplify(pattern)?;
+            Ok(format_json(&result))
+        }
+
+        "regex_normalize" => {
+            let pattern = arguments
+                .get("pattern")
+                .and_then(|v| v.as_str())
+                .ok_or("pattern is required")?;
+
+            let result = core::canonicalize(pattern)?;
+            Ok(format_json(&result))
+        }
+
+        "regex_lint" => {
+            let pattern = arguments
+                .get("pattern")
+                .and_then(|v| v.as_str())
+                .ok_or("pattern is required")?;
+
+            let result = core::lint_pattern(pattern)?;
+            Ok(format_json(&result))
+        }
+
+        "regex_ast_diff" => {
+            let a = arguments
+                .get("a")
+                .and_then(|v| v.as_str())
+                .ok_or("a is required")?;
+            let b = arguments
+                .get("b")
+                .and_then(|v| v.as_str())
+                .ok_or("b is required")?;
+
+            let result = core::ast_diff(a, b)?;
+            Ok(format_json(&result))
+        }
+
+        "regex_generate_corpus" => {
+            let pattern = arguments
+                .get("pattern")
+                .and_then(|v| v.as_str())
+                .ok_or("pattern is required")?;
+
+            let count = arguments
+                .get("count")
+                .and_then(|v| v.as_u64())
+                .map(|v| v as usize)
+                .unwrap_or(10);
+
+            let include_near_misses = arguments
+                .get("include_near_misses")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+
+            let options = core::CorpusOptions {
+                count,
+                include_near_misses,
+            };
+            let result = core::generate_corpus(pattern, &options)?;
             Ok(format_json(&result))
         }
 
@@ -534,7 +1233,12 @@ fn call_tool(name: &str, arguments: &Value) -> Result<String, String> {
                         .collect()
                 });
 
-            let result = core::infer_patterns(&examples, negatives.as_deref())?;
+            let format_only = arguments
+                .get("format_only")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+
+            let result = core::infer_patterns(&examples, negatives.as_deref(), format_only)?;
 
             Ok(format_json(&result))
         }
@@ -545,6 +1249,12 @@ fn call_tool(name: &str, arguments: &Value) -> Result<String, String> {
                 .and_then(|v| v.as_str())
                 .ok_or("pattern is required")?;
 
+            let allow_empty = arguments
+                .get("allow_empty")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            core::validate_runnable_pattern(pattern, allow_empty)?;
+
             let replacement = arguments
                 .get("replacement")
                 .and_then(|v| v.as_str())
@@ -576,52 +1286,175 @@ fn call_tool(name: &str, arguments: &Value) -> Result<String, String> {
                 .and_then(|v| v.as_bool())
                 .unwrap_or(false);
 
+            let literal_replacement = arguments
+                .get("literal_replacement")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+
+            let count_only = arguments
+                .get("count_only")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+
+            let count = arguments
+                .get("count")
+                .and_then(|v| v.as_u64())
+                .map(|v| v as usize);
+
+            let only_lines_matching = arguments
+                .get("only_lines_matching")
+                .and_then(|v| v.as_str());
+
+            let diff = arguments
+                .get("diff")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+
             let result = core::apply_file(
                 pattern,
                 replacement,
                 std::path::Path::new(file_path),
-                dry_run,
+                dry_run || diff,
                 backup,
-                Some(max_preview),
+                Some(if count_only { 0 } else { max_preview }),
                 multiline,
+                literal_replacement,
+                count,
+                only_lines_matching,
+                diff,
             )?;
 
+            if count_only {
+                return Ok(format_json(&ReplaceCountResult {
+                    replacements_made: result.replacements_made,
+                }));
+            }
             Ok(format_json(&result))
         }
 
-        "regex_benchmark" => {
-            let pattern = arguments
-                .get("pattern")
-                .and_then(|v| v.as_str())
-                .ok_or("pattern is required")?;
+        "regex_benchmark" => benchmark_tool(arguments),
 
-            let input = arguments.get("input").and_then(|v| v.as_str());
-
-            let file_path = arguments.get("file_path").and_then(|v| v.as_str());
-
-            let timeout_ms = arguments
-                .get("timeout_ms")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(5000);
+        _ => Err(format!("Unknown tool: {}", name).into()),
+    }
+}
 
-            let options = core::BenchmarkOptions {
-                iterations: 100,
-                timeout_ms,
-            };
+/// Run the `regex_benchmark` tool
+fn benchmark_tool(arguments: &Value) -> Result<String, CoreError> {
+    let timeout_ms = arguments
+        .get("timeout_ms")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(5000);
+    // Every phase, and every pass within it, stops at this wall-clock cap
+    let deadline = Instant::now().checked_add(Duration::from_millis(timeout_ms));
+
+    let pattern = arguments
+        .get("pattern")
+        .and_then(|v| v.as_str())
+        .ok_or("pattern is required")?;
+
+    let input = arguments.get("input").and_then(|v| v.as_str());
+
+    let file_path = arguments.get("file_path").and_then(|v| v.as_str());
+
+    let detect_only = arguments
+        .get("detect_only")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let per_line = arguments
+        .get("per_line")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    if per_line && file_path.is_none() {
+        return Err("per_line requires file_path".into());
+    }
 
-            let result = if let Some(fp) = file_path {
-                core::benchmark_file(pattern, std::path::Path::new(fp), &options)?
-            } else if let Some(text) = input {
-                core::benchmark_pattern(pattern, text, &options)?
-            } else {
-                // Generate adversarial input
-                let evil_input = core::benchmark::generate_redos_input(pattern);
-                core::benchmark_pattern(pattern, &evil_input, &options)?
-            };
+    let sample = arguments
+        .get("sample")
+        .and_then(|v| v.as_u64())
+        .map(|n| n as usize);
+    if sample.is_some() && file_path.is_none() {
+        return Err("sample requires file_path".into());
+    }
+    let seed = arguments.get("seed").and_then(|v| v.as_u64()).unwrap_or(0);
+
+    let sweep = arguments
+        .get("sweep")
+        .and_then(|v| v.as_array())
+        .map(|sizes| {
+            sizes
+                .iter()
+                .map(|n| n.as_u64().map(|n| n as usize))
+                .collect::<Option<Vec<_>>>()
+                .ok_or("sweep must be an array of integers")
+        })
+        .transpose()?
+        .unwrap_or_default();
+
+    let options = core::BenchmarkOptions {
+        iterations: 100,
+        timeout_ms,
+        per_line,
+        sweep,
+        sample,
+        seed,
+        deadline,
+        ..Default::default()
+    };
+
+    let result = if detect_only {
+        core::benchmark::detect_only(pattern)?
+    } else if let Some(fp) = file_path {
+        core::benchmark_file(pattern, std::path::Path::new(fp), &options)?
+    } else if let Some(text) = input {
+        core::benchmark_pattern(pattern, text, &options)?
+    } else {
+        // Generate adversarial input
+        let templates = match arguments.get("redos_templates").and_then(|v| v.as_str()) {
+            Some(path) => core::benchmark::parse_redos_templates(
+                &std::fs::read_to_string(path)
+                    .map_err(|e| format!("Failed to read ReDoS templates file: {}", e))?,
+            )?,
+            None => Vec::new(),
+        };
+        let evil_input = core::benchmark::generate_redos_input(pattern, &templates);
+        core::benchmark_pattern(pattern, &evil_input, &options)?
+    };
 
-            Ok(format_json(&result))
-        }
+    Ok(format_json(&result))
+}
 
-        _ => Err(format!("Unknown tool: {}", name)),
+/// Run `f` on a worker thread, giving up once `deadline` has passed so a
+/// runaway match cannot hang the server loop.
+///
+/// Threads cannot be interrupted, so an abandoned worker runs to completion
+/// in the background and its result is discarded. Calls are refused while
+/// `MAX_ABANDONED_WORKERS` of those are still running, so a client retrying
+/// slow patterns cannot pile up threads.
+fn run_with_deadline<F>(deadline: Duration, f: F) -> Result<String, CoreError>
+where
+    F: FnOnce() -> Result<String, CoreError> + Send + 'static,
+{
+    // Calls are handled one at a time, so every other live worker was abandoned
+    let slot = WorkerSlot::claim().ok_or_else(|| {
+        format!(
+            "{} timed-out tool calls are still running; retry once they finish",
+            MAX_ABANDONED_WORKERS
+        )
+    })?;
+
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let _slot = slot;
+        // The receiver is gone if the deadline already passed
+        let _ = sender.send(f());
+    });
+
+    match receiver.recv_timeout(deadline) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => Err(CoreError::Timeout {
+            millis: deadline.as_millis(),
+        }),
+        Err(RecvTimeoutError::Disconnected) => Err("Tool worker thread panicked".into()),
     }
 }
@@ -0,0 +1,83 @@
+//! CSV output formatting
+//!
+//! Used when --format csv is specified. Only `test` results are tabular:
+//! matches, `--by-capture` counts, `--per-line-count` lines and
+//! `--only-groups` tables each become a header row plus one row per record.
+
+use super::types::*;
+
+/// Format a test result as CSV: one row per match with its span, text and
+/// capture values (columns from `groups`), or the counts for `--by-capture`
+/// and `--per-line-count`
+pub fn format_test_csv(result: &TestResult, groups: &GroupRowsResult, delimiter: u8) -> String {
+    if !result.lines.is_empty() {
+        return write_csv(
+            delimiter,
+            ["line_number", "count"].map(String::from).to_vec(),
+            result
+                .lines
+                .iter()
+                .map(|l| vec![l.line_number.to_string(), l.count.to_string()]),
+        );
+    }
+
+    if !result.capture_counts.is_empty() {
+        return write_csv(
+            delimiter,
+            ["value", "count"].map(String::from).to_vec(),
+            result
+                .capture_counts
+                .iter()
+                .map(|c| vec![c.value.clone(), c.count.to_string()]),
+        );
+    }
+
+    let header = ["start", "end", "text"]
+        .map(String::from)
+        .into_iter()
+        .chain(groups.columns.iter().cloned())
+        .collect();
+    let rows = result.matches.iter().zip(&groups.rows).map(|(m, row)| {
+        [m.start.to_string(), m.end.to_string(), m.text.clone()]
+            .into_iter()
+            .chain(row.iter().map(|v| v.clone().unwrap_or_default()))
+            .collect()
+    });
+    write_csv(delimiter, header, rows)
+}
+
+/// Format an `--only-groups` table as CSV; a group that did not participate
+/// is an empty field
+pub fn format_group_rows_csv(result: &GroupRowsResult, delimiter: u8) -> String {
+    write_csv(
+        delimiter,
+        result.columns.clone(),
+        result
+            .rows
+            .iter()
+            .map(|row| row.iter().map(|v| v.clone().unwrap_or_default()).collect()),
+    )
+}
+
+/// Write a header and records, quoting fields that contain the delimiter,
+/// quotes or newlines
+fn write_csv(
+    delimiter: u8,
+    header: Vec<String>,
+    rows: impl Iterator<Item = Vec<String>>,
+) -> String {
+    let mut writer = ::csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(Vec::new());
+
+    let written = std::iter::once(header)
+        .chain(rows)
+        .try_for_each(|record| writer.write_record(&record))
+        .map_err(|e| e.to_string())
+        .and_then(|_| writer.into_inner().map_err(|e| e.to_string()));
+
+    match written {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+        Err(e) => super::json::format_json(&ErrorResponse::new("SERIALIZATION_ERROR", e)),
+    }
+}
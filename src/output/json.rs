@@ -15,7 +15,6 @@ pub fn format_json<T: Serialize>(result: &T) -> String {
 }
 
 /// Format a result as compact JSON (single line)
-#[allow(dead_code)]
 pub fn format_json_compact<T: Serialize>(result: &T) -> String {
     serde_json::to_string(result).unwrap_or_else(|e| {
         format!(
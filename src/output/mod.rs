@@ -1,7 +1,8 @@
 //! Output formatting module for re-x
 //!
-//! Provides JSON (default) and text output formats.
+//! Provides JSON (default), text and CSV output formats.
 
+pub mod csv;
 pub mod json;
 pub mod text;
 pub mod types;
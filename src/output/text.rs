@@ -5,10 +5,20 @@
 use super::types::*;
 
 /// Format TestResult as human-readable text
-pub fn format_test_result(result: &TestResult) -> String {
+///
+/// With `highlight_captures`, each match is also rendered with ANSI colors:
+/// the match in one color and each capture group in its own, inner groups
+/// drawn over the groups that contain them.
+pub fn format_test_result(result: &TestResult, highlight_captures: bool) -> String {
     let mut output = String::new();
 
     output.push_str(&format!("Pattern: {}\n", result.pattern));
+    if let Some(ref normalized) = result.normalized_pattern {
+        output.push_str(&format!("As:      {}\n", normalized));
+    }
+    if let Some(ref effective) = result.effective_pattern {
+        output.push_str(&format!("Runs as: {}\n", effective));
+    }
     output.push_str(&format!(
         "Engine:  {} ({})\n",
         result.engine,
@@ -18,17 +28,58 @@ pub fn format_test_result(result: &TestResult) -> String {
             "backtracking"
         }
     ));
+    if let Some(ref reason) = result.engine_reason {
+        output.push_str(&format!("Reason:  {}\n", reason));
+    }
     output.push('\n');
 
-    if result.matched {
+    if result.full_match {
+        output.push_str(if result.matched {
+            "✓ Entire input matches\n"
+        } else {
+            "✗ Input does not match in full\n"
+        });
+        for m in &result.matches {
+            for cap in &m.captures {
+                let name_str = cap
+                    .name
+                    .as_ref()
+                    .map(|n| format!(" ({})", n))
+                    .unwrap_or_default();
+                output.push_str(&format!(
+                    "  Group {}{}: \"{}\" [{}..{}]\n",
+                    cap.group, name_str, cap.text, cap.start, cap.end
+                ));
+            }
+        }
+    } else if result.matched {
+        for line in &result.lines {
+            output.push_str(&format!("Line {}: {}\n", line.line_number, line.count));
+        }
         for (i, m) in result.matches.iter().enumerate() {
+            let occurrences = m
+                .occurrences
+                .map(|n| format!(" × {}", n))
+                .unwrap_or_default();
+            let location = m
+                .location
+                .map(|l| format!(" at {}:{}", l.line, l.column))
+                .unwrap_or_default();
             output.push_str(&format!(
-                "Match {}: \"{}\" [{}..{}]\n",
-                i + 1,
+                "Match {}: \"{}\"{} [{}..{}]{}\n",
+                m.index.unwrap_or(i) + 1,
                 m.text,
+                occurrences,
                 m.start,
-                m.end
+                m.end,
+                location
             ));
+            if let Some(ref context) = m.context {
+                output.push_str(&format!("  Context: {}\n", context));
+            }
+            if highlight_captures {
+                output.push_str(&format!("  Highlight: {}\n", highlight_match(m)));
+            }
 
             for cap in &m.captures {
                 let name_str = cap
@@ -36,23 +87,106 @@ pub fn format_test_result(result: &TestResult) -> String {
                     .as_ref()
                     .map(|n| format!(" ({})", n))
                     .unwrap_or_default();
+                let label = format!("Group {}{}", cap.group, name_str);
+                let label = if highlight_captures {
+                    format!("{}{}{}", capture_style(cap.group), label, ANSI_RESET)
+                } else {
+                    label
+                };
                 output.push_str(&format!(
-                    "  Group {}{}: \"{}\" [{}..{}]\n",
-                    cap.group, name_str, cap.text, cap.start, cap.end
+                    "  {}: \"{}\" [{}..{}]\n",
+                    label, cap.text, cap.start, cap.end
                 ));
             }
         }
         output.push('\n');
         output.push_str(&format!(
-            "{} match{} found in {}μs\n",
+            "{} match{} found in {}μs (compile {}μs, match {}μs)\n",
             result.match_count,
             if result.match_count == 1 { "" } else { "es" },
-            result.elapsed_us
+            result.elapsed_us,
+            result.compile_us,
+            result.match_us
         ));
     } else {
         output.push_str("No matches found\n");
     }
 
+    if !result.capture_counts.is_empty() {
+        output.push_str("\nCounts:\n");
+        for c in &result.capture_counts {
+            output.push_str(&format!("  {:>6}  {}\n", c.count, c.value));
+        }
+    }
+
+    if let Some(ref warning) = result.warning {
+        output.push_str(&format!("\n⚠ {}\n", warning));
+    }
+
+    output
+}
+
+const ANSI_RESET: &str = "\x1b[0m";
+const MATCH_STYLE: &str = "\x1b[1;33m";
+const CAPTURE_STYLES: [&str; 5] = [
+    "\x1b[1;32m",
+    "\x1b[1;36m",
+    "\x1b[1;35m",
+    "\x1b[1;34m",
+    "\x1b[1;31m",
+];
+
+/// Color for capture group `group` (1-based), cycling through the palette
+fn capture_style(group: usize) -> &'static str {
+    CAPTURE_STYLES[group.saturating_sub(1) % CAPTURE_STYLES.len()]
+}
+
+/// The match text with each capture group colored.
+///
+/// The text is cut at every group boundary; each piece takes the color of
+/// the innermost group covering it (the shortest span, and the later group
+/// on ties), or the match color if none does. Captures outside the match,
+/// e.g. from lookahead, are clipped to it.
+fn highlight_match(m: &Match) -> String {
+    let spans: Vec<(usize, usize, usize)> = m
+        .captures
+        .iter()
+        .map(|c| (c.group, c.start.max(m.start), c.end.min(m.end)))
+        .filter(|&(_, start, end)| start < end)
+        .collect();
+
+    let mut cuts: Vec<usize> = spans
+        .iter()
+        .flat_map(|&(_, start, end)| [start, end])
+        .chain([m.start, m.end])
+        .collect();
+    cuts.sort_unstable();
+    cuts.dedup();
+
+    let mut output = String::new();
+    for piece in cuts.windows(2) {
+        let (from, to) = (piece[0], piece[1]);
+        let style = spans
+            .iter()
+            .filter(|&&(_, start, end)| start <= from && to <= end)
+            .min_by_key(|&&(group, start, end)| (end - start, std::cmp::Reverse(group)))
+            .map_or(MATCH_STYLE, |&(group, _, _)| capture_style(group));
+        output.push_str(style);
+        output.push_str(&m.text[from - m.start..to - m.start]);
+        output.push_str(ANSI_RESET);
+    }
+    output
+}
+
+/// Format GroupRowsResult as tab-separated values with a header row;
+/// a group that did not participate is an empty cell
+pub fn format_group_rows(result: &GroupRowsResult) -> String {
+    let mut output = result.columns.join("\t");
+    for row in &result.rows {
+        output.push('\n');
+        let cells: Vec<&str> = row.iter().map(|v| v.as_deref().unwrap_or("")).collect();
+        output.push_str(&cells.join("\t"));
+    }
     output
 }
 
@@ -61,10 +195,24 @@ pub fn format_replace_result(result: &ReplaceResult) -> String {
     let mut output = String::new();
 
     output.push_str(&format!("Pattern:     {}\n", result.pattern));
+    if let Some(ref normalized) = result.normalized_pattern {
+        output.push_str(&format!("As:          {}\n", normalized));
+    }
     output.push_str(&format!("Replacement: {}\n", result.replacement));
     output.push('\n');
     output.push_str(&format!("Original: {}\n", result.original));
     output.push_str(&format!("Result:   {}\n", result.result));
+    if !result.spans.is_empty() {
+        let spans: Vec<String> = result
+            .spans
+            .iter()
+            .map(|s| format!("[{}..{}]", s.start, s.end))
+            .collect();
+        output.push_str(&format!("Replaced: {}\n", spans.join(", ")));
+    }
+    if result.truncated {
+        output.push_str("(original/result truncated by --max-result-bytes)\n");
+    }
     output.push('\n');
     output.push_str(&format!(
         "{} replacement{} made\n",
@@ -75,10 +223,91 @@ pub fn format_replace_result(result: &ReplaceResult) -> String {
             "s"
         }
     ));
+    if let Some(remaining) = result.remaining_matches {
+        output.push_str(&format!("{} more left untouched by --count\n", remaining));
+    }
+
+    output
+}
+
+/// Format TestManyResult as one line per pattern: a ✓/✗ mark, the pattern
+/// and its match count, or `!` and the error for a pattern that failed
+pub fn format_test_many_result(result: &TestManyResult) -> String {
+    let mut output = String::new();
+
+    let width = result
+        .results
+        .iter()
+        .map(|r| r.pattern.chars().count())
+        .max()
+        .unwrap_or(0);
+    for r in &result.results {
+        let (mark, detail) = match r.error {
+            Some(ref error) => ("!", error.clone()),
+            None => (
+                if r.matched { "✓" } else { "✗" },
+                format!(
+                    "{} match{}",
+                    r.match_count,
+                    if r.match_count == 1 { "" } else { "es" }
+                ),
+            ),
+        };
+        output.push_str(&format!(
+            "{} {:width$}  {}\n",
+            mark,
+            r.pattern,
+            detail,
+            width = width
+        ));
+    }
+
+    output.push_str(&format!(
+        "\n{} of {} patterns matched",
+        result.matched_patterns,
+        result.results.len()
+    ));
+    if result.failed_patterns > 0 {
+        output.push_str(&format!(", {} failed", result.failed_patterns));
+    }
+    output.push_str(&format!(" in {}μs\n", result.elapsed_us));
 
     output
 }
 
+/// Format CoverageResult as the pattern with the part that matches marked
+/// and the point where matching stops
+pub fn format_coverage_result(result: &CoverageResult) -> String {
+    let mut output = format!("Pattern: {}\n", result.pattern);
+
+    let Some(failed_at) = result.failed_at else {
+        output.push_str(&format!(
+            "\nThe whole pattern matches: {:?}\n",
+            result.prefix_match.as_deref().unwrap_or_default()
+        ));
+        return output;
+    };
+
+    let Some(ref text) = result.prefix_match else {
+        output.push_str("\nNo prefix of the pattern matches the input\n");
+        return output;
+    };
+    output.push_str(&format!(
+        "         {}^ matching stops here\n\n",
+        " ".repeat(result.pattern[..failed_at].chars().count())
+    ));
+    output.push_str(&format!(
+        "Matches: {} (first match {:?})\n",
+        result.matched_prefix, text
+    ));
+    output.push_str(&format!(
+        "Fails:   {} (from position {})\n",
+        &result.pattern[failed_at..],
+        failed_at
+    ));
+    output
+}
+
 /// Format ValidateResult as human-readable text
 pub fn format_validate_result(result: &ValidateResult) -> String {
     let mut output = String::new();
@@ -90,10 +319,48 @@ pub fn format_validate_result(result: &ValidateResult) -> String {
             output.push_str(&format!("\nEngine required: {}\n", engine));
         }
 
+        if let Some(ref effective) = result.effective_pattern {
+            output.push_str(&format!("Effective pattern: {}\n", effective));
+        }
+
         if let Some(ref reason) = result.reason {
             output.push_str(&format!("Reason: {}\n", reason));
         }
 
+        if let Some(ref flags) = result.active_flags {
+            let enabled: Vec<&str> = [
+                (flags.case_insensitive, "case-insensitive"),
+                (flags.multiline, "multi-line mode"),
+                (flags.dot_matches_newline, "dot matches newline"),
+                (flags.swap_greed, "swap greedy/non-greedy"),
+                (flags.unicode, "unicode mode"),
+                (flags.ignore_whitespace, "ignore whitespace"),
+                (flags.crlf, "CRLF mode"),
+            ]
+            .into_iter()
+            .filter_map(|(on, name)| on.then_some(name))
+            .collect();
+            let enabled = if enabled.is_empty() {
+                "none".to_string()
+            } else {
+                enabled.join(", ")
+            };
+            output.push_str(&format!("Flags: {}\n", enabled));
+            if flags.mid_pattern {
+                output.push_str("  (set mid-pattern; earlier parts may use different flags)\n");
+            }
+        }
+
+        if !result.capture_groups.is_empty() {
+            output.push_str("\nCapture groups:\n");
+            for group in &result.capture_groups {
+                match group.name {
+                    Some(ref name) => output.push_str(&format!("  ${} ({})\n", group.index, name)),
+                    None => output.push_str(&format!("  ${}\n", group.index)),
+                }
+            }
+        }
+
         if let Some(ref portability) = result.portability {
             output.push_str("\nPortability:\n");
             output.push_str(&format!(
@@ -138,6 +405,62 @@ pub fn format_validate_result(result: &ValidateResult) -> String {
                 "  Ruby:          {}\n",
                 if portability.ruby { "✓" } else { "✗" }
             ));
+            output.push_str(&format!(
+                "  POSIX ERE:     {}\n",
+                if portability.posix_ere { "✓" } else { "✗" }
+            ));
+            output.push_str(&format!(
+                "  POSIX BRE:     {}\n",
+                if portability.posix_bre { "✓" } else { "✗" }
+            ));
+        }
+
+        if let Some(ref detailed) = result.detailed_portability {
+            output.push_str("\nPortability:\n");
+            for (name, engine) in [
+                ("Rust regex", &detailed.rust_regex),
+                ("PCRE2", &detailed.pcre2),
+                ("JavaScript", &detailed.javascript),
+                ("Python re", &detailed.python_re),
+                ("Python regex", &detailed.python_regex),
+                ("Go regexp", &detailed.go_regexp),
+                ("Java", &detailed.java),
+                (".NET", &detailed.dotnet),
+                ("Ruby", &detailed.ruby),
+            ] {
+                output.push_str(&format!(
+                    "  {:<14} {}\n",
+                    format!("{}:", name),
+                    if engine.compatible { "✓" } else { "✗" }
+                ));
+                for issue in &engine.issues {
+                    output.push_str(&format!("      {}\n", issue));
+                }
+            }
+        }
+
+        if !result.warnings.is_empty() {
+            output.push_str("\nWarnings:\n");
+            for warning in &result.warnings {
+                output.push_str(&format!("  ⚠ {}\n", warning));
+            }
+        }
+
+        if !result.targets.is_empty() {
+            output.push_str("\nTargets:\n");
+            for report in &result.targets {
+                output.push_str(&format!(
+                    "  {} {}\n",
+                    if report.compatible { "✓" } else { "✗" },
+                    report.target
+                ));
+                for issue in &report.issues {
+                    output.push_str(&format!("      {}\n", issue));
+                }
+                if let Some(ref rewrite) = report.rewrite {
+                    output.push_str(&format!("      Rewrite: {}\n", rewrite));
+                }
+            }
         }
     } else {
         output.push_str("✗ Pattern is invalid\n");
@@ -158,6 +481,50 @@ pub fn format_validate_result(result: &ValidateResult) -> String {
     output
 }
 
+/// Format CompareLanguagesResult as a table: one row per target with ✓/✗
+/// and, for rejecting targets, the unsupported features
+pub fn format_compare_languages_result(result: &CompareLanguagesResult) -> String {
+    let mut output = String::new();
+
+    output.push_str(&format!("Pattern: {}\n", result.pattern));
+    output.push_str(&format!(
+        "Accepted by {} of {} targets\n\n",
+        result.compatible.len(),
+        result.targets.len()
+    ));
+
+    let width = result
+        .targets
+        .iter()
+        .map(|report| report.target.len())
+        .max()
+        .unwrap_or(0);
+    for report in &result.targets {
+        let mark = if report.compatible { "✓" } else { "✗" };
+        if report.issues.is_empty() {
+            output.push_str(&format!("  {} {}\n", mark, report.target));
+        } else {
+            output.push_str(&format!(
+                "  {} {:width$}  {}\n",
+                mark,
+                report.target,
+                report.issues.join("; "),
+                width = width
+            ));
+        }
+        if let Some(ref rewrite) = report.rewrite {
+            output.push_str(&format!(
+                "    {:width$}  Rewrite: {}\n",
+                "",
+                rewrite,
+                width = width
+            ));
+        }
+    }
+
+    output
+}
+
 /// Format ExplainResult as human-readable text
 pub fn format_explain_result(result: &ExplainResult) -> String {
     let mut output = String::new();
@@ -183,6 +550,9 @@ pub fn format_explain_result(result: &ExplainResult) -> String {
                 indent_str, part.token, part.token_type, quantifier_str, group_str
             ));
             output.push_str(&format!("{}  {}\n", indent_str, part.desc));
+            if let Some(ref note) = part.portability_note {
+                output.push_str(&format!("{}  ⚠ {}\n", indent_str, note));
+            }
 
             if let Some(ref children) = part.children {
                 format_parts(children, indent + 1, output);
@@ -194,6 +564,274 @@ pub fn format_explain_result(result: &ExplainResult) -> String {
 
     output.push('\n');
     output.push_str(&format!("Summary: {}\n", result.summary));
+    output.push_str(&format!(
+        "Readability: {}/100 ({})\n",
+        result.readability.score, result.readability.label
+    ));
+
+    output
+}
+
+/// Format the named groups of `explain --names-only`, one `index<TAB>name` per line
+pub fn format_group_names(result: &GroupNamesResult) -> String {
+    if result.groups.is_empty() {
+        return "No named groups".to_string();
+    }
+
+    result
+        .groups
+        .iter()
+        .map(|g| format!("{}\t{}", g.index, g.name.as_deref().unwrap_or_default()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Format ExplainResult as a tree with box-drawing connectors (`explain --tree`)
+pub fn format_explain_tree(result: &ExplainResult) -> String {
+    let mut output = String::new();
+
+    output.push_str(&format!("{}\n", result.pattern));
+
+    fn format_nodes(parts: &[ExplainPart], prefix: &str, output: &mut String) {
+        for (i, part) in parts.iter().enumerate() {
+            let last = i + 1 == parts.len();
+            let (connector, continuation) = if last {
+                ("└─ ", "   ")
+            } else {
+                ("├─ ", "│  ")
+            };
+            let quantifier_str = part
+                .quantifier
+                .as_ref()
+                .map(|q| format!(" ({})", q))
+                .unwrap_or_default();
+            let group_str = part
+                .group
+                .map(|g| format!(" [group {}]", g))
+                .unwrap_or_default();
+
+            output.push_str(&format!(
+                "{}{}{} [{}]{}{} — {}\n",
+                prefix,
+                connector,
+                part.token,
+                part.token_type,
+                quantifier_str,
+                group_str,
+                part.desc
+            ));
+
+            let child_prefix = format!("{}{}", prefix, continuation);
+            let children = part.children.as_deref().unwrap_or_default();
+            if let Some(ref note) = part.portability_note {
+                // Keep the guide line to the children running past the note
+                let guide = if children.is_empty() { "" } else { "│ " };
+                output.push_str(&format!("{}{}⚠ {}\n", child_prefix, guide, note));
+            }
+            format_nodes(children, &child_prefix, output);
+        }
+    }
+
+    format_nodes(&result.parts, "", &mut output);
+
+    output.push('\n');
+    output.push_str(&format!("Summary: {}\n", result.summary));
+    output.push_str(&format!(
+        "Readability: {}/100 ({})\n",
+        result.readability.score, result.readability.label
+    ));
+
+    output
+}
+
+/// Format SimplifyResult as human-readable text
+pub fn format_simplify_result(result: &SimplifyResult) -> String {
+    let mut output = String::new();
+
+    output.push_str(&format!("Original:   {}\n", result.original));
+    output.push_str(&format!("Simplified: {}\n", result.simplified));
+    output.push('\n');
+    if result.changed {
+        output.push_str("✓ Simplified (equivalent pattern)\n");
+    } else {
+        output.push_str("Pattern is already minimal\n");
+    }
+
+    output
+}
+
+/// Format NormalizeResult as human-readable text
+pub fn format_normalize_result(result: &NormalizeResult) -> String {
+    let mut output = String::new();
+
+    output.push_str(&format!("Original:   {}\n", result.original));
+    output.push_str(&format!("Normalized: {}\n", result.normalized));
+    output.push('\n');
+    if result.changed {
+        output.push_str("✓ Normalized (equivalent pattern)\n");
+    } else {
+        output.push_str("Pattern is already in canonical form\n");
+    }
+
+    output
+}
+
+/// Format matches as `path:line:col:text` lines for editor quickfix lists
+/// (`test --vimgrep`), `col` being a 1-based byte column as vim and grep
+/// expect; the text is the first line of the match. Matches must
+/// have been located (see `TestOptions::context_json`).
+pub fn format_vimgrep(result: &TestResult, path: &str) -> String {
+    result
+        .matches
+        .iter()
+        .filter_map(|m| {
+            let location = m.location?;
+            let text = m.text.lines().next().unwrap_or_default();
+            Some(format!(
+                "{}:{}:{}:{}\n",
+                path, location.line, location.byte_column, text
+            ))
+        })
+        .collect()
+}
+
+/// Format DedupePatternsResult as human-readable text: each group's canonical
+/// form followed by its members
+pub fn format_dedupe_patterns_result(result: &DedupePatternsResult) -> String {
+    let mut output = String::new();
+
+    for group in &result.groups {
+        output.push_str(&format!("{}\n", group.canonical));
+        for member in &group.members {
+            output.push_str(&format!("  line {}: {}\n", member.line, member.pattern));
+        }
+        output.push('\n');
+    }
+    for invalid in &result.invalid {
+        output.push_str(&format!(
+            "! line {}: {}  ({})\n",
+            invalid.line, invalid.pattern, invalid.error
+        ));
+    }
+    if !result.invalid.is_empty() {
+        output.push('\n');
+    }
+
+    output.push_str(&format!(
+        "{} patterns, {} distinct; {} group{} of equivalent patterns",
+        result.total,
+        result.distinct,
+        result.groups.len(),
+        if result.groups.len() == 1 { "" } else { "s" }
+    ));
+    if !result.invalid.is_empty() {
+        output.push_str(&format!(", {} invalid", result.invalid.len()));
+    }
+    output.push('\n');
+
+    output
+}
+
+/// Format LintResult as human-readable text
+pub fn format_lint_result(result: &LintResult) -> String {
+    let mut output = String::new();
+
+    output.push_str(&format!("Pattern: {}\n\n", result.pattern));
+
+    if result.clean {
+        output.push_str("✓ No issues found\n");
+        return output;
+    }
+
+    for issue in &result.issues {
+        output.push_str(&format!(
+            "[{}..{}] {} → {}  ({})\n",
+            issue.span.start, issue.span.end, issue.token, issue.suggestion, issue.rule
+        ));
+        output.push_str(&format!("  {}\n", issue.message));
+    }
+
+    if let Some(ref fixed) = result.fixed {
+        output.push_str(&format!("\nFixed:   {}\n", fixed));
+    }
+    output
+}
+
+/// Format BuildResult as human-readable text
+pub fn format_build_result(result: &BuildResult) -> String {
+    format!("Pattern: {}\nEngine:  {}\n", result.pattern, result.engine)
+}
+
+/// Format AstDiffResult as human-readable text
+pub fn format_ast_diff_result(result: &AstDiffResult) -> String {
+    let mut output = String::new();
+
+    output.push_str(&format!("A: {}\n", result.a));
+    output.push_str(&format!("B: {}\n\n", result.b));
+
+    if result.identical {
+        output.push_str("✓ Patterns have the same structure\n");
+        return output;
+    }
+
+    for change in &result.changes {
+        let span = |s: &Option<Span>| s.map(|s| format!("{}..{}", s.start, s.end));
+        match change.change.as_str() {
+            "added" => output.push_str(&format!(
+                "+ {} {} (B {})\n",
+                change.node,
+                change.after.as_deref().unwrap_or_default(),
+                span(&change.b_span).unwrap_or_default()
+            )),
+            "removed" => output.push_str(&format!(
+                "- {} {} (A {})\n",
+                change.node,
+                change.before.as_deref().unwrap_or_default(),
+                span(&change.a_span).unwrap_or_default()
+            )),
+            _ => output.push_str(&format!(
+                "~ {} {} → {} (A {}, B {})\n",
+                change.node,
+                change.before.as_deref().unwrap_or_default(),
+                change.after.as_deref().unwrap_or_default(),
+                span(&change.a_span).unwrap_or_default(),
+                span(&change.b_span).unwrap_or_default()
+            )),
+        }
+        if let Some(ref detail) = change.detail {
+            output.push_str(&format!("    {}\n", detail));
+        }
+    }
+
+    output.push_str(&format!(
+        "\n{} structural change{}\n",
+        result.changes.len(),
+        if result.changes.len() == 1 { "" } else { "s" }
+    ));
+    output
+}
+
+/// Format CorpusResult as human-readable text
+pub fn format_corpus_result(result: &CorpusResult) -> String {
+    let mut output = String::new();
+
+    output.push_str(&format!("Pattern: {}\n", result.pattern));
+
+    output.push_str(&format!("\nMatching ({}):\n", result.positives.len()));
+    for positive in &result.positives {
+        output.push_str(&format!("  ✓ {:?}\n", positive));
+    }
+
+    if !result.negatives.is_empty() {
+        output.push_str(&format!("\nNear misses ({}):\n", result.negatives.len()));
+        for negative in &result.negatives {
+            output.push_str(&format!("  ✗ {:?}\n", negative));
+        }
+    }
+
+    if let Some(ref note) = result.note {
+        output.push_str(&format!("\nNote: {}\n", note));
+    }
 
     output
 }
@@ -204,16 +842,64 @@ pub fn format_benchmark_result(result: &BenchmarkResult) -> String {
 
     output.push_str(&format!("Pattern: {}\n", result.pattern));
     output.push_str(&format!("Engine:  {}\n", result.engine));
-    output.push_str(&format!("Input:   {} bytes\n\n", result.input_size_bytes));
+    if let Some(ref note) = result.note {
+        output.push_str(&format!("\n{}\n", note));
+    } else {
+        output.push_str(&format!("Input:   {} bytes", result.input_size_bytes));
+        if let Some(ref sample) = result.sample {
+            output.push_str(&format!(
+                " (sample of {} / {} lines, seed {})",
+                sample.lines, sample.total_lines, sample.seed
+            ));
+        }
+        output.push_str("\n\n");
 
-    output.push_str("Performance:\n");
-    output.push_str(&format!("  Iterations: {}\n", result.iterations));
-    output.push_str(&format!("  Average:    {:.1}μs\n", result.avg_us));
-    output.push_str(&format!("  Median:     {:.1}μs\n", result.median_us));
-    output.push_str(&format!(
-        "  Throughput: {:.2} MB/s\n",
-        result.throughput_mb_s
-    ));
+        output.push_str("Performance:\n");
+        output.push_str(&format!(
+            "  Iterations: {} (+{} warmup)\n",
+            result.iterations, result.warmup_iterations
+        ));
+        output.push_str(&format!("  Average:    {:.1}μs\n", result.avg_us));
+        output.push_str(&format!("  Median:     {:.1}μs\n", result.median_us));
+        output.push_str(&format!(
+            "  Throughput: {:.2} MB/s\n",
+            result.throughput_mb_s
+        ));
+    }
+
+    if let Some(ref c) = result.complexity {
+        output.push_str("\nComplexity:\n");
+        output.push_str(&format!("  Nodes:        {}\n", c.node_count));
+        output.push_str(&format!("  Alternations: {}\n", c.alternation_count));
+        output.push_str(&format!("  Repetitions:  {}\n", c.repetition_count));
+        if let Some(bound) = c.max_quantifier_bound {
+            output.push_str(&format!("  Max bound:    {}\n", bound));
+        }
+        output.push_str(&format!("  Group depth:  {}\n", c.max_group_depth));
+    }
+
+    if !result.growth.is_empty() {
+        output.push_str("\nGrowth:\n");
+        for point in &result.growth {
+            output.push_str(&format!(
+                "  {:>10} bytes: {:.1}μs\n",
+                point.size, point.avg_us
+            ));
+        }
+        if let Some(ref class) = result.growth_class {
+            output.push_str(&format!("  Trend: {}\n", class));
+        }
+    }
+
+    if !result.slowest_lines.is_empty() {
+        output.push_str("\nSlowest lines:\n");
+        for slow in &result.slowest_lines {
+            output.push_str(&format!(
+                "  L{}: {:.1}μs  {}\n",
+                slow.line, slow.time_us, slow.content
+            ));
+        }
+    }
 
     output.push('\n');
     if result.catastrophic_backtracking {
@@ -232,7 +918,10 @@ pub fn format_benchmark_result(result: &BenchmarkResult) -> String {
 }
 
 /// Format ApplyResult as human-readable text
-pub fn format_apply_result(result: &ApplyResult) -> String {
+///
+/// A `--diff` result shows the unified diff in place of the preview, with
+/// removed and added lines in ANSI colors if `color` is set.
+pub fn format_apply_result(result: &ApplyResult, color: bool) -> String {
     let mut output = String::new();
 
     let mode = if result.applied { "APPLIED" } else { "DRY-RUN" };
@@ -255,8 +944,16 @@ pub fn format_apply_result(result: &ApplyResult) -> String {
             "s"
         }
     ));
+    if let Some(remaining) = result.remaining_matches {
+        output.push_str(&format!("{} more left untouched by --count\n", remaining));
+    }
 
-    if !result.preview.is_empty() {
+    if let Some(ref diff) = result.diff {
+        if !diff.is_empty() {
+            output.push('\n');
+            output.push_str(&format_diff(diff, color));
+        }
+    } else if !result.preview.is_empty() {
         output.push_str("\nPreview:\n");
         for p in &result.preview {
             output.push_str(&format!("  L{}: {} -> {}\n", p.line, p.before, p.after));
@@ -266,8 +963,64 @@ pub fn format_apply_result(result: &ApplyResult) -> String {
     output
 }
 
-/// Format FromExamplesResult as human-readable text
-pub fn format_from_examples_result(result: &FromExamplesResult) -> String {
+const DIFF_HEADER_STYLE: &str = "\x1b[1m";
+const DIFF_HUNK_STYLE: &str = "\x1b[36m";
+const DIFF_REMOVED_STYLE: &str = "\x1b[31m";
+const DIFF_ADDED_STYLE: &str = "\x1b[32m";
+
+/// A unified diff, each line colored by its kind when `color` is set
+fn format_diff(diff: &str, color: bool) -> String {
+    if !color {
+        return diff.to_string();
+    }
+
+    let mut output = String::new();
+    for (i, line) in diff.lines().enumerate() {
+        // Only the first two lines are file headers; a removed "-- x" is not
+        let style = if i < 2 {
+            Some(DIFF_HEADER_STYLE)
+        } else if line.starts_with("@@") {
+            Some(DIFF_HUNK_STYLE)
+        } else if line.starts_with('-') {
+            Some(DIFF_REMOVED_STYLE)
+        } else if line.starts_with('+') {
+            Some(DIFF_ADDED_STYLE)
+        } else {
+            None
+        };
+        match style {
+            Some(style) => output.push_str(&format!("{}{}{}\n", style, line, ANSI_RESET)),
+            None => output.push_str(&format!("{}\n", line)),
+        }
+    }
+    output
+}
+
+/// Format the per-file results of `apply --stdin-list` as human-readable text
+pub fn format_apply_results(results: &[ApplyResult], color: bool) -> String {
+    let mut sections = Vec::new();
+
+    for result in results {
+        sections.push(match result.error {
+            Some(ref error) => format!("[ERROR] {}\n{}\n", result.file_path, error),
+            None => format_apply_result(result, color),
+        });
+    }
+
+    let failed = results.iter().filter(|r| r.error.is_some()).count();
+    sections.push(format!(
+        "{} file{} processed, {} failed\n",
+        results.len(),
+        if results.len() == 1 { "" } else { "s" },
+        failed
+    ));
+    sections.join("\n")
+}
+
+/// Format FromExamplesResult as human-readable text.
+///
+/// With `show_unmatched`, each candidate lists the examples it misses.
+pub fn format_from_examples_result(result: &FromExamplesResult, show_unmatched: bool) -> String {
     let mut output = String::new();
 
     output.push_str("Examples:\n");
@@ -291,6 +1044,15 @@ pub fn format_from_examples_result(result: &FromExamplesResult) -> String {
             inf.confidence * 100.0
         ));
         output.push_str(&format!("   {}\n", inf.desc));
+        if show_unmatched && !inf.unmatched_examples.is_empty() {
+            output.push_str(&format!(
+                "   Misses: {}\n",
+                inf.unmatched_examples.join(", ")
+            ));
+        }
+    }
+    if let Some(note) = &result.note {
+        output.push_str(&format!("\n{}\n", note));
     }
 
     output
@@ -31,6 +31,37 @@ pub struct Match {
     pub end: usize,
     /// Capture groups (empty if no capturing groups)
     pub captures: Vec<Capture>,
+    /// Position in match order (0-indexed), kept when `--sort` reorders matches
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<usize>,
+    /// How many matches had this exact text (only with --dedup)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub occurrences: Option<usize>,
+    /// Spans of every match with this text, in match order (only with --dedup)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub positions: Vec<Span>,
+    /// Surrounding text with the match marked «like this» (only with --context-chars)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    /// Line and column of the match in the input (only with --context-json or --vimgrep)
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub location: Option<FileLocation>,
+}
+
+/// Editor coordinates of a match in a file (`test --file --context-json`).
+///
+/// Lines and columns are 1-based; columns count characters, not bytes, and a
+/// `\r` before `\n` belongs to the line it ends. The end is the position
+/// just past the match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileLocation {
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+    /// 1-based byte column of the start, as `--vimgrep` reports it
+    #[serde(skip)]
+    pub byte_column: usize,
 }
 
 /// Result of `re-x test` command
@@ -38,8 +69,24 @@ pub struct Match {
 pub struct TestResult {
     /// The pattern that was tested
     pub pattern: String,
+    /// The pattern as compiled, when `\Q...\E` blocks were expanded or
+    /// `(?#...)` comments stripped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub normalized_pattern: Option<String>,
+    /// The pattern exactly as handed to the engine, after --multiline,
+    /// --no-unicode, --full-match and `\Q...\E` expansion (only with
+    /// --show-flags-normalized)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effective_pattern: Option<String>,
     /// Which engine was used (regex or fancy-regex)
     pub engine: String,
+    /// Why the backtracking fancy-regex engine was needed (None for regex)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub engine_reason: Option<String>,
+    /// Whether the pattern fell back to fancy-regex because the `regex`
+    /// crate rejected it, despite using no fancy-regex features
+    /// (`engine_reason` says why)
+    pub fallback_occurred: bool,
     /// Length of input in bytes
     pub input_length: usize,
     /// Whether any match was found
@@ -48,8 +95,124 @@ pub struct TestResult {
     pub match_count: usize,
     /// All matches with positions and captures
     pub matches: Vec<Match>,
-    /// Elapsed time in microseconds
+    /// Elapsed time in microseconds, from start to finish
     pub elapsed_us: u64,
+    /// Part of `elapsed_us` spent compiling the pattern
+    pub compile_us: u64,
+    /// Part of `elapsed_us` spent finding matches (including reading a file,
+    /// which is matched as it is read)
+    pub match_us: u64,
+    /// Whether the pattern was required to match the entire input (--full-match)
+    pub full_match: bool,
+    /// Whether the pattern can match the empty string as well as text (e.g. `a*`);
+    /// its empty matches are left out unless --allow-empty-matches is given
+    pub matches_empty: bool,
+    /// Distinct values of the --by-capture group, most frequent first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub capture_counts: Vec<CaptureCount>,
+    /// Lines with at least one match and how many (--per-line-count)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub lines: Vec<LineCount>,
+    /// The line (1-based) the scan stopped at for running past `--timeout-per-line-ms`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub timed_out_lines: Vec<usize>,
+    /// Heuristic warning about likely-unintended behavior (if any)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+}
+
+/// One pattern's outcome in `re-x test-many`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamedTestResult {
+    /// Line of the patterns file the pattern was read from (1-based)
+    pub line: usize,
+    /// The pattern as written in the file
+    pub pattern: String,
+    /// Whether the pattern matched anywhere in the input
+    pub matched: bool,
+    /// Number of matches found
+    pub match_count: usize,
+    /// Which engine was used (absent if the pattern failed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub engine: Option<String>,
+    /// Why this pattern could not be tested (it does not affect the others)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Result of `re-x test-many`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestManyResult {
+    /// Length of input in bytes
+    pub input_length: usize,
+    /// Number of patterns that matched
+    pub matched_patterns: usize,
+    /// Number of patterns that reported an error
+    pub failed_patterns: usize,
+    /// One entry per pattern, in file order
+    pub results: Vec<NamedTestResult>,
+    /// Elapsed time in microseconds for all patterns
+    pub elapsed_us: u64,
+}
+
+/// Result of `re-x test --coverage`: how much of the pattern fits the input
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverageResult {
+    /// The pattern that was probed
+    pub pattern: String,
+    /// Whether the whole pattern matches the input
+    pub matched: bool,
+    /// Longest prefix of the pattern that matches somewhere in the input
+    /// (the whole pattern when it matches; empty when no prefix does)
+    pub matched_prefix: String,
+    /// Byte offset in the pattern where matching stops working (absent when
+    /// the whole pattern matches)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failed_at: Option<usize>,
+    /// First text the matched prefix matches in the input
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefix_match: Option<String>,
+    /// Elapsed time in microseconds for all probes
+    pub elapsed_us: u64,
+}
+
+/// How often one distinct captured value occurred (`test --by-capture`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CaptureCount {
+    /// The captured text
+    pub value: String,
+    /// Number of matches that captured this value
+    pub count: usize,
+}
+
+/// Number of matches on one input line (`test --per-line-count`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineCount {
+    /// Line number (1-indexed)
+    pub line_number: usize,
+    /// Number of matches on this line
+    pub count: usize,
+}
+
+/// Capture values of every match as a table (`test --only-groups`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupRowsResult {
+    /// The pattern that was tested
+    pub pattern: String,
+    /// Column headers: each group's name, or its number if unnamed
+    pub columns: Vec<String>,
+    /// One row per match with the group values in group order
+    /// (null where a group did not participate)
+    pub rows: Vec<Vec<Option<String>>>,
+}
+
+/// A byte range in the original input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span {
+    /// Start byte position (0-indexed)
+    pub start: usize,
+    /// End byte position (exclusive)
+    pub end: usize,
 }
 
 /// Result of `re-x replace` command
@@ -57,6 +220,10 @@ pub struct TestResult {
 pub struct ReplaceResult {
     /// The pattern that was used
     pub pattern: String,
+    /// The pattern as compiled, when `\Q...\E` blocks were expanded or
+    /// `(?#...)` comments stripped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub normalized_pattern: Option<String>,
     /// The replacement string
     pub replacement: String,
     /// Original input
@@ -65,6 +232,14 @@ pub struct ReplaceResult {
     pub result: String,
     /// Number of replacements made
     pub replacements_made: usize,
+    /// Regions of the original input that were replaced
+    pub spans: Vec<Span>,
+    /// Whether `original`/`result` (and `spans` with them) were cut short by
+    /// --max-result-bytes
+    pub truncated: bool,
+    /// Matches left untouched after the first --count replacements (only with --count)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remaining_matches: Option<usize>,
 }
 
 /// A single replacement preview (for file dry-run)
@@ -87,6 +262,9 @@ pub struct ReplaceFileResult {
     pub replacement: String,
     /// Total number of replacements
     pub replacements_made: usize,
+    /// Matches left untouched after the first --count replacements (only with --count)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remaining_matches: Option<usize>,
     /// Preview of changes
     pub preview: Vec<ReplacePreview>,
 }
@@ -113,6 +291,43 @@ pub struct Portability {
     pub dotnet: bool,
     /// Ruby (Oniguruma/Onigmo)
     pub ruby: bool,
+    /// POSIX extended regular expressions (grep -E, sed -E, awk)
+    pub posix_ere: bool,
+    /// POSIX basic regular expressions (grep, sed)
+    pub posix_bre: bool,
+}
+
+/// Whether one engine accepts a pattern, and why not
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineCompatibility {
+    /// Whether the pattern runs unchanged on this engine
+    pub compatible: bool,
+    /// Unsupported features that break compatibility (empty if compatible)
+    pub issues: Vec<String>,
+}
+
+/// Portability with the reasons behind each incompatibility, per engine
+/// (same engines as [`Portability`])
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetailedPortability {
+    /// Rust regex crate
+    pub rust_regex: EngineCompatibility,
+    /// PCRE2
+    pub pcre2: EngineCompatibility,
+    /// JavaScript RegExp
+    pub javascript: EngineCompatibility,
+    /// Python re module
+    pub python_re: EngineCompatibility,
+    /// Python regex module (third-party)
+    pub python_regex: EngineCompatibility,
+    /// Go regexp package
+    pub go_regexp: EngineCompatibility,
+    /// Java java.util.regex
+    pub java: EngineCompatibility,
+    /// .NET System.Text.RegularExpressions
+    pub dotnet: EngineCompatibility,
+    /// Ruby (Oniguruma/Onigmo)
+    pub ruby: EngineCompatibility,
 }
 
 /// Error information for validation
@@ -127,6 +342,30 @@ pub struct ValidationError {
     pub message: String,
 }
 
+/// A capture group declared in a pattern
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CaptureInfo {
+    /// Group number (1-indexed, usable as `$N` in replacements)
+    pub index: usize,
+    /// Group name for named captures
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// Result of the MCP `regex_is_match` tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IsMatchResult {
+    /// Whether the pattern matched anywhere in the input
+    pub matched: bool,
+}
+
+/// Result of the MCP `regex_replace` and `regex_apply` tools with `count_only`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplaceCountResult {
+    /// Total number of replacements
+    pub replacements_made: usize,
+}
+
 /// Result of `re-x validate` command
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ValidateResult {
@@ -141,14 +380,270 @@ pub struct ValidateResult {
     /// Reason for engine requirement
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reason: Option<String>,
+    /// Whether only fancy-regex accepts the pattern even though it uses no
+    /// fancy-regex features, so compiling it falls back from `regex`
+    /// (`reason` says why)
+    pub fallback_occurred: bool,
     /// Portability to other languages
     #[serde(skip_serializing_if = "Option::is_none")]
     pub portability: Option<Portability>,
+    /// Per-engine portability with reasons, output as `portability` in place
+    /// of the flat booleans (only with --verbose-portability)
+    #[serde(
+        rename = "portability",
+        skip_serializing_if = "Option::is_none",
+        skip_deserializing
+    )]
+    pub detailed_portability: Option<DetailedPortability>,
+    /// Capture groups declared in the pattern (empty if invalid)
+    pub capture_groups: Vec<CaptureInfo>,
+    /// Per-target compatibility report (only with --all-targets)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub targets: Vec<TargetReport>,
+    /// Non-fatal lints about likely mistakes in a valid pattern
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// Flags the pattern sets inline (only with --flags)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active_flags: Option<FlagState>,
+    /// The pattern as validated, when `\Q...\E` blocks were expanded or
+    /// `(?#...)` comments stripped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub normalized_pattern: Option<String>,
+    /// The pattern exactly as compiled, after --extended and --pattern-file
+    /// (only with --show-flags-normalized)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effective_pattern: Option<String>,
     /// Suggested fix (if invalid)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub suggestion: Option<String>,
 }
 
+/// Effective state of the regex flags at the end of the pattern, after its
+/// top-level inline flag directives (`(?i)`, `(?-u)`); group-scoped ones such
+/// as `(?s:...)` only set `mid_pattern`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlagState {
+    /// `i`
+    pub case_insensitive: bool,
+    /// `m`: `^`/`$` match at line boundaries
+    pub multiline: bool,
+    /// `s`: `.` matches `\n`
+    pub dot_matches_newline: bool,
+    /// `U`: quantifiers are lazy by default
+    pub swap_greed: bool,
+    /// `u`: Unicode-aware classes (on unless disabled)
+    pub unicode: bool,
+    /// `x`: whitespace and `#` comments are ignored
+    pub ignore_whitespace: bool,
+    /// `R`: `\r\n` counts as a line terminator
+    pub crlf: bool,
+    /// Whether any directive only applies to part of the pattern (appears
+    /// after the start or inside a group), so the flags differ by position
+    pub mid_pattern: bool,
+}
+
+impl Default for FlagState {
+    fn default() -> Self {
+        Self {
+            case_insensitive: false,
+            multiline: false,
+            dot_matches_newline: false,
+            swap_greed: false,
+            unicode: true,
+            ignore_whitespace: false,
+            crlf: false,
+            mid_pattern: false,
+        }
+    }
+}
+
+/// Compatibility of a pattern with one target language
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TargetReport {
+    /// Target name (e.g. javascript, go_regexp)
+    pub target: String,
+    /// Whether the pattern runs unchanged on this target
+    pub compatible: bool,
+    /// Unsupported features that break compatibility
+    pub issues: Vec<String>,
+    /// How the pattern could be rewritten for this target (if known)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rewrite: Option<String>,
+}
+
+/// Result of `re-x compare-languages` command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompareLanguagesResult {
+    /// The pattern that was compared
+    pub pattern: String,
+    /// Which re-x engine the pattern needs (regex or fancy-regex)
+    pub engine_required: String,
+    /// Targets that accept the pattern unchanged
+    pub compatible: Vec<String>,
+    /// Every target, with the features it rejects and a rewrite if one is known
+    pub targets: Vec<TargetReport>,
+}
+
+/// Result of `re-x simplify` command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimplifyResult {
+    /// The pattern as given
+    pub original: String,
+    /// Equivalent, shorter pattern (same as original if nothing could be simplified)
+    pub simplified: String,
+    /// Whether any rewrite was applied
+    pub changed: bool,
+}
+
+/// Result of `re-x normalize` command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NormalizeResult {
+    /// The pattern as given
+    pub original: String,
+    /// Canonical spelling of the pattern (same as original if it already was)
+    pub normalized: String,
+    /// Whether any rewrite was applied
+    pub changed: bool,
+}
+
+/// Result of `re-x dedupe-patterns` command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DedupePatternsResult {
+    /// Number of patterns read (blank lines are skipped)
+    pub total: usize,
+    /// Number of distinct canonical forms among the valid patterns
+    pub distinct: usize,
+    /// Canonical forms shared by more than one pattern, in file order
+    pub groups: Vec<PatternGroup>,
+    /// Patterns that failed to compile
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub invalid: Vec<InvalidPattern>,
+}
+
+/// Patterns that normalize to the same canonical form
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatternGroup {
+    /// The shared canonical form
+    pub canonical: String,
+    /// The patterns as written, with their line numbers
+    pub members: Vec<PatternSource>,
+}
+
+/// A pattern read from a file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatternSource {
+    /// 1-based line number in the file
+    pub line: usize,
+    /// The pattern as written on that line
+    pub pattern: String,
+}
+
+/// A pattern from a file that could not be compiled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvalidPattern {
+    /// 1-based line number in the file
+    pub line: usize,
+    /// The pattern as written on that line
+    pub pattern: String,
+    /// Why it failed
+    pub error: String,
+}
+
+/// Result of `re-x build` command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildResult {
+    /// The rendered pattern
+    pub pattern: String,
+    /// Which engine the pattern compiles with (regex or fancy-regex)
+    pub engine: String,
+}
+
+/// Result of `re-x lint` command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LintResult {
+    /// The pattern that was linted
+    pub pattern: String,
+    /// The pattern as linted, when `\Q...\E` blocks were expanded or
+    /// `(?#...)` comments stripped; spans and `fixed` refer to it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub normalized_pattern: Option<String>,
+    /// Whether no issues were found
+    pub clean: bool,
+    /// Issues found, in pattern order
+    pub issues: Vec<LintIssue>,
+    /// The pattern with every suggestion applied (None if clean)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fixed: Option<String>,
+}
+
+/// A single lint finding
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LintIssue {
+    /// Rule that produced the issue, e.g. "unnecessary-escape"
+    pub rule: String,
+    /// Human-readable explanation
+    pub message: String,
+    /// Where the offending token sits in the pattern
+    pub span: Span,
+    /// The offending token as written
+    pub token: String,
+    /// What to write instead
+    pub suggestion: String,
+}
+
+/// Result of `re-x ast-diff` command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AstDiffResult {
+    /// The first (old) pattern
+    pub a: String,
+    /// The second (new) pattern
+    pub b: String,
+    /// Whether both patterns parse to the same structure
+    pub identical: bool,
+    /// Structural differences, in pattern order
+    pub changes: Vec<AstChange>,
+}
+
+/// One structural difference between two parsed patterns
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AstChange {
+    /// "added", "removed" or "changed"
+    pub change: String,
+    /// Node type (literal, class, quantifier, group, alternation, branch, ...)
+    pub node: String,
+    /// Source text of the node in pattern A (None if added)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<String>,
+    /// Source text of the node in pattern B (None if removed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+    /// Where the node sits in pattern A
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub a_span: Option<Span>,
+    /// Where the node sits in pattern B
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub b_span: Option<Span>,
+    /// What changed about the node itself, e.g. "{2,5} → {2,6}"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// Result of `re-x generate-corpus` command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorpusResult {
+    /// The pattern strings were generated for
+    pub pattern: String,
+    /// Strings the pattern matches
+    pub positives: Vec<String>,
+    /// Near misses: a match with one character changed so the pattern rejects it
+    /// (only with --include-near-misses)
+    pub negatives: Vec<String>,
+    /// Why fewer strings than requested were generated (if so)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
 /// A single token/part in pattern explanation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExplainPart {
@@ -159,6 +654,9 @@ pub struct ExplainPart {
     pub token_type: String,
     /// Human-readable description
     pub desc: String,
+    /// Why this part won't port to the `--target` language (if it won't)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub portability_note: Option<String>,
     /// Quantifier if any
     #[serde(skip_serializing_if = "Option::is_none")]
     pub quantifier: Option<String>,
@@ -175,10 +673,48 @@ pub struct ExplainPart {
 pub struct ExplainResult {
     /// The pattern that was explained
     pub pattern: String,
+    /// The pattern as explained, when `\Q...\E` blocks were expanded or
+    /// `(?#...)` comments stripped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub normalized_pattern: Option<String>,
     /// Breakdown of pattern parts
     pub parts: Vec<ExplainPart>,
     /// High-level summary of what the pattern does
     pub summary: String,
+    /// Heuristic score of how hard the pattern is to read
+    pub readability: ReadabilityScore,
+}
+
+/// How hard a pattern is to read, for setting regex complexity budgets in review
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadabilityScore {
+    /// 0 (trivial) to 100 (very hard to read)
+    pub score: u32,
+    /// `simple` (< 25), `moderate` (< 50), `complex` (< 75) or `very complex`
+    pub label: String,
+    /// Pattern length in characters
+    pub length: usize,
+    /// Deepest group nesting, lookarounds included
+    pub max_nesting: usize,
+    /// Alternation branches beyond the first (`a|b|c` counts 2)
+    pub alternations: usize,
+    /// Quantified elements (`*`, `+`, `?`, `{n,m}`)
+    pub quantifiers: usize,
+    /// Lookahead and lookbehind assertions
+    pub lookarounds: usize,
+    /// Backreferences (`\1`, `\k<name>`)
+    pub backreferences: usize,
+    /// Atomic groups, conditionals, subroutine calls and `\K` / `\G`
+    pub advanced: usize,
+}
+
+/// Result of `re-x explain --names-only`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupNamesResult {
+    /// The pattern that was examined
+    pub pattern: String,
+    /// Named capture groups in index order
+    pub groups: Vec<CaptureInfo>,
 }
 
 /// A single inferred pattern candidate
@@ -190,6 +726,9 @@ pub struct InferredPattern {
     pub confidence: f64,
     /// Human-readable description
     pub desc: String,
+    /// Examples the pattern fails to match
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unmatched_examples: Vec<String>,
 }
 
 /// Result of `re-x from-examples` command
@@ -202,6 +741,25 @@ pub struct FromExamplesResult {
     pub negative_examples: Option<Vec<String>>,
     /// Inferred pattern candidates
     pub inferred: Vec<InferredPattern>,
+    /// Why the candidate list is empty (`--format-only` with no matching format)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// Structural complexity of a pattern, derived from its AST
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PatternComplexity {
+    /// Total number of AST nodes
+    pub node_count: usize,
+    /// Number of alternations (`a|b`)
+    pub alternation_count: usize,
+    /// Number of quantified elements (`*`, `+`, `?`, `{n,m}`)
+    pub repetition_count: usize,
+    /// Largest explicit counted-repetition bound (e.g. 1000 for `a{2,1000}`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_quantifier_bound: Option<u32>,
+    /// Maximum group nesting depth
+    pub max_group_depth: usize,
 }
 
 /// Result of `re-x benchmark` command
@@ -209,12 +767,18 @@ pub struct FromExamplesResult {
 pub struct BenchmarkResult {
     /// The pattern that was benchmarked
     pub pattern: String,
+    /// The pattern as benchmarked, when `\Q...\E` blocks were expanded or
+    /// `(?#...)` comments stripped; `vulnerable_span` refers to it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub normalized_pattern: Option<String>,
     /// Which engine was used
     pub engine: String,
     /// Input size in bytes
     pub input_size_bytes: usize,
     /// Number of iterations run
     pub iterations: usize,
+    /// Number of warmup iterations run before measuring (excluded from stats)
+    pub warmup_iterations: usize,
     /// Average time in microseconds
     pub avg_us: f64,
     /// Median time in microseconds
@@ -223,6 +787,9 @@ pub struct BenchmarkResult {
     pub throughput_mb_s: f64,
     /// Whether catastrophic backtracking was detected
     pub catastrophic_backtracking: bool,
+    /// Structural complexity (None for fancy-regex-only patterns)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub complexity: Option<PatternComplexity>,
     /// Whether timeout occurred
     #[serde(skip_serializing_if = "Option::is_none")]
     pub timeout: Option<bool>,
@@ -232,6 +799,56 @@ pub struct BenchmarkResult {
     /// Suggestion for improvement
     #[serde(skip_serializing_if = "Option::is_none")]
     pub suggestion: Option<String>,
+    /// How the result was produced, when it differs from a normal timed run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Lines that took longest to match, slowest first (--per-line)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub slowest_lines: Vec<SlowLine>,
+    /// Average time at each input size, smallest first (--sweep)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub growth: Vec<GrowthPoint>,
+    /// Crude verdict on the growth curve: "linear" or "superlinear" (--sweep)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub growth_class: Option<String>,
+    /// Which part of the file was benchmarked (--sample)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sample: Option<BenchmarkSample>,
+    /// Byte span of the pattern construct behind catastrophic backtracking,
+    /// when it can be located
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vulnerable_span: Option<Span>,
+}
+
+/// The random subset of lines timed by `benchmark --sample`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkSample {
+    /// Number of lines in the sample
+    pub lines: usize,
+    /// Number of lines in the whole file
+    pub total_lines: usize,
+    /// Seed that chose the lines; pass it again to reproduce the sample
+    pub seed: u64,
+}
+
+/// Timing at one input size of `benchmark --sweep`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrowthPoint {
+    /// Input size in bytes
+    pub size: usize,
+    /// Average time in microseconds
+    pub avg_us: f64,
+}
+
+/// A single input line timed by `benchmark --per-line`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlowLine {
+    /// Line number (1-indexed)
+    pub line: usize,
+    /// Time to match this line in microseconds
+    pub time_us: f64,
+    /// Line content, truncated to keep the report readable
+    pub content: String,
 }
 
 /// Result of `re-x apply` command
@@ -248,10 +865,19 @@ pub struct ApplyResult {
     pub backup_path: Option<String>,
     /// Total number of replacements made
     pub replacements_made: usize,
+    /// Matches left untouched after the first --count replacements (only with --count)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remaining_matches: Option<usize>,
     /// Whether changes were actually written (false for dry-run)
     pub applied: bool,
     /// Preview of changes
     pub preview: Vec<ReplacePreview>,
+    /// The changes as a unified diff, empty if nothing changed (only with --diff)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff: Option<String>,
+    /// Why this file could not be processed (only with --stdin-list)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
 
 /// Generic error response
@@ -292,21 +918,18 @@ impl ErrorResponse {
     }
 
     /// Add position information
-    #[allow(dead_code)]
     pub fn with_position(mut self, position: usize) -> Self {
         self.position = Some(position);
         self
     }
 
     /// Add context
-    #[allow(dead_code)]
     pub fn with_context(mut self, context: impl Into<String>) -> Self {
         self.context = Some(context.into());
         self
     }
 
     /// Add suggestion
-    #[allow(dead_code)]
     pub fn with_suggestion(mut self, suggestion: impl Into<String>) -> Self {
         self.suggestion = Some(suggestion.into());
         self
@@ -314,7 +937,6 @@ impl ErrorResponse {
 }
 
 /// Error codes used throughout re-x
-#[allow(dead_code)]
 pub mod error_codes {
     pub const INVALID_PATTERN: &str = "INVALID_PATTERN";
     pub const FILE_NOT_FOUND: &str = "FILE_NOT_FOUND";
@@ -323,4 +945,5 @@ pub mod error_codes {
     pub const ENCODING_ERROR: &str = "ENCODING_ERROR";
     pub const ENGINE_UNSUPPORTED: &str = "ENGINE_UNSUPPORTED";
     pub const INVALID_INPUT: &str = "INVALID_INPUT";
+    pub const TOO_MANY_GROUPS: &str = "TOO_MANY_GROUPS";
 }
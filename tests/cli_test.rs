@@ -37,7 +37,7 @@ fn test_no_match() {
     re_x()
         .args(["test", r"\d+", "hello world"])
         .assert()
-        .success()
+        .code(1)
         .stdout(predicate::str::contains("\"matched\": false"))
         .stdout(predicate::str::contains("\"match_count\": 0"));
 }
@@ -77,284 +77,2597 @@ fn test_validate_invalid() {
     re_x()
         .args(["validate", r"(\d+"])
         .assert()
-        .success()
+        .code(2)
         .stdout(predicate::str::contains("\"valid\": false"));
 }
 
 #[test]
-fn test_explain() {
+fn test_validate_warns_on_anchored_alternation() {
     re_x()
-        .args(["explain", r"\d+"])
+        .args(["validate", "^foo|bar$"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("\"parts\""))
-        .stdout(predicate::str::contains("\"summary\""));
+        .stdout(predicate::str::contains("\"valid\": true"))
+        .stdout(predicate::str::contains("^(?:foo|bar)$"));
 }
 
 #[test]
-fn test_from_examples() {
+fn test_validate_warns_on_dubious_constructs() {
     re_x()
-        .args(["from-examples", "2024-01-15", "2025-12-31"])
+        .args(["validate", "id-[0-9]{0}x{1,1}"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("\"inferred\""))
-        .stdout(predicate::str::contains("\"confidence\""));
+        .stdout(predicate::str::contains("\"valid\": true"))
+        .stdout(predicate::str::contains("`[0-9]{0}` repeats zero times"))
+        .stdout(predicate::str::contains("`x` alone is equivalent"));
 }
 
 #[test]
-fn test_benchmark() {
+fn test_validate_backreference_groups() {
     re_x()
-        .args(["benchmark", r"\d+", "--input", "hello 123"])
+        .args(["validate", r"(a)(?:b)\2"])
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains(
+            "\"kind\": \"invalid_backreference\"",
+        ))
+        .stdout(predicate::str::contains("only 1 capture group"))
+        .stdout(predicate::str::contains("are not numbered"));
+
+    re_x()
+        .args(["validate", r"\1(a)"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("\"avg_us\""))
-        .stdout(predicate::str::contains("\"catastrophic_backtracking\""));
+        .stdout(predicate::str::contains("comes before group 1"));
 }
 
 #[test]
-fn test_text_format() {
+fn test_validate_oniguruma_escapes() {
     re_x()
-        .args(["test", r"\d+", "hello 123", "--format", "text"])
+        .args(["validate", r"a\Rb"])
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("any line break"));
+
+    re_x()
+        .args(["validate", r"\h+"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("Pattern:"))
-        .stdout(predicate::str::contains("Match 1:"));
+        .stdout(predicate::str::contains("\"rust_regex\": false"))
+        .stdout(predicate::str::contains("[0-9a-fA-F]"));
 }
 
 #[test]
-fn test_portability_check() {
+fn test_validate_unicode_properties() {
     re_x()
-        .args(["validate", r"(?=\d)\w+"])
+        .args(["validate", r"[\p{Scrpt=Greek}]"])
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("unknown_unicode_property"))
+        .stdout(predicate::str::contains(
+            r"Did you mean `\\p{Script=Greek}`?",
+        ));
+
+    re_x()
+        .args(["validate", r"\p{Script=Greek}+", "--format", "text"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("\"portability\""))
-        .stdout(predicate::str::contains("\"rust_regex\": false"));
+        .stdout(predicate::str::contains("Go regexp:     ✗"))
+        .stdout(predicate::str::contains("JavaScript needs the `u` flag"));
 }
 
 #[test]
-fn test_portability_dotnet() {
+fn test_validate_flags() {
     re_x()
-        .args(["validate", r"\d+"])
+        .args(["validate", "--flags", "a(?is)b"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("\"dotnet\": true"))
-        .stdout(predicate::str::contains("\"ruby\": true"));
-}
+        .stdout(predicate::str::contains("\"case_insensitive\": true"))
+        .stdout(predicate::str::contains("\"multiline\": false"))
+        .stdout(predicate::str::contains("\"mid_pattern\": true"));
 
-// --- apply command tests ---
+    re_x()
+        .args(["validate", "(?i)abc"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("active_flags").not());
+}
 
 #[test]
-fn test_apply_basic() {
-    let dir = tempfile::tempdir().unwrap();
-    let file_path = dir.path().join("test.txt");
-    fs::write(&file_path, "hello world\nfoo bar\n").unwrap();
+fn test_validate_verbose_portability() {
+    re_x()
+        .args(["validate", "--verbose-portability", "foo(?=bar)"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\"rust_regex\": {\n      \"compatible\": false,\n      \"issues\": [\n        \"Lookahead",
+        ))
+        .stdout(predicate::str::contains(
+            "\"pcre2\": {\n      \"compatible\": true,\n      \"issues\": []",
+        ));
 
+    // The flat booleans stay the default
+    re_x()
+        .args(["validate", "foo(?=bar)"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"rust_regex\": false"));
+}
+
+#[test]
+fn test_benchmark_sweep() {
     re_x()
         .args([
-            "apply",
-            r"\bworld\b",
-            "earth",
-            "--file",
-            file_path.to_str().unwrap(),
-            "--no-backup",
+            "benchmark",
+            r"\d+",
+            "--input",
+            "abc 123",
+            "--iterations",
+            "3",
+            "--sweep",
+            "10,100",
         ])
         .assert()
         .success()
-        .stdout(predicate::str::contains("\"applied\": true"))
-        .stdout(predicate::str::contains("\"replacements_made\": 1"));
-
-    let content = fs::read_to_string(&file_path).unwrap();
-    assert_eq!(content, "hello earth\nfoo bar\n");
+        .stdout(predicate::str::contains("\"size\": 100"))
+        .stdout(predicate::str::contains("\"growth_class\""));
 }
 
 #[test]
-fn test_apply_dry_run() {
-    let dir = tempfile::tempdir().unwrap();
-    let file_path = dir.path().join("test.txt");
-    fs::write(&file_path, "hello world\n").unwrap();
+fn test_explain() {
+    re_x()
+        .args(["explain", r"\d+"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"parts\""))
+        .stdout(predicate::str::contains("\"summary\""));
+}
 
+#[test]
+fn test_explain_readability() {
     re_x()
-        .args([
-            "apply",
-            "world",
-            "earth",
-            "--file",
-            file_path.to_str().unwrap(),
-            "--dry-run",
-        ])
+        .args(["explain", r"\d+"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("\"applied\": false"))
-        .stdout(predicate::str::contains("\"replacements_made\": 1"));
+        .stdout(predicate::str::contains("\"label\": \"simple\""));
 
-    // File should NOT be modified
-    let content = fs::read_to_string(&file_path).unwrap();
-    assert_eq!(content, "hello world\n");
+    re_x()
+        .args(["explain", r"(?<=\$)(\d+)(?!\d)\1", "--format", "text"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Readability: 30/100 (moderate)"));
 }
 
 #[test]
-fn test_apply_multiline() {
-    let dir = tempfile::tempdir().unwrap();
-    let file_path = dir.path().join("test.txt");
-    fs::write(&file_path, "hello\nworld\n").unwrap();
+fn test_explain_tree() {
+    re_x()
+        .args(["explain", r"(a(b|c))x", "--tree", "--format", "text"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("├─ (a(b|c))"))
+        .stdout(predicate::str::contains("│  └─ (b|c)"))
+        .stdout(predicate::str::contains("└─ x"));
+}
 
+#[test]
+fn test_explain_names_only() {
     re_x()
         .args([
-            "apply",
-            r"hello.world",
-            "REPLACED",
-            "--file",
-            file_path.to_str().unwrap(),
-            "--no-backup",
-            "-m",
+            "explain",
+            r"(?<user>\w+)@(\w+)\.(?<tld>\w+)",
+            "--names-only",
         ])
         .assert()
         .success()
-        .stdout(predicate::str::contains("\"applied\": true"))
-        .stdout(predicate::str::contains("\"replacements_made\": 1"));
+        .stdout(predicate::str::contains("\"index\": 3"))
+        .stdout(predicate::str::contains("\"name\": \"tld\""))
+        .stdout(predicate::str::contains("parts").not());
 
-    let content = fs::read_to_string(&file_path).unwrap();
-    assert!(content.contains("REPLACED"));
+    re_x()
+        .args([
+            "explain",
+            r"(?<user>\w+)@(\w+)",
+            "--names-only",
+            "-f",
+            "text",
+        ])
+        .assert()
+        .success()
+        .stdout("1\tuser\n");
 }
 
-// --- MCP server tests ---
+#[test]
+fn test_quoted_literal_block() {
+    re_x()
+        .args(["test", r"\Q1+1\E=\d", "x 1+1=2"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            r#""normalized_pattern": "1\\+1=\\d""#,
+        ))
+        .stdout(predicate::str::contains(r#""text": "1+1=2""#));
+}
 
 #[test]
-fn test_mcp_initialize() {
-    let req = r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2025-03-26","capabilities":{},"clientInfo":{"name":"test","version":"1.0"}}}"#;
+fn test_inline_comments_stripped() {
     re_x()
-        .arg("--mcp")
-        .write_stdin(format!("{}\n", req))
+        .args(["test", r"(?#year)\d{4}-(?# month )\d\d", "on 2024-05"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("\"protocolVersion\""))
-        .stdout(predicate::str::contains("re-x"));
+        .stdout(predicate::str::contains(
+            r#""normalized_pattern": "\\d{4}-\\d\\d""#,
+        ))
+        .stdout(predicate::str::contains(r#""engine": "regex""#))
+        .stdout(predicate::str::contains(r#""text": "2024-05""#));
 }
 
 #[test]
-fn test_mcp_tools_list() {
-    let init = r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2025-03-26","capabilities":{},"clientInfo":{"name":"test","version":"1.0"}}}"#;
-    let list = r#"{"jsonrpc":"2.0","id":2,"method":"tools/list","params":{}}"#;
+fn test_inline_comments_stripped_by_every_command() {
     re_x()
-        .arg("--mcp")
-        .write_stdin(format!("{}\n{}\n", init, list))
+        .args(["validate", "(?#c)a"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("regex_test"))
-        .stdout(predicate::str::contains("regex_validate"))
-        .stdout(predicate::str::contains("regex_explain"))
-        .stdout(predicate::str::contains("regex_replace"))
-        .stdout(predicate::str::contains("regex_apply"))
-        .stdout(predicate::str::contains("regex_benchmark"))
-        .stdout(predicate::str::contains("regex_from_examples"));
+        .stdout(predicate::str::contains(r#""engine_required": "regex""#))
+        .stdout(predicate::str::contains(r#""fallback_occurred": false"#))
+        .stdout(predicate::str::contains(r#""normalized_pattern": "a""#));
+
+    re_x()
+        .args(["explain", "(?#c)a"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""pattern": "(?#c)a""#))
+        .stdout(predicate::str::contains(r#""desc": "Literal 'a'""#));
+
+    re_x()
+        .args(["lint", r"(?#c)a\/"])
+        .assert()
+        .stdout(predicate::str::contains(r#""normalized_pattern": "a\\/""#))
+        .stdout(predicate::str::contains(r#""start": 1,"#))
+        .stdout(predicate::str::contains(r#""fixed": "a/""#));
+
+    re_x()
+        .args(["benchmark", "(?#c)(a|aa)+$", "--detect-only"])
+        .assert()
+        .code(3)
+        .stdout(predicate::str::contains(r#""engine": "regex""#))
+        .stdout(predicate::str::contains(r#""start": 1,"#));
 }
 
 #[test]
-fn test_mcp_tool_call_test() {
-    let init = r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2025-03-26","capabilities":{},"clientInfo":{"name":"test","version":"1.0"}}}"#;
-    let call = r#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"regex_test","arguments":{"pattern":"\\d+","input":"hello 123"}}}"#;
+fn test_from_examples() {
     re_x()
-        .arg("--mcp")
-        .write_stdin(format!("{}\n{}\n", init, call))
+        .args(["from-examples", "2024-01-15", "2025-12-31"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("\\\"matched\\\": true"))
-        .stdout(predicate::str::contains("123"));
+        .stdout(predicate::str::contains("\"inferred\""))
+        .stdout(predicate::str::contains("\"confidence\""));
 }
 
 #[test]
-fn test_mcp_tool_call_validate() {
-    let init = r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2025-03-26","capabilities":{},"clientInfo":{"name":"test","version":"1.0"}}}"#;
-    let call = r#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"regex_validate","arguments":{"pattern":"\\d+"}}}"#;
+fn test_from_examples_show_unmatched() {
     re_x()
-        .arg("--mcp")
-        .write_stdin(format!("{}\n{}\n", init, call))
+        .args([
+            "--format",
+            "text",
+            "from-examples",
+            "ab-12",
+            "cd-34",
+            "--show-unmatched",
+        ])
         .assert()
         .success()
-        .stdout(predicate::str::contains("\\\"valid\\\": true"));
+        .stdout(predicate::str::contains("Inferred patterns:"))
+        .stdout(predicate::str::contains("Misses:").not());
+
+    // The email template detects x@y.z but its pattern needs a 2-letter TLD
+    re_x()
+        .args([
+            "--format",
+            "text",
+            "from-examples",
+            "joe@example.com",
+            "x@y.z",
+            "--show-unmatched",
+        ])
+        .assert()
+        .code(0)
+        .stdout(predicate::str::contains(
+            "Email address\n   Misses: x@y.z\n",
+        ));
 }
 
 #[test]
-fn test_mcp_invalid_json() {
+fn test_from_examples_format_only() {
     re_x()
-        .arg("--mcp")
-        .write_stdin("not valid json\n")
+        .args(["from-examples", "abc12", "xyz34", "--format-only"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("-32700"))
-        .stdout(predicate::str::contains("Parse error"));
+        .stdout(predicate::str::contains("\"inferred\": []"))
+        .stdout(predicate::str::contains("No known format matches"));
 }
 
 #[test]
-fn test_mcp_unknown_method() {
-    let req = r#"{"jsonrpc":"2.0","id":1,"method":"nonexistent","params":{}}"#;
+fn test_benchmark() {
     re_x()
-        .arg("--mcp")
-        .write_stdin(format!("{}\n", req))
+        .args(["benchmark", r"\d+", "--input", "hello 123"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("-32601"))
-        .stdout(predicate::str::contains("Method not found"));
+        .stdout(predicate::str::contains("\"avg_us\""))
+        .stdout(predicate::str::contains("\"catastrophic_backtracking\""));
 }
 
 #[test]
-fn test_mcp_ping() {
-    let req = r#"{"jsonrpc":"2.0","id":1,"method":"ping","params":{}}"#;
+fn test_benchmark_input_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let sample = dir.path().join("sample.txt");
+    fs::write(&sample, "id=42 ").unwrap();
+    let path = sample.to_str().unwrap();
+
     re_x()
-        .arg("--mcp")
-        .write_stdin(format!("{}\n", req))
+        .args([
+            "benchmark",
+            r"\d+",
+            "--input-file",
+            path,
+            "--repeat-input",
+            "3",
+            "--iterations",
+            "5",
+        ])
         .assert()
         .success()
-        .stdout(predicate::str::contains("\"result\""));
+        .stdout(predicate::str::contains("\"input_size_bytes\": 18"));
+
+    re_x()
+        .args([
+            "benchmark",
+            r"\d+",
+            "--input-file",
+            path,
+            "--repeat-input",
+            "0",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--repeat-input"));
+
+    re_x()
+        .args(["benchmark", r"\d+", "--input-file", path, "--input", "x"])
+        .assert()
+        .failure();
 }
 
-// --- replace --file tests ---
+#[test]
+fn test_benchmark_rejects_zero_catastrophic_threshold() {
+    re_x()
+        .args([
+            "benchmark",
+            r"\d+",
+            "--input",
+            "123",
+            "--catastrophic-threshold-ms",
+            "0",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--catastrophic-threshold-ms"));
+}
 
 #[test]
-fn test_replace_file() {
+fn test_benchmark_redos_templates() {
     let dir = tempfile::tempdir().unwrap();
-    let file_path = dir.path().join("urls.txt");
+    let templates = dir.path().join("templates.json");
     fs::write(
-        &file_path,
-        "http://example.com\nhttps://safe.com\nhttp://test.org\n",
+        &templates,
+        r#"[{"fragment": "(x+y?)+", "input": "xxxxxx!"}]"#,
     )
     .unwrap();
 
     re_x()
         .args([
-            "replace",
-            "http://",
-            "https://",
-            "--file",
-            file_path.to_str().unwrap(),
+            "benchmark",
+            "^(x+y?)+$",
+            "--iterations",
+            "3",
+            "--redos-templates",
         ])
+        .arg(&templates)
         .assert()
-        .success()
-        .stdout(predicate::str::contains("\"replacements_made\": 2"))
-        .stdout(predicate::str::contains("\"preview\""));
+        .stdout(predicate::str::contains("\"input_size_bytes\": 7,"));
 
-    // replace --file never modifies the file
-    let content = fs::read_to_string(&file_path).unwrap();
-    assert!(content.contains("http://example.com"));
+    fs::write(&templates, r#"[{"fragment": "(x+y?)+"}]"#).unwrap();
+    re_x()
+        .args(["benchmark", "^(x+y?)+$", "--redos-templates"])
+        .arg(&templates)
+        .assert()
+        .code(5)
+        .stderr(predicate::str::contains(
+            "Invalid ReDoS templates JSON: missing field `input`",
+        ));
 }
 
 #[test]
-fn test_replace_file_fancy_captures() {
-    let dir = tempfile::tempdir().unwrap();
-    let file_path = dir.path().join("data.txt");
-    fs::write(&file_path, "price: 100USD\nprice: 200EUR\n").unwrap();
+fn test_benchmark_auto() {
+    let iterations = |budget: &str| {
+        let output = re_x()
+            .args([
+                "benchmark",
+                r"\d+",
+                "--input",
+                "hello 123",
+                "--auto",
+                budget,
+            ])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let result: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert!(result.get("timeout").is_none());
+        result["iterations"].as_u64().unwrap()
+    };
+    // A ten times larger budget runs many more iterations
+    let short = iterations("20");
+    let long = iterations("200");
+    assert!(long > short * 3, "{} vs {}", short, long);
 
-    // Lookahead pattern (fancy-regex) with capture group and $1 expansion
+    // A budget over --timeout-ms is cut to it rather than timing out
     re_x()
         .args([
-            "replace",
-            r"(\d+)(?=USD|EUR)",
-            "[$1]",
-            "--file",
-            file_path.to_str().unwrap(),
+            "benchmark",
+            r"\d+",
+            "--input",
+            "hello 123",
+            "--auto",
+            "60000",
+            "--timeout-ms",
+            "50",
         ])
         .assert()
         .success()
-        .stdout(predicate::str::contains("\"replacements_made\": 2"))
-        .stdout(predicate::str::contains("\"preview\""));
+        .stdout(predicate::str::contains("\"timeout\"").not());
+
+    re_x()
+        .args(["benchmark", r"\d+", "--auto", "--iterations", "5"])
+        .assert()
+        .code(5);
+}
+
+#[test]
+fn test_text_format() {
+    re_x()
+        .args(["test", r"\d+", "hello 123", "--format", "text"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Pattern:"))
+        .stdout(predicate::str::contains("Match 1:"));
+}
+
+#[test]
+fn test_portability_check() {
+    re_x()
+        .args(["validate", r"(?=\d)\w+"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"portability\""))
+        .stdout(predicate::str::contains("\"rust_regex\": false"));
+}
+
+#[test]
+fn test_portability_dotnet() {
+    re_x()
+        .args(["validate", r"\d+"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"dotnet\": true"))
+        .stdout(predicate::str::contains("\"ruby\": true"));
+}
+
+// --- apply command tests ---
+
+#[test]
+fn test_apply_basic() {
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir.path().join("test.txt");
+    fs::write(&file_path, "hello world\nfoo bar\n").unwrap();
+
+    re_x()
+        .args([
+            "apply",
+            r"\bworld\b",
+            "earth",
+            "--file",
+            file_path.to_str().unwrap(),
+            "--no-backup",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"applied\": true"))
+        .stdout(predicate::str::contains("\"replacements_made\": 1"));
+
+    let content = fs::read_to_string(&file_path).unwrap();
+    assert_eq!(content, "hello earth\nfoo bar\n");
+}
+
+#[test]
+fn test_apply_interactive() {
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir.path().join("test.txt");
+    fs::write(&file_path, "foo 1\nfoo 2\nfoo 3\nfoo 4\n").unwrap();
+
+    // Skip the first hunk, approve the second, then quit
+    re_x()
+        .args([
+            "apply",
+            "foo",
+            "bar",
+            "--file",
+            file_path.to_str().unwrap(),
+            "--no-backup",
+            "--interactive",
+        ])
+        .write_stdin("n\nwhat\ny\nq\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"replacements_made\": 1"))
+        .stderr(predicate::str::contains("[2/4]"))
+        .stderr(predicate::str::contains("+ bar 2"));
+
+    let content = fs::read_to_string(&file_path).unwrap();
+    assert_eq!(content, "foo 1\nbar 2\nfoo 3\nfoo 4\n");
+
+    // "a" approves the current hunk and everything after it
+    re_x()
+        .args([
+            "apply",
+            r"(\w+) (\d)",
+            "$2 $1",
+            "--file",
+            file_path.to_str().unwrap(),
+            "--no-backup",
+            "--interactive",
+        ])
+        .write_stdin("n\na\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"replacements_made\": 3"));
+
+    let content = fs::read_to_string(&file_path).unwrap();
+    assert_eq!(content, "foo 1\n2 bar\n3 foo\n4 foo\n");
+}
+
+#[test]
+fn test_apply_stdin_list() {
+    let dir = tempfile::tempdir().unwrap();
+    let a = dir.path().join("a.txt");
+    let b = dir.path().join("b c.txt");
+    let missing = dir.path().join("missing.txt");
+    fs::write(&a, "foo\n").unwrap();
+    fs::write(&b, "foo foo\n").unwrap();
+
+    // Newline-separated; a missing file is reported but does not stop the rest
+    let list = format!("{}\n{}\n{}\n", a.display(), missing.display(), b.display());
+    re_x()
+        .args(["apply", "foo", "bar", "--stdin-list", "--no-backup"])
+        .write_stdin(list)
+        .assert()
+        .code(5)
+        .stdout(predicate::str::contains("\"replacements_made\": 2"))
+        .stdout(predicate::str::contains("Failed to open file"));
+    assert_eq!(fs::read_to_string(&a).unwrap(), "bar\n");
+    assert_eq!(fs::read_to_string(&b).unwrap(), "bar bar\n");
+
+    // NUL-separated, as produced by find -print0
+    let list = format!("{}\0{}\0", a.display(), b.display());
+    re_x()
+        .args(["apply", "bar", "baz", "--stdin-list", "-z", "--no-backup"])
+        .write_stdin(list)
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&b).unwrap(), "baz baz\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_apply_stdin_list_non_utf8_path() {
+    use std::os::unix::ffi::{OsStrExt, OsStringExt};
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(std::ffi::OsStr::from_bytes(b"caf\xe9.txt"));
+    fs::write(&path, "foo\n").unwrap();
+
+    let mut list = path.clone().into_os_string().into_vec();
+    list.push(b'\0');
+    re_x()
+        .args(["apply", "foo", "bar", "--stdin-list", "-z", "--no-backup"])
+        .write_stdin(list)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"replacements_made\": 1"));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "bar\n");
+}
+
+#[test]
+fn test_replace_and_apply_count() {
+    re_x()
+        .args(["replace", "TODO", "DONE", "TODO TODO TODO", "--count", "2"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""result": "DONE DONE TODO""#))
+        .stdout(predicate::str::contains(r#""replacements_made": 2"#))
+        .stdout(predicate::str::contains(r#""remaining_matches": 1"#));
+
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir.path().join("todo.txt");
+    fs::write(&file_path, "TODO a\nb TODO TODO\n").unwrap();
+
+    re_x()
+        .args(["replace", "TODO", "DONE", "--raw", "--count", "2", "--file"])
+        .arg(&file_path)
+        .assert()
+        .success()
+        .stdout("DONE a\nb DONE TODO\n");
+
+    re_x()
+        .args([
+            "apply",
+            "TODO",
+            "DONE",
+            "--no-backup",
+            "--replace-count-limit",
+            "1",
+        ])
+        .arg("--file")
+        .arg(&file_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""replacements_made": 1"#))
+        .stdout(predicate::str::contains(r#""remaining_matches": 2"#));
+    assert_eq!(
+        fs::read_to_string(&file_path).unwrap(),
+        "DONE a\nb TODO TODO\n"
+    );
+}
+
+#[test]
+fn test_apply_dry_run() {
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir.path().join("test.txt");
+    fs::write(&file_path, "hello world\n").unwrap();
+
+    re_x()
+        .args([
+            "apply",
+            "world",
+            "earth",
+            "--file",
+            file_path.to_str().unwrap(),
+            "--dry-run",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"applied\": false"))
+        .stdout(predicate::str::contains("\"replacements_made\": 1"));
+
+    // File should NOT be modified
+    let content = fs::read_to_string(&file_path).unwrap();
+    assert_eq!(content, "hello world\n");
+}
+
+#[test]
+fn test_apply_diff() {
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir.path().join("test.txt");
+    fs::write(&file_path, "keep\r\nhello world\r\nend").unwrap();
+    let path = file_path.to_str().unwrap();
+
+    // --diff implies --dry-run; piped output is not colored
+    re_x()
+        .args(["-f", "text", "apply", "world|end", "X", "--file", path, "--diff"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[DRY-RUN]"))
+        .stdout(predicate::str::contains(
+            "@@ -1,3 +1,3 @@\n keep\n-hello world\n-end\n\\ No newline at end of file\n+hello X\n+X\n\\ No newline at end of file\n",
+        ))
+        .stdout(predicate::str::contains("\x1b[").not())
+        .stdout(predicate::str::contains("Preview:").not());
+    assert_eq!(
+        fs::read_to_string(&file_path).unwrap(),
+        "keep\r\nhello world\r\nend"
+    );
+
+    re_x()
+        .args([
+            "-f", "text", "apply", "world", "X", "--file", path, "--diff", "--color", "always",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\x1b[31m-hello world\x1b[0m"))
+        .stdout(predicate::str::contains("\x1b[32m+hello X\x1b[0m"));
+
+    re_x()
+        .args(["apply", "world", "X", "--file", path, "--diff"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"diff\": \"--- "));
+
+    re_x()
+        .args(["apply", "world", "X", "--file", path, "--color", "always"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_apply_multiline() {
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir.path().join("test.txt");
+    fs::write(&file_path, "hello\nworld\n").unwrap();
+
+    re_x()
+        .args([
+            "apply",
+            r"hello.world",
+            "REPLACED",
+            "--file",
+            file_path.to_str().unwrap(),
+            "--no-backup",
+            "-m",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"applied\": true"))
+        .stdout(predicate::str::contains("\"replacements_made\": 1"));
+
+    let content = fs::read_to_string(&file_path).unwrap();
+    assert!(content.contains("REPLACED"));
+}
+
+#[test]
+fn test_apply_only_lines_matching() {
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir.path().join("test.txt");
+    fs::write(&file_path, "let x = 1;\n// x is kept\nlet y = x;\n").unwrap();
+
+    re_x()
+        .args([
+            "apply",
+            r"\bx\b",
+            "z",
+            "--file",
+            file_path.to_str().unwrap(),
+            "--no-backup",
+            "--only-lines-matching",
+            "^let ",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"replacements_made\": 2"));
+
+    assert_eq!(
+        fs::read_to_string(&file_path).unwrap(),
+        "let z = 1;\n// x is kept\nlet y = z;\n"
+    );
+
+    // An invalid guard is reported before anything is written
+    re_x()
+        .args([
+            "apply",
+            "z",
+            "x",
+            "--file",
+            file_path.to_str().unwrap(),
+            "--only-lines-matching",
+            "(",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--only-lines-matching"));
+
+    // Guards are per line, so multiline mode is refused
+    re_x()
+        .args([
+            "apply",
+            "z",
+            "x",
+            "--file",
+            file_path.to_str().unwrap(),
+            "-m",
+            "--only-lines-matching",
+            "let",
+        ])
+        .assert()
+        .failure();
+}
+
+// --- MCP server tests ---
+
+#[test]
+fn test_mcp_initialize() {
+    let req = r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2025-03-26","capabilities":{},"clientInfo":{"name":"test","version":"1.0"}}}"#;
+    re_x()
+        .arg("--mcp")
+        .write_stdin(format!("{}\n", req))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"protocolVersion\""))
+        .stdout(predicate::str::contains("re-x"));
+}
+
+#[test]
+fn test_mcp_tools_list() {
+    let init = r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2025-03-26","capabilities":{},"clientInfo":{"name":"test","version":"1.0"}}}"#;
+    let list = r#"{"jsonrpc":"2.0","id":2,"method":"tools/list","params":{}}"#;
+    re_x()
+        .arg("--mcp")
+        .write_stdin(format!("{}\n{}\n", init, list))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("regex_test"))
+        .stdout(predicate::str::contains("regex_validate"))
+        .stdout(predicate::str::contains("regex_explain"))
+        .stdout(predicate::str::contains("regex_replace"))
+        .stdout(predicate::str::contains("regex_apply"))
+        .stdout(predicate::str::contains("regex_benchmark"))
+        .stdout(predicate::str::contains("regex_from_examples"))
+        .stdout(predicate::str::contains("regex_is_match"));
+}
+
+#[test]
+fn test_test_many() {
+    let dir = tempfile::tempdir().unwrap();
+    let patterns = dir.path().join("rules.txt");
+    fs::write(&patterns, "ERROR \\d+\n(unclosed\n\n^WARN\n").unwrap();
+    let log = dir.path().join("app.log");
+    fs::write(&log, "ERROR 1\nINFO ok\nERROR 2\n").unwrap();
+
+    re_x()
+        .args(["test-many"])
+        .arg(&patterns)
+        .arg("--file")
+        .arg(&log)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"matched_patterns\": 1"))
+        .stdout(predicate::str::contains("\"failed_patterns\": 1"))
+        .stdout(predicate::str::contains("\"match_count\": 2"))
+        .stdout(predicate::str::contains("\"line\": 4"));
+
+    re_x()
+        .args(["test-many"])
+        .arg(&patterns)
+        .args(["nothing here", "--format", "text"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("✗ ^WARN"))
+        .stdout(predicate::str::contains(
+            "0 of 3 patterns matched, 1 failed",
+        ));
+}
+
+#[test]
+fn test_mcp_tool_call_is_match() {
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir.path().join("app.log");
+    fs::write(&file_path, "INFO start\nWARN disk\nINFO done\n").unwrap();
+
+    let init = r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2025-03-26","capabilities":{},"clientInfo":{"name":"test","version":"1.0"}}}"#;
+    let hit = format!(
+        r#"{{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{{"name":"regex_is_match","arguments":{{"pattern":"WARN d","file_path":{}}}}}}}"#,
+        serde_json::to_string(file_path.to_str().unwrap()).unwrap()
+    );
+    let miss = r#"{"jsonrpc":"2.0","id":3,"method":"tools/call","params":{"name":"regex_is_match","arguments":{"pattern":"ERROR","input":"INFO ok"}}}"#;
+
+    let output = re_x()
+        .arg("--mcp")
+        .write_stdin(format!("{}\n{}\n{}\n", init, hit, miss))
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert!(lines[1].contains(r#"\"matched\": true"#), "{}", stdout);
+    assert!(lines[2].contains(r#"\"matched\": false"#), "{}", stdout);
+    assert!(!stdout.contains("matches"), "{}", stdout);
+}
+
+#[test]
+fn test_mcp_tool_call_test() {
+    let init = r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2025-03-26","capabilities":{},"clientInfo":{"name":"test","version":"1.0"}}}"#;
+    let call = r#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"regex_test","arguments":{"pattern":"\\d+","input":"hello 123"}}}"#;
+    re_x()
+        .arg("--mcp")
+        .write_stdin(format!("{}\n{}\n", init, call))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\\\"matched\\\": true"))
+        .stdout(predicate::str::contains("123"));
+}
+
+#[test]
+fn test_mcp_tool_call_validate() {
+    let init = r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2025-03-26","capabilities":{},"clientInfo":{"name":"test","version":"1.0"}}}"#;
+    let call = r#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"regex_validate","arguments":{"pattern":"\\d+"}}}"#;
+    re_x()
+        .arg("--mcp")
+        .write_stdin(format!("{}\n{}\n", init, call))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\\\"valid\\\": true"));
+}
+
+#[test]
+fn test_mcp_replace_and_apply_count_only() {
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir.path().join("notes.txt");
+    fs::write(&file_path, "foo bar\nfoo foo\n").unwrap();
+
+    let init = r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2025-03-26","capabilities":{},"clientInfo":{"name":"test","version":"1.0"}}}"#;
+    let replace = r#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"regex_replace","arguments":{"pattern":"o","replacement":"0","input":"foo boo","count_only":true}}}"#;
+    let apply = format!(
+        r#"{{"jsonrpc":"2.0","id":3,"method":"tools/call","params":{{"name":"regex_apply","arguments":{{"pattern":"foo","replacement":"baz","file_path":{},"backup":false,"count_only":true}}}}}}"#,
+        serde_json::to_string(file_path.to_str().unwrap()).unwrap()
+    );
+
+    let output = re_x()
+        .arg("--mcp")
+        .write_stdin(format!("{}\n{}\n{}\n", init, replace, apply))
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert!(
+        lines[1].contains(r#"\"replacements_made\": 4"#),
+        "{}",
+        stdout
+    );
+    assert!(
+        lines[2].contains(r#"\"replacements_made\": 3"#),
+        "{}",
+        stdout
+    );
+    assert!(!stdout.contains("preview"), "{}", stdout);
+    assert!(!stdout.contains("original"), "{}", stdout);
+    assert_eq!(
+        fs::read_to_string(&file_path).unwrap(),
+        "baz bar\nbaz baz\n"
+    );
+}
+
+#[test]
+fn test_mcp_invalid_json() {
+    re_x()
+        .arg("--mcp")
+        .write_stdin("not valid json\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("-32700"))
+        .stdout(predicate::str::contains("Parse error"));
+}
+
+#[test]
+fn test_mcp_unknown_method() {
+    let req = r#"{"jsonrpc":"2.0","id":1,"method":"nonexistent","params":{}}"#;
+    re_x()
+        .arg("--mcp")
+        .write_stdin(format!("{}\n", req))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("-32601"))
+        .stdout(predicate::str::contains("Method not found"));
+}
+
+#[test]
+fn test_mcp_ping() {
+    let req = r#"{"jsonrpc":"2.0","id":1,"method":"ping","params":{}}"#;
+    re_x()
+        .arg("--mcp")
+        .write_stdin(format!("{}\n", req))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"result\""));
+}
+
+#[test]
+fn test_mcp_benchmark_deadline_keeps_server_responsive() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("big.txt");
+    fs::write(&path, "lorem ipsum dolor sit amet ".repeat(200_000)).unwrap();
+
+    let call = format!(
+        r#"{{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{{"name":"regex_benchmark","arguments":{{"pattern":"(\\w+)\\s+\\1x","file_path":{},"timeout_ms":1}}}}}}"#,
+        serde_json::to_string(&path).unwrap()
+    );
+    let ping = r#"{"jsonrpc":"2.0","id":2,"method":"ping","params":{}}"#;
+    re_x()
+        .arg("--mcp")
+        .write_stdin(format!("{}\n{}\n", call, ping))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#"\"timeout\": true"#))
+        .stdout(predicate::str::contains(r#""id":2,"result""#));
+}
+
+#[test]
+fn test_mcp_timeout_applies_to_every_tool() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("big.txt");
+    fs::write(&path, "lorem ipsum dolor sit amet ".repeat(200_000)).unwrap();
+
+    let call = format!(
+        r#"{{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{{"name":"regex_test","arguments":{{"pattern":"(\\w+)\\s+\\1x","file_path":{}}}}}}}"#,
+        serde_json::to_string(&path).unwrap()
+    );
+    let ping = r#"{"jsonrpc":"2.0","id":2,"method":"ping","params":{}}"#;
+    re_x()
+        .args(["--mcp", "--mcp-timeout-ms", "1"])
+        .write_stdin(format!("{}\n{}\n", call, ping))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("TIMEOUT"))
+        .stdout(predicate::str::contains(r#""id":2,"result""#));
+}
+
+#[test]
+fn test_mcp_caps_abandoned_workers() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("big.txt");
+    fs::write(&path, "lorem ipsum dolor sit amet ".repeat(200_000)).unwrap();
+
+    // Each call outlives its 1ms budget by far, so its worker keeps running
+    let calls: String = (1..=5)
+        .map(|id| {
+            format!(
+                r#"{{"jsonrpc":"2.0","id":{},"method":"tools/call","params":{{"name":"regex_test","arguments":{{"pattern":"(\\w+)\\s+\\1x","file_path":{}}}}}}}"#,
+                id,
+                serde_json::to_string(&path).unwrap()
+            ) + "\n"
+        })
+        .collect();
+    let output = re_x()
+        .args(["--mcp", "--mcp-timeout-ms", "1"])
+        .write_stdin(calls)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 5);
+    assert!(lines[..4].iter().all(|line| line.contains("TIMEOUT")));
+    assert!(lines[4].contains("timed-out tool calls are still running"));
+}
+
+#[test]
+fn test_mcp_apply_is_not_abandoned_at_timeout() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("big.txt");
+    fs::write(&path, "lorem ipsum dolor sit amet\n".repeat(20_000)).unwrap();
+
+    let call = format!(
+        r#"{{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{{"name":"regex_apply","arguments":{{"pattern":"ipsum","replacement":"IPSUM","file_path":{},"backup":false,"count_only":true}}}}}}"#,
+        serde_json::to_string(&path).unwrap()
+    );
+    // A timeout reported for apply would be a lie: the write still happens
+    re_x()
+        .args(["--mcp", "--mcp-timeout-ms", "1"])
+        .write_stdin(format!("{}\n", call))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("TIMEOUT").not())
+        .stdout(predicate::str::contains(r#"\"replacements_made\": 20000"#));
+    assert!(!fs::read_to_string(&path).unwrap().contains("ipsum"));
+}
+
+#[test]
+fn test_mcp_max_groups() {
+    let call = |id: u32, pattern: &str| {
+        format!(
+            r#"{{"jsonrpc":"2.0","id":{},"method":"tools/call","params":{{"name":"regex_explain","arguments":{{"pattern":"{}"}}}}}}"#,
+            id, pattern
+        )
+    };
+    re_x()
+        .args(["--mcp", "--max-groups", "3"])
+        .write_stdin(format!(
+            "{}\n{}\n",
+            call(1, "(a)(b)(c)(d)"),
+            call(2, "(a)(?:b)(c)")
+        ))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("TOO_MANY_GROUPS"))
+        .stdout(predicate::str::contains(
+            "too many capture groups: 4 (limit: 3",
+        ))
+        .stdout(predicate::str::contains(r#""id":2,"result""#));
+
+    re_x()
+        .args(["test", "a", "a", "--max-groups", "3"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_first_match_only() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.txt");
+    fs::write(&path, "a1\nb22\nc333\n").unwrap();
+
+    re_x()
+        .args(["test", r"\d{2,}", "--first", "--file"])
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"match_count\": 1"))
+        .stdout(predicate::str::contains("\"start\": 4"));
+
+    // Streaming must not make ^ match at every line start
+    re_x()
+        .args(["test", "^b", "--first", "--file"])
+        .arg(&path)
+        .assert()
+        .code(1);
+}
+
+#[test]
+fn test_timeout_per_line_stops_at_slow_line() {
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir.path().join("input.txt");
+    // The middle line makes the backtracking engine take quadratic time
+    // (seconds), far past the timeout; the others match in microseconds
+    fs::write(&file_path, format!("ab1\n{}\ncd2\n", "a".repeat(20_000))).unwrap();
+
+    re_x()
+        .args([
+            "test",
+            r"(?=\w)\w*\d",
+            "--file",
+            file_path.to_str().unwrap(),
+            "--timeout-per-line-ms",
+            "500",
+        ])
+        .timeout(std::time::Duration::from_secs(5))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"match_count\": 1"))
+        .stdout(predicate::str::contains("\"ab1\""))
+        .stdout(predicate::str::contains("\"cd2\"").not())
+        .stdout(predicate::str::contains(
+            "Line 2 ran past the per-line timeout",
+        ))
+        .stdout(predicate::str::contains(
+            "\"timed_out_lines\": [\n    2\n  ]",
+        ));
+}
+
+#[test]
+fn test_context_json_line_and_column() {
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir.path().join("notes.txt");
+    fs::write(&file_path, "café foo\r\nbar foo\n").unwrap();
+
+    re_x()
+        .args(["test", "foo", "--file", file_path.to_str().unwrap()])
+        .arg("--context-json")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""line": 1,"#))
+        .stdout(predicate::str::contains(r#""column": 6,"#))
+        .stdout(predicate::str::contains(r#""end_column": 9"#))
+        .stdout(predicate::str::contains(r#""line": 2,"#))
+        .stdout(predicate::str::contains(r#""column": 5,"#));
+
+    // Without the flag matches carry offsets only
+    re_x()
+        .args(["test", "foo", "--file", file_path.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""line""#).not());
+
+    re_x()
+        .args(["test", "foo", "foo", "--context-json"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_vimgrep() {
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir.path().join("notes.txt");
+    fs::write(&file_path, "café foo\r\nbar foo\n").unwrap();
+
+    re_x()
+        .args(["test", "fo+", "--vimgrep", "--file"])
+        .arg(&file_path)
+        .assert()
+        .success()
+        .stdout(format!(
+            "{0}:1:7:foo\n{0}:2:5:foo\n",
+            file_path.to_str().unwrap()
+        ));
+
+    re_x()
+        .args(["test", "b.r", "--vimgrep"])
+        .write_stdin("foo\nxbar\n")
+        .assert()
+        .success()
+        .stdout("<stdin>:2:2:bar\n");
+
+    // Columns count bytes, as vim and grep do
+    re_x()
+        .args(["test", "x", "--vimgrep"])
+        .write_stdin("aé x\n")
+        .assert()
+        .success()
+        .stdout("<stdin>:1:5:x\n");
+
+    re_x()
+        .args(["test", "z", "abc", "--vimgrep"])
+        .assert()
+        .code(1)
+        .stdout("");
+}
+
+#[test]
+fn test_coverage() {
+    re_x()
+        .args([
+            "test",
+            r"user=(\w+) id=(\d{4})",
+            "user=ann id=12",
+            "--coverage",
+        ])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains(r#""failed_at": 17"#))
+        .stdout(predicate::str::contains(
+            r#""prefix_match": "user=ann id=1""#,
+        ));
+
+    re_x()
+        .args(["test", "a+b", "xaab", "--coverage", "-f", "text"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "The whole pattern matches: \"aab\"",
+        ));
+}
+
+#[test]
+fn test_sticky() {
+    re_x()
+        .args(["test", r"\d", "12a3", "--sticky", "-f", "text"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2 matches found"));
+
+    re_x()
+        .args(["test", "x", "ax", "--sticky"])
+        .assert()
+        .code(1);
+
+    re_x()
+        .args(["test", "x", "ax", "--sticky", "--byte-mode"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_stream_json() {
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir.path().join("app.log");
+    fs::write(&file_path, "id=1 id=22\r\nnone\nid=333\n").unwrap();
+    let file = file_path.to_str().unwrap();
+
+    let output = re_x()
+        .args(["test", r"id=(\d+)", "--file", file, "--stream-json"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let matches: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let spans: Vec<(u64, u64)> = matches
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|m| (m["start"].as_u64().unwrap(), m["end"].as_u64().unwrap()))
+        .collect();
+    assert_eq!(spans, vec![(0, 4), (5, 10), (17, 23)]);
+    assert_eq!(matches[2]["captures"][0]["text"], "333");
+
+    re_x()
+        .args(["test", "id", "--file", file, "--stream-json", "--first"])
+        .assert()
+        .success()
+        .stdout("[\n  {\"text\":\"id\",\"start\":0,\"end\":2,\"captures\":[]}\n]\n");
+
+    re_x()
+        .args(["test", "zzz", "--file", file, "--stream-json"])
+        .assert()
+        .code(1)
+        .stdout("[]\n");
+
+    // No default cap of 100 on a stream, only an explicit one
+    fs::write(&file_path, "id ".repeat(150)).unwrap();
+    let count = |extra: &[&str]| {
+        let output = re_x()
+            .args(["test", "id", "--file", file, "--stream-json"])
+            .args(extra)
+            .output()
+            .unwrap();
+        let matches: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        matches.as_array().unwrap().len()
+    };
+    assert_eq!(count(&[]), 150);
+    assert_eq!(count(&["--max-matches", "120"]), 120);
+}
+
+#[test]
+fn test_patterns_from_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let patterns_path = dir.path().join("patterns.txt");
+    fs::write(&patterns_path, "foo\n\nb.r\n").unwrap();
+    let patterns = patterns_path.to_str().unwrap();
+
+    re_x()
+        .args(["test", "--patterns-from", patterns, "bar foo"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""match_count": 2"#))
+        .stdout(predicate::str::contains(r#""name": "alt3""#))
+        .stdout(predicate::str::contains(r#""name": "alt1""#));
+
+    re_x()
+        .args([
+            "test",
+            "--patterns-from",
+            patterns,
+            "--fixed-strings",
+            "bar",
+        ])
+        .assert()
+        .code(1);
+
+    re_x()
+        .args(["test", "--patterns-from", patterns, "--fixed-strings"])
+        .write_stdin("a b.r")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""text": "b.r""#));
+
+    re_x()
+        .args(["test", "--patterns-from", patterns, "bar", "foo"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--patterns-from replaces PATTERN"));
+}
+
+#[test]
+fn test_byte_mode_reads_invalid_utf8() {
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir.path().join("dump.bin");
+    fs::write(&file_path, b"GET /\xff\xfe HTTP/1.1\nid=42 \xc3\x28\n").unwrap();
+
+    re_x()
+        .args(["test", r"id=\d+", "--file", file_path.to_str().unwrap()])
+        .assert()
+        .code(5)
+        .stderr(predicate::str::contains("use --byte-mode"));
+
+    re_x()
+        .args(["test", r"id=\d+", "--file", file_path.to_str().unwrap()])
+        .arg("--byte-mode")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""text": "id=42""#))
+        .stdout(predicate::str::contains(r#""start": 17"#));
+
+    re_x()
+        .args([
+            "test",
+            r"(?-u)/\xFF\xFE",
+            "--file",
+            file_path.to_str().unwrap(),
+        ])
+        .arg("--byte-mode")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"text\": \"/\u{FFFD}\u{FFFD}\""));
+}
+
+#[test]
+fn test_csv_output() {
+    re_x()
+        .args(["--format", "csv", "test", r#""([^"]*)""#, r#"x "a,b" y"#])
+        .assert()
+        .success()
+        .stdout("start,end,text,1\n2,7,\"\"\"a,b\"\"\",\"a,b\"\n");
+
+    re_x()
+        .args([
+            "-f",
+            "csv",
+            "--delimiter",
+            "tab",
+            "test",
+            r"(\w)\w*",
+            "ab ac b",
+        ])
+        .args(["--by-capture", "1"])
+        .assert()
+        .success()
+        .stdout("value\tcount\na\t2\nb\t1\n");
+
+    re_x()
+        .args(["-f", "csv", "explain", "a+"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"pattern\""))
+        .stderr(predicate::str::contains("only available for test"));
+}
+
+#[test]
+fn test_only_groups_table() {
+    re_x()
+        .args([
+            "--format",
+            "text",
+            "test",
+            r"(\w+)=(\d+)?",
+            "a=1 b=",
+            "--only-groups",
+        ])
+        .assert()
+        .success()
+        .stdout("1\t2\na\t1\nb\t\n");
+
+    re_x()
+        .args(["test", r"\w+", "abc", "--only-groups"])
+        .assert()
+        .code(5)
+        .stderr(predicate::str::contains("capture groups"));
+}
+
+#[test]
+fn test_empty_matches_left_out() {
+    re_x()
+        .args(["test", "x*", "axxb"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"matches_empty\": true"))
+        .stdout(predicate::str::contains("\"match_count\": 1"));
+
+    re_x()
+        .args(["test", "x*", "axxb", "--allow-empty-matches"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"match_count\": 3"));
+}
+
+// --- watch command tests ---
+
+#[test]
+fn test_watch_reruns_on_change() {
+    use std::io::{BufRead, BufReader};
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("log.txt");
+    fs::write(&path, "a 1\n").unwrap();
+
+    let mut child = std::process::Command::new(assert_cmd::cargo_bin!("re-x"))
+        .args(["watch", r"\d+", "--file"])
+        .arg(&path)
+        .args(["--debounce-ms", "50", "--max-runs", "2"])
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut runs = BufReader::new(child.stdout.take().unwrap()).lines();
+
+    // The watcher is running before the first result is printed, so a write
+    // after reading it is always seen; the second run then ends the watch
+    let first = runs.next().unwrap().unwrap();
+    assert!(first.contains(r#""match_count":1"#), "{}", first);
+    fs::write(&path, "a 1\nb 22\n").unwrap();
+    let second = runs.next().unwrap().unwrap();
+    assert!(second.contains(r#""match_count":2"#), "{}", second);
+
+    assert!(child.wait().unwrap().success());
+    assert!(runs.next().is_none());
+}
+
+// --- replace --file tests ---
+
+#[test]
+fn test_replace_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir.path().join("urls.txt");
+    fs::write(
+        &file_path,
+        "http://example.com\nhttps://safe.com\nhttp://test.org\n",
+    )
+    .unwrap();
+
+    re_x()
+        .args([
+            "replace",
+            "http://",
+            "https://",
+            "--file",
+            file_path.to_str().unwrap(),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"replacements_made\": 2"))
+        .stdout(predicate::str::contains("\"preview\""));
+
+    // replace --file never modifies the file
+    let content = fs::read_to_string(&file_path).unwrap();
+    assert!(content.contains("http://example.com"));
+}
+
+#[test]
+fn test_replace_file_fancy_captures() {
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir.path().join("data.txt");
+    fs::write(&file_path, "price: 100USD\nprice: 200EUR\n").unwrap();
+
+    // Lookahead pattern (fancy-regex) with capture group and $1 expansion
+    re_x()
+        .args([
+            "replace",
+            r"(\d+)(?=USD|EUR)",
+            "[$1]",
+            "--file",
+            file_path.to_str().unwrap(),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"replacements_made\": 2"))
+        .stdout(predicate::str::contains("\"preview\""));
+}
+
+#[test]
+fn test_replace_raw() {
+    re_x()
+        .args(["replace", "--raw", "http://", "https://"])
+        .write_stdin("http://a.com\nhttp://b.org\n")
+        .assert()
+        .success()
+        .stdout("https://a.com\nhttps://b.org\n");
+
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir.path().join("urls.txt");
+    fs::write(&file_path, "http://a.com\nkeep\nhttp://b.org").unwrap();
+
+    re_x()
+        .args(["replace", "--raw", "http://", "https://", "--file"])
+        .arg(&file_path)
+        .assert()
+        .success()
+        .stdout("https://a.com\nkeep\nhttps://b.org");
+}
+
+// --- null-data tests ---
+
+#[test]
+fn test_null_data_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir.path().join("names.bin");
+    fs::write(&file_path, "a.txt\0dir/with\nnewline.rs\0b.rs\0").unwrap();
+
+    re_x()
+        .args([
+            "test",
+            r"^[^\n]*\.rs$",
+            "--file",
+            file_path.to_str().unwrap(),
+            "-z",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"match_count\": 1"))
+        .stdout(predicate::str::contains("\"text\": \"b.rs\""))
+        .stdout(predicate::str::contains("\"start\": 26"));
+
+    // Records are matched one at a time, so matching across them is refused
+    re_x()
+        .args([
+            "test",
+            "a.b",
+            "--file",
+            file_path.to_str().unwrap(),
+            "-z",
+            "-m",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+// --- nesting depth guard tests ---
+
+#[test]
+fn test_max_depth_rejects_deep_nesting() {
+    let deep = format!("{}a{}", "(".repeat(50), ")".repeat(50));
+
+    re_x()
+        .args(["validate", &deep, "--max-depth", "16"])
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("\"valid\": false"))
+        .stdout(predicate::str::contains("nesting too deep (limit: 16"));
+
+    re_x()
+        .args(["explain", &deep, "--max-depth", "16"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("nesting too deep"));
+}
+
+#[test]
+fn test_validate_all_targets() {
+    re_x()
+        .args(["validate", r"foo(?=bar)", "--all-targets"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"targets\""))
+        .stdout(predicate::str::contains("\"target\": \"go_regexp\""))
+        .stdout(predicate::str::contains("\"rewrite\""));
+}
+
+#[test]
+fn test_validate_posix_target() {
+    re_x()
+        .args(["validate", r"\d{3}-\d{4}", "--target-lang", "posix"])
+        .assert()
+        .code(4)
+        .stdout(predicate::str::contains("[[:digit:]]"));
+
+    re_x()
+        .args(["validate", "[0-9]{3}-[0-9]{4}", "--target-lang", "ere"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"posix_ere\": true"))
+        .stdout(predicate::str::contains("\"posix_bre\": false"));
+
+    // BRE writes the interval \{3\}
+    re_x()
+        .args(["validate", "[0-9]{3}-[0-9]{4}", "--target-lang", "bre"])
+        .assert()
+        .code(4)
+        .stdout(predicate::str::contains("intervals are written"));
+}
+
+#[test]
+fn test_posix_target_warns_on_leftmost_longest_divergence() {
+    re_x()
+        .args(["validate", "(a|ab)+", "--target-lang", "posix"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "POSIX engines (leftmost-longest) prefer `ab`",
+        ));
+
+    re_x()
+        .args(["explain", "cat|category", "--target", "posix"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\"portability_note\": \"Alternation `cat|category` depends on match semantics",
+        ));
+
+    // Nothing follows an alternation that ends the pattern either
+    re_x()
+        .args(["explain", "x(a|ab)", "--target", "posix"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\"portability_note\": \"Alternation `a|ab` depends on match semantics",
+        ));
+
+    // Something after the alternation forces the choice, so both agree
+    re_x()
+        .args(["validate", "x(a|ab)y", "--target-lang", "posix"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("leftmost-longest").not());
+}
+
+#[test]
+fn test_engine_fallback_warning() {
+    re_x()
+        .args(["test", r"\Ga", "ab", "--engine-fallback-warning"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"fallback_occurred\": true"))
+        .stderr(predicate::str::contains(
+            "re-x: warning: Fell back to fancy-regex because the regex crate rejected the pattern",
+        ));
+
+    re_x()
+        .args(["validate", "a{,3}", "--engine-fallback-warning"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"fallback_occurred\": true"))
+        .stderr(predicate::str::contains("repetition quantifier"));
+
+    // Lookahead picks fancy-regex up front, which is not a fallback
+    re_x()
+        .args(["test", "a(?=b)", "ab", "--engine-fallback-warning"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"fallback_occurred\": false"))
+        .stderr(predicate::str::is_empty());
+}
+
+#[test]
+fn test_compare_languages() {
+    re_x()
+        .args(["compare-languages", r"(?<=\$)\d+"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            r#""engine_required": "fancy-regex""#,
+        ))
+        .stdout(predicate::str::contains(r#""target": "go_regexp""#))
+        .stdout(predicate::str::contains("Lookbehind assertions"));
+
+    re_x()
+        .args(["compare-languages", r"\d+", "--format", "text"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Accepted by 9 of 11 targets"))
+        .stdout(predicate::str::contains("✗ posix_ere"));
+
+    re_x()
+        .args(["compare-languages", "(a"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Invalid pattern"));
+}
+
+#[test]
+fn test_validate_size_limit() {
+    re_x()
+        .args(["validate", "a{1000000}"])
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("\"kind\": \"size_limit\""));
+
+    re_x()
+        .args(["validate", "a{1000000}", "--size-limit", "100000000"])
+        .assert()
+        .success();
+}
+
+// --- simplify command tests ---
+
+#[test]
+fn test_simplify_command() {
+    re_x()
+        .args(["simplify", "(?:[0-9][0-9][0-9])-x"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"simplified\": \"[0-9]{3}-x\""))
+        .stdout(predicate::str::contains("\"changed\": true"));
+}
+
+// --- normalize command tests ---
+
+#[test]
+fn test_normalize_command() {
+    for pattern in [r"(?:[cba])x{1,}\/", r"[a-c]x+/"] {
+        re_x()
+            .args(["normalize", pattern])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(r#""normalized": "[a-c]x+/""#));
+    }
+
+    re_x()
+        .args(["normalize", "[a-c]x+", "--format", "text"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("already in canonical form"));
+}
+
+#[test]
+fn test_dedupe_patterns_command() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("patterns.txt");
+    fs::write(&path, "[0-9]{1,}\nfoo\n\n(?:[0-9])+\n").unwrap();
+
+    re_x()
+        .arg("dedupe-patterns")
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""canonical": "[0-9]+""#))
+        .stdout(predicate::str::contains(r#""line": 4"#))
+        .stdout(predicate::str::contains(r#""distinct": 2"#));
+
+    re_x()
+        .args(["dedupe-patterns", "--format", "text"])
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[0-9]+\n  line 1: [0-9]{1,}\n  line 4: (?:[0-9])+",
+        ));
+}
+
+// --- lint command tests ---
+
+#[test]
+fn test_lint_command() {
+    re_x()
+        .args(["lint", r"a\/b\,c"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"rule\": \"unnecessary-escape\""))
+        .stdout(predicate::str::contains("\"fixed\": \"a/b,c\""));
+
+    re_x()
+        .args(["lint", r"\d+\.\d+"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"clean\": true"));
+}
+
+#[test]
+fn test_lint_invalid_pattern() {
+    for pattern in ["(", r"[a-\-]"] {
+        re_x()
+            .args(["lint", pattern])
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains("\"code\":\"INVALID_PATTERN\""));
+    }
+
+    // Valid, but only for fancy-regex
+    re_x()
+        .args(["lint", r"a(?=b)"])
+        .assert()
+        .code(5)
+        .stderr(predicate::str::contains("no lookaround"));
+}
+
+// --- ast-diff command tests ---
+
+#[test]
+fn test_ast_diff_command() {
+    re_x()
+        .args(["ast-diff", r"(\d{2,5})-x", r"(?:\d{2,6})-x"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"identical\": false"))
+        .stdout(predicate::str::contains(
+            "capture group #1 → non-capturing group",
+        ))
+        .stdout(predicate::str::contains("{2,5} → {2,6}"));
+
+    re_x()
+        .args(["ast-diff", "a+", "a+"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"identical\": true"));
+}
+
+#[test]
+fn test_ast_diff_invalid_pattern() {
+    re_x()
+        .args(["ast-diff", "a+", "(a"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("\"code\":\"INVALID_PATTERN\""));
+
+    re_x()
+        .args(["ast-diff", "a+", "a(?=b)"])
+        .assert()
+        .code(5)
+        .stderr(predicate::str::contains("lookaround"));
+}
+
+#[test]
+fn test_build_command() {
+    let dir = tempfile::tempdir().unwrap();
+    let ast_path = dir.path().join("price.json");
+    fs::write(
+        &ast_path,
+        r#"{"type": "concat", "nodes": [
+            {"type": "literal", "value": "$"},
+            {"type": "group", "name": "amount", "node": {"type": "repeat", "min": 1, "node": {"type": "perl", "class": "digit"}}},
+            {"type": "repeat", "max": 1, "node": {"type": "literal", "value": ".99"}}
+        ]}"#,
+    )
+    .unwrap();
+
+    re_x()
+        .args(["build", ast_path.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            r#""pattern": "\\$(?<amount>\\d+)(?:\\.99)?""#,
+        ));
+
+    fs::write(&ast_path, r#"{"type": "alternation", "nodes": []}"#).unwrap();
+    re_x()
+        .args(["build", ast_path.to_str().unwrap()])
+        .assert()
+        .code(5);
+}
+
+// --- generate-corpus command tests ---
+
+#[test]
+fn test_generate_corpus_command() {
+    re_x()
+        .args([
+            "generate-corpus",
+            r"^\d{3}$",
+            "--count",
+            "5",
+            "--include-near-misses",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"positives\""))
+        .stdout(predicate::str::contains("\"negatives\""));
+}
+
+// --- capture group selection tests ---
+
+#[test]
+fn test_group_selects_capture_text() {
+    re_x()
+        .args(["test", r"(\w+)@(\w+)", "a@x b@y", "--group", "2"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"text\": \"x\""))
+        .stdout(predicate::str::contains("\"text\": \"y\""))
+        .stdout(predicate::str::contains("\"text\": \"a@x\"").not());
+}
+
+#[test]
+fn test_group_unknown_name_fails() {
+    re_x()
+        .args(["test", r"(?P<user>\w+)@", "a@x", "--group", "domain"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("does not exist"));
+}
+
+#[test]
+fn test_benchmark_detect_only() {
+    re_x()
+        .args(["benchmark", "(a+)+$", "--detect-only"])
+        .assert()
+        .code(3)
+        .stdout(predicate::str::contains(
+            "\"catastrophic_backtracking\": true",
+        ))
+        .stdout(predicate::str::contains("\"iterations\": 0"))
+        .stdout(predicate::str::contains("\"note\""));
+}
+
+// --- exit code tests ---
+
+#[test]
+fn test_exit_code_invalid_pattern() {
+    re_x().args(["test", r"(\d+", "abc"]).assert().code(2);
+
+    // Rejected by the nesting guard although the engine would compile it
+    let deep = format!("{}a{}", "(".repeat(50), ")".repeat(50));
+    re_x()
+        .args(["explain", &deep, "--max-depth", "16"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("\"code\":\"INVALID_PATTERN\""));
+
+    // An I/O failure with a valid pattern is not a pattern error
+    re_x()
+        .args(["replace", r"\d+", "x", "--file", "/nonexistent/file.txt"])
+        .assert()
+        .code(5);
+}
+
+#[test]
+fn test_exit_code_incompatible_target() {
+    re_x()
+        .args(["validate", r"foo(?=bar)", "--target-lang", "go"])
+        .assert()
+        .code(4);
+
+    re_x()
+        .args(["validate", r"\d+", "--target-lang", "go"])
+        .assert()
+        .code(0);
+}
+
+#[test]
+fn test_exit_code_other_error() {
+    re_x()
+        .args(["test", r"\d+", "--file", "/nonexistent/file.txt"])
+        .assert()
+        .code(5);
+
+    re_x().args(["test", "--no-such-flag"]).assert().code(5);
+}
+
+#[test]
+fn test_no_unicode_flag() {
+    re_x()
+        .args(["test", r"^\w$", "é", "--no-unicode"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("\"matched\": false"));
+
+    re_x()
+        .args(["test", r"^\w$", "é"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"matched\": true"));
+}
+
+#[test]
+fn test_replace_max_result_bytes() {
+    let input = "x1 ".repeat(50);
+    re_x()
+        .args(["replace", r"\d", "N", &input, "--max-result-bytes", "6"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"truncated\": true"))
+        .stdout(predicate::str::contains("\"result\": \"xN xN \""))
+        .stdout(predicate::str::contains("\"replacements_made\": 50"));
+}
+
+#[test]
+fn test_replace_literal_replacement() {
+    re_x()
+        .args([
+            "replace",
+            r"(\w+): TBD",
+            "$1: $5",
+            "price: TBD",
+            "--literal-replacement",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"result\": \"$1: $5\""));
+}
+
+#[test]
+fn test_apply_literal_replacement() {
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir.path().join("prices.txt");
+    fs::write(&file_path, "cost: X\n").unwrap();
+
+    re_x()
+        .args([
+            "apply",
+            "X",
+            "$10",
+            "--file",
+            file_path.to_str().unwrap(),
+            "--no-backup",
+            "--literal-replacement",
+        ])
+        .assert()
+        .success();
+
+    let content = fs::read_to_string(&file_path).unwrap();
+    assert_eq!(content, "cost: $10\n");
+}
+
+// --- RE_X_FORMAT environment variable tests ---
+
+#[test]
+fn test_format_from_env() {
+    re_x()
+        .env("RE_X_FORMAT", "text")
+        .args(["test", r"\d+", "abc 123"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Pattern: \\d+"));
+}
+
+#[test]
+fn test_format_flag_overrides_env() {
+    re_x()
+        .env("RE_X_FORMAT", "text")
+        .args(["test", r"\d+", "abc 123", "--format", "json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"matched\": true"));
+}
+
+#[test]
+fn test_format_env_unknown_value_falls_back_to_json() {
+    re_x()
+        .env("RE_X_FORMAT", "yaml")
+        .args(["test", r"\d+", "abc 123"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"matched\": true"))
+        .stderr(predicate::str::contains("RE_X_FORMAT"));
+}
+
+// --- Full match tests ---
+
+#[test]
+fn test_full_match_conforms() {
+    re_x()
+        .args(["test", r"(\d{3})-(\d{4})", "555-1234", "--full-match"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"full_match\": true"))
+        .stdout(predicate::str::contains("\"match_count\": 1"));
+}
+
+#[test]
+fn test_show_flags_normalized() {
+    re_x()
+        .args([
+            "test",
+            "a.b",
+            "a\nb",
+            "-m",
+            "--full-match",
+            "--show-flags-normalized",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            r#""effective_pattern": "(?ms)\\A(?:a.b)\\z""#,
+        ));
+
+    re_x()
+        .args(["-X", "validate", r"a \d", "--show-flags-normalized"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            r#""effective_pattern": "(?x)a \\d""#,
+        ));
+
+    re_x()
+        .args(["test", "a", "a"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("effective_pattern").not());
+}
+
+#[test]
+fn test_full_match_rejects_partial() {
+    re_x()
+        .args([
+            "test",
+            r"\d+",
+            "abc 123",
+            "--full-match",
+            "--format",
+            "text",
+        ])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("Input does not match in full"));
+}
+
+#[test]
+fn test_mcp_invalid_pattern_error_details() {
+    let init = r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2025-03-26","capabilities":{},"clientInfo":{"name":"test","version":"1.0"}}}"#;
+    let call = r#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"regex_test","arguments":{"pattern":"(abc","input":"abc"}}}"#;
+    re_x()
+        .arg("--mcp")
+        .write_stdin(format!("{}\n{}\n", init, call))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"isError\":true"))
+        .stdout(predicate::str::contains(
+            "\\\"code\\\":\\\"INVALID_PATTERN\\\"",
+        ))
+        .stdout(predicate::str::contains("\\\"position\\\":0"))
+        .stdout(predicate::str::contains("\\\"context\\\""))
+        .stdout(predicate::str::contains("\\\"suggestion\\\""));
+}
+
+#[test]
+fn test_benchmark_per_line() {
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir.path().join("input.txt");
+    fs::write(&file_path, "abc 123\ndef 456\nghi\n").unwrap();
+
+    re_x()
+        .args([
+            "benchmark",
+            r"\d+",
+            "--file",
+            file_path.to_str().unwrap(),
+            "--iterations",
+            "3",
+            "--per-line",
+            "--top-lines",
+            "2",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"slowest_lines\""))
+        .stdout(predicate::str::contains("\"time_us\""));
+}
+
+#[test]
+fn test_benchmark_per_line_requires_file() {
+    re_x()
+        .args(["benchmark", r"\d+", "--input", "abc 123", "--per-line"])
+        .assert()
+        .code(5);
+}
+
+#[test]
+fn test_benchmark_sample() {
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir.path().join("input.txt");
+    let content: String = (0..500)
+        .map(|i| format!("row {} value={}\n", i, i * 7))
+        .collect();
+    fs::write(&file_path, content).unwrap();
+
+    re_x()
+        .args([
+            "benchmark",
+            r"value=\d+",
+            "--file",
+            file_path.to_str().unwrap(),
+            "--iterations",
+            "3",
+            "--sample",
+            "20",
+            "--seed",
+            "42",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"lines\": 20"))
+        .stdout(predicate::str::contains("\"total_lines\": 500"))
+        .stdout(predicate::str::contains("\"seed\": 42"));
+
+    re_x()
+        .args(["benchmark", r"\d+", "--input", "abc 123", "--sample", "5"])
+        .assert()
+        .code(5);
+}
+
+// --- Extended mode / pattern file tests ---
+
+#[test]
+fn test_extended_pattern_from_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let pattern_path = dir.path().join("date.re");
+    fs::write(
+        &pattern_path,
+        "# ISO date, e.g. 2024-05-17\n\
+         (?<year>\\d{4}) -   # year\n\
+         (?<month>\\d{2}) -  # month\n\
+         (?<day>\\d{2})      # day\n",
+    )
+    .unwrap();
+
+    re_x()
+        .args([
+            "test",
+            "--pattern-file",
+            pattern_path.to_str().unwrap(),
+            "--extended",
+            "released 2024-05-17, patched 2024-06-02",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"match_count\": 2"))
+        .stdout(predicate::str::contains("\"text\": \"2024-05-17\""))
+        .stdout(predicate::str::contains("\"name\": \"month\""));
+}
+
+#[test]
+fn test_pattern_file_missing() {
+    re_x()
+        .args(["test", "--pattern-file", "/nonexistent/pattern.re", "abc"])
+        .assert()
+        .code(5)
+        .stderr(predicate::str::contains("Failed to read pattern file"));
+}
+
+#[test]
+fn test_engine_reason_reported() {
+    re_x()
+        .args(["test", r"foo(?=bar)", "foobar"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\"engine_reason\": \"Pattern uses lookahead assertion\"",
+        ));
+}
+
+#[test]
+fn test_by_capture_histogram() {
+    re_x()
+        .args(["test", r"user=(\w+)", "--by-capture", "1"])
+        .write_stdin("user=ann ok\nuser=bob fail\nuser=ann fail\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\"capture_counts\": [\n    {\n      \"value\": \"ann\",\n      \"count\": 2\n    }",
+        ));
+}
+
+#[test]
+fn test_sort_by_text() {
+    re_x()
+        .args(["test", r"\w+", "pear fig apple", "--sort", "text"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\"text\": \"apple\",\n      \"start\": 9,\n      \"end\": 14,\n      \"captures\": [],\n      \"index\": 2",
+        ));
+
+    re_x()
+        .args(["test", r"\w+", "a", "--sort", "size"])
+        .assert()
+        .code(5);
+}
+
+#[test]
+fn test_dedup_matches() {
+    re_x()
+        .args(["test", r"\w+", "err ok err", "--dedup"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"match_count\": 3"))
+        .stdout(predicate::str::contains("\"occurrences\": 2"))
+        .stdout(predicate::str::contains("\"start\": 7"));
+
+    re_x()
+        .args(["-f", "text", "test", r"\w+", "err ok err", "--dedup"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"err\" × 2"));
+}
+
+#[test]
+fn test_context_chars() {
+    re_x()
+        .args(["test", r"\d+", "order 1234 shipped", "--context-chars", "4"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\"context\": \"…der «1234» shi…\"",
+        ));
+}
+
+#[test]
+fn test_highlight_captures() {
+    // Group 2 nests inside group 1, so "b" takes group 2's color; the "-"
+    // outside both groups keeps the match color
+    re_x()
+        .args([
+            "test",
+            r"(a(b))-",
+            "ab-",
+            "--format",
+            "text",
+            "--highlight-captures",
+            "--color",
+            "always",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "  Highlight: \x1b[1;32ma\x1b[0m\x1b[1;36mb\x1b[0m\x1b[1;33m-\x1b[0m\n",
+        ))
+        .stdout(predicate::str::contains(
+            "  \x1b[1;36mGroup 2\x1b[0m: \"b\"",
+        ));
+
+    // Piped output (auto) and NO_COLOR get no escape codes
+    for color in ["auto", "never"] {
+        re_x()
+            .args([
+                "test",
+                r"(a(b))-",
+                "ab-",
+                "--format",
+                "text",
+                "--highlight-captures",
+                "--color",
+                color,
+            ])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("  Group 2: \"b\""))
+            .stdout(predicate::str::contains("\x1b[").not());
+    }
+    re_x()
+        .env("NO_COLOR", "1")
+        .args([
+            "test",
+            r"(a(b))-",
+            "ab-",
+            "--format",
+            "text",
+            "--highlight-captures",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\x1b[").not());
+}
+
+#[test]
+fn test_per_line_count() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("app.log");
+    std::fs::write(&path, "ERROR a\nok\nERROR b ERROR c\n").unwrap();
+
+    re_x()
+        .args(["test", "ERROR", "--per-line-count", "--file"])
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"match_count\": 3"))
+        .stdout(predicate::str::contains("\"line_number\": 3"))
+        .stdout(predicate::str::contains("\"matches\": []"));
+
+    re_x()
+        .args(["test", "ERROR", "ERROR here", "--per-line-count"])
+        .assert()
+        .code(5);
+}
+
+#[test]
+fn test_per_line_count_skips_empty_matches() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("input.txt");
+    std::fs::write(&path, "x\nabc\nab\n").unwrap();
+
+    let output = re_x()
+        .args(["test", "a*", "--per-line-count", "--file"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let result: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(result["match_count"], 2);
+    let lines: Vec<u64> = result["lines"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|l| l["line_number"].as_u64().unwrap())
+        .collect();
+    assert_eq!(lines, [2, 3]);
+    assert!(result["warning"].as_str().is_some());
+
+    let output = re_x()
+        .args([
+            "test",
+            "a*",
+            "--per-line-count",
+            "--allow-empty-matches",
+            "--file",
+        ])
+        .arg(&path)
+        .output()
+        .unwrap();
+    let result: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(result["match_count"], 7);
+}
+
+#[test]
+fn test_by_capture_unknown_group() {
+    re_x()
+        .args(["test", r"(\d+)", "a1", "--by-capture", "name"])
+        .assert()
+        .code(5)
+        .stderr(predicate::str::contains("does not exist"));
+}
+
+// --- Empty pattern handling ---
+
+#[test]
+fn test_empty_pattern_refused_by_test() {
+    re_x()
+        .args(["test", "", "abc"])
+        .assert()
+        .code(5)
+        .stderr(predicate::str::contains("\"code\":\"INVALID_INPUT\""))
+        .stderr(predicate::str::contains("empty pattern matches everywhere"));
+}
+
+#[test]
+fn test_empty_pattern_allowed_by_test() {
+    re_x()
+        .args(["test", "", "ab", "--allow-empty"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"match_count\": 3"));
+}
+
+#[test]
+fn test_empty_pattern_refused_by_replace() {
+    re_x()
+        .args(["replace", "(?:)", "-", "abc"])
+        .assert()
+        .code(5)
+        .stderr(predicate::str::contains("\"code\":\"INVALID_INPUT\""));
+
+    re_x()
+        .args(["replace", "", "-", "abc", "--allow-empty"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"result\": \"-a-b-c-\""));
+}
+
+#[test]
+fn test_empty_pattern_refused_by_apply() {
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir.path().join("test.txt");
+    fs::write(&file_path, "abc\n").unwrap();
+
+    re_x()
+        .args(["apply", "", "-", "--file", file_path.to_str().unwrap()])
+        .assert()
+        .code(5)
+        .stderr(predicate::str::contains("\"code\":\"INVALID_INPUT\""));
+
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "abc\n");
+}
+
+#[test]
+fn test_empty_pattern_accepted_by_validate_and_explain() {
+    re_x()
+        .args(["validate", ""])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"valid\": true"));
+
+    re_x()
+        .args(["explain", ""])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"summary\": \"Empty pattern\""));
+}
+
+#[test]
+fn test_mcp_empty_pattern_error() {
+    let init = r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2025-03-26","capabilities":{},"clientInfo":{"name":"test","version":"1.0"}}}"#;
+    let call = r#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"regex_test","arguments":{"pattern":"","input":"abc"}}}"#;
+    re_x()
+        .arg("--mcp")
+        .write_stdin(format!("{}\n{}\n", init, call))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"isError\":true"))
+        .stdout(predicate::str::contains(
+            "\\\"code\\\":\\\"INVALID_INPUT\\\"",
+        ));
+}
+
+// --- Quiet mode ---
+
+#[test]
+fn test_quiet_suppresses_output() {
+    re_x()
+        .args(["test", r"\d+", "abc 123", "--quiet"])
+        .assert()
+        .code(0)
+        .stdout(predicate::str::is_empty());
+
+    re_x()
+        .args(["-q", "test", r"\d+", "abc"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::is_empty());
+}
+
+#[test]
+fn test_quiet_still_reports_errors() {
+    re_x()
+        .args(["test", "(abc", "abc", "-q"])
+        .assert()
+        .code(2)
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains("\"error\":true"));
 }